@@ -0,0 +1,30 @@
+// Everything a player can do, whatever key or other input it came from.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action {
+    MoveLeft,
+    MoveRight,
+    RotateClockwise,
+    RotateAnticlockwise,
+    SoftDrop,
+    HardDrop,
+    SonicDrop,
+    Hold,
+    Pause,
+    Quit,
+    ToggleStats,
+    ToggleHint,
+    ToggleDebug,
+    // Freeze the game and then step it on one tick at a time, for looking at what it does closely.
+    ToggleFrameStep,
+    FrameStep,
+    // Go back a few seconds, or to before the last piece was placed, in a practice game.
+    Rewind,
+    Undo,
+    // Save what's on the screen to a file.
+    Screenshot,
+    // Use the item picked up in a game with items.
+    UseItem,
+    // Pick which opponent attacks go to in a network game with more than one.
+    SwitchTarget
+}
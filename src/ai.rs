@@ -0,0 +1,409 @@
+use rand::Rng;
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+
+use crate::gameboard::{ActivePiece, Cell, Game, GameBoard};
+use crate::rules::RuleSet;
+use crate::tetromino::Tetromino;
+
+// How much each feature of the board left behind by a placement counts for. These are the weights
+// found by Yiyuan Lee's genetic search for a player that only looks at the current piece, with a
+// bonus on top for T-spins so that the hints show them off when they're there to be had.
+const BALANCED: Weights = Weights {
+    height: -0.510066,
+    line: 0.760666,
+    tetris: 0.0,
+    holes: -0.35663,
+    bumpiness: -0.184483,
+    t_spin: 2.0,
+    well: 0.0
+};
+// Lines are worth more and height costs more, so anything that can be cleared is.
+const DOWNSTACK: Weights = Weights {
+    height: -0.8,
+    line: 1.5,
+    tetris: 0.0,
+    holes: -0.6,
+    bumpiness: -0.15,
+    t_spin: 2.0,
+    well: 0.0
+};
+// Smaller clears cost something and Tetrises are worth a lot, with the rightmost column kept clear
+// for the I pieces that make them.
+const TETRIS: Weights = Weights {
+    height: -0.3,
+    line: -1.0,
+    tetris: 12.0,
+    holes: -0.6,
+    bumpiness: -0.184483,
+    t_spin: 0.0,
+    well: -1.0
+};
+// Only the most promising placements at each step are looked further ahead from.
+const BEAM_WIDTH: usize = 4;
+pub const MAX_DEPTH: usize = 3;
+
+// What the AI goes for when it plays a game of its own.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Style {
+    // The same as the hints: a low, flat stack without holes.
+    Balanced,
+    // Clears whatever it can as soon as it can, which digs out garbage quickly.
+    Downstack,
+    // Builds up beside a well and clears with Tetrises only, until the stack gets past halfway.
+    Tetris
+}
+
+impl Display for Style {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Style::Balanced => "balanced",
+                Style::Downstack => "downstack",
+                Style::Tetris => "tetris"
+            }
+        )
+    }
+}
+
+// How well the AI plays: how many pieces it looks at, from 1 for only the active one to
+// `MAX_DEPTH`, and the percent chance it puts a piece a column away from where it meant to.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub struct Personality {
+    pub depth: usize,
+    pub misdrop: u32,
+    pub style: Style
+}
+
+struct Weights {
+    height: f64,
+    line: f64,
+    // For each Tetris, on top of `line` for each of its lines.
+    tetris: f64,
+    holes: f64,
+    bumpiness: f64,
+    t_spin: f64,
+    // For each filled square in the rightmost column.
+    well: f64
+}
+
+impl Style {
+    // Building for Tetrises stops once the stack's past halfway, to dig out instead.
+    fn weights(self, board: &GameBoard) -> &'static Weights {
+        match self {
+            Style::Balanced => &BALANCED,
+            Style::Downstack => &DOWNSTACK,
+            Style::Tetris if board.stack_height() * 2 > board.height => &DOWNSTACK,
+            Style::Tetris => &TETRIS
+        }
+    }
+}
+
+// A landing as (its score, what its clear earned, the board it leaves, where the piece went).
+type Scored = (f64, f64, GameBoard, ActivePiece);
+
+// A place the active piece can be moved to and locked, and whether the last move getting there was
+// a rotation.
+#[derive(Copy, Clone)]
+struct Landing {
+    piece: ActivePiece,
+    rotated: bool
+}
+
+// The best place for the active piece out of everywhere it can reach from where it is now, with
+// the same moves and wall kicks the player has. Soft drops are included, so tucks and spins are
+// found as well as straight drops.
+pub fn best_placement(game: &Game) -> Option<ActivePiece> {
+    ranked_placements(game, Style::Balanced, 1)
+        .first()
+        .map(|&(_, piece)| piece)
+}
+
+// Where the AI puts the active piece. It looks ahead through the preview as far as it's allowed,
+// and sometimes misdrops next to where it meant to go.
+pub fn choose_placement<R: Rng>(
+    game: &Game,
+    personality: Personality,
+    rng: &mut R
+) -> Option<ActivePiece> {
+    let ranked = ranked_placements(game, personality.style, personality.depth);
+    let &(_, best) = ranked.first()?;
+    if rng.gen_range(0, 100) >= personality.misdrop {
+        return Some(best);
+    }
+    let beside = ranked
+        .iter()
+        .map(|&(_, piece)| piece)
+        .filter(|piece| piece.rotation == best.rotation && (piece.x - best.x).abs() == best.scale)
+        .collect::<Vec<_>>();
+    let misdrops = if beside.is_empty() {
+        ranked.iter().map(|&(_, piece)| piece).collect()
+    } else {
+        beside
+    };
+    Some(misdrops[rng.gen_range(0, misdrops.len())])
+}
+
+// Every reachable place for the active piece with how good it looks, best first. Past the first
+// piece, each placement is scored by the best that the pieces in the preview can do after it.
+fn ranked_placements(game: &Game, style: Style, depth: usize) -> Vec<(f64, ActivePiece)> {
+    let mut ranked = scored_landings(game, &game.board, game.active_piece, style);
+    sort_best_first(&mut ranked);
+    let ahead = game.shown_preview();
+    let ahead = &ahead[..ahead.len().min(depth.max(1) - 1)];
+    if !ahead.is_empty() {
+        ranked.truncate(BEAM_WIDTH);
+        for scored in ranked.iter_mut() {
+            scored.0 = scored.1 + search(game, &scored.2, ahead, style);
+        }
+        sort_best_first(&mut ranked);
+    }
+    ranked
+        .into_iter()
+        .map(|(score, _, _, piece)| (score, piece))
+        .collect()
+}
+
+// The best that placing each of `pieces` in turn on `board` can be scored.
+fn search(game: &Game, board: &GameBoard, pieces: &[Tetromino], style: Style) -> f64 {
+    let start = game.spawn_position(pieces[0]);
+    let mut scored = scored_landings(game, board, start, style);
+    // Nowhere to go means topping out, which is as bad as it gets.
+    if scored.is_empty() {
+        return f64::MIN;
+    }
+    sort_best_first(&mut scored);
+    if pieces.len() == 1 {
+        return scored[0].0;
+    }
+    scored
+        .iter()
+        .take(BEAM_WIDTH)
+        .map(|&(_, reward, ref after, _)| reward + search(game, after, &pieces[1..], style))
+        .fold(f64::MIN, f64::max)
+}
+
+// Each landing for a piece starting at `start`.
+fn scored_landings(
+    game: &Game,
+    board: &GameBoard,
+    start: ActivePiece,
+    style: Style
+) -> Vec<Scored> {
+    let weights = style.weights(board);
+    reachable_landings(board, game, start)
+        .into_iter()
+        .map(|landing| {
+            let (reward, after) = place(board, landing, weights);
+            (
+                reward + evaluate(&after, weights),
+                reward,
+                after,
+                landing.piece
+            )
+        })
+        .collect()
+}
+
+fn sort_best_first(scored: &mut [Scored]) {
+    scored.sort_by(|a, b| b.0.partial_cmp(&a.0).unwrap_or(Ordering::Equal));
+}
+
+fn reachable_landings(board: &GameBoard, game: &Game, piece: ActivePiece) -> Vec<Landing> {
+    let start = Landing {
+        piece,
+        rotated: false
+    };
+    if !board.fits(&start.piece) {
+        return Vec::new();
+    }
+    let key = |landing: &Landing| {
+        let piece = landing.piece;
+        (piece.x, piece.y, piece.rotation, landing.rotated)
+    };
+    let mut seen = HashSet::new();
+    seen.insert(key(&start));
+    let mut frontier = vec![start];
+    let mut landings = Vec::new();
+    while let Some(landing) = frontier.pop() {
+        let piece = landing.piece;
+        if !board.fits(&piece.shifted(0, 1)) {
+            landings.push(landing);
+        }
+        let mut next = Vec::with_capacity(5);
+        for &(dx, dy) in [(-piece.scale, 0), (piece.scale, 0), (0, 1)].iter() {
+            let moved = piece.shifted(dx, dy);
+            if board.fits(&moved) {
+                next.push(Landing {
+                    piece: moved,
+                    rotated: false
+                });
+            }
+        }
+        for &clockwise in [true, false].iter() {
+            if let Some(rotated) = rotate(board, game.rules.rule_set(), piece, clockwise) {
+                next.push(Landing {
+                    piece: rotated,
+                    rotated: true
+                });
+            }
+        }
+        for candidate in next {
+            if seen.insert(key(&candidate)) {
+                frontier.push(candidate);
+            }
+        }
+    }
+    landings
+}
+
+// Rotate the same way the game does, including its lack of wall kicks in classic mode.
+fn rotate(
+    board: &GameBoard,
+    rule_set: &dyn RuleSet,
+    piece: ActivePiece,
+    clockwise: bool
+) -> Option<ActivePiece> {
+    let turns = if clockwise { 1 } else { 3 };
+    let kicks = rule_set.kicks(piece.tetromino, piece.rotation, clockwise);
+    kicks
+        .iter()
+        .map(|&(dx, dy)| ActivePiece {
+            rotation: (piece.rotation + turns) & 3,
+            x: piece.x + dx * piece.scale,
+            y: piece.y + dy * piece.scale,
+            ..piece
+        })
+        .find(|rotated| board.fits(rotated))
+}
+
+// Lock a piece in and clear its lines, for what the clear earned and the board it leaves.
+fn place(board: &GameBoard, landing: Landing, weights: &Weights) -> (f64, GameBoard) {
+    let piece = landing.piece;
+    let t_spin = piece.tetromino == Tetromino::T
+        && landing.rotated
+        && [(0, 0), (2, 0), (0, 2), (2, 2)]
+            .iter()
+            .filter(|&&(dx, dy)| {
+                !board.is_free(piece.x + dx * piece.scale, piece.y + dy * piece.scale)
+            })
+            .count()
+            >= 3;
+    let mut after = board.clone();
+    after.place(&piece, Cell::garbage());
+    let lines = after.clear_full_rows();
+    let mut reward = weights.line * lines as f64;
+    if lines >= 4 * piece.scale as usize {
+        reward += weights.tetris;
+    }
+    if t_spin && lines > 0 {
+        reward += weights.t_spin * lines as f64;
+    }
+    (reward, after)
+}
+
+// How good a board is to carry on from. With a well to keep clear, the well isn't counted as a dip
+// in the stack.
+fn evaluate(board: &GameBoard, weights: &Weights) -> f64 {
+    let heights = (0..board.width)
+        .map(|x| {
+            (0..board.height)
+                .find(|&y| board.get(x, y).is_some())
+                .map_or(0, |top| board.height - top)
+        })
+        .collect::<Vec<_>>();
+    let holes = (0..board.width)
+        .map(|x| {
+            (board.height - heights[x]..board.height)
+                .filter(|&y| board.get(x, y).is_none())
+                .count()
+        })
+        .sum::<usize>();
+    let stack = if weights.well != 0.0 {
+        &heights[..board.width - 1]
+    } else {
+        &heights[..]
+    };
+    let bumpiness = stack
+        .windows(2)
+        .map(|pair| (pair[0] as isize - pair[1] as isize).unsigned_abs())
+        .sum::<usize>();
+    let well_filled = (0..board.height)
+        .filter(|&y| board.get(board.width - 1, y).is_some())
+        .count();
+    weights.height * heights.iter().sum::<usize>() as f64
+        + weights.holes * holes as f64
+        + weights.bumpiness * bumpiness as f64
+        + weights.well * well_filled as f64
+}
+
+// With a well waiting for it, an I piece should be stood up in the well.
+#[test]
+fn test_best_placement_fills_well() {
+    use crate::rules::Rules;
+    let rules = Rules {
+        garbage_hole_repeat: 100,
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    game.add_garbage(4);
+    let bottom = game.board.height - 1;
+    let well = (0..game.board.width)
+        .find(|&x| game.board.get(x, bottom).is_none())
+        .unwrap();
+    game.active_piece = ActivePiece {
+        tetromino: Tetromino::I,
+        ..game.active_piece
+    };
+    let placement = best_placement(&game).unwrap();
+    let mut columns = placement
+        .cells()
+        .iter()
+        .map(|&(x, _)| x)
+        .collect::<Vec<_>>();
+    columns.dedup();
+    assert_eq!(columns, vec![well as isize]);
+}
+
+// Without misdrops the AI goes where the hint would, and with nothing but misdrops it lands a
+// column to the side.
+#[test]
+fn test_choose_placement_misdrops() {
+    use crate::rules::Rules;
+    use rand::thread_rng;
+    let game = Game::with_seed(Rules::default(), 0);
+    let best = best_placement(&game).unwrap();
+    let mut personality = Personality {
+        depth: 1,
+        misdrop: 0,
+        style: Style::Balanced
+    };
+    let chosen = choose_placement(&game, personality, &mut thread_rng()).unwrap();
+    assert_eq!(chosen.cells(), best.cells());
+    personality.misdrop = 100;
+    let misdrop = choose_placement(&game, personality, &mut thread_rng()).unwrap();
+    assert_eq!(misdrop.rotation, best.rotation);
+    assert_eq!((misdrop.x - best.x).abs(), 1);
+}
+
+// Building for Tetrises keeps the rightmost column empty, even looking ahead.
+#[test]
+fn test_tetris_style_keeps_well() {
+    use crate::rules::Rules;
+    use rand::thread_rng;
+    let mut game = Game::with_seed(Rules::default(), 0);
+    let personality = Personality {
+        depth: MAX_DEPTH,
+        misdrop: 0,
+        style: Style::Tetris
+    };
+    for _ in 0..12 {
+        let placement = choose_placement(&game, personality, &mut thread_rng()).unwrap();
+        assert!(game.play_placement(placement));
+    }
+    let well = game.board.width - 1;
+    assert!((0..game.board.height).all(|y| game.board.get(well, y).is_none()));
+}
@@ -0,0 +1,234 @@
+use crossterm::Color;
+use std::fs::read;
+use std::io::Result as IoResult;
+use std::path::Path;
+
+// ANSI art is drawn 80 columns wide, so lines wrap there even without a line break.
+const ART_WIDTH: usize = 80;
+// Files can end with a SAUCE record describing them, which starts after this character.
+const END_OF_FILE: char = '\x1A';
+// The top half of code page 437, which is what most `.ans` files are written in.
+const CP437: [char; 128] = [
+    'Ç', 'ü', 'é', 'â', 'ä', 'à', 'å', 'ç', 'ê', 'ë', 'è', 'ï', 'î', 'ì', 'Ä', 'Å', 'É', 'æ', 'Æ',
+    'ô', 'ö', 'ò', 'û', 'ù', 'ÿ', 'Ö', 'Ü', '¢', '£', '¥', '₧', 'ƒ', 'á', 'í', 'ó', 'ú', 'ñ', 'Ñ',
+    'ª', 'º', '¿', '⌐', '¬', '½', '¼', '¡', '«', '»', '░', '▒', '▓', '│', '┤', '╡', '╢', '╖', '╕',
+    '╣', '║', '╗', '╝', '╜', '╛', '┐', '└', '┴', '┬', '├', '─', '┼', '╞', '╟', '╚', '╔', '╩', '╦',
+    '╠', '═', '╬', '╧', '╨', '╤', '╥', '╙', '╘', '╒', '╓', '╫', '╪', '┘', '┌', '█', '▄', '▌', '▐',
+    '▀', 'α', 'ß', 'Γ', 'π', 'Σ', 'σ', 'µ', 'τ', 'Φ', 'Θ', 'Ω', 'δ', '∞', 'φ', 'ε', '∩', '≡', '±',
+    '≥', '≤', '⌠', '⌡', '÷', '≈', '°', '∙', '·', '√', 'ⁿ', '²', '■', '\u{A0}'
+];
+// The eight colors SGR has numbers for, in their order, and then their bright versions.
+const SGR_COLORS: [Color; 8] = [
+    Color::Black,
+    Color::DarkRed,
+    Color::DarkGreen,
+    Color::DarkYellow,
+    Color::DarkBlue,
+    Color::DarkMagenta,
+    Color::DarkCyan,
+    Color::Grey
+];
+const BRIGHT_SGR_COLORS: [Color; 8] = [
+    Color::DarkGrey,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White
+];
+
+// One character of the art and the colors it was drawn in, if it set any.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct ArtSquare {
+    pub(crate) character: char,
+    pub(crate) fg: Option<Color>,
+    pub(crate) bg: Option<Color>
+}
+
+// The colors set so far while reading the art. Bold makes the eight basic colors bright, as
+// the art was drawn for terminals that did that.
+#[derive(Default)]
+struct Pen {
+    fg: Option<usize>,
+    fg_color: Option<Color>,
+    bg: Option<Color>,
+    bold: bool
+}
+
+impl Pen {
+    fn fg(&self) -> Option<Color> {
+        match self.fg {
+            Some(i) if self.bold => Some(BRIGHT_SGR_COLORS[i]),
+            Some(i) => Some(SGR_COLORS[i]),
+            None => self.fg_color
+        }
+    }
+
+    fn apply(&mut self, params: &str) {
+        let mut numbers = params.split(';').map(|n| n.parse::<u8>().unwrap_or(0));
+        while let Some(n) = numbers.next() {
+            match n {
+                0 => *self = Pen::default(),
+                1 => self.bold = true,
+                22 => self.bold = false,
+                30..=37 => self.fg = Some((n - 30) as usize),
+                90..=97 => {
+                    self.fg = None;
+                    self.fg_color = Some(BRIGHT_SGR_COLORS[(n - 90) as usize]);
+                }
+                40..=47 => self.bg = Some(SGR_COLORS[(n - 40) as usize]),
+                100..=107 => self.bg = Some(BRIGHT_SGR_COLORS[(n - 100) as usize]),
+                39 => {
+                    self.fg = None;
+                    self.fg_color = None;
+                }
+                49 => self.bg = None,
+                38 | 48 => {
+                    let colour = match numbers.next() {
+                        Some(5) => numbers.next().map(Color::AnsiValue),
+                        Some(2) => match (numbers.next(), numbers.next(), numbers.next()) {
+                            (Some(r), Some(g), Some(b)) => Some(Color::Rgb { r, g, b }),
+                            _ => None
+                        },
+                        _ => None
+                    };
+                    if n == 38 {
+                        self.fg = None;
+                        self.fg_color = colour;
+                    } else {
+                        self.bg = colour;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+// ANSI art to draw behind the game, from the `background_art` setting. Only what ANSI art uses is
+// understood: text, colors, and moving the cursor. Everything else is skipped over.
+#[derive(Debug, Default)]
+pub struct Art {
+    rows: Vec<Vec<Option<ArtSquare>>>
+}
+
+impl Art {
+    pub fn load<P: AsRef<Path>>(path: P) -> IoResult<Self> {
+        Ok(Art::parse(&read(path)?))
+    }
+
+    // Files that aren't UTF-8 are taken to be code page 437.
+    pub fn parse(bytes: &[u8]) -> Self {
+        let text = match String::from_utf8(bytes.to_vec()) {
+            Ok(text) => text,
+            Err(_) => bytes
+                .iter()
+                .map(|&b| {
+                    if b < 0x80 {
+                        b as char
+                    } else {
+                        CP437[b as usize - 0x80]
+                    }
+                })
+                .collect()
+        };
+        let mut art = Art::default();
+        let (mut x, mut y): (usize, usize) = (0, 0);
+        let mut pen = Pen::default();
+        let mut chars = text.chars().take_while(|&c| c != END_OF_FILE).peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\n' => {
+                    x = 0;
+                    y += 1;
+                }
+                '\r' => x = 0,
+                '\x1B' => {
+                    if chars.next_if_eq(&'[').is_none() {
+                        continue;
+                    }
+                    let mut params = String::new();
+                    let end = loop {
+                        match chars.next() {
+                            Some(c) if c.is_ascii_digit() || c == ';' || c == '?' => params.push(c),
+                            end => break end
+                        }
+                    };
+                    let count = || params.parse::<usize>().unwrap_or(1).max(1);
+                    match end {
+                        Some('m') => pen.apply(&params),
+                        Some('A') => y = y.saturating_sub(count()),
+                        Some('B') => y += count(),
+                        Some('C') => x = (x + count()).min(ART_WIDTH - 1),
+                        Some('D') => x = x.saturating_sub(count()),
+                        Some('H') | Some('f') => {
+                            let mut numbers =
+                                params.split(';').map(|n| n.parse::<usize>().unwrap_or(1));
+                            y = numbers.next().unwrap_or(1).saturating_sub(1);
+                            x = numbers.next().unwrap_or(1).saturating_sub(1);
+                        }
+                        _ => {}
+                    }
+                }
+                c if c.is_control() => {}
+                character => {
+                    if x >= ART_WIDTH {
+                        x = 0;
+                        y += 1;
+                    }
+                    let square = ArtSquare {
+                        character,
+                        fg: pen.fg(),
+                        bg: pen.bg
+                    };
+                    art.put(x, y, square);
+                    x += 1;
+                }
+            }
+        }
+        art
+    }
+
+    fn put(&mut self, x: usize, y: usize, square: ArtSquare) {
+        if self.rows.len() <= y {
+            self.rows.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.rows[y];
+        if row.len() <= x {
+            row.resize(x + 1, None);
+        }
+        row[x] = Some(square);
+    }
+
+    pub fn rows(&self) -> &[Vec<Option<ArtSquare>>] {
+        &self.rows
+    }
+
+    // What's at a spot on the screen, if the art covers it.
+    pub fn get(&self, x: usize, y: usize) -> Option<ArtSquare> {
+        self.rows
+            .get(y)
+            .and_then(|row| row.get(x))
+            .and_then(|&square| square)
+    }
+}
+
+// Art keeps its colors and where the cursor put it, in either encoding, and stops at the SAUCE
+// record.
+#[test]
+fn test_art() {
+    let art = Art::parse(b"\x1B[1;31mab\x1B[0m\r\n\x1B[2C\xDB\x1B[44mc\x1ASAUCE00");
+    let square = |character, fg, bg| Some(ArtSquare { character, fg, bg });
+    assert_eq!(art.get(0, 0), square('a', Some(Color::Red), None));
+    assert_eq!(art.get(0, 1), None);
+    assert_eq!(art.get(2, 1), square('█', None, None));
+    assert_eq!(art.get(3, 1), square('c', None, Some(Color::DarkBlue)));
+    assert_eq!(art.rows().len(), 2);
+    let art = Art::parse("\x1B[38;2;1;2;3m█".as_bytes());
+    assert_eq!(
+        art.get(0, 0),
+        square('█', Some(Color::Rgb { r: 1, g: 2, b: 3 }), None)
+    );
+}
@@ -1,18 +1,62 @@
+// The frontend modules are compiled into the benchmark binary as well, which doesn't use all of
+// them. The game itself comes from the library.
+#![allow(dead_code)]
+
 #[macro_use] extern crate criterion;
 extern crate rand;
-extern crate crossterm;
+#[macro_use] extern crate crossterm;
+extern crate dirs;
+extern crate serde;
+extern crate serde_json;
+#[macro_use] extern crate serde_derive;
+extern crate toml;
+extern crate tui_tetris;
+#[cfg(feature = "online")]
+extern crate ureq;
 
-use rand::{thread_rng, Rng};
+use rand::rngs::StdRng;
+use rand::{thread_rng, Rng, SeedableRng};
 
+mod art;
+mod bot;
+mod color;
 mod game_config;
-mod gameboard;
-mod tetromino;
+mod input;
+mod leaderboard;
+mod locale;
+mod net;
+mod paths;
+mod records;
+mod render;
+mod session;
+mod settings_menu;
 
-use gameboard::decode_sequence_number;
+use tui_tetris::action::Action;
+use tui_tetris::ai::best_placement;
+use tui_tetris::gameboard::{decode_sequence_number, Game};
+use tui_tetris::rollback::{Rollback, MAX_ROLLBACK};
+use tui_tetris::rules::Rules;
+use tui_tetris::versus::{Match, Targeting};
 
-use criterion::{Criterion, black_box};
+use criterion::{Benchmark, Criterion, black_box};
 use std::fs::read_to_string;
 use game_config::GameConfig;
+use session::Session;
+
+// Random play only gets so far before topping out, but this keeps it from running forever if it
+// somehow doesn't.
+const MAX_RANDOM_FRAMES: usize = 100_000;
+// How many pieces the AI places per iteration of its benchmark. Searching every placement takes a
+// few milliseconds, so this is kept short and the benchmark takes fewer samples than the rest.
+const AI_PIECES: usize = 10;
+const AI_SAMPLES: usize = 20;
+const MOVES: [Action; 5] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::RotateClockwise,
+    Action::SoftDrop,
+    Action::HardDrop
+];
 
 fn bench_decode_sequence_number(c: &mut Criterion) {
     let mut rng = thread_rng();
@@ -27,9 +71,113 @@ fn bench_parse_game_config(c: &mut Criterion) {
     c.bench_function("Parse config file", move |b| {
         let file_string = read_to_string("tui_tetris.conf").unwrap();
         b.iter(|| {
-            if let Err(e) = GameConfig::parse(file_string.as_str()) {
-                panic!(e);
+            if let Err(e) = GameConfig::parse(file_string.as_str(), &[], None) {
+                panic!("{}", e);
+            }
+        })
+    });
+}
+
+fn bench_parse_toml_game_config(c: &mut Criterion) {
+    c.bench_function("Parse TOML config", move |b| {
+        let toml_string = GameConfig::default().to_toml();
+        b.iter(|| {
+            if let Err(e) = GameConfig::parse_toml(toml_string.as_str(), &[], None, None) {
+                panic!("{}", e);
+            }
+        })
+    });
+}
+
+// Mashing random moves with a tick after each one, the way a frame of input would go, until the
+// stack tops out. Covers spawning, movement, rotation, and locking.
+fn bench_random_game(c: &mut Criterion) {
+    c.bench_function("Play a random game", move |b| {
+        let mut seed = 0;
+        b.iter_with_setup(
+            || {
+                seed += 1;
+                (Game::with_seed(Rules::default(), seed), StdRng::seed_from_u64(seed))
+            },
+            |(mut game, mut rng)| {
+                for _ in 0..MAX_RANDOM_FRAMES {
+                    if game.game_over {
+                        break;
+                    }
+                    game.handle_action(MOVES[rng.gen_range(0, MOVES.len())]);
+                    game.tick();
+                }
+                black_box(game.score);
             }
+        )
+    });
+}
+
+// The AI clears lines steadily, so this covers line clears and the hint search as well.
+fn bench_ai_game(c: &mut Criterion) {
+    let benchmark = Benchmark::new("Place pieces with the AI", move |b| {
+        let mut seed = 0;
+        b.iter_with_setup(
+            || {
+                seed += 1;
+                Game::with_seed(Rules::default(), seed)
+            },
+            |mut game| {
+                for _ in 0..AI_PIECES {
+                    match best_placement(&game) {
+                        Some(placement) if game.play_placement(placement) => {}
+                        _ => break
+                    }
+                }
+                black_box(game.lines_cleared);
+            }
+        )
+    });
+    c.bench("AI", benchmark.sample_size(AI_SAMPLES));
+}
+
+// The worst case for rollback netcode: inputs from the start of the window turning up once a
+// four player match has been played as far ahead as it goes, so all of it has to be played again.
+fn bench_rollback(c: &mut Criterion) {
+    c.bench_function("Roll back a four player match", move |b| {
+        let players = vec![(Rules::default(), Targeting::Random); 4];
+        let mut seed = 0;
+        b.iter_with_setup(
+            || {
+                seed += 1;
+                let mut rollback = Rollback::new(Match::new(seed, &players), 0, 0);
+                for tick in 0..MAX_ROLLBACK {
+                    rollback.advance(vec![MOVES[tick as usize % MOVES.len()]]);
+                }
+                rollback
+            },
+            |mut rollback| {
+                for player in 1..4 {
+                    rollback.receive(player, 0, vec![Action::HardDrop]);
+                }
+                rollback.catch_up();
+                black_box(rollback.rollbacks);
+            }
+        )
+    });
+}
+
+// Drawing a whole frame into a buffer, without the terminal. The board is partly filled so that
+// there's something on it.
+fn bench_render(c: &mut Criterion) {
+    let mut game = Game::with_seed(Rules::default(), 0);
+    for _ in 0..20 {
+        if let Some(placement) = best_placement(&game) {
+            game.play_placement(placement);
+        }
+    }
+    let session = Session::new(GameConfig::default(), game);
+    c.bench_function("Render a frame", move |b| {
+        let mut frame = Vec::new();
+        b.iter(|| {
+            frame.clear();
+            session.draw(&mut frame).unwrap();
+            black_box(frame.len());
         })
     });
 }
@@ -37,7 +185,8 @@ fn bench_parse_game_config(c: &mut Criterion) {
 criterion_group! {
     name = bench;
     config = Criterion::default();
-    targets = bench_decode_sequence_number, bench_parse_game_config
+    targets = bench_decode_sequence_number, bench_parse_game_config, bench_parse_toml_game_config,
+        bench_random_game, bench_ai_game, bench_rollback, bench_render
 }
 
 criterion_main!{bench}
\ No newline at end of file
@@ -0,0 +1,505 @@
+use crate::rand::thread_rng;
+use crate::serde_json;
+use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use tui_tetris::ai::{choose_placement, Personality};
+use tui_tetris::gameboard::{ActivePiece, Game};
+use tui_tetris::rules::{Handicap, Rules};
+use tui_tetris::tetromino::Tetromino;
+use tui_tetris::versus::Attack;
+
+use crate::net::RemoteBoard;
+use crate::session::Session;
+
+// The Tetris Bot Protocol always uses a 10 wide board with 40 rows, counted up from the bottom.
+pub const TBP_BOARD_WIDTH: usize = 10;
+const TBP_BOARD_HEIGHT: usize = 40;
+// How long a bot gets to introduce itself and get ready before it's given up on.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(10);
+// Minos of each piece in the north orientation relative to its center, with y going up. Other
+// orientations are these turned about the center.
+const TBP_SHAPES: [[(isize, isize); 4]; 7] = [
+    // I
+    [(-1, 0), (0, 0), (1, 0), (2, 0)],
+    // J
+    [(-1, 0), (0, 0), (1, 0), (-1, 1)],
+    // L
+    [(-1, 0), (0, 0), (1, 0), (1, 1)],
+    // S
+    [(-1, 0), (0, 0), (0, 1), (1, 1)],
+    // Z
+    [(-1, 1), (0, 1), (0, 0), (1, 0)],
+    // T
+    [(-1, 0), (0, 0), (1, 0), (0, 1)],
+    // O
+    [(0, 0), (1, 0), (0, 1), (1, 1)]
+];
+const ORIENTATIONS: [&str; 4] = ["north", "east", "south", "west"];
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Location {
+    #[serde(rename = "type")]
+    piece: String,
+    orientation: String,
+    x: isize,
+    y: isize
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Move {
+    location: Location,
+    spin: String
+}
+
+impl Move {
+    // Where the move puts the piece on a board `height` rows tall, if it's a move that makes sense.
+    pub fn placement(&self, height: usize) -> Option<ActivePiece> {
+        let location = &self.location;
+        let mut letters = location.piece.chars();
+        let tetromino = match (letters.next(), letters.next()) {
+            (Some(letter), None) => Tetromino::from_letter(letter)?,
+            _ => return None
+        };
+        let turns = ORIENTATIONS
+            .iter()
+            .position(|&orientation| orientation == location.orientation)?;
+        let mut cells = TBP_SHAPES[tetromino as usize];
+        for cell in cells.iter_mut() {
+            let (mut dx, mut dy) = *cell;
+            for _ in 0..turns {
+                let turned = (dy, -dx);
+                dx = turned.0;
+                dy = turned.1;
+            }
+            *cell = (location.x + dx, height as isize - 1 - (location.y + dy));
+        }
+        cells.sort();
+        // Some pieces look the same in more than one orientation, so any of them will do if the
+        // one asked for doesn't line up.
+        (0..4)
+            .map(|offset| (turns + offset) & 3)
+            .find_map(|rotation| {
+                let mut minos = tetromino.minos(rotation);
+                minos.sort();
+                let (dx, dy) = (cells[0].0 - minos[0].0, cells[0].1 - minos[0].1);
+                let lined_up = minos
+                    .iter()
+                    .zip(cells.iter())
+                    .all(|(&(mx, my), &cell)| (mx + dx, my + dy) == cell);
+                if lined_up {
+                    Some(ActivePiece {
+                        tetromino,
+                        rotation,
+                        x: dx,
+                        y: dy,
+                        scale: 1
+                    })
+                } else {
+                    None
+                }
+            })
+    }
+}
+
+// Sent to the bot.
+#[derive(Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum FrontendMessage {
+    Rules,
+    Start {
+        hold: Option<String>,
+        queue: Vec<String>,
+        combo: u32,
+        back_to_back: bool,
+        board: Vec<Vec<Option<String>>>
+    },
+    Stop,
+    Suggest,
+    Play {
+        #[serde(rename = "move")]
+        mv: Move
+    },
+    NewPiece {
+        piece: String
+    },
+    Quit
+}
+
+// Sent by the bot. Anything else it might say is ignored.
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum BotMessage {
+    Info {
+        name: String
+    },
+    Ready,
+    Error {
+        reason: String
+    },
+    Suggestion {
+        moves: Vec<Move>
+    },
+    #[serde(other)]
+    Other
+}
+
+// An external bot run as a child process. It's told about the game as it goes and asked where each
+// piece should go.
+pub struct Bot {
+    child: Child,
+    stdin: ChildStdin,
+    incoming: Receiver<BotMessage>,
+    pub(crate) name: String,
+    // Set while waiting for an answer to a suggestion request.
+    waiting: bool,
+    // How many pieces had been dealt and how much garbage had come in when the bot was last
+    // brought up to date, for spotting what it needs telling about.
+    dealt: u64,
+    garbage_rows: u64
+}
+
+impl Bot {
+    // Start the bot from a command line and wait for it to be ready to play.
+    pub fn launch(command: &str) -> IoResult<Self> {
+        let mut words = command.split_whitespace();
+        let program = words
+            .next()
+            .ok_or_else(|| IoError::new(ErrorKind::InvalidInput, "No bot command given"))?;
+        let mut child = Command::new(program)
+            .args(words)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        let stdin = child.stdin.take().unwrap();
+        let reader = BufReader::new(child.stdout.take().unwrap());
+        let (sender, incoming) = channel();
+        thread::spawn(move || {
+            for line in reader.lines() {
+                let message = match line {
+                    Ok(line) => serde_json::from_str(&line).unwrap_or(BotMessage::Other),
+                    Err(_) => return
+                };
+                if sender.send(message).is_err() {
+                    return;
+                }
+            }
+        });
+        let mut bot = Bot {
+            child,
+            stdin,
+            incoming,
+            name: String::new(),
+            waiting: false,
+            dealt: 0,
+            garbage_rows: 0
+        };
+        bot.name = match bot.wait_for_startup()? {
+            BotMessage::Info { name } => name,
+            _ => return Err(protocol_error("The bot didn't introduce itself"))
+        };
+        bot.send(&FrontendMessage::Rules)?;
+        match bot.wait_for_startup()? {
+            BotMessage::Ready => Ok(bot),
+            BotMessage::Error { reason } => Err(protocol_error(&reason)),
+            _ => Err(protocol_error("The bot didn't get ready"))
+        }
+    }
+
+    fn wait_for_startup(&mut self) -> IoResult<BotMessage> {
+        loop {
+            match self.incoming.recv_timeout(STARTUP_TIMEOUT) {
+                Ok(BotMessage::Other) => {}
+                Ok(message) => return Ok(message),
+                Err(RecvTimeoutError::Timeout) => {
+                    return Err(IoError::new(
+                        ErrorKind::TimedOut,
+                        "The bot took too long to start"
+                    ))
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(protocol_error("The bot quit"))
+            }
+        }
+    }
+
+    fn send(&mut self, message: &FrontendMessage) -> IoResult<()> {
+        let mut line = serde_json::to_string(message)?;
+        line.push('\n');
+        self.stdin.write_all(line.as_bytes())?;
+        self.stdin.flush()
+    }
+
+    // Tell the bot everything about the game so far, which it'll play on from.
+    pub fn start(&mut self, game: &Game) -> IoResult<()> {
+        let height = game.board.height;
+        let board = (0..TBP_BOARD_HEIGHT)
+            .map(|row| {
+                (0..TBP_BOARD_WIDTH)
+                    .map(|x| {
+                        if row >= height || x >= game.board.width {
+                            return None;
+                        }
+                        game.board
+                            .get(x, height - 1 - row)
+                            .map(|cell| cell.tetromino.map_or('G', Tetromino::letter).to_string())
+                    })
+                    .collect()
+            })
+            .collect();
+        let queue = game
+            .queue()
+            .iter()
+            .map(|tetromino| tetromino.letter().to_string())
+            .collect();
+        self.send(&FrontendMessage::Start {
+            hold: game.hold.map(|held| held.letter().to_string()),
+            queue,
+            combo: 0,
+            back_to_back: game.stats.back_to_back,
+            board
+        })?;
+        self.waiting = false;
+        self.dealt = game.dealt;
+        self.garbage_rows = game.garbage_rows;
+        Ok(())
+    }
+
+    // Ask where the active piece should go, if that hasn't been asked already. The answer is
+    // picked up by `suggestion`.
+    pub fn suggest(&mut self) -> IoResult<()> {
+        if !self.waiting {
+            self.send(&FrontendMessage::Suggest)?;
+            self.waiting = true;
+        }
+        Ok(())
+    }
+
+    // The bot's best move, once it's come up with one.
+    pub fn suggestion(&mut self) -> IoResult<Option<Move>> {
+        loop {
+            match self.incoming.try_recv() {
+                Ok(BotMessage::Suggestion { moves }) => {
+                    self.waiting = false;
+                    return match moves.into_iter().next() {
+                        Some(mv) => Ok(Some(mv)),
+                        None => Err(protocol_error("The bot has no moves left"))
+                    };
+                }
+                Ok(BotMessage::Error { reason }) => return Err(protocol_error(&reason)),
+                Ok(_) => {}
+                Err(TryRecvError::Empty) => return Ok(None),
+                Err(TryRecvError::Disconnected) => return Err(protocol_error("The bot quit"))
+            }
+        }
+    }
+
+    // Make a move in the game and let the bot know it's been made. Returns false if the move
+    // couldn't be made.
+    pub fn play(&mut self, game: &mut Game, mv: Move) -> IoResult<bool> {
+        let placed = match mv.placement(game.board.height) {
+            Some(piece) => game.play_placement(piece),
+            None => false
+        };
+        if !placed {
+            return Ok(false);
+        }
+        self.send(&FrontendMessage::Play { mv })?;
+        // Garbage can only be passed on by starting over, since the protocol has no message for it.
+        if game.garbage_rows != self.garbage_rows {
+            self.send(&FrontendMessage::Stop)?;
+            return self.start(game).map(|_| true);
+        }
+        // Every piece dealt shows up at the end of the queue.
+        let queue = game.queue();
+        let new = (game.dealt - self.dealt) as usize;
+        for tetromino in queue[queue.len().saturating_sub(new)..].iter() {
+            self.send(&FrontendMessage::NewPiece {
+                piece: tetromino.letter().to_string()
+            })?;
+        }
+        self.dealt = game.dealt;
+        Ok(true)
+    }
+}
+
+impl Drop for Bot {
+    fn drop(&mut self) {
+        let _ = self.send(&FrontendMessage::Quit);
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+// What plays against the player in a bot game.
+pub enum BotPlayer {
+    // A Tetris Bot Protocol bot, one piece every `bot_delay` milliseconds.
+    External(Bot),
+    // The built-in AI, playing as well as the `ai_difficulty` and `ai_style` settings say.
+    Builtin
+}
+
+// A bot playing its own game against the player. It deals from the same seed so both sides get
+// the same pieces.
+pub struct BotOpponent {
+    bot: BotPlayer,
+    pub(crate) game: Game,
+    last_move: Instant,
+    // Where the built-in AI is going to put the active piece, once it's worked it out on a thread
+    // of its own. Looking far ahead takes longer than a frame.
+    thinking: Option<Receiver<Option<ActivePiece>>>
+}
+
+impl BotOpponent {
+    pub fn new(mut bot: BotPlayer, player: &mut Session) -> IoResult<Self> {
+        // The handicap is the player's own, so the bot plays without one.
+        let rules = Rules {
+            handicap: Handicap::default(),
+            ..player.game.rules.clone()
+        };
+        let mut game = Game::with_seed(rules, player.game.seed);
+        game.versus = true;
+        player.game.start_versus();
+        player.opponents = vec![RemoteBoard::of(&game)];
+        if let BotPlayer::External(ref mut bot) = bot {
+            bot.start(&game)?;
+        }
+        Ok(BotOpponent {
+            bot,
+            game,
+            last_move: Instant::now(),
+            thinking: None
+        })
+    }
+
+    // Let the bot move if it's time to, and pass garbage and results between the two games.
+    pub fn update(&mut self, session: &mut Session) -> IoResult<()> {
+        let counting_down = session.countdown > 0;
+        let player = &mut session.game;
+        if !player.paused && !counting_down && !player.game_over && !self.game.game_over {
+            match self.bot {
+                BotPlayer::External(ref mut bot) => {
+                    bot.suggest()?;
+                    let delay = Duration::from_millis(session.config.bot_delay);
+                    if self.last_move.elapsed() >= delay {
+                        if let Some(mv) = bot.suggestion()? {
+                            // A move that can't be made counts as the bot topping out.
+                            if !bot.play(&mut self.game, mv)? {
+                                self.game.game_over = true;
+                            }
+                            self.last_move = Instant::now();
+                        }
+                    }
+                }
+                BotPlayer::Builtin => {
+                    let delay = Duration::from_millis(session.config.ai_difficulty.delay());
+                    self.think(&session.config.ai_personality());
+                    if self.last_move.elapsed() >= delay {
+                        self.play_thought();
+                    }
+                }
+            }
+        }
+        if player.outgoing_attack > 0 {
+            session
+                .attack_log
+                .push(Attack::Sent(player.outgoing_attack));
+        }
+        if self.game.outgoing_attack > 0 {
+            session
+                .attack_log
+                .push(Attack::Incoming(self.game.outgoing_attack));
+        }
+        self.game.pending_garbage += player.outgoing_attack;
+        player.pending_garbage += self.game.outgoing_attack;
+        player.outgoing_attack = 0;
+        self.game.outgoing_attack = 0;
+        for item in player.outgoing_items.drain(..) {
+            self.game.receive_item(item);
+        }
+        for item in self.game.outgoing_items.drain(..) {
+            player.receive_item(item);
+        }
+        let player_board = RemoteBoard::of(player);
+        let bot_board = RemoteBoard::of(&self.game);
+        // Whoever tops out first loses, and the other game stops there.
+        if bot_board.topped_out {
+            player.win();
+        }
+        if player_board.topped_out {
+            self.game.win();
+        }
+        session.opponents = vec![RemoteBoard::of(&self.game)];
+        Ok(())
+    }
+
+    // Start the built-in AI working out where the active piece goes, if it isn't already.
+    fn think(&mut self, personality: &Personality) {
+        if self.thinking.is_some() {
+            return;
+        }
+        let (sender, receiver) = channel();
+        let (game, personality) = (self.game.clone(), *personality);
+        thread::spawn(move || {
+            let _ = sender.send(choose_placement(&game, personality, &mut thread_rng()));
+        });
+        self.thinking = Some(receiver);
+    }
+
+    // Put the active piece where the built-in AI decided, once it has. Nowhere to put it means
+    // the AI has topped out. Garbage or items can change the board while it's thinking, so a
+    // placement that no longer fits is thought about again.
+    fn play_thought(&mut self) {
+        let placement = match self.thinking.as_ref().map(Receiver::try_recv) {
+            Some(Ok(placement)) => placement,
+            Some(Err(TryRecvError::Empty)) | None => return,
+            Some(Err(TryRecvError::Disconnected)) => None
+        };
+        self.thinking = None;
+        match placement {
+            Some(piece) => {
+                if self.game.play_placement(piece) {
+                    self.last_move = Instant::now();
+                }
+            }
+            None => self.game.game_over = true
+        }
+    }
+}
+
+fn protocol_error(reason: &str) -> IoError {
+    IoError::new(ErrorKind::InvalidData, reason.to_string())
+}
+
+// Moves should land pieces in the same cells the protocol puts them in.
+#[test]
+fn test_move_placement() {
+    let placement = |piece: &str, orientation: &str, x: isize, y: isize| {
+        let mv = Move {
+            location: Location {
+                piece: piece.to_string(),
+                orientation: orientation.to_string(),
+                x,
+                y
+            },
+            spin: "none".to_string()
+        };
+        let mut cells = mv.placement(20).unwrap().cells();
+        cells.sort();
+        cells
+    };
+    assert_eq!(
+        placement("T", "north", 4, 0),
+        [(3, 19), (4, 18), (4, 19), (5, 19)]
+    );
+    assert_eq!(
+        placement("I", "east", 0, 2),
+        [(0, 16), (0, 17), (0, 18), (0, 19)]
+    );
+    assert_eq!(
+        placement("O", "south", 1, 1),
+        [(0, 18), (0, 19), (1, 18), (1, 19)]
+    );
+}
@@ -0,0 +1,127 @@
+use crate::serde_json;
+use std::error::Error;
+use std::io::Write;
+use tui_tetris::replay::Replay;
+use tui_tetris::timing::TICK_RATE;
+
+use crate::game_config::GameConfig;
+use crate::screenshot::Screen;
+use crate::session::{Session, Splits};
+
+// How many frames a second the recording has, and how long the results stay up at the end of it.
+const CAST_FPS: u64 = 30;
+const FRAME_TICKS: u64 = TICK_RATE / CAST_FPS;
+const RESULTS_SECONDS: f64 = 3.0;
+// Hides the cursor and clears the screen before the first frame.
+const CAST_START: &str = "\x1B[?25l\x1B[2J";
+
+// The first line of an asciicast v2 file. Every line after it is an event.
+#[derive(Serialize)]
+struct Header {
+    version: u8,
+    width: usize,
+    height: usize,
+    title: String
+}
+
+// Play a replay through without a terminal and write it out as an asciinema recording. Each frame
+// only redraws what changed since the last, which keeps long games from making huge files. The
+// config decides how it looks, and the replay how it plays.
+pub fn export<W: Write>(
+    replay: &Replay,
+    mut config: GameConfig,
+    out: &mut W
+) -> Result<(), Box<dyn Error>> {
+    let game = replay.start().clone();
+    config.set_rules(&game.rules);
+    let title = format!("tui_tetris {} {}", Splits::course(&game), game.seed_text());
+    let start = game.stats.ticks;
+    let mut session = Session::new(config, game);
+    let mut inputs = replay.inputs().iter().peekable();
+    let mut events = Vec::new();
+    let mut screen = Screen::default();
+    let mut frame = Vec::new();
+    let (mut width, mut height) = (0, 0);
+    loop {
+        let ticks = session.game.stats.ticks;
+        while let Some(input) = inputs.next_if(|input| input.ticks <= ticks) {
+            session.handle_action(input.action);
+        }
+        let over = session.game.game_over || ticks >= replay.ticks();
+        if (ticks - start).is_multiple_of(FRAME_TICKS) || over {
+            frame.clear();
+            session.draw(&mut frame)?;
+            let next = Screen::of(&frame);
+            let size = next.size();
+            width = width.max(size.0);
+            height = height.max(size.1);
+            let changes = next.changes_from(&screen);
+            if !changes.is_empty() {
+                events.push(((ticks - start) as f64 / TICK_RATE as f64, changes));
+            }
+            screen = next;
+        }
+        if over {
+            break;
+        }
+        session.tick();
+        // A game that can't go on would never end.
+        if session.game.stats.ticks == ticks {
+            break;
+        }
+    }
+    let end = events.last().map_or(0.0, |event| event.0) + RESULTS_SECONDS;
+    events.push((end, String::new()));
+    if let Some(first) = events.first_mut() {
+        first.1.insert_str(0, CAST_START);
+    }
+    let header = Header {
+        version: 2,
+        width,
+        height,
+        title
+    };
+    writeln!(out, "{}", serde_json::to_string(&header)?)?;
+    for (time, changes) in events {
+        writeln!(out, "{}", serde_json::to_string(&(time, "o", changes))?)?;
+    }
+    Ok(())
+}
+
+// A recording starts with its header, and then has events in order with the screen cleared first.
+#[test]
+fn test_export() {
+    use serde_json::Value;
+    use tui_tetris::action::Action;
+    use tui_tetris::gameboard::Game;
+    use tui_tetris::replay::Input;
+
+    let config = GameConfig::default();
+    let mut game = Game::with_seed(config.rules(), 1);
+    let mut replay = Replay::new(&game);
+    for i in 0..TICK_RATE * 2 {
+        if i % 20 == 0 {
+            replay.record(Input {
+                ticks: game.stats.ticks,
+                frame: i,
+                action: Action::HardDrop
+            });
+            game.handle_action(Action::HardDrop);
+        }
+        game.tick();
+        replay.tick(&game);
+    }
+    let mut cast = Vec::new();
+    export(&replay, config, &mut cast).unwrap();
+    let cast = String::from_utf8(cast).unwrap();
+    let mut lines = cast.lines();
+    let header: Value = serde_json::from_str(lines.next().unwrap()).unwrap();
+    assert_eq!(header["version"], 2);
+    assert!(header["width"].as_u64().unwrap() > 0);
+    let events = lines
+        .map(|line| serde_json::from_str::<(f64, String, String)>(line).unwrap())
+        .collect::<Vec<_>>();
+    assert!(events[0].2.starts_with(CAST_START));
+    assert!(events.windows(2).all(|pair| pair[0].0 <= pair[1].0));
+    assert!(events.len() > 2);
+}
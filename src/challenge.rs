@@ -0,0 +1,166 @@
+use crate::gameboard::Game;
+use crate::timing::TICK_RATE;
+
+// What has to be done to complete a challenge. Every target that's set has to be reached.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Goal {
+    pub score: Option<u64>,
+    pub lines: Option<usize>,
+    pub tetrises: Option<u64>,
+    pub t_spins: Option<u64>,
+    // Leave the board with nothing on it.
+    pub perfect_clear: bool
+}
+
+// How much can be used up reaching the goal before the challenge is failed.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Limit {
+    pub pieces: Option<u64>,
+    pub seconds: Option<u64>
+}
+
+// An objective for a single game, e.g. "score 5000 in 100 pieces". Custom ones are written in TOML
+// with the same layout, with `goal` and `limit` as tables.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Challenge {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    pub goal: Goal,
+    #[serde(default)]
+    pub limit: Limit
+}
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Outcome {
+    InProgress,
+    Succeeded,
+    Failed
+}
+
+impl Challenge {
+    fn new(name: &str, description: &str, goal: Goal, limit: Limit) -> Self {
+        Challenge {
+            name: name.to_string(),
+            description: description.to_string(),
+            goal,
+            limit
+        }
+    }
+
+    // A challenge with no targets would be over before it started.
+    pub fn is_valid(&self) -> bool {
+        let goal = &self.goal;
+        goal.score.is_some()
+            || goal.lines.is_some()
+            || goal.tetrises.is_some()
+            || goal.t_spins.is_some()
+            || goal.perfect_clear
+    }
+
+    // Reaching the goal on the last piece allowed still counts, and topping out always fails. An
+    // empty board is only a perfect clear once lines have been cleared to get there.
+    pub fn check(&self, game: &Game) -> Outcome {
+        let (goal, stats) = (&self.goal, &game.stats);
+        let reached = goal.score.is_none_or(|score| game.score >= score)
+            && goal.lines.is_none_or(|lines| game.lines_cleared >= lines)
+            && goal
+                .tetrises
+                .is_none_or(|tetrises| stats.tetrises >= tetrises)
+            && goal.t_spins.is_none_or(|t_spins| stats.t_spins >= t_spins)
+            && (!goal.perfect_clear || (game.lines_cleared > 0 && game.board.is_empty()));
+        if reached {
+            return Outcome::Succeeded;
+        }
+        let out_of_pieces = self
+            .limit
+            .pieces
+            .is_some_and(|pieces| stats.pieces >= pieces);
+        let out_of_time = self
+            .limit
+            .seconds
+            .is_some_and(|seconds| stats.ticks >= seconds * TICK_RATE);
+        if out_of_pieces || out_of_time || game.game_over {
+            Outcome::Failed
+        } else {
+            Outcome::InProgress
+        }
+    }
+}
+
+// The challenges that come with the game.
+pub fn built_in() -> [Challenge; 5] {
+    [
+        Challenge::new(
+            "sprint",
+            "Clear 40 lines",
+            Goal {
+                lines: Some(40),
+                ..Goal::default()
+            },
+            Limit::default()
+        ),
+        Challenge::new(
+            "ultra",
+            "Score 10000 in 2 minutes",
+            Goal {
+                score: Some(10000),
+                ..Goal::default()
+            },
+            Limit {
+                seconds: Some(120),
+                ..Limit::default()
+            }
+        ),
+        Challenge::new(
+            "5000-in-100",
+            "Score 5000 in 100 pieces",
+            Goal {
+                score: Some(5000),
+                ..Goal::default()
+            },
+            Limit {
+                pieces: Some(100),
+                ..Limit::default()
+            }
+        ),
+        Challenge::new(
+            "tetris-trio",
+            "Clear 3 Tetrises in 60 pieces",
+            Goal {
+                tetrises: Some(3),
+                ..Goal::default()
+            },
+            Limit {
+                pieces: Some(60),
+                ..Limit::default()
+            }
+        ),
+        Challenge::new(
+            "spin-doctor",
+            "Get 5 T-spins",
+            Goal {
+                t_spins: Some(5),
+                ..Goal::default()
+            },
+            Limit::default()
+        )
+    ]
+}
+
+// The limit is checked after the goal, so the piece that reaches the goal isn't also a failure.
+#[test]
+fn test_challenge_outcome() {
+    use crate::rules::Rules;
+
+    let challenge = &built_in()[2];
+    let mut game = Game::with_seed(Rules::default(), 0);
+    assert_eq!(challenge.check(&game), Outcome::InProgress);
+    game.stats.pieces = 100;
+    assert_eq!(challenge.check(&game), Outcome::Failed);
+    game.score = 5000;
+    assert_eq!(challenge.check(&game), Outcome::Succeeded);
+}
@@ -0,0 +1,294 @@
+use crossterm::Color;
+use std::env;
+use std::fs::read;
+use std::path::PathBuf;
+
+// Index of the `colors` capability in a compiled terminfo entry's numbers section.
+const TERMINFO_COLORS: usize = 13;
+
+// Levels used by each axis of the xterm 6x6x6 color cube (palette entries 16 to 231).
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+// The usual xterm RGB values for the 16 basic colors, in palette order.
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::DarkRed, (205, 0, 0)),
+    (Color::DarkGreen, (0, 205, 0)),
+    (Color::DarkYellow, (205, 205, 0)),
+    (Color::DarkBlue, (0, 0, 238)),
+    (Color::DarkMagenta, (205, 0, 205)),
+    (Color::DarkCyan, (0, 205, 205)),
+    (Color::Grey, (229, 229, 229)),
+    (Color::DarkGrey, (127, 127, 127)),
+    (Color::Red, (255, 0, 0)),
+    (Color::Green, (0, 255, 0)),
+    (Color::Yellow, (255, 255, 0)),
+    (Color::Blue, (92, 92, 255)),
+    (Color::Magenta, (255, 0, 255)),
+    (Color::Cyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255))
+];
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum ColorSupport {
+    TrueColor,
+    Ansi256,
+    Ansi16
+}
+
+impl ColorSupport {
+    // `COLORTERM` is the only reliable way terminals advertise 24-bit color. Failing that, the
+    // terminfo entry for `TERM` says how many palette entries there are, and if that can't be
+    // found either we guess from the name of the terminal.
+    pub fn detect() -> Self {
+        if let Ok(colorterm) = env::var("COLORTERM") {
+            let colorterm = colorterm.to_ascii_lowercase();
+            if colorterm == "truecolor" || colorterm == "24bit" {
+                return ColorSupport::TrueColor;
+            }
+        }
+        let term = match env::var("TERM") {
+            Ok(term) => term,
+            // Windows consoles don't set TERM, and the ones crossterm supports all do truecolor.
+            Err(_) if cfg!(windows) => return ColorSupport::TrueColor,
+            Err(_) => return ColorSupport::Ansi16
+        };
+        match terminfo_colors(&term) {
+            Some(colors) if colors >= 256 => ColorSupport::Ansi256,
+            Some(_) => ColorSupport::Ansi16,
+            None if term.contains("256color") => ColorSupport::Ansi256,
+            None => ColorSupport::Ansi16
+        }
+    }
+
+    // Convert a color into the closest one the terminal can actually show.
+    pub fn quantize(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorSupport::TrueColor, _) => color,
+            (ColorSupport::Ansi256, Color::Rgb { r, g, b }) => {
+                Color::AnsiValue(nearest_ansi256((r, g, b)))
+            }
+            (ColorSupport::Ansi16, Color::Rgb { r, g, b }) => nearest_basic((r, g, b)),
+            (ColorSupport::Ansi16, Color::AnsiValue(value)) => {
+                if value < 16 {
+                    BASIC_COLORS[value as usize].0
+                } else {
+                    nearest_basic(ansi256_rgb(value))
+                }
+            }
+            _ => color
+        }
+    }
+
+    pub fn fg(self, color: Color) -> String {
+        sgr(self.quantize(color), false)
+    }
+
+    pub fn bg(self, color: Color) -> String {
+        sgr(self.quantize(color), true)
+    }
+}
+
+// Scale a color's brightness to `percent` of what it was. Palette colors are converted to RGB
+// first, and the result is quantized as usual when it gets drawn.
+pub fn dim(color: Color, percent: u8) -> Color {
+    let (r, g, b) = match color {
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::AnsiValue(value) => ansi256_rgb(value),
+        _ => BASIC_COLORS
+            .iter()
+            .find(|&&(basic, _)| basic == color)
+            .map_or((255, 255, 255), |&(_, rgb)| rgb)
+    };
+    let scale = |v: u8| (v as u32 * percent.min(100) as u32 / 100) as u8;
+    Color::Rgb {
+        r: scale(r),
+        g: scale(g),
+        b: scale(b)
+    }
+}
+
+// crossterm 0.11's `SetFg` and `SetBg` are built against a different version of crossterm_utils
+// than the one `queue!` comes from, so they can't be queued. This produces the same SGR escape
+// sequences as plain strings to be queued with `Output` instead.
+fn sgr(color: Color, background: bool) -> String {
+    let offset = if background { 10 } else { 0 };
+    match color {
+        Color::Rgb { r, g, b } => format!("\x1B[{};2;{};{};{}m", 38 + offset, r, g, b),
+        Color::AnsiValue(value) => format!("\x1B[{};5;{}m", 38 + offset, value),
+        _ => format!("\x1B[{}m", basic_code(color) + offset)
+    }
+}
+
+fn basic_code(color: Color) -> u8 {
+    match color {
+        Color::Black => 30,
+        Color::DarkRed => 31,
+        Color::DarkGreen => 32,
+        Color::DarkYellow => 33,
+        Color::DarkBlue => 34,
+        Color::DarkMagenta => 35,
+        Color::DarkCyan => 36,
+        Color::Grey => 37,
+        Color::DarkGrey => 90,
+        Color::Red => 91,
+        Color::Green => 92,
+        Color::Yellow => 93,
+        Color::Blue => 94,
+        Color::Magenta => 95,
+        Color::Cyan => 96,
+        Color::White => 97,
+        _ => 39
+    }
+}
+
+fn distance((r1, g1, b1): (u8, u8, u8), (r2, g2, b2): (u8, u8, u8)) -> u32 {
+    let dr = r1 as i32 - r2 as i32;
+    let dg = g1 as i32 - g2 as i32;
+    let db = b1 as i32 - b2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC_COLORS
+        .iter()
+        .min_by_key(|&&(_, basic)| distance(rgb, basic))
+        .unwrap()
+        .0
+}
+
+// Both the nearest color cube entry and the nearest grey ramp entry are worked out and whichever
+// is closer wins. The basic 16 colors are skipped since terminals often theme them.
+fn nearest_ansi256(rgb: (u8, u8, u8)) -> u8 {
+    let cube_index = |v: u8| {
+        (0..6)
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - v as i32).abs())
+            .unwrap()
+    };
+    let (ri, gi, bi) = (cube_index(rgb.0), cube_index(rgb.1), cube_index(rgb.2));
+    let cube = (16 + 36 * ri + 6 * gi + bi) as u8;
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let grey = 232 + ((average.saturating_sub(3) / 10).min(23)) as u8;
+    if distance(rgb, ansi256_rgb(grey)) < distance(rgb, ansi256_rgb(cube)) {
+        grey
+    } else {
+        cube
+    }
+}
+
+fn ansi256_rgb(value: u8) -> (u8, u8, u8) {
+    match value {
+        0..=15 => BASIC_COLORS[value as usize].1,
+        16..=231 => {
+            let value = value as usize - 16;
+            (
+                CUBE_LEVELS[value / 36],
+                CUBE_LEVELS[(value / 6) % 6],
+                CUBE_LEVELS[value % 6]
+            )
+        }
+        _ => {
+            let level = 8 + (value - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+// Look up the number of colors in the compiled terminfo entry for `term`. Only the header and
+// numbers section are read; see term(5) for the layout.
+fn terminfo_colors(term: &str) -> Option<u32> {
+    let first = term.chars().next()?;
+    let mut dirs = Vec::new();
+    if let Some(dir) = env::var_os("TERMINFO") {
+        dirs.push(PathBuf::from(dir));
+    }
+    if let Some(home) = env::var_os("HOME") {
+        dirs.push(PathBuf::from(home).join(".terminfo"));
+    }
+    if let Ok(terminfo_dirs) = env::var("TERMINFO_DIRS") {
+        dirs.extend(
+            terminfo_dirs
+                .split(':')
+                .filter(|d| !d.is_empty())
+                .map(PathBuf::from)
+        );
+    }
+    for dir in &["/etc/terminfo", "/lib/terminfo", "/usr/share/terminfo"] {
+        dirs.push(PathBuf::from(dir));
+    }
+    let entry = dirs.iter().find_map(|dir| {
+        // Most systems use the first letter as the subdirectory, macOS uses its hex value.
+        read(dir.join(first.to_string()).join(term))
+            .or_else(|_| read(dir.join(format!("{:x}", first as u32)).join(term)))
+            .ok()
+    })?;
+    let short = |at: usize| -> Option<i32> {
+        Some(i16::from_le_bytes([*entry.get(at)?, *entry.get(at + 1)?]) as i32)
+    };
+    let number_size = match short(0)? {
+        0o432 => 2,
+        0o1036 => 4,
+        _ => return None
+    };
+    let (names_size, bools_count, numbers_count) = (short(2)?, short(4)?, short(6)?);
+    if numbers_count <= TERMINFO_COLORS as i32 {
+        return None;
+    }
+    let mut numbers_start = 12 + names_size as usize + bools_count as usize;
+    numbers_start += numbers_start % 2;
+    let at = numbers_start + TERMINFO_COLORS * number_size;
+    let colors = if number_size == 2 {
+        short(at)?
+    } else {
+        i32::from_le_bytes([
+            *entry.get(at)?,
+            *entry.get(at + 1)?,
+            *entry.get(at + 2)?,
+            *entry.get(at + 3)?
+        ])
+    };
+    if colors > 0 {
+        Some(colors as u32)
+    } else {
+        None
+    }
+}
+
+// Pure colors should land on the matching palette entries.
+#[test]
+fn test_quantize() {
+    let red = Color::Rgb { r: 255, g: 0, b: 0 };
+    assert_eq!(ColorSupport::TrueColor.quantize(red), red);
+    assert_eq!(ColorSupport::Ansi256.quantize(red), Color::AnsiValue(196));
+    assert_eq!(ColorSupport::Ansi16.quantize(red), Color::Red);
+    let grey = Color::Rgb {
+        r: 128,
+        g: 128,
+        b: 128
+    };
+    assert_eq!(ColorSupport::Ansi256.quantize(grey), Color::AnsiValue(244));
+    assert_eq!(
+        ColorSupport::Ansi16.quantize(Color::AnsiValue(196)),
+        Color::Red
+    );
+}
+
+#[test]
+fn test_dim() {
+    assert_eq!(
+        dim(
+            Color::Rgb {
+                r: 200,
+                g: 100,
+                b: 0
+            },
+            40
+        ),
+        Color::Rgb { r: 80, g: 40, b: 0 }
+    );
+    assert_eq!(dim(Color::Red, 40), Color::Rgb { r: 102, g: 0, b: 0 });
+    assert_eq!(
+        dim(Color::AnsiValue(196), 100),
+        Color::Rgb { r: 255, g: 0, b: 0 }
+    );
+}
@@ -0,0 +1,42 @@
+use crossterm::{Output, RawScreen, Show};
+use std::backtrace::Backtrace;
+use std::fs::{create_dir_all, File};
+use std::io::{stdout, Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::panic;
+use std::path::PathBuf;
+
+use crate::paths;
+
+const CRASH_FILE: &str = "crash.log";
+// `AlternateScreen` only leaves when it's dropped, which is after the hook has run.
+const LEAVE_ALTERNATE_SCREEN: &str = "\x1b[?1049l";
+
+// Put the terminal back the way it was before saying what went wrong, since the message would
+// otherwise be printed to the game screen and wiped along with it. The full report goes to a file
+// so that it can be attached to a bug report.
+pub fn install_panic_hook() {
+    panic::set_hook(Box::new(|info| {
+        let _ = RawScreen::disable_raw_mode();
+        let mut out = stdout();
+        let _ = execute!(out, Output(LEAVE_ALTERNATE_SCREEN.to_string()), Show);
+        let report = format!("{}\n\n{}", info, Backtrace::force_capture());
+        error!("{}", report);
+        match write_report(&report) {
+            Ok(path) => println!(
+                "tui_tetris crashed: {}\nThe full report was written to {}.",
+                info,
+                path.display()
+            ),
+            Err(_) => println!("tui_tetris crashed: {}", report)
+        }
+    }));
+}
+
+fn write_report(report: &str) -> IoResult<PathBuf> {
+    let dir =
+        paths::data_dir().ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))?;
+    create_dir_all(&dir)?;
+    let path = dir.join(CRASH_FILE);
+    File::create(&path)?.write_all(report.as_bytes())?;
+    Ok(path)
+}
@@ -0,0 +1,288 @@
+#[cfg(any(feature = "discord", test))]
+use crate::serde::Serialize;
+#[cfg(any(feature = "discord", test))]
+use crate::serde_json;
+#[cfg(feature = "discord")]
+use std::env;
+#[cfg(all(feature = "discord", windows))]
+use std::fs::OpenOptions;
+#[cfg(feature = "discord")]
+use std::io::{ErrorKind, Read, Result as IoResult, Write};
+#[cfg(all(feature = "discord", unix))]
+use std::os::unix::net::UnixStream;
+#[cfg(feature = "discord")]
+use std::path::PathBuf;
+#[cfg(feature = "discord")]
+use std::process;
+#[cfg(feature = "discord")]
+use std::sync::mpsc::{channel, Sender};
+#[cfg(feature = "discord")]
+use std::thread;
+use std::time::Instant;
+#[cfg(feature = "discord")]
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tui_tetris::gameboard::Game;
+
+#[cfg(any(feature = "discord", test))]
+use crate::locale::fill;
+use crate::locale::Strings;
+#[cfg(any(feature = "discord", test))]
+use crate::session::Splits;
+
+// Discord's local RPC takes frames of an opcode and a length, both little endian, followed by that
+// much JSON. The first frame is a handshake, and everything after is a command.
+#[cfg(feature = "discord")]
+const HANDSHAKE: u32 = 0;
+#[cfg(any(feature = "discord", test))]
+const FRAME: u32 = 1;
+// Discord only takes a few activity updates every 20 seconds, so there's no point in sending more.
+#[cfg(feature = "discord")]
+const UPDATE_INTERVAL: Duration = Duration::from_secs(15);
+// How long to wait for Discord to answer before giving up on it. Only the thread talking to it
+// waits.
+#[cfg(feature = "discord")]
+const REPLY_TIMEOUT: Duration = Duration::from_secs(1);
+// Discord listens on the first of these that's free.
+#[cfg(feature = "discord")]
+const PIPES: usize = 10;
+
+#[cfg(feature = "discord")]
+trait Pipe: Read + Write {}
+
+#[cfg(feature = "discord")]
+impl<T: Read + Write> Pipe for T {}
+
+#[cfg(feature = "discord")]
+#[derive(Serialize)]
+struct Handshake {
+    v: u8,
+    client_id: String
+}
+
+#[cfg(feature = "discord")]
+#[derive(Serialize)]
+struct Command<'a> {
+    cmd: &'static str,
+    args: CommandArgs<'a>,
+    nonce: String
+}
+
+#[cfg(feature = "discord")]
+#[derive(Serialize)]
+struct CommandArgs<'a> {
+    pid: u32,
+    activity: &'a Activity
+}
+
+// The kind of game as the title, with the level and score under it and the time since it started.
+#[cfg(any(feature = "discord", test))]
+#[derive(Clone, PartialEq, Serialize)]
+struct Activity {
+    details: String,
+    state: String,
+    timestamps: Timestamps
+}
+
+#[cfg(any(feature = "discord", test))]
+#[derive(Clone, PartialEq, Serialize)]
+struct Timestamps {
+    start: u64
+}
+
+#[cfg(any(feature = "discord", test))]
+impl Activity {
+    fn of(game: &Game, started: u64, text: &Strings) -> Self {
+        let course = Splits::course(game);
+        let mut chars = course.chars();
+        let details = chars.next().map_or(String::new(), |first| {
+            first.to_uppercase().chain(chars).collect()
+        });
+        Activity {
+            details,
+            state: fill(text.discord_state, &[&game.level, &game.score]),
+            timestamps: Timestamps { start: started }
+        }
+    }
+}
+
+// The game shown as the player's activity in Discord, from the `discord_app_id` setting. Without
+// the `discord` feature, when Discord isn't running, or once anything goes wrong talking to it,
+// this does nothing.
+pub struct Presence {
+    // Where activities go to be sent, until the thread talking to Discord gives up.
+    #[cfg(feature = "discord")]
+    activities: Option<Sender<Activity>>,
+    // What was sent last and when, so that it's only sent again once it's changed.
+    #[cfg(feature = "discord")]
+    last: Option<(Activity, Instant)>,
+    // When the game started, in seconds since the epoch, for Discord to count up from.
+    #[cfg(feature = "discord")]
+    started: u64
+}
+
+impl Presence {
+    #[cfg(feature = "discord")]
+    pub fn connect(app_id: u64) -> Self {
+        Presence {
+            activities: Some(talk_on(app_id)),
+            last: None,
+            started: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs())
+        }
+    }
+
+    #[cfg(not(feature = "discord"))]
+    pub fn connect(_app_id: u64) -> Self {
+        Presence {}
+    }
+
+    #[cfg(feature = "discord")]
+    pub fn update(&mut self, game: &Game, text: &Strings, now: Instant) {
+        let activities = match self.activities {
+            Some(ref activities) => activities,
+            None => return
+        };
+        let activity = Activity::of(game, self.started, text);
+        let due = match self.last {
+            Some((ref last, at)) => *last != activity && now.duration_since(at) >= UPDATE_INTERVAL,
+            None => true
+        };
+        if !due {
+            return;
+        }
+        if activities.send(activity.clone()).is_ok() {
+            self.last = Some((activity, now));
+        } else {
+            self.activities = None;
+        }
+    }
+
+    #[cfg(not(feature = "discord"))]
+    pub fn update(&mut self, _game: &Game, _text: &Strings, _now: Instant) {}
+}
+
+// Talk to Discord on a thread of its own, so that one that's slow to answer or not there at all
+// never holds up the game. The thread gives up, and so drops the receiving end, once anything goes
+// wrong.
+#[cfg(feature = "discord")]
+fn talk_on(app_id: u64) -> Sender<Activity> {
+    let (sender, activities) = channel::<Activity>();
+    thread::spawn(move || {
+        let pipe = open_pipe().and_then(|mut pipe| {
+            let handshake = Handshake {
+                v: 1,
+                client_id: app_id.to_string()
+            };
+            write_frame(&mut pipe, HANDSHAKE, &handshake)?;
+            read_frame(&mut pipe)?;
+            Ok(pipe)
+        });
+        let mut pipe = match pipe {
+            Ok(pipe) => pipe,
+            Err(e) => {
+                info!("Not showing activity in Discord: {}", e);
+                return;
+            }
+        };
+        // The nonce numbers each command.
+        for (sent, activity) in activities.iter().enumerate() {
+            let command = Command {
+                cmd: "SET_ACTIVITY",
+                args: CommandArgs {
+                    pid: process::id(),
+                    activity: &activity
+                },
+                nonce: (sent + 1).to_string()
+            };
+            if let Err(e) =
+                write_frame(&mut pipe, FRAME, &command).and_then(|()| read_frame(&mut pipe))
+            {
+                warn!("Stopped showing activity in Discord: {}", e);
+                return;
+            }
+        }
+    });
+    sender
+}
+
+#[cfg(any(feature = "discord", test))]
+fn encode_frame<T: Serialize>(opcode: u32, payload: &T) -> Vec<u8> {
+    let payload = serde_json::to_vec(payload).unwrap_or_default();
+    let mut frame = Vec::with_capacity(payload.len() + 8);
+    frame.extend_from_slice(&opcode.to_le_bytes());
+    frame.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    frame.extend(payload);
+    frame
+}
+
+#[cfg(feature = "discord")]
+fn write_frame<T: Serialize>(pipe: &mut Box<dyn Pipe>, opcode: u32, payload: &T) -> IoResult<()> {
+    pipe.write_all(&encode_frame(opcode, payload))?;
+    pipe.flush()
+}
+
+// Discord answers every frame. What it says doesn't matter, only that it's read so that the two
+// stay in step.
+#[cfg(feature = "discord")]
+fn read_frame(pipe: &mut Box<dyn Pipe>) -> IoResult<()> {
+    let mut header = [0; 8];
+    pipe.read_exact(&mut header)?;
+    let mut length = [0; 4];
+    length.copy_from_slice(&header[4..]);
+    let mut payload = vec![0; u32::from_le_bytes(length) as usize];
+    pipe.read_exact(&mut payload)
+}
+
+// Discord's socket is in the runtime or temporary directory, or inside the sandbox's folder there
+// for the Flatpak and Snap versions.
+#[cfg(all(feature = "discord", unix))]
+fn open_pipe() -> IoResult<Box<dyn Pipe>> {
+    let dir = ["XDG_RUNTIME_DIR", "TMPDIR", "TMP", "TEMP"]
+        .iter()
+        .find_map(env::var_os)
+        .map_or_else(|| PathBuf::from("/tmp"), PathBuf::from);
+    let mut last_error = ErrorKind::NotFound.into();
+    for sandbox in &["", "app/com.discordapp.Discord", "snap.discord"] {
+        for n in 0..PIPES {
+            let path = dir.join(sandbox).join(format!("discord-ipc-{}", n));
+            match UnixStream::connect(&path) {
+                Ok(stream) => {
+                    stream.set_read_timeout(Some(REPLY_TIMEOUT))?;
+                    return Ok(Box::new(stream));
+                }
+                Err(e) => last_error = e
+            }
+        }
+    }
+    Err(last_error)
+}
+
+#[cfg(all(feature = "discord", windows))]
+fn open_pipe() -> IoResult<Box<dyn Pipe>> {
+    let mut last_error = ErrorKind::NotFound.into();
+    for n in 0..PIPES {
+        let path = PathBuf::from(format!(r"\\.\pipe\discord-ipc-{}", n));
+        match OpenOptions::new().read(true).write(true).open(path) {
+            Ok(pipe) => return Ok(Box::new(pipe)),
+            Err(e) => last_error = e
+        }
+    }
+    Err(last_error)
+}
+
+// Frames are the opcode and length as little endian numbers, then the JSON.
+#[test]
+fn test_discord_frame() {
+    use tui_tetris::rules::Rules;
+
+    use crate::locale::SPANISH;
+
+    let game = Game::with_seed(Rules::default(), 0);
+    let activity = Activity::of(&game, 5, &SPANISH);
+    assert_eq!(activity.details, "Modern");
+    assert_eq!(activity.state, "Nivel 0, 0 puntos");
+    let frame = encode_frame(FRAME, &activity.timestamps);
+    assert_eq!(&frame[..8], &[1, 0, 0, 0, 11, 0, 0, 0]);
+    assert_eq!(&frame[8..], b"{\"start\":5}");
+}
@@ -0,0 +1,381 @@
+use crate::gameboard::{Cell, GameBoard};
+use crate::rules::Rules;
+use crate::tetromino::Tetromino;
+
+// Fumen is the board editor most of the Tetris community shares positions with. Its v115 format is
+// a run length encoding of each page's changes from the last, written out in base 64.
+const PREFIX: &str = "v115@";
+const ENCODE_TABLE: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+// Fumen's field is always 10 wide, with 23 rows above the row that garbage rises from.
+pub const FIELD_WIDTH: usize = 10;
+const FIELD_TOP: usize = 23;
+const FIELD_BLOCKS: usize = FIELD_WIDTH * (FIELD_TOP + 1);
+// Fumen's numbers for each kind of square. Garbage is grey.
+const EMPTY: u32 = 0;
+const GARBAGE: u32 = 8;
+const PIECES: [Tetromino; 7] = [
+    Tetromino::I,
+    Tetromino::L,
+    Tetromino::O,
+    Tetromino::Z,
+    Tetromino::T,
+    Tetromino::J,
+    Tetromino::S
+];
+// What page flags and pieces are multiplied by in an action.
+const FLAG_COLORIZE: u32 = 1 << 2;
+const FLAG_COMMENT: u32 = 1 << 3;
+const ACTION_FLAGS_SHIFT: u32 = 8 * 4 * FIELD_BLOCKS as u32;
+// Comments are escaped the way JavaScript's `escape` does it, then packed four characters to five
+// digits, with each character counted from a space.
+const COMMENT_BASE: u32 = 96;
+// Quiz comments give the hold and queue, as in `#Q=[hold](current)next`.
+const QUIZ_PREFIX: &str = "#Q=";
+
+fn piece_number(tetromino: Tetromino) -> u32 {
+    match tetromino {
+        Tetromino::I => 1,
+        Tetromino::L => 2,
+        Tetromino::O => 3,
+        Tetromino::Z => 4,
+        Tetromino::T => 5,
+        Tetromino::J => 6,
+        Tetromino::S => 7
+    }
+}
+
+// The squares of a board from the top left of fumen's field down to the garbage row, which is
+// always empty. Rows the board doesn't have are empty too, and rows above fumen's are left out.
+fn field(board: &GameBoard) -> Vec<u32> {
+    let bottom = board.height as isize - 1;
+    let top = -(board.hidden as isize);
+    let mut field = Vec::with_capacity(FIELD_BLOCKS);
+    for row in (0..FIELD_TOP as isize).rev() {
+        let y = bottom - row;
+        for x in 0..FIELD_WIDTH {
+            field.push(if y < top {
+                EMPTY
+            } else {
+                board
+                    .cell(x, y)
+                    .map_or(EMPTY, |cell| cell.tetromino.map_or(GARBAGE, piece_number))
+            });
+        }
+    }
+    field.extend([EMPTY; FIELD_WIDTH].iter());
+    field
+}
+
+// A piece from its letter, as written in quiz comments.
+pub(crate) fn piece_from_letter(letter: char) -> Option<Tetromino> {
+    PIECES
+        .iter()
+        .cloned()
+        .find(|&tetromino| format!("{:?}", tetromino) == letter.to_string())
+}
+
+fn push_digits(out: &mut Vec<u8>, mut value: u32, digits: usize) {
+    for _ in 0..digits {
+        out.push((value % 64) as u8);
+        value /= 64;
+    }
+}
+
+// Each run of squares that changed the same way is written as the change and its length, in two
+// digits. Returns whether anything changed at all.
+fn push_field(out: &mut Vec<u8>, last: &[u32], field: &[u32]) -> bool {
+    let diffs = last
+        .iter()
+        .zip(field.iter())
+        .map(|(&last, &square)| square + 8 - last)
+        .collect::<Vec<_>>();
+    let mut start = 0;
+    while start < diffs.len() {
+        let length = diffs[start..]
+            .iter()
+            .take_while(|&&diff| diff == diffs[start])
+            .count();
+        push_digits(
+            out,
+            diffs[start] * FIELD_BLOCKS as u32 + length as u32 - 1,
+            2
+        );
+        start += length;
+    }
+    diffs.iter().any(|&diff| diff != 8)
+}
+
+// A fumen with a page for each board, in order, or `None` if the boards aren't 10 wide. Each page
+// is just the board as it was, without a piece being placed on it.
+pub fn encode(boards: &[GameBoard]) -> Option<String> {
+    if boards.iter().any(|board| board.width != FIELD_WIDTH) {
+        return None;
+    }
+    let mut digits = Vec::new();
+    let mut last = vec![EMPTY; FIELD_BLOCKS];
+    // Where the count of pages after an unchanged one that are also unchanged is, so that they
+    // can share it rather than each being written out.
+    let mut repeat: Option<usize> = None;
+    for (page, board) in boards.iter().enumerate() {
+        let field = field(board);
+        let mut page_digits = Vec::with_capacity(4);
+        let changed = push_field(&mut page_digits, &last, &field);
+        match repeat {
+            _ if changed => {
+                digits.extend(page_digits);
+                repeat = None;
+            }
+            Some(count) if digits[count] < 63 => digits[count] += 1,
+            _ => {
+                digits.extend(page_digits);
+                digits.push(0);
+                repeat = Some(digits.len() - 1);
+            }
+        }
+        // No piece, locked so that fumen carries the field over to the next page, with the usual
+        // piece colors turned on from the first page.
+        let flags = if page == 0 { FLAG_COLORIZE } else { 0 };
+        push_digits(&mut digits, flags * ACTION_FLAGS_SHIFT, 3);
+        last = field;
+    }
+    let encoded = digits
+        .into_iter()
+        .map(|digit| ENCODE_TABLE[digit as usize] as char);
+    Some(PREFIX.chars().chain(encoded).collect())
+}
+
+// What the first page of a fumen starts with: its field, bottom row first, and the hold and queue
+// from a quiz comment if it has one.
+#[derive(Clone)]
+pub struct Position {
+    pub rows: Vec<[Option<Cell>; FIELD_WIDTH]>,
+    pub hold: Option<Tetromino>,
+    pub queue: Vec<Tetromino>
+}
+
+impl Position {
+    // Fumen's field is always 10 wide, and whatever's on it has to fit in the visible and hidden
+    // rows.
+    pub fn check(&self, rules: &Rules) -> Result<(), String> {
+        if rules.board_width != FIELD_WIDTH {
+            return Err("Fumen positions can only be played on boards 10 wide.".to_string());
+        }
+        let rows = rules.board_height + rules.hidden_rows;
+        if self.rows.len() > rows {
+            return Err(format!(
+                "The position is {} rows tall, but the board only has {}.",
+                self.rows.len(),
+                rows
+            ));
+        }
+        Ok(())
+    }
+}
+
+// Reads the digits of a fumen in turn.
+struct Digits {
+    digits: Vec<u32>,
+    at: usize
+}
+
+impl Digits {
+    fn poll(&mut self, count: usize) -> Result<u32, String> {
+        if self.at + count > self.digits.len() {
+            return Err("The fumen ends too soon.".to_string());
+        }
+        let value = self.digits[self.at..self.at + count]
+            .iter()
+            .rev()
+            .fold(0, |value, &digit| value * 64 + digit);
+        self.at += count;
+        Ok(value)
+    }
+}
+
+// Undo JavaScript's `escape`, which writes characters as `%XX` or `%uXXXX`.
+fn unescape(escaped: &str) -> String {
+    let mut unescaped = String::with_capacity(escaped.len());
+    let mut rest = escaped;
+    while let Some(i) = rest.find('%') {
+        unescaped.push_str(&rest[..i]);
+        rest = &rest[i + 1..];
+        let (length, hex) = if rest.starts_with('u') {
+            (5, rest.get(1..5))
+        } else {
+            (2, rest.get(..2))
+        };
+        let code = hex
+            .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+            .and_then(char::from_u32);
+        match code {
+            Some(c) => {
+                unescaped.push(c);
+                rest = &rest[length..];
+            }
+            None => unescaped.push('%')
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+fn decode_comment(digits: &mut Digits) -> Result<String, String> {
+    let length = digits.poll(2)? as usize;
+    let mut escaped = String::with_capacity(length);
+    while escaped.len() < length {
+        let mut value = digits.poll(5)?;
+        for _ in 0..4 {
+            escaped.push((b' ' + (value % COMMENT_BASE) as u8) as char);
+            value /= COMMENT_BASE;
+        }
+    }
+    escaped.truncate(length);
+    Ok(unescape(&escaped))
+}
+
+// The hold and queue in a quiz comment, e.g. `#Q=[](T)IOS` for T now and I, O, and S next with
+// nothing held.
+fn parse_quiz(comment: &str) -> Result<(Option<Tetromino>, Vec<Tetromino>), String> {
+    let error = || format!("Couldn't read the quiz comment {}", comment);
+    let quiz = comment[QUIZ_PREFIX.len()..].split(';').next().unwrap_or("");
+    let quiz = quiz.strip_prefix('[').ok_or_else(error)?;
+    let end = quiz.find(']').ok_or_else(error)?;
+    let hold = match quiz[..end].chars().next() {
+        Some(letter) => Some(piece_from_letter(letter).ok_or_else(error)?),
+        None => None
+    };
+    let letters = quiz[end + 1..].chars().filter(|&c| c != '(' && c != ')');
+    let queue = letters
+        .map(piece_from_letter)
+        .collect::<Option<Vec<_>>>()
+        .ok_or_else(error)?;
+    Ok((hold, queue))
+}
+
+// The first page of a fumen, which can be given on its own or as the whole URL of a fumen viewer.
+// Pieces being placed on the page are left out, since they aren't part of the field yet.
+pub fn decode(fumen: &str) -> Result<Position, String> {
+    let start = fumen
+        .find(PREFIX)
+        .ok_or_else(|| "Only v115 fumens can be read.".to_string())?;
+    // Older viewers break the data up with question marks.
+    let digits = fumen[start + PREFIX.len()..]
+        .split('&')
+        .next()
+        .unwrap_or("")
+        .chars()
+        .filter(|&c| c != '?' && !c.is_whitespace())
+        .map(|c| {
+            ENCODE_TABLE
+                .iter()
+                .position(|&digit| digit as char == c)
+                .map(|digit| digit as u32)
+                .ok_or_else(|| format!("'{}' can't be in a fumen.", c))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let mut digits = Digits { digits, at: 0 };
+    let mut field = Vec::with_capacity(FIELD_BLOCKS);
+    while field.len() < FIELD_BLOCKS {
+        let value = digits.poll(2)?;
+        let (diff, length) = (value / FIELD_BLOCKS as u32, value % FIELD_BLOCKS as u32 + 1);
+        if !(8..=8 + GARBAGE).contains(&diff) || field.len() + length as usize > FIELD_BLOCKS {
+            return Err("The fumen's field doesn't make sense.".to_string());
+        }
+        field.extend((0..length).map(|_| diff - 8));
+    }
+    // A page with nothing on it is followed by how many more there are like it.
+    if field.iter().all(|&square| square == EMPTY) {
+        digits.poll(1)?;
+    }
+    let flags = digits.poll(3)? / ACTION_FLAGS_SHIFT;
+    let (hold, queue) = if flags & FLAG_COMMENT != 0 {
+        let comment = decode_comment(&mut digits)?;
+        if comment.starts_with(QUIZ_PREFIX) {
+            parse_quiz(&comment)?
+        } else {
+            (None, Vec::new())
+        }
+    } else {
+        (None, Vec::new())
+    };
+    // The garbage row under the field is left out, and so are empty rows at the top.
+    let mut rows = field[..FIELD_WIDTH * FIELD_TOP]
+        .chunks(FIELD_WIDTH)
+        .rev()
+        .map(|squares| {
+            let mut row = [None; FIELD_WIDTH];
+            for (cell, &square) in row.iter_mut().zip(squares.iter()) {
+                *cell = match square {
+                    EMPTY => None,
+                    GARBAGE => Some(Cell::garbage()),
+                    piece => Some(Cell {
+                        tetromino: Some(PIECES[piece as usize - 1]),
+                        item: None
+                    })
+                };
+            }
+            row
+        })
+        .collect::<Vec<_>>();
+    while rows
+        .last()
+        .is_some_and(|row| row.iter().all(Option::is_none))
+    {
+        rows.pop();
+    }
+    Ok(Position { rows, hold, queue })
+}
+
+// An empty board is a single run of unchanged squares, and empty pages after it only add to the
+// count of repeats.
+#[test]
+fn test_empty_board() {
+    use crate::gameboard::Game;
+    use crate::rules::Rules;
+    use std::slice;
+
+    let board = Game::with_seed(Rules::default(), 0).board;
+    assert_eq!(encode(slice::from_ref(&board)).unwrap(), "v115@vhAAgH");
+    assert_eq!(encode(&[board.clone(), board]).unwrap(), "v115@vhBAgHAAA");
+}
+
+// A flat I piece in the bottom left corner.
+#[test]
+fn test_placed_piece() {
+    use crate::gameboard::{ActivePiece, Cell, Game};
+    use crate::rules::Rules;
+
+    let mut board = Game::with_seed(Rules::default(), 0).board;
+    let piece = ActivePiece {
+        tetromino: Tetromino::I,
+        rotation: 0,
+        x: 0,
+        y: board.height as isize - 2,
+        scale: 1
+    };
+    board.place(
+        &piece,
+        Cell {
+            tetromino: Some(Tetromino::I),
+            item: None
+        }
+    );
+    assert_eq!(encode(&[board]).unwrap(), "v115@bhzhPeAgH");
+}
+
+// The field and queue of a quiz come through, and the queue is dealt before the usual pieces.
+#[test]
+fn test_decode_quiz() {
+    use crate::gameboard::Game;
+
+    let position = decode("v115@bhzhPeAgWWAFLDmClcJSAVDEHBEooRBUoAVBJHBAA").unwrap();
+    assert_eq!(position.rows.len(), 1);
+    assert_eq!(position.hold, None);
+    assert_eq!(position.queue, [Tetromino::T, Tetromino::I, Tetromino::O]);
+    let mut game = Game::with_seed(Rules::default(), 0);
+    game.start_from(position).unwrap();
+    assert_eq!(game.active_piece.tetromino, Tetromino::T);
+    assert_eq!(game.preview[..2], [Tetromino::I, Tetromino::O]);
+    let corner = game.board.get(0, game.board.height - 1).unwrap();
+    assert_eq!(corner.tetromino, Some(Tetromino::I));
+}
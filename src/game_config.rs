@@ -1,20 +1,75 @@
-use crate::crossterm::{Color, KeyEvent};
-use std::collections::HashMap;
+use crate::color::ColorSupport;
+use crate::crossterm::Color;
+use crate::input::{Button, Key, KeyCode, KeyModifiers};
+use crate::locale::{fill, Language, Strings, ENGLISH};
+use crate::paths;
+use crate::toml::{self, value::Table, Value};
+use std::collections::{BTreeMap, HashMap};
 use std::fmt::{self, Display};
-use std::fs::File;
-use std::io::{Result as IoResult, Write};
-use std::ops::{RangeBounds, RangeFrom};
+use std::fs::read_to_string;
+use std::ops::{RangeBounds, RangeFrom, RangeInclusive};
 use std::str::FromStr;
+use tui_tetris::ai::{Personality, Style};
+use tui_tetris::rules::{Handicap, LevelGoal, Mode, Rules};
+use tui_tetris::tetromino::Tetromino;
+use tui_tetris::versus::Targeting;
 
-type Settings<'a> = HashMap<&'a str, (&'a str, usize, &'a str)>;
+// Setting name -> (value, line number, complete line, where the setting came from)
+type Settings<'a> = HashMap<&'a str, (&'a str, usize, &'a str, Source<'a>)>;
 
-const CONFIG_OPTIONS: [&str; 35] = [
+// Where a setting came from. For command line arguments the "line number" is the position of the
+// argument instead.
+#[derive(Copy, Clone)]
+enum Source<'a> {
+    Config,
+    Theme(&'a str),
+    Arguments
+}
+
+pub(crate) const CONFIG_OPTIONS: [&str; 109] = [
     "fps_limiter",
     "board_width",
     "board_height",
+    "hidden_rows",
     "monochrome",
+    "color_support",
+    "theme",
     "cascade",
+    "big_mode",
+    "hold_limit",
+    "irs",
+    "input_conflicts",
+    "entry_delay",
+    "line_clear_delay",
+    "lock_delay",
+    "lock_resets",
+    "soft_drop_lock",
     "const_level",
+    "lines_per_level",
+    "level_goal",
+    "stats_export",
+    "fumen_export",
+    "garbage_hole_repeat",
+    "items",
+    "targeting",
+    "rollback",
+    "input_delay",
+    "bot_delay",
+    "ai_difficulty",
+    "ai_style",
+    "handicap_garbage",
+    "handicap_gravity",
+    "handicap_preview",
+    "handicap_attack",
+    "countdown",
+    "game_speed",
+    "sound",
+    "accessibility_cues",
+    "leaderboard_url",
+    "leaderboard_name",
+    "mirror_file",
+    "discord_app_id",
+    "language",
     "ghost_tetromino_character",
     "ghost_tetromino_color",
     "top_border_character",
@@ -28,15 +83,537 @@ const CONFIG_OPTIONS: [&str; 35] = [
     "border_color",
     "block_character",
     "block_size",
+    "renderer",
+    "grid",
+    "grid_character",
+    "grid_color",
+    "effects",
+    "danger_height",
+    "danger_pulse",
+    "splits",
+    "ghost_race",
+    "terminal_title",
+    "background_art",
+    "mode",
+    "move_left",
+    "move_right",
+    "rotate_clockwise",
+    "rotate_anticlockwise",
+    "soft_drop",
+    "hard_drop",
+    "sonic_drop",
+    "hold",
+    "use_item",
+    "switch_target",
+    "pause",
+    "quit",
+    "toggle_stats",
+    "toggle_hint",
+    "toggle_debug",
+    "toggle_frame_step",
+    "frame_step",
+    "rewind",
+    "undo",
+    "screenshot",
+    "menu_up",
+    "menu_down",
+    "menu_select",
+    "menu_back",
+    "key_bindings",
+    "background_color",
+    "palette",
+    "i_color",
+    "j_color",
+    "l_color",
+    "s_color",
+    "z_color",
+    "t_color",
+    "o_color",
+    "i_character",
+    "j_character",
+    "l_character",
+    "s_character",
+    "z_character",
+    "t_character",
+    "o_character"
+];
+
+// Every setting name, listed after an unknown one.
+const SETTING_NAMES: &str = "\
+fps_limiter, board_width, board_height, hidden_rows, monochrome, color_support, theme, cascade,\n\
+big_mode, hold_limit, irs, input_conflicts, entry_delay, line_clear_delay, lock_delay,\n\
+lock_resets, soft_drop_lock, const_level, lines_per_level, level_goal, stats_export,\n\
+fumen_export, garbage_hole_repeat, items, targeting, rollback, input_delay, bot_delay,\n\
+ai_difficulty, ai_style, handicap_garbage, handicap_gravity, handicap_preview, handicap_attack,\n\
+countdown, game_speed, sound, accessibility_cues, leaderboard_url, leaderboard_name,\n\
+mirror_file, discord_app_id, language, ghost_tetromino_character, ghost_tetromino_color,\n\
+top_border_character, left_border_character, bottom_border_character, right_border_character,\n\
+tl_corner_character, bl_corner_character, br_corner_character, tr_corner_character,\n\
+border_color, block_character, block_size, renderer, grid, grid_character, grid_color, effects,\n\
+danger_height, danger_pulse, splits, ghost_race, terminal_title, background_art, mode,\n\
+move_left, move_right, rotate_clockwise, rotate_anticlockwise, soft_drop, hard_drop, sonic_drop,\n\
+hold, use_item, switch_target, pause, quit, toggle_stats, toggle_hint, toggle_debug,\n\
+toggle_frame_step, frame_step, rewind, undo, screenshot, menu_up, menu_down, menu_select,\n\
+menu_back, key_bindings, background_color, palette, i_color, j_color, l_color, s_color, z_color,\n\
+t_color, o_color, i_character, j_character, l_character, s_character, z_character, t_character,\n\
+o_character";
+
+// Names that settings used to have, which still work but are warned about, as (old name, setting
+// name).
+const SETTING_ALIASES: [(&str, &str); 4] = [
+    ("left", "move_left"),
+    ("right", "move_right"),
+    ("rot_cw", "rotate_clockwise"),
+    ("rot_acw", "rotate_anticlockwise")
+];
+
+// What each setting does and the values it accepts, for the comments in a new config.
+const SETTING_HELP: [(&str, &str); 109] = [
+    (
+        "fps_limiter",
+        "Frames drawn per second, at least 30, or none for no limit."
+    ),
+    (
+        "board_width",
+        "Width of the board in squares, at least 1, or auto to fill the terminal."
+    ),
+    (
+        "board_height",
+        "Height of the board in squares, at least 1, or auto to fill the terminal."
+    ),
+    (
+        "hidden_rows",
+        "Rows above the board that pieces spawn in, from 0 to 4."
+    ),
+    ("monochrome", "A color to draw everything in, or none."),
+    (
+        "color_support",
+        "Colors the terminal can show: auto, truecolor, 24bit, 256, or 16."
+    ),
+    (
+        "theme",
+        "Theme in the themes directory to take appearance settings from, or none."
+    ),
+    (
+        "cascade",
+        "Let blocks fall into gaps after lines are cleared."
+    ),
+    (
+        "big_mode",
+        "Play with pieces twice the size, on an even board at least 8 wide."
+    ),
+    ("hold_limit", "Holds allowed per piece, or inf."),
+    (
+        "irs",
+        "Rotate or hold new pieces with keys that are already held when they spawn."
+    ),
+    (
+        "input_conflicts",
+        "When left and right, or both rotations, are pressed on the same frame: latest to do \
+         the last one pressed, or cancel to do neither."
+    ),
+    (
+        "entry_delay",
+        "Milliseconds before each new piece appears, from 0 to 1000, or none for the mode's own."
+    ),
+    (
+        "line_clear_delay",
+        "Frames the game stops for after clearing lines, from 0 to 120."
+    ),
+    (
+        "lock_delay",
+        "Milliseconds a piece can rest before locking, from 0 to 5000, or none for the mode's own."
+    ),
+    (
+        "lock_resets",
+        "Times moving or rotating a resting piece restarts its lock delay, or inf."
+    ),
+    (
+        "soft_drop_lock",
+        "Lock a resting piece as soon as it's soft dropped, as in TGM, or auto for the mode's own."
+    ),
+    (
+        "const_level",
+        "A level to stay on, at least 1, or none to level up as usual."
+    ),
+    (
+        "lines_per_level",
+        "Lines to clear for each level with a fixed level goal, at least 1."
+    ),
+    (
+        "level_goal",
+        "fixed for the same number of lines every level, or variable for more each level."
+    ),
+    (
+        "stats_export",
+        "Save each game's stats to the data directory: none, json, or csv."
+    ),
+    (
+        "fumen_export",
+        "Save each game as a fumen in the data directory."
+    ),
+    (
+        "garbage_hole_repeat",
+        "Percent chance that a garbage hole lines up with the last one, from 0 to 100."
+    ),
+    (
+        "items",
+        "Casual item mode: clearing a line with a marked block picks up an item to use."
+    ),
+    (
+        "targeting",
+        "Who attacks go to with more than one opponent: random, leader for the highest score, \
+         danger for the highest stack, or even to split them between everyone."
+    ),
+    (
+        "rollback",
+        "Host network games with rollback netcode, which plays on without waiting for the other \
+         players' inputs and corrects itself when they arrive."
+    ),
+    (
+        "input_delay",
+        "Ticks your inputs wait before they count in a game with rollback netcode, from 0 to 10. \
+         A little delay means fewer corrections."
+    ),
+    (
+        "bot_delay",
+        "Milliseconds a bot opponent waits between pieces."
+    ),
+    (
+        "ai_difficulty",
+        "How fast and how well the built-in AI plays: easy, normal, hard, or expert."
+    ),
+    (
+        "ai_style",
+        "What the built-in AI goes for: balanced, downstack to clear everything it can, or \
+         tetris to clear only with Tetrises."
+    ),
+    (
+        "handicap_garbage",
+        "Rows of garbage under your stack at the start of a versus game, from 0 to 10."
+    ),
+    (
+        "handicap_gravity",
+        "Levels added to the one your gravity goes by in a versus game, from -30 to 30."
+    ),
+    (
+        "handicap_preview",
+        "How many preview pieces you see in a versus game, or none to see them all."
+    ),
+    (
+        "handicap_attack",
+        "Percent of your attack that gets sent in a versus game, from 0 to 400."
+    ),
+    (
+        "countdown",
+        "Seconds counted down before play starts, from 0 to 9."
+    ),
+    (
+        "game_speed",
+        "Percent of normal speed that practice games run at, from 10 to 400."
+    ),
+    ("sound", "Play sound cues, if built with sound."),
+    (
+        "accessibility_cues",
+        "Announce each new piece and a high stack with a tone, or the terminal bell."
+    ),
+    (
+        "leaderboard_url",
+        "Online leaderboard to send sprint and ultra results to and show from the pause menu, if built with online, or none."
+    ),
+    ("leaderboard_name", "Name results are sent to the leaderboard under."),
+    (
+        "mirror_file",
+        "File or FIFO the score, lines, time and board are written to every second, or none."
+    ),
+    (
+        "discord_app_id",
+        "ID of the Discord application to show the game as your activity under, if built with discord, or none."
+    ),
+    (
+        "language",
+        "Language of the menus, side panel and messages: english or spanish. Messages from \
+         before the config is read follow the LANG environment variable instead."
+    ),
+    (
+        "ghost_tetromino_character",
+        "Character the ghost piece is drawn with, or none to hide it."
+    ),
+    (
+        "ghost_tetromino_color",
+        "Color of the ghost piece, or auto for a dim copy of the active piece's."
+    ),
+    (
+        "top_border_character",
+        "Character for the top of the border."
+    ),
+    (
+        "left_border_character",
+        "Character for the left of the border."
+    ),
+    (
+        "bottom_border_character",
+        "Character for the bottom of the border."
+    ),
+    (
+        "right_border_character",
+        "Character for the right of the border."
+    ),
+    (
+        "tl_corner_character",
+        "Character for the top left corner of the border."
+    ),
+    (
+        "bl_corner_character",
+        "Character for the bottom left corner of the border."
+    ),
+    (
+        "br_corner_character",
+        "Character for the bottom right corner of the border."
+    ),
+    (
+        "tr_corner_character",
+        "Character for the top right corner of the border."
+    ),
+    ("border_color", "Color of the border and text."),
+    ("block_character", "Character pieces are drawn with."),
+    (
+        "block_size",
+        "Characters each square takes up across and down, at least 1."
+    ),
+    (
+        "renderer",
+        "blocks, or braille to fit big boards in small terminals."
+    ),
+    (
+        "grid",
+        "Pattern drawn in empty squares: none, dots, or checkerboard."
+    ),
+    ("grid_character", "Character the grid is drawn with."),
+    ("grid_color", "Color of the grid."),
+    (
+        "effects",
+        "Shake the board on Tetrises and flash the border on T-spins."
+    ),
+    (
+        "danger_height",
+        "Percent of the board's height past which the border turns red, or 0 for never."
+    ),
+    ("danger_pulse", "Pulse the red border."),
+    (
+        "splits",
+        "Show a timer with splits every 10 lines or every level, compared against your best."
+    ),
+    (
+        "ghost_race",
+        "Replay your best run beside the board in challenges played against the clock, like sprint."
+    ),
+    (
+        "terminal_title",
+        "Show the mode and score in the terminal's title, and notify on new personal bests."
+    ),
+    (
+        "background_art",
+        "ANSI art file (.ans) drawn dimmed behind and around the board, or none."
+    ),
+    (
+        "mode",
+        "Rules to play by: classic (c), modern (m), or master (20g)."
+    ),
+    ("move_left", "Keys that move the piece left."),
+    ("move_right", "Keys that move the piece right."),
+    ("rotate_clockwise", "Keys that rotate the piece clockwise."),
+    (
+        "rotate_anticlockwise",
+        "Keys that rotate the piece anticlockwise."
+    ),
+    ("soft_drop", "Keys that move the piece down faster."),
+    ("hard_drop", "Keys that drop and lock the piece, or none."),
+    (
+        "sonic_drop",
+        "Keys that drop the piece without locking it, or none."
+    ),
+    ("hold", "Keys that hold the piece, or none."),
+    (
+        "use_item",
+        "Keys that use the item picked up in a game with items, or none."
+    ),
+    (
+        "switch_target",
+        "Keys that pick the next opponent to attack, and then go back to targeting, or none."
+    ),
+    ("pause", "Keys that pause the game."),
+    ("quit", "Keys that quit."),
+    (
+        "toggle_stats",
+        "Keys that show or hide live stats, or none."
+    ),
+    (
+        "toggle_hint",
+        "Keys that show or hide the suggested placement, or none."
+    ),
+    (
+        "toggle_debug",
+        "Keys that show or hide debug info, or none."
+    ),
+    (
+        "toggle_frame_step",
+        "Keys that turn frame step mode on or off in practice games, or none."
+    ),
+    (
+        "frame_step",
+        "Keys that run one tick in frame step mode, or none."
+    ),
+    (
+        "rewind",
+        "Keys that go back five seconds in practice games, or none."
+    ),
+    (
+        "undo",
+        "Keys that take back the last placement in practice games, or none."
+    ),
+    (
+        "screenshot",
+        "Keys that save the screen as .ans and .txt files in the data directory, or none."
+    ),
+    ("menu_up", "Keys that move up in menus."),
+    ("menu_down", "Keys that move down in menus."),
+    ("menu_select", "Keys that pick a menu item."),
+    ("menu_back", "Keys that leave a menu."),
+    (
+        "key_bindings",
+        "Which [bindings.<name>] section's keys to use, or default for the ones above."
+    ),
+    ("background_color", "Color behind the board."),
+    (
+        "palette",
+        "Color blind piece colors: none, deuteranopia, protanopia, tritanopia, or high_contrast."
+    ),
+    ("i_color", "Color of the I piece."),
+    ("j_color", "Color of the J piece."),
+    ("l_color", "Color of the L piece."),
+    ("s_color", "Color of the S piece."),
+    ("z_color", "Color of the Z piece."),
+    ("t_color", "Color of the T piece."),
+    ("o_color", "Color of the O piece."),
+    (
+        "i_character",
+        "Character the I piece is drawn with, or none for the block character."
+    ),
+    (
+        "j_character",
+        "Character the J piece is drawn with, or none for the block character."
+    ),
+    (
+        "l_character",
+        "Character the L piece is drawn with, or none for the block character."
+    ),
+    (
+        "s_character",
+        "Character the S piece is drawn with, or none for the block character."
+    ),
+    (
+        "z_character",
+        "Character the Z piece is drawn with, or none for the block character."
+    ),
+    (
+        "t_character",
+        "Character the T piece is drawn with, or none for the block character."
+    ),
+    (
+        "o_character",
+        "Character the O piece is drawn with, or none for the block character."
+    )
+];
+
+// Comes first in a new config, for the formats shared by many settings.
+const COMMENTED_HEADER: &str = "\
+# tui_tetris config. Each setting is listed with what it does and its default.
+# Colors are rgb r,g,b or ansi n. Keys are comma separated names like a, space, up, f5, or ctrl+z.
+# Controller buttons are pad_ and a name like south, lb, start, or up, with the gamepad feature.
+";
+
+// Sections of tui_tetris.toml and the settings that go in each. Every setting belongs to exactly
+// one section.
+const TOML_GAME_OPTIONS: [&str; 40] = [
+    "fps_limiter",
+    "board_width",
+    "board_height",
+    "hidden_rows",
     "mode",
+    "cascade",
+    "big_mode",
+    "hold_limit",
+    "irs",
+    "input_conflicts",
+    "entry_delay",
+    "line_clear_delay",
+    "lock_delay",
+    "lock_resets",
+    "soft_drop_lock",
+    "const_level",
+    "lines_per_level",
+    "level_goal",
+    "stats_export",
+    "fumen_export",
+    "garbage_hole_repeat",
+    "items",
+    "targeting",
+    "rollback",
+    "input_delay",
+    "bot_delay",
+    "ai_difficulty",
+    "ai_style",
+    "handicap_garbage",
+    "handicap_gravity",
+    "handicap_preview",
+    "handicap_attack",
+    "countdown",
+    "game_speed",
+    "sound",
+    "accessibility_cues",
+    "leaderboard_url",
+    "leaderboard_name",
+    "mirror_file",
+    "discord_app_id"
+];
+
+const TOML_KEYS_OPTIONS: [&str; 25] = [
     "move_left",
     "move_right",
     "rotate_clockwise",
     "rotate_anticlockwise",
     "soft_drop",
     "hard_drop",
+    "sonic_drop",
     "hold",
+    "use_item",
+    "switch_target",
+    "pause",
+    "quit",
+    "toggle_stats",
+    "toggle_hint",
+    "toggle_debug",
+    "toggle_frame_step",
+    "frame_step",
+    "rewind",
+    "undo",
+    "screenshot",
+    "menu_up",
+    "menu_down",
+    "menu_select",
+    "menu_back",
+    "key_bindings"
+];
+
+const TOML_COLORS_OPTIONS: [&str; 14] = [
+    "color_support",
+    "monochrome",
+    "palette",
+    "ghost_tetromino_color",
+    "border_color",
     "background_color",
+    "grid_color",
     "i_color",
     "j_color",
     "l_color",
@@ -46,34 +623,264 @@ const CONFIG_OPTIONS: [&str; 35] = [
     "o_color"
 ];
 
-const VALID_SETTINGS: &'static str = "Valid settings:\n\
-fps_limiter, board_width, board_height, monochrome, cascade, const_level, ghost_tetromino_character,\n\
-ghost_tetromino_color, top_border_character, left_border_character, bottom_border_character,\n\
-right_border_character, tl_corner_character, bl_corner_character, br_corner_character,\n\
-tr_corner_character, border_color, block_character, block_size, mode, move_left, move_right,\n\
-rotate_clockwise, rotate_anticlockwise, soft_drop, hard_drop, hold, background_color, i_color,\n\
-j_color, l_color, s_color, z_color, t_color, o_color";
+const TOML_APPEARANCE_OPTIONS: [&str; 30] = [
+    "theme",
+    "language",
+    "renderer",
+    "grid",
+    "grid_character",
+    "effects",
+    "danger_height",
+    "danger_pulse",
+    "splits",
+    "ghost_race",
+    "terminal_title",
+    "background_art",
+    "block_size",
+    "block_character",
+    "ghost_tetromino_character",
+    "top_border_character",
+    "tl_corner_character",
+    "left_border_character",
+    "bl_corner_character",
+    "bottom_border_character",
+    "br_corner_character",
+    "right_border_character",
+    "tr_corner_character",
+    "i_character",
+    "j_character",
+    "l_character",
+    "s_character",
+    "z_character",
+    "t_character",
+    "o_character"
+];
+
+// Settings written out as TOML integers and booleans rather than strings. "none" is still written
+// as a string for the optional ones.
+const TOML_INTEGER_SETTINGS: [&str; 23] = [
+    "fps_limiter",
+    "board_width",
+    "board_height",
+    "hidden_rows",
+    "hold_limit",
+    "entry_delay",
+    "line_clear_delay",
+    "lock_delay",
+    "lock_resets",
+    "const_level",
+    "lines_per_level",
+    "garbage_hole_repeat",
+    "input_delay",
+    "bot_delay",
+    "handicap_garbage",
+    "handicap_gravity",
+    "handicap_preview",
+    "handicap_attack",
+    "countdown",
+    "game_speed",
+    "block_size",
+    "danger_height",
+    "discord_app_id"
+];
+const TOML_BOOLEAN_SETTINGS: [&str; 14] = [
+    "cascade",
+    "big_mode",
+    "irs",
+    "soft_drop_lock",
+    "fumen_export",
+    "items",
+    "rollback",
+    "sound",
+    "accessibility_cues",
+    "effects",
+    "danger_pulse",
+    "splits",
+    "ghost_race",
+    "terminal_title"
+];
+
+// Only appearance settings can be put in a theme file.
+const THEME_OPTIONS: [&str; 32] = [
+    "ghost_tetromino_character",
+    "ghost_tetromino_color",
+    "monochrome",
+    "border_color",
+    "top_border_character",
+    "tl_corner_character",
+    "left_border_character",
+    "bl_corner_character",
+    "bottom_border_character",
+    "br_corner_character",
+    "right_border_character",
+    "tr_corner_character",
+    "background_color",
+    "block_character",
+    "grid",
+    "grid_character",
+    "grid_color",
+    "palette",
+    "i_color",
+    "j_color",
+    "l_color",
+    "s_color",
+    "z_color",
+    "t_color",
+    "o_color",
+    "i_character",
+    "j_character",
+    "l_character",
+    "s_character",
+    "z_character",
+    "t_character",
+    "o_character"
+];
+
+// Every setting a theme can have, listed after an unknown one.
+const THEME_SETTING_NAMES: &str = "\
+ghost_tetromino_character, ghost_tetromino_color, monochrome, border_color, top_border_character,\n\
+tl_corner_character, left_border_character, bl_corner_character, bottom_border_character,\n\
+br_corner_character, right_border_character, tr_corner_character, background_color,\n\
+block_character, grid, grid_character, grid_color, palette, i_color, j_color, l_color, s_color,\n\
+z_color, t_color, o_color, i_character, j_character, l_character, s_character, z_character, t_character, o_character";
+
+// Theme files are looked up by name in this subdirectory of each of the config search directories.
+const THEMES_DIR: &str = "themes";
 
 const D_FPS_LIMITER: Option<u64> = Some(60);
 const D_BOARD_WIDTH: usize = 10;
 const D_BOARD_HEIGHT: usize = 20;
+const D_HIDDEN_ROWS: usize = 2;
+const MAX_HIDDEN_ROWS: usize = 4;
+// Board sizes can be `auto` to fill the terminal instead.
+const AUTO_BOARD_SIZE: &str = "auto";
+// How much room the panel to the right of the board takes up, including the gap before it.
+const PANEL_WIDTH: usize = 17;
 const D_MODE: Mode = Mode::Modern;
-const D_LEFT: KeyEvent = KeyEvent::Left;
-const D_RIGHT: KeyEvent = KeyEvent::Right;
-const D_ROT_CW: KeyEvent = KeyEvent::ShiftLeft;
-const D_ROT_ACW: KeyEvent = KeyEvent::Up;
-const D_SOFT_DROP: KeyEvent = KeyEvent::Down;
-const D_HARD_DROP: Option<KeyEvent> = Some(KeyEvent::Char(' '));
-const D_HOLD: Option<KeyEvent> = Some(KeyEvent::Char('c'));
+const D_LEFT: &[Key] = &[
+    Key::plain(KeyCode::Left),
+    Key::plain(KeyCode::Button(Button::Left))
+];
+const D_RIGHT: &[Key] = &[
+    Key::plain(KeyCode::Right),
+    Key::plain(KeyCode::Button(Button::Right))
+];
+const D_ROT_CW: &[Key] = &[
+    Key::new(KeyCode::Left, KeyModifiers::SHIFT),
+    Key::plain(KeyCode::Button(Button::South))
+];
+const D_ROT_ACW: &[Key] = &[
+    Key::plain(KeyCode::Up),
+    Key::plain(KeyCode::Button(Button::East))
+];
+const D_SOFT_DROP: &[Key] = &[
+    Key::plain(KeyCode::Down),
+    Key::plain(KeyCode::Button(Button::Down))
+];
+const D_HARD_DROP: &[Key] = &[
+    Key::plain(KeyCode::Char(' ')),
+    Key::plain(KeyCode::Button(Button::Up))
+];
+const D_SONIC_DROP: &[Key] = &[];
+const D_HOLD: &[Key] = &[
+    Key::plain(KeyCode::Char('c')),
+    Key::plain(KeyCode::Button(Button::LeftBumper))
+];
+const D_USE_ITEM: &[Key] = &[
+    Key::plain(KeyCode::Char('v')),
+    Key::plain(KeyCode::Button(Button::North))
+];
+const D_SWITCH_TARGET: &[Key] = &[
+    Key::plain(KeyCode::Char('t')),
+    Key::plain(KeyCode::Button(Button::Select))
+];
+const D_PAUSE: &[Key] = &[
+    Key::plain(KeyCode::Esc),
+    Key::plain(KeyCode::Button(Button::Start))
+];
+const D_QUIT: &[Key] = &[Key::plain(KeyCode::Char('q'))];
+const D_TOGGLE_STATS: &[Key] = &[Key::plain(KeyCode::Tab)];
+const D_TOGGLE_HINT: &[Key] = &[Key::plain(KeyCode::Char('h'))];
+const D_TOGGLE_DEBUG: &[Key] = &[Key::plain(KeyCode::F(3))];
+const D_TOGGLE_FRAME_STEP: &[Key] = &[Key::plain(KeyCode::F(4))];
+const D_FRAME_STEP: &[Key] = &[Key::plain(KeyCode::F(5))];
+const D_REWIND: &[Key] = &[Key::plain(KeyCode::Char('r'))];
+const D_UNDO: &[Key] = &[Key::new(KeyCode::Char('z'), KeyModifiers::CONTROL)];
+const D_SCREENSHOT: &[Key] = &[Key::plain(KeyCode::F(12))];
+const D_MENU_UP: &[Key] = &[
+    Key::plain(KeyCode::Up),
+    Key::plain(KeyCode::Char('k')),
+    Key::plain(KeyCode::Button(Button::Up))
+];
+const D_MENU_DOWN: &[Key] = &[
+    Key::plain(KeyCode::Down),
+    Key::plain(KeyCode::Char('j')),
+    Key::plain(KeyCode::Button(Button::Down))
+];
+const D_MENU_SELECT: &[Key] = &[
+    Key::plain(KeyCode::Enter),
+    Key::plain(KeyCode::Button(Button::South))
+];
+const D_MENU_BACK: &[Key] = &[
+    Key::plain(KeyCode::Esc),
+    Key::plain(KeyCode::Button(Button::East))
+];
+const D_KEY_BINDINGS: &str = "default";
 const D_GHOST_TETROMINO_CHARACTER: Option<char> = Some('□');
-const D_GHOST_TETROMINO_COLOR: Option<Color> = Some(Color::Rgb {
+const D_GHOST_TETROMINO_COLOR: Option<GhostColor> = Some(GhostColor::Fixed(Color::Rgb {
     r: 240,
     g: 240,
     b: 240
-});
+}));
 const D_CASCADE: bool = false;
+const D_BIG_MODE: bool = false;
+pub(crate) const BIG_MODE_MIN_WIDTH: usize = 8;
+const D_HOLD_LIMIT: Option<usize> = Some(1);
+const D_IRS: bool = false;
+const D_INPUT_CONFLICTS: InputConflicts = InputConflicts::Latest;
+const D_ENTRY_DELAY: Option<u64> = None;
+const MAX_ENTRY_DELAY: u64 = 1000;
+const D_LINE_CLEAR_DELAY: u32 = 0;
+const MAX_LINE_CLEAR_DELAY: u32 = 120;
+const D_LOCK_DELAY: Option<u64> = None;
+const MAX_LOCK_DELAY: u64 = 5000;
+const D_LOCK_RESETS: Option<usize> = Some(0);
+const D_SOFT_DROP_LOCK: Option<bool> = None;
 const D_CONST_LEVEL: Option<usize> = None;
+const D_LINES_PER_LEVEL: usize = 10;
+const D_LEVEL_GOAL: LevelGoal = LevelGoal::Fixed;
+const D_STATS_EXPORT: Option<StatsExport> = None;
+const D_FUMEN_EXPORT: bool = false;
+const D_GARBAGE_HOLE_REPEAT: usize = 70;
+const D_ITEMS: bool = false;
+const D_TARGETING: Targeting = Targeting::Random;
+const D_ROLLBACK: bool = true;
+const D_INPUT_DELAY: u64 = 2;
+const MAX_INPUT_DELAY: u64 = 10;
+const D_BOT_DELAY: u64 = 1000;
+const D_AI_DIFFICULTY: AiDifficulty = AiDifficulty::Normal;
+const D_AI_STYLE: Style = Style::Balanced;
+const D_HANDICAP_GARBAGE: usize = 0;
+const MAX_HANDICAP_GARBAGE: usize = 10;
+const D_HANDICAP_GRAVITY: isize = 0;
+const MAX_HANDICAP_GRAVITY: isize = 30;
+const D_HANDICAP_PREVIEW: Option<usize> = None;
+const D_HANDICAP_ATTACK: u64 = 100;
+const MAX_HANDICAP_ATTACK: u64 = 400;
+const D_COUNTDOWN: u64 = 3;
+pub(crate) const MAX_COUNTDOWN: u64 = 9;
+const D_GAME_SPEED: u32 = 100;
+const D_SOUND: bool = true;
+const D_ACCESSIBILITY_CUES: bool = false;
+const D_LEADERBOARD_URL: Option<String> = None;
+const D_LEADERBOARD_NAME: &str = "anonymous";
+const MAX_LEADERBOARD_NAME: usize = 16;
+const D_MIRROR_FILE: Option<String> = None;
+const D_DISCORD_APP_ID: Option<u64> = None;
+const D_LANGUAGE: Language = Language::English;
 const D_MONOCHROME: Option<Color> = None;
+const D_COLOR_SUPPORT: Option<ColorSupport> = None;
+const D_THEME: Option<String> = None;
 const D_BORDER_COLOR: Color = Color::Rgb {
     r: 255,
     g: 255,
@@ -90,6 +897,22 @@ const D_TR_CORNER_CHARACTER: char = '╗';
 const D_BACKGROUND_COLOR: Color = Color::Rgb { r: 0, g: 0, b: 0 };
 const D_BLOCK_CHARACTER: char = '■';
 const D_BLOCK_SIZE: usize = 1;
+const D_RENDERER: Renderer = Renderer::Blocks;
+const D_GRID: Option<Grid> = None;
+const D_GRID_CHARACTER: char = '·';
+const D_GRID_COLOR: Color = Color::Rgb {
+    r: 60,
+    g: 60,
+    b: 60
+};
+const D_EFFECTS: bool = true;
+const D_DANGER_HEIGHT: usize = 75;
+const D_DANGER_PULSE: bool = false;
+const D_SPLITS: bool = false;
+const D_GHOST_RACE: bool = false;
+const D_TERMINAL_TITLE: bool = false;
+const D_BACKGROUND_ART: Option<String> = None;
+const D_PALETTE: Option<Palette> = None;
 const D_I_COLOR: Color = Color::Rgb {
     r: 0,
     g: 240,
@@ -113,59 +936,487 @@ const D_O_COLOR: Color = Color::Rgb {
     g: 240,
     b: 0
 };
+const D_I_CHARACTER: Option<char> = None;
+const D_J_CHARACTER: Option<char> = None;
+const D_L_CHARACTER: Option<char> = None;
+const D_S_CHARACTER: Option<char> = None;
+const D_Z_CHARACTER: Option<char> = None;
+const D_T_CHARACTER: Option<char> = None;
+const D_O_CHARACTER: Option<char> = None;
 
+// How the board is drawn. Braille packs 2x4 block-size squares into each character cell, which
+// lets large boards fit in small terminals at the cost of losing the block characters.
 #[derive(Copy, Clone, Eq, PartialEq)]
-pub enum Mode {
-    Classic,
-    Modern
+pub enum Renderer {
+    Blocks,
+    Braille
 }
 
-impl Display for Mode {
+impl Display for Renderer {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
             "{}",
             match self {
-                Mode::Classic => "classic",
-                Mode::Modern => "modern"
+                Renderer::Blocks => "blocks",
+                Renderer::Braille => "braille"
             }
         )
     }
 }
 
-#[derive(Debug)]
-pub enum ParseErrorKind {
-    InvalidLineFormat,
-    UnknownSetting,
-    InvalidValue,
-    DuplicateSetting,
-    FailedParseValue,
-    MissingValue
+// What's done with opposite moves or rotations pressed on the same frame.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum InputConflicts {
+    Latest,
+    Cancel
+}
+
+impl Display for InputConflicts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                InputConflicts::Latest => "latest",
+                InputConflicts::Cancel => "cancel"
+            }
+        )
+    }
+}
+
+// How well the built-in AI plays. Each step up places pieces faster, looks further into the
+// preview, and misdrops less.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum AiDifficulty {
+    Easy,
+    Normal,
+    Hard,
+    Expert
 }
 
-impl Display for ParseErrorKind {
+pub(crate) const AI_DIFFICULTIES: [AiDifficulty; 4] = [
+    AiDifficulty::Easy,
+    AiDifficulty::Normal,
+    AiDifficulty::Hard,
+    AiDifficulty::Expert
+];
+pub(crate) const AI_STYLES: [Style; 3] = [Style::Balanced, Style::Downstack, Style::Tetris];
+
+impl AiDifficulty {
+    // Milliseconds between the AI's pieces.
+    pub fn delay(self) -> u64 {
+        match self {
+            AiDifficulty::Easy => 1500,
+            AiDifficulty::Normal => 1000,
+            AiDifficulty::Hard => 600,
+            AiDifficulty::Expert => 250
+        }
+    }
+
+    pub fn personality(self, style: Style) -> Personality {
+        let (depth, misdrop) = match self {
+            AiDifficulty::Easy => (1, 15),
+            AiDifficulty::Normal => (1, 5),
+            AiDifficulty::Hard => (2, 2),
+            AiDifficulty::Expert => (3, 0)
+        };
+        Personality {
+            depth,
+            misdrop,
+            style
+        }
+    }
+}
+
+impl Display for AiDifficulty {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(
             f,
             "{}",
             match self {
-                ParseErrorKind::InvalidLineFormat => "Invalid line format",
-                ParseErrorKind::UnknownSetting => "Unknown setting",
-                ParseErrorKind::InvalidValue => "Invalid value",
-                ParseErrorKind::DuplicateSetting => "Duplicate setting",
-                ParseErrorKind::FailedParseValue => "Failed to parse value",
-                ParseErrorKind::MissingValue => "Missing value"
+                AiDifficulty::Easy => "easy",
+                AiDifficulty::Normal => "normal",
+                AiDifficulty::Hard => "hard",
+                AiDifficulty::Expert => "expert"
             }
         )
     }
 }
 
+// The ghost piece is either always drawn in one color, or in a dimmed copy of whichever piece is
+// active.
+#[derive(Copy, Clone, PartialEq)]
+pub enum GhostColor {
+    Fixed(Color),
+    Auto
+}
+
+impl Display for GhostColor {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GhostColor::Fixed(color) => write!(f, "{}", color_string(color)),
+            GhostColor::Auto => write!(f, "auto")
+        }
+    }
+}
+
+// Patterns drawn in the board's empty squares to make columns easier to count. `Dots` marks every
+// empty square and `Checkerboard` every other one.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Grid {
+    Dots,
+    Checkerboard
+}
+
+impl Grid {
+    // Whether the empty square at (x, y) gets the grid character.
+    pub(crate) fn marks(self, x: usize, y: usize) -> bool {
+        match self {
+            Grid::Dots => true,
+            Grid::Checkerboard => (x + y).is_multiple_of(2)
+        }
+    }
+}
+
+impl Display for Grid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Grid::Dots => "dots",
+                Grid::Checkerboard => "checkerboard"
+            }
+        )
+    }
+}
+
+// Formats that each finished game can be recorded in, for looking at in other tools.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum StatsExport {
+    Json,
+    Csv
+}
+
+impl Display for StatsExport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                StatsExport::Json => "json",
+                StatsExport::Csv => "csv"
+            }
+        )
+    }
+}
+
+// Built-in piece color schemes. Individual piece color settings still take priority over these.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Palette {
+    Deuteranopia,
+    Protanopia,
+    Tritanopia,
+    HighContrast
+}
+
+impl Palette {
+    // Piece colors in I, J, L, S, Z, T, O order. The red-green presets are based on the Okabe-Ito
+    // palette, which stays distinguishable with the common forms of color blindness.
+    pub(crate) fn colors(self) -> [Color; 7] {
+        let rgb = |r, g, b| Color::Rgb { r, g, b };
+        match self {
+            Palette::Deuteranopia => [
+                rgb(86, 180, 233),
+                rgb(0, 114, 178),
+                rgb(230, 159, 0),
+                rgb(0, 158, 115),
+                rgb(213, 94, 0),
+                rgb(204, 121, 167),
+                rgb(240, 228, 66)
+            ],
+            Palette::Protanopia => [
+                rgb(86, 180, 233),
+                rgb(0, 114, 178),
+                rgb(230, 159, 0),
+                rgb(0, 158, 115),
+                rgb(170, 170, 170),
+                rgb(204, 121, 167),
+                rgb(240, 228, 66)
+            ],
+            Palette::Tritanopia => [
+                rgb(0, 190, 190),
+                rgb(0, 90, 110),
+                rgb(255, 130, 110),
+                rgb(150, 150, 150),
+                rgb(200, 0, 40),
+                rgb(255, 180, 220),
+                rgb(240, 240, 240)
+            ],
+            Palette::HighContrast => [
+                rgb(0, 255, 255),
+                rgb(80, 80, 255),
+                rgb(255, 140, 0),
+                rgb(0, 255, 0),
+                rgb(255, 0, 0),
+                rgb(255, 0, 255),
+                rgb(255, 255, 0)
+            ]
+        }
+    }
+}
+
+impl Display for Palette {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Palette::Deuteranopia => "deuteranopia",
+                Palette::Protanopia => "protanopia",
+                Palette::Tritanopia => "tritanopia",
+                Palette::HighContrast => "high_contrast"
+            }
+        )
+    }
+}
+
+// The settings a `[bindings.<name>]` section can have.
+const BINDING_OPTIONS: [&str; 24] = [
+    "move_left",
+    "move_right",
+    "rotate_clockwise",
+    "rotate_anticlockwise",
+    "soft_drop",
+    "hard_drop",
+    "sonic_drop",
+    "hold",
+    "use_item",
+    "switch_target",
+    "pause",
+    "quit",
+    "toggle_stats",
+    "toggle_hint",
+    "toggle_debug",
+    "toggle_frame_step",
+    "frame_step",
+    "rewind",
+    "undo",
+    "screenshot",
+    "menu_up",
+    "menu_down",
+    "menu_select",
+    "menu_back"
+];
+
+// A named set of keys for every action, from a `[bindings.<name>]` section, to switch to with the
+// `key_bindings` setting or from the settings screen.
+#[derive(Clone)]
+pub struct Bindings {
+    pub(crate) name: String,
+    // The keys for each of `BINDING_OPTIONS`, in order.
+    keys: Vec<Vec<Key>>
+}
+
+impl Bindings {
+    fn of(name: &str, config: &mut GameConfig) -> Self {
+        Bindings {
+            name: name.to_owned(),
+            keys: BINDING_OPTIONS
+                .iter()
+                .map(|setting| config.keys_mut(setting).clone())
+                .collect()
+        }
+    }
+
+    fn apply(&self, config: &mut GameConfig) {
+        for (setting, keys) in BINDING_OPTIONS.iter().zip(self.keys.iter()) {
+            *config.keys_mut(setting) = keys.clone();
+        }
+    }
+}
+
+// A built-in control scheme, picked from the settings screen or with `--preset`. Only the keys it
+// lists are changed, and those are written to the config once it's picked. Presets are for the
+// keyboard, so any controller buttons bound to the same actions are kept.
+pub struct Preset {
+    pub(crate) name: &'static str,
+    pub(crate) description: &'static str,
+    keys: &'static [(&'static str, &'static [Key])]
+}
+
+pub const PRESETS: [Preset; 4] = [
+    Preset {
+        name: "guideline",
+        description: "The arrow keys, with X and Z to rotate and C to hold",
+        keys: &[
+            ("move_left", &[Key::plain(KeyCode::Left)]),
+            ("move_right", &[Key::plain(KeyCode::Right)]),
+            (
+                "rotate_clockwise",
+                &[Key::plain(KeyCode::Up), Key::plain(KeyCode::Char('x'))]
+            ),
+            ("rotate_anticlockwise", &[Key::plain(KeyCode::Char('z'))]),
+            ("soft_drop", &[Key::plain(KeyCode::Down)]),
+            ("hard_drop", &[Key::plain(KeyCode::Char(' '))]),
+            ("sonic_drop", &[]),
+            ("hold", &[Key::plain(KeyCode::Char('c'))])
+        ]
+    },
+    // The NES had no hard drop or hold, and its A and B buttons rotated.
+    Preset {
+        name: "nes",
+        description: "The arrow keys, with X and Z as A and B, and no hard drop or hold",
+        keys: &[
+            ("move_left", &[Key::plain(KeyCode::Left)]),
+            ("move_right", &[Key::plain(KeyCode::Right)]),
+            ("rotate_clockwise", &[Key::plain(KeyCode::Char('x'))]),
+            ("rotate_anticlockwise", &[Key::plain(KeyCode::Char('z'))]),
+            ("soft_drop", &[Key::plain(KeyCode::Down)]),
+            ("hard_drop", &[]),
+            ("sonic_drop", &[]),
+            ("hold", &[])
+        ]
+    },
+    // H is taken for moving left, so the hint moves to ?.
+    Preset {
+        name: "vi-keys",
+        description: "H and L to move, J to drop, K and U to rotate, and Y to hold",
+        keys: &[
+            ("move_left", &[Key::plain(KeyCode::Char('h'))]),
+            ("move_right", &[Key::plain(KeyCode::Char('l'))]),
+            ("rotate_clockwise", &[Key::plain(KeyCode::Char('k'))]),
+            ("rotate_anticlockwise", &[Key::plain(KeyCode::Char('u'))]),
+            ("soft_drop", &[Key::plain(KeyCode::Char('j'))]),
+            ("hard_drop", &[Key::plain(KeyCode::Char(' '))]),
+            ("sonic_drop", &[]),
+            ("hold", &[Key::plain(KeyCode::Char('y'))]),
+            ("toggle_hint", &[Key::plain(KeyCode::Char('?'))])
+        ]
+    },
+    // Moving is what's done most, so it goes to the left hand.
+    Preset {
+        name: "left-handed",
+        description: "WASD to move and drop, with the arrow keys to rotate and hold",
+        keys: &[
+            ("move_left", &[Key::plain(KeyCode::Char('a'))]),
+            ("move_right", &[Key::plain(KeyCode::Char('d'))]),
+            ("rotate_clockwise", &[Key::plain(KeyCode::Right)]),
+            ("rotate_anticlockwise", &[Key::plain(KeyCode::Left)]),
+            ("soft_drop", &[Key::plain(KeyCode::Char('s'))]),
+            ("hard_drop", &[Key::plain(KeyCode::Char('w'))]),
+            ("sonic_drop", &[]),
+            ("hold", &[Key::plain(KeyCode::Up)])
+        ]
+    }
+];
+
+impl Preset {
+    pub fn named(name: &str) -> Option<&'static Preset> {
+        PRESETS.iter().find(|preset| preset.name == name)
+    }
+
+    // Presets change the default keys, since those are the ones that are saved, even while
+    // another set is in use.
+    pub fn apply(&self, config: &mut GameConfig) {
+        for &(setting, keys) in self.keys {
+            let bound = if config.is_default_bindings() {
+                config.keys_mut(setting)
+            } else {
+                let i = BINDING_OPTIONS.iter().position(|&s| s == setting).unwrap();
+                &mut config.bindings[0].keys[i]
+            };
+            bound.retain(Key::is_button);
+            bound.splice(0..0, keys.iter().cloned());
+        }
+    }
+
+    // Whether the config's keys are this preset's, for showing which one is in use.
+    pub fn matches(&self, config: &GameConfig) -> bool {
+        self.keys.iter().all(|&(setting, keys)| {
+            let bound = config.keys(setting).iter().filter(|key| !key.is_button());
+            bound.eq(keys.iter())
+        })
+    }
+}
+
+// The layout of tui_tetris.toml. Each section is a table of settings from the matching
+// `TOML_*_OPTIONS` list, with the same names and values as in the legacy format.
+#[derive(Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TomlConfig {
+    #[serde(default)]
+    game: Table,
+    #[serde(default)]
+    keys: Table,
+    #[serde(default)]
+    colors: Table,
+    #[serde(default)]
+    appearance: Table,
+    // Named sets of settings that replace the ones above when picked with `--profile`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    profile: BTreeMap<String, TomlConfig>,
+    // Sets of settings that replace the ones above in games of one mode, like `[mode.sprint.game]`.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    mode: BTreeMap<String, TomlConfig>,
+    // Named sets of keys, like `[bindings.leftie]`. Only the main config's are used.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    bindings: BTreeMap<String, Table>
+}
+
+impl TomlConfig {
+    fn sections(&self) -> [(&Table, &'static [&'static str]); 4] {
+        [
+            (&self.game, &TOML_GAME_OPTIONS),
+            (&self.keys, &TOML_KEYS_OPTIONS),
+            (&self.colors, &TOML_COLORS_OPTIONS),
+            (&self.appearance, &TOML_APPEARANCE_OPTIONS)
+        ]
+    }
+}
+
+#[derive(Debug)]
+pub enum ParseErrorKind {
+    InvalidLineFormat,
+    UnknownSetting,
+    // An old name for a setting, which still works but should be replaced.
+    DeprecatedSetting,
+    InvalidValue,
+    DuplicateSetting,
+    FailedParseValue,
+    MissingValue,
+    InvalidToml(String)
+}
+
+impl ParseErrorKind {
+    fn describe(&self, text: &Strings) -> String {
+        match self {
+            ParseErrorKind::InvalidLineFormat => text.invalid_line_format.to_string(),
+            ParseErrorKind::UnknownSetting => text.unknown_setting.to_string(),
+            ParseErrorKind::DeprecatedSetting => text.deprecated_setting.to_string(),
+            ParseErrorKind::InvalidValue => text.invalid_value.to_string(),
+            ParseErrorKind::DuplicateSetting => text.duplicate_setting.to_string(),
+            ParseErrorKind::FailedParseValue => text.failed_parse_value.to_string(),
+            ParseErrorKind::MissingValue => text.missing_value.to_string(),
+            ParseErrorKind::InvalidToml(ref message) => fill(text.invalid_toml, &[message])
+        }
+    }
+}
+
+// A message in the string table, so that corrections are given in the player's language.
+type Message = fn(&Strings) -> &'static str;
+
 #[derive(Debug)]
 pub struct ParseError {
     kind: ParseErrorKind,
     line_num: usize,
     line: String,
-    correction: Option<&'static str>
+    correction: Option<Message>,
+    // What goes in place of each `{}` in the correction.
+    values: Vec<String>,
+    file: Option<String>,
+    in_arguments: bool
 }
 
 impl ParseError {
@@ -173,42 +1424,103 @@ impl ParseError {
         kind: ParseErrorKind,
         line_num: usize,
         line: &str,
-        correction: Option<&'static str>
+        correction: Option<Message>
     ) -> Self {
         ParseError {
             kind,
             line_num,
             line: line.to_owned(),
-            correction
+            correction,
+            values: Vec::new(),
+            file: None,
+            in_arguments: false
+        }
+    }
+
+    fn with_values(mut self, values: &[&dyn Display]) -> Self {
+        self.values = values.iter().map(|value| value.to_string()).collect();
+        self
+    }
+
+    // How to fix the error in the language of `text`, if that's known.
+    fn correction(&self, text: &Strings) -> Option<String> {
+        let values = self
+            .values
+            .iter()
+            .map(|value| value as &dyn Display)
+            .collect::<Vec<_>>();
+        self.correction
+            .map(|correction| fill(correction(text), &values))
+    }
+
+    // Attribute the error to wherever the setting came from if it wasn't the main config.
+    fn with_source(mut self, source: Source) -> Self {
+        match source {
+            Source::Config => {}
+            Source::Theme(file) => self.file = Some(file.to_owned()),
+            Source::Arguments => self.in_arguments = true
+        }
+        self
+    }
+
+    // A setting in a config or theme that this version doesn't know about, which may have been
+    // written for a newer one. Unknown command line options are always mistakes.
+    pub fn is_unknown_setting(&self) -> bool {
+        matches!(self.kind, ParseErrorKind::UnknownSetting) && !self.in_arguments
+    }
+
+    // Old setting names are only ever warned about.
+    pub fn is_deprecated(&self) -> bool {
+        matches!(self.kind, ParseErrorKind::DeprecatedSetting)
+    }
+
+    // A line saying what's being done about a setting that's only warned about, i.e. that an
+    // unknown setting is being skipped or that an old name should be replaced.
+    pub fn warning(&self, text: &Strings) -> String {
+        let location = if self.in_arguments {
+            text.in_arguments.to_string()
+        } else {
+            let file = self
+                .file
+                .as_ref()
+                .map_or(String::new(), |file| fill(text.of_file, &[file]));
+            fill(text.on_line, &[&(self.line_num + 1)]) + &file
+        };
+        match self.kind {
+            ParseErrorKind::DeprecatedSetting => fill(
+                text.deprecated_warning,
+                &[
+                    &location,
+                    &self.line,
+                    &self.correction(text).unwrap_or_default()
+                ]
+            ),
+            _ => fill(text.unknown_warning, &[&location, &self.line])
+        }
+    }
+
+    // The error in full, with where it is, what's wrong and how to fix it if that's known.
+    pub fn report(&self, text: &Strings) -> String {
+        let mut report = self
+            .file
+            .as_ref()
+            .map_or(String::new(), |file| fill(text.in_file, &[file]) + "\n");
+        let location = if self.in_arguments {
+            text.error_in_arguments.to_string()
+        } else {
+            fill(text.error_on_line, &[&(self.line_num + 1)])
+        };
+        report += &format!("{}: {}\n{}", location, self.line, self.kind.describe(text));
+        if let Some(correction) = self.correction(text) {
+            report += &format!("\n{}", correction);
         }
+        report
     }
 }
 
 impl Display for ParseError {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        if let Some(correction) = self.correction {
-            write!(
-                f,
-                "\
-                 Error on line {}: {}\n\
-                 {}\n\
-                 {}",
-                self.line_num + 1,
-                self.line,
-                self.kind,
-                correction
-            )
-        } else {
-            write!(
-                f,
-                "\
-                 Error on line {}: {}\n\
-                 {}",
-                self.line_num + 1,
-                self.line,
-                self.kind
-            )
-        }
+        write!(f, "{}", self.report(&ENGLISH))
     }
 }
 
@@ -216,18 +1528,28 @@ impl Display for ParseError {
 // First &str: RHS of the setting line. This is what gets parsed.
 // usize: line number for the setting. Part of ParseError.
 // Second &str: complete setting line. Part of ParseError.
+//
+// All four add any error to `errors` and carry on with the default value, so that every problem
+// with a config can be reported at once.
 
 // If the setting map contains the setting, try to parse it. Otherwise, use the default value.
 fn general_parse<T>(
     map: &Settings,
     key: &str,
     default: T,
-    parser: fn(&str, usize, &str) -> Result<T, ParseError>
-) -> Result<T, ParseError> {
-    if let Some(&(unparsed_setting, line_num, line)) = map.get(key) {
-        parser(unparsed_setting, line_num, line)
+    parser: fn(&str, usize, &str) -> Result<T, ParseError>,
+    errors: &mut Vec<ParseError>
+) -> T {
+    if let Some(&(unparsed_setting, line_num, line, source)) = map.get(key) {
+        match parser(unparsed_setting, line_num, line) {
+            Ok(value) => value,
+            Err(e) => {
+                errors.push(e.with_source(source));
+                default
+            }
+        }
     } else {
-        Ok(default)
+        default
     }
 }
 
@@ -237,16 +1559,52 @@ fn opt_general_parse<T>(
     map: &Settings,
     key: &str,
     default: Option<T>,
-    parser: fn(&str, usize, &str) -> Result<T, ParseError>
-) -> Result<Option<T>, ParseError> {
-    if let Some(&(rhs, line_num, line)) = map.get(key) {
+    parser: fn(&str, usize, &str) -> Result<T, ParseError>,
+    errors: &mut Vec<ParseError>
+) -> Option<T> {
+    if let Some(&(rhs, line_num, line, source)) = map.get(key) {
         if rhs.to_ascii_lowercase().as_str() == "none" {
-            Ok(None)
+            None
         } else {
-            Ok(Some(parser(rhs, line_num, line)?))
+            match parser(rhs, line_num, line) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    errors.push(e.with_source(source));
+                    default
+                }
+            }
         }
     } else {
-        Ok(default)
+        default
+    }
+}
+
+// Parse `rhs` as a number and check that it's within the specified range.
+fn parse_in_range<T: PartialOrd + FromStr, R: RangeBounds<T>>(
+    rhs: &str,
+    line_num: usize,
+    line: &str,
+    range: R,
+    fp_message: Message,
+    oor_message: Message
+) -> Result<T, ParseError> {
+    let parsed = rhs.parse::<T>().map_err(|_| {
+        ParseError::new(
+            ParseErrorKind::FailedParseValue,
+            line_num,
+            line,
+            Some(fp_message)
+        )
+    })?;
+    if range.contains(&parsed) {
+        Ok(parsed)
+    } else {
+        Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(oor_message)
+        ))
     }
 }
 
@@ -257,30 +1615,20 @@ fn parse_num_range<T: PartialOrd + FromStr, R: RangeBounds<T>>(
     key: &str,
     default: T,
     range: R,
-    fp_message: &'static str,
-    oor_message: &'static str
-) -> Result<T, ParseError> {
-    if let Some(&(rhs, line_num, line)) = map.get(key) {
-        let parsed = rhs.parse::<T>().map_err(|_| {
-            ParseError::new(
-                ParseErrorKind::FailedParseValue,
-                line_num,
-                line,
-                Some(fp_message)
-            )
-        })?;
-        if range.contains(&parsed) {
-            Ok(parsed)
-        } else {
-            Err(ParseError::new(
-                ParseErrorKind::InvalidValue,
-                line_num,
-                line,
-                Some(oor_message)
-            ))
+    fp_message: Message,
+    oor_message: Message,
+    errors: &mut Vec<ParseError>
+) -> T {
+    if let Some(&(rhs, line_num, line, source)) = map.get(key) {
+        match parse_in_range(rhs, line_num, line, range, fp_message, oor_message) {
+            Ok(value) => value,
+            Err(e) => {
+                errors.push(e.with_source(source));
+                default
+            }
         }
     } else {
-        Ok(default)
+        default
     }
 }
 
@@ -292,34 +1640,60 @@ fn opt_parse_num_range<T: PartialOrd + FromStr, R: RangeBounds<T>>(
     key: &str,
     default: Option<T>,
     range: R,
-    fp_message: &'static str,
-    oor_message: &'static str
-) -> Result<Option<T>, ParseError> {
-    if let Some(&(rhs, line_num, line)) = map.get(key) {
+    fp_message: Message,
+    oor_message: Message,
+    errors: &mut Vec<ParseError>
+) -> Option<T> {
+    if let Some(&(rhs, line_num, line, source)) = map.get(key) {
         if rhs.to_ascii_lowercase().as_str() == "none" {
-            Ok(None)
+            None
         } else {
-            let parsed = rhs.parse::<T>().map_err(|_| {
-                ParseError::new(
-                    ParseErrorKind::FailedParseValue,
-                    line_num,
-                    line,
-                    Some(fp_message)
-                )
-            })?;
-            if range.contains(&parsed) {
-                Ok(Some(parsed))
-            } else {
-                Err(ParseError::new(
-                    ParseErrorKind::InvalidValue,
-                    line_num,
-                    line,
-                    Some(oor_message)
-                ))
+            match parse_in_range(rhs, line_num, line, range, fp_message, oor_message) {
+                Ok(value) => Some(value),
+                Err(e) => {
+                    errors.push(e.with_source(source));
+                    default
+                }
             }
         }
     } else {
-        Ok(default)
+        default
+    }
+}
+
+// A count that can also be `inf` for no limit.
+fn parse_count(
+    rhs: &str,
+    line_num: usize,
+    line: &str,
+    message: Message
+) -> Result<Option<usize>, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "inf" | "infinite" => Ok(None),
+        count => count.parse().map(Some).map_err(|_| {
+            ParseError::new(ParseErrorKind::InvalidValue, line_num, line, Some(message))
+        })
+    }
+}
+
+fn parse_hold_limit(rhs: &str, line_num: usize, line: &str) -> Result<Option<usize>, ParseError> {
+    parse_count(rhs, line_num, line, |text| text.hold_limit_count)
+}
+
+fn parse_lock_resets(rhs: &str, line_num: usize, line: &str) -> Result<Option<usize>, ParseError> {
+    parse_count(rhs, line_num, line, |text| text.lock_resets_count)
+}
+
+// `auto` (represented as `None`) leaves it to the mode.
+fn parse_soft_drop_lock(
+    rhs: &str,
+    line_num: usize,
+    line: &str
+) -> Result<Option<bool>, ParseError> {
+    if rhs.eq_ignore_ascii_case("auto") {
+        Ok(None)
+    } else {
+        parse_bool(rhs, line_num, line).map(Some)
     }
 }
 
@@ -327,39 +1701,67 @@ fn parse_mode(rhs: &str, line_num: usize, line: &str) -> Result<Mode, ParseError
     match rhs.to_ascii_lowercase().as_str() {
         "c" | "classic" => Ok(Mode::Classic),
         "m" | "modern" => Ok(Mode::Modern),
+        "20g" | "master" => Ok(Mode::Master),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.mode_values)
+        ))
+    }
+}
+
+fn parse_level_goal(rhs: &str, line_num: usize, line: &str) -> Result<LevelGoal, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "fixed" => Ok(LevelGoal::Fixed),
+        "variable" => Ok(LevelGoal::Variable),
         _ => Err(ParseError::new(
             ParseErrorKind::InvalidValue,
             line_num,
             line,
-            Some("Accepted game mode indicators: c, classic, m, modern.")
-        ))
-    }
+            Some(|text| text.level_goal_values)
+        ))
+    }
+}
+
+// Keys are written as any of 'ctrl+', 'alt+', and 'shift+' followed by either a single character
+// or the name of the key.
+fn parse_key(rhs: &str, line_num: usize, line: &str) -> Result<Key, ParseError> {
+    Key::from_name(rhs).ok_or_else(|| {
+        ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.key_names)
+        )
+    })
 }
 
-fn parse_keyevent(rhs: &str, line_num: usize, line: &str) -> Result<KeyEvent, ParseError> {
-    match rhs.len() {
-        1 => Ok(KeyEvent::Char(rhs.chars().next().unwrap())),
-        _ => match rhs {
-            "space" => Ok(KeyEvent::Char(' ')),
-            "left" => Ok(KeyEvent::Left),
-            "right" => Ok(KeyEvent::Right),
-            "up" => Ok(KeyEvent::Up),
-            "down" => Ok(KeyEvent::Down),
-            "lshift" => Ok(KeyEvent::ShiftLeft),
-            "rshift" => Ok(KeyEvent::ShiftRight),
-            "lctrl" => Ok(KeyEvent::CtrlLeft),
-            "rctrl" => Ok(KeyEvent::CtrlRight),
-            "esc" => Ok(KeyEvent::Esc),
-            _ => Err(ParseError::new(
+// Keybindings are comma-separated lists of keys, any of which will do the action. A comma on its
+// own is still the comma key, but it has to be written as 'comma' in a list.
+fn parse_keys(rhs: &str, line_num: usize, line: &str) -> Result<Vec<Key>, ParseError> {
+    if rhs == "," {
+        return Ok(vec![Key::plain(KeyCode::Char(','))]);
+    }
+    rhs.split(',')
+        .map(|key| match key.trim() {
+            "" => Err(ParseError::new(
                 ParseErrorKind::InvalidValue,
                 line_num,
                 line,
-                Some(
-                    "Supported non-single-character values: 'space', 'left', 'right', 'up', \
-                     'down', 'lshift', 'rshift', 'lctrl', 'rctrl', and 'esc'."
-                )
-            ))
-        }
+                Some(|text| text.empty_key)
+            )),
+            key => parse_key(key, line_num, line)
+        })
+        .collect()
+}
+
+// Same as `parse_keys`, but "none" leaves the action unbound.
+fn parse_opt_keys(rhs: &str, line_num: usize, line: &str) -> Result<Vec<Key>, ParseError> {
+    if rhs.eq_ignore_ascii_case("none") {
+        Ok(Vec::new())
+    } else {
+        parse_keys(rhs, line_num, line)
     }
 }
 
@@ -373,7 +1775,7 @@ fn parse_color(rhs: &str, line_num: usize, line: &str) -> Result<Color, ParseErr
             ParseErrorKind::MissingValue,
             line_num,
             line,
-            Some("Missing color type.")
+            Some(|text| text.missing_color_type)
         )
     })?;
     let color = parts.next().ok_or_else(|| {
@@ -381,7 +1783,7 @@ fn parse_color(rhs: &str, line_num: usize, line: &str) -> Result<Color, ParseErr
             ParseErrorKind::MissingValue,
             line_num,
             line,
-            Some("Missing color.")
+            Some(|text| text.missing_color)
         )
     })?;
     match color_type.to_ascii_lowercase().as_str() {
@@ -395,7 +1797,7 @@ fn parse_color(rhs: &str, line_num: usize, line: &str) -> Result<Color, ParseErr
                     ParseErrorKind::FailedParseValue,
                     line_num,
                     line,
-                    Some("Failed to parse ANSI color value.")
+                    Some(|text| text.ansi_color_unparsed)
                 )
             })?;
             Ok(Color::AnsiValue(c))
@@ -404,7 +1806,7 @@ fn parse_color(rhs: &str, line_num: usize, line: &str) -> Result<Color, ParseErr
             ParseErrorKind::InvalidValue,
             line_num,
             line,
-            Some("Accepted color formats are: rgb, ansi.")
+            Some(|text| text.color_formats)
         ))
     }
 }
@@ -418,7 +1820,7 @@ fn parse_rgb_triple(s: &str, line_num: usize, line: &str) -> Result<(u8, u8, u8)
                 ParseErrorKind::MissingValue,
                 line_num,
                 line,
-                Some("Missing R value.")
+                Some(|text| text.missing_red)
             )
         })?
         .parse::<u8>()
@@ -427,7 +1829,7 @@ fn parse_rgb_triple(s: &str, line_num: usize, line: &str) -> Result<(u8, u8, u8)
                 ParseErrorKind::FailedParseValue,
                 line_num,
                 line,
-                Some("Failed to parse R value.")
+                Some(|text| text.red_unparsed)
             )
         })?;
     let g = parts
@@ -437,7 +1839,7 @@ fn parse_rgb_triple(s: &str, line_num: usize, line: &str) -> Result<(u8, u8, u8)
                 ParseErrorKind::MissingValue,
                 line_num,
                 line,
-                Some("Missing G value.")
+                Some(|text| text.missing_green)
             )
         })?
         .parse::<u8>()
@@ -446,7 +1848,7 @@ fn parse_rgb_triple(s: &str, line_num: usize, line: &str) -> Result<(u8, u8, u8)
                 ParseErrorKind::FailedParseValue,
                 line_num,
                 line,
-                Some("Failed to parse G value.")
+                Some(|text| text.green_unparsed)
             )
         })?;
     let b = parts
@@ -456,7 +1858,7 @@ fn parse_rgb_triple(s: &str, line_num: usize, line: &str) -> Result<(u8, u8, u8)
                 ParseErrorKind::MissingValue,
                 line_num,
                 line,
-                Some("Missing B value.")
+                Some(|text| text.missing_blue)
             )
         })?
         .parse::<u8>()
@@ -465,7 +1867,7 @@ fn parse_rgb_triple(s: &str, line_num: usize, line: &str) -> Result<(u8, u8, u8)
                 ParseErrorKind::FailedParseValue,
                 line_num,
                 line,
-                Some("Failed to parse B value.")
+                Some(|text| text.blue_unparsed)
             )
         })?;
     Ok((r, g, b))
@@ -473,57 +1875,686 @@ fn parse_rgb_triple(s: &str, line_num: usize, line: &str) -> Result<(u8, u8, u8)
 
 fn parse_char(rhs: &str, line_num: usize, line: &str) -> Result<char, ParseError> {
     let mut char_iter = rhs.chars();
-    let first = char_iter.next().ok_or_else(|| ParseError::new(
-        ParseErrorKind::MissingValue,
-        line_num,
-        line,
-        Some("Missing character value.")
-    ))?;
+    let first = char_iter.next().ok_or_else(|| {
+        ParseError::new(
+            ParseErrorKind::MissingValue,
+            line_num,
+            line,
+            Some(|text| text.missing_character)
+        )
+    })?;
     if char_iter.next().is_some() {
         Err(ParseError::new(
             ParseErrorKind::InvalidValue,
             line_num,
             line,
-            Some("Expected a single character value.")
+            Some(|text| text.single_character)
         ))
     } else {
         Ok(first)
     }
 }
 
+// `auto` (represented as `None`) means the color support is detected from the environment.
+fn parse_color_support(
+    rhs: &str,
+    line_num: usize,
+    line: &str
+) -> Result<Option<ColorSupport>, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "auto" => Ok(None),
+        "truecolor" | "24bit" => Ok(Some(ColorSupport::TrueColor)),
+        "256" => Ok(Some(ColorSupport::Ansi256)),
+        "16" => Ok(Some(ColorSupport::Ansi16)),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.color_support_values)
+        ))
+    }
+}
+
+// Theme names are used as file names, so they can't point outside the themes directory.
+fn parse_theme_name(rhs: &str, line_num: usize, line: &str) -> Result<String, ParseError> {
+    if rhs.contains(&['/', '\\', '.'][..]) {
+        Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.theme_name_characters)
+        ))
+    } else {
+        Ok(rhs.to_owned())
+    }
+}
+
+fn parse_leaderboard_url(rhs: &str, line_num: usize, line: &str) -> Result<String, ParseError> {
+    if rhs.starts_with("http://") || rhs.starts_with("https://") {
+        Ok(rhs.to_owned())
+    } else {
+        Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.leaderboard_url_scheme)
+        ))
+    }
+}
+
+fn parse_mirror_file(rhs: &str, _line_num: usize, _line: &str) -> Result<String, ParseError> {
+    Ok(rhs.to_owned())
+}
+
+fn parse_key_bindings(rhs: &str, _line_num: usize, _line: &str) -> Result<String, ParseError> {
+    Ok(rhs.to_owned())
+}
+
+fn parse_background_art(rhs: &str, _line_num: usize, _line: &str) -> Result<String, ParseError> {
+    Ok(rhs.to_owned())
+}
+
+fn parse_discord_app_id(rhs: &str, line_num: usize, line: &str) -> Result<u64, ParseError> {
+    rhs.parse::<u64>().map_err(|_| {
+        ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.discord_id_number)
+        )
+    })
+}
+
+fn parse_leaderboard_name(rhs: &str, line_num: usize, line: &str) -> Result<String, ParseError> {
+    let length = rhs.chars().count();
+    if length == 0 || length > MAX_LEADERBOARD_NAME || rhs.chars().any(char::is_control) {
+        Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.leaderboard_name_length)
+        ))
+    } else {
+        Ok(rhs.to_owned())
+    }
+}
+
+fn parse_ai_difficulty(rhs: &str, line_num: usize, line: &str) -> Result<AiDifficulty, ParseError> {
+    match AI_DIFFICULTIES
+        .iter()
+        .find(|difficulty| rhs.eq_ignore_ascii_case(&difficulty.to_string()))
+    {
+        Some(&difficulty) => Ok(difficulty),
+        None => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.ai_difficulty_values)
+        ))
+    }
+}
+
+fn parse_ai_style(rhs: &str, line_num: usize, line: &str) -> Result<Style, ParseError> {
+    match AI_STYLES
+        .iter()
+        .find(|style| rhs.eq_ignore_ascii_case(&style.to_string()))
+    {
+        Some(&style) => Ok(style),
+        None => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.ai_style_values)
+        ))
+    }
+}
+
+fn parse_targeting(rhs: &str, line_num: usize, line: &str) -> Result<Targeting, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "random" => Ok(Targeting::Random),
+        "leader" => Ok(Targeting::Leader),
+        "danger" => Ok(Targeting::Danger),
+        "even" => Ok(Targeting::Even),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.targeting_values)
+        ))
+    }
+}
+
+fn parse_input_conflicts(
+    rhs: &str,
+    line_num: usize,
+    line: &str
+) -> Result<InputConflicts, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "latest" => Ok(InputConflicts::Latest),
+        "cancel" => Ok(InputConflicts::Cancel),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.input_conflicts_values)
+        ))
+    }
+}
+
+fn parse_renderer(rhs: &str, line_num: usize, line: &str) -> Result<Renderer, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "blocks" => Ok(Renderer::Blocks),
+        "braille" => Ok(Renderer::Braille),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.renderer_values)
+        ))
+    }
+}
+
+fn parse_language(rhs: &str, line_num: usize, line: &str) -> Result<Language, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "english" => Ok(Language::English),
+        "spanish" => Ok(Language::Spanish),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.language_values)
+        ))
+    }
+}
+
+fn parse_ghost_color(rhs: &str, line_num: usize, line: &str) -> Result<GhostColor, ParseError> {
+    if rhs.eq_ignore_ascii_case("auto") {
+        Ok(GhostColor::Auto)
+    } else {
+        parse_color(rhs, line_num, line).map(GhostColor::Fixed)
+    }
+}
+
+fn parse_grid(rhs: &str, line_num: usize, line: &str) -> Result<Grid, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "dots" => Ok(Grid::Dots),
+        "checkerboard" => Ok(Grid::Checkerboard),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.grid_values)
+        ))
+    }
+}
+
+fn parse_stats_export(rhs: &str, line_num: usize, line: &str) -> Result<StatsExport, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "json" => Ok(StatsExport::Json),
+        "csv" => Ok(StatsExport::Csv),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.stats_export_values)
+        ))
+    }
+}
+
+fn parse_palette(rhs: &str, line_num: usize, line: &str) -> Result<Palette, ParseError> {
+    match rhs.to_ascii_lowercase().as_str() {
+        "deuteranopia" => Ok(Palette::Deuteranopia),
+        "protanopia" => Ok(Palette::Protanopia),
+        "tritanopia" => Ok(Palette::Tritanopia),
+        "high_contrast" => Ok(Palette::HighContrast),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.palette_values)
+        ))
+    }
+}
+
 fn parse_bool(rhs: &str, line_num: usize, line: &str) -> Result<bool, ParseError> {
     match rhs.to_ascii_lowercase().as_str() {
-        "1" | "t" | "true" => Ok(true),
-        "0" | "f" | "false" => Ok(false),
+        "1" | "t" | "true" | "on" => Ok(true),
+        "0" | "f" | "false" | "off" => Ok(false),
+        _ => Err(ParseError::new(
+            ParseErrorKind::InvalidValue,
+            line_num,
+            line,
+            Some(|text| text.bool_values)
+        ))
+    }
+}
+
+// Put each setting line in `s` into `settings`, tagged with where it came from. Lines whose
+// setting isn't in `options` or that repeat an earlier setting are added to `errors` and skipped.
+// Only the lines in the `[<name>]` section named by `section` are looked at, or with `None` the
+// ones before the first section.
+fn parse_lines<'a>(
+    s: &'a str,
+    options: &[&str],
+    valid_settings: (Message, &'static str),
+    source: Source<'a>,
+    section: Option<&str>,
+    settings: &mut Settings<'a>,
+    errors: &mut Vec<ParseError>
+) {
+    let mut current = None;
+    for (num, line) in s.lines().enumerate() {
+        // Skip blank lines
+        if line.is_empty() {
+            continue;
+        }
+        // Skip comment lines
+        if let Some('#') = line.chars().take(1).next() {
+            continue;
+        }
+        if let Some(name) = section_header(line) {
+            current = Some(name);
+            continue;
+        }
+        if current != section {
+            continue;
+        }
+        match parse_line(line, num, options, valid_settings) {
+            Ok((lhs, rhs)) => {
+                let lhs = match setting_alias(lhs) {
+                    Some(name) => {
+                        errors.push(deprecated(num, line, name).with_source(source));
+                        name
+                    }
+                    None => lhs
+                };
+                if settings.contains_key(lhs) {
+                    errors.push(
+                        ParseError::new(ParseErrorKind::DuplicateSetting, num, line, None)
+                            .with_source(source)
+                    );
+                } else {
+                    settings.insert(lhs, (rhs, num, line, source));
+                }
+            }
+            Err(e) => errors.push(e.with_source(source))
+        }
+    }
+}
+
+// The name of the section a `[<name>]` line starts.
+fn section_header(line: &str) -> Option<&str> {
+    line.trim()
+        .strip_prefix('[')
+        .and_then(|rest| rest.strip_suffix(']'))
+        .map(str::trim)
+}
+
+// The setting an old name stands for.
+fn setting_alias(name: &str) -> Option<&'static str> {
+    SETTING_ALIASES
+        .iter()
+        .find(|&&(alias, _)| alias == name)
+        .map(|&(_, name)| name)
+}
+
+// A note saying what an old name is called now.
+fn deprecated(num: usize, line: &str, name: &str) -> ParseError {
+    ParseError::new(
+        ParseErrorKind::DeprecatedSetting,
+        num,
+        line,
+        Some(|text| text.renamed_setting)
+    )
+    .with_values(&[&name])
+}
+
+// A setting that isn't one of the options where it is, with a correction and the setting names to
+// list in it, if it lists them.
+fn unknown_setting(num: usize, line: &str, (correction, names): (Message, &str)) -> ParseError {
+    ParseError::new(ParseErrorKind::UnknownSetting, num, line, Some(correction))
+        .with_values(&[&names])
+}
+
+// Split a setting line into its LHS and RHS, checking that the LHS is one of `options`.
+fn parse_line<'a>(
+    line: &'a str,
+    num: usize,
+    options: &[&str],
+    valid_settings: (Message, &'static str)
+) -> Result<(&'a str, &'a str), ParseError> {
+    // Split into LHS and RHS at '='
+    let mut sections = line.split('=');
+    // Each valid line has a LHS
+    let lhs = sections
+        .next()
+        .ok_or_else(|| ParseError::new(ParseErrorKind::InvalidLineFormat, num, line, None))?
+        .trim();
+    // LHS length must be > 0
+    if lhs.is_empty() {
+        return Err(ParseError::new(
+            ParseErrorKind::InvalidLineFormat,
+            num,
+            line,
+            Some(|text| text.missing_setting_name)
+        ));
+    }
+    // Each valid line has a RHS
+    let rhs = sections
+        .next()
+        .ok_or_else(|| ParseError::new(ParseErrorKind::InvalidLineFormat, num, line, None))?
+        .trim();
+    // RHS length must be > 0
+    if rhs.is_empty() {
+        return Err(ParseError::new(
+            ParseErrorKind::InvalidLineFormat,
+            num,
+            line,
+            Some(|text| text.missing_setting_value)
+        ));
+    }
+    // Check that the LHS is a valid setting name, or an old name for one
+    if options.contains(&lhs) || setting_alias(lhs).is_some_and(|name| options.contains(&name)) {
+        Ok((lhs, rhs))
+    } else {
+        Err(unknown_setting(num, line, valid_settings))
+    }
+}
+
+// Every problem with a config that failed to parse, one after another with a blank line between
+// them.
+#[derive(Debug)]
+pub struct ParseErrors(pub(crate) Vec<ParseError>);
+
+impl ParseErrors {
+    pub fn report(&self, text: &Strings) -> String {
+        let reports = self.0.iter().map(|e| e.report(text)).collect::<Vec<_>>();
+        reports.join("\n\n")
+    }
+}
+
+impl Display for ParseErrors {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.report(&ENGLISH))
+    }
+}
+
+// Errors from the config come first in line order, then any from a theme, then from the command
+// line.
+fn sort_errors(errors: &mut [ParseError]) {
+    errors.sort_by(|a, b| {
+        (a.in_arguments, &a.file, a.line_num).cmp(&(b.in_arguments, &b.file, b.line_num))
+    });
+}
+
+// Deprecated setting names aren't a reason to fail.
+fn all_errors(
+    (config, mut errors): (GameConfig, Vec<ParseError>)
+) -> Result<GameConfig, ParseErrors> {
+    errors.retain(|e| !e.is_deprecated());
+    if errors.is_empty() {
+        Ok(config)
+    } else {
+        Err(ParseErrors(errors))
+    }
+}
+
+// Deserialized TOML doesn't remember where each value came from, so find the line a setting is on
+// for error messages. Setting names are unique across sections, so the first match from `start`
+// onwards is the one. Profiles repeat setting names, so their search starts at the profile.
+fn toml_line<'a>(s: &'a str, key: &str, start: usize) -> (usize, &'a str) {
+    s.lines()
+        .enumerate()
+        .skip(start)
+        .find(|(_, line)| {
+            line.match_indices(key).any(|(i, _)| {
+                let before = line[..i].chars().next_back();
+                let after = line[i + key.len()..]
+                    .trim_start_matches(|c: char| c == '"' || c == '\'' || c.is_whitespace());
+                !before.is_some_and(|c| c == '_' || c.is_alphanumeric()) && after.starts_with('=')
+            })
+        })
+        .unwrap_or((0, ""))
+}
+
+// Convert each setting in a TOML table into the text the legacy format would have for it, checking
+// that it's one of `options` and looking for its line from `start`.
+fn toml_values<'a>(
+    s: &'a str,
+    table: &Table,
+    options: &[&'static str],
+    start: usize,
+    values: &mut Vec<(&'static str, String, usize, &'a str)>,
+    errors: &mut Vec<ParseError>
+) {
+    for (key, value) in table.iter() {
+        let (line_num, line) = toml_line(s, key, start);
+        let alias = setting_alias(key).filter(|name| options.contains(name));
+        let key = match options.iter().find(|&option| option == key) {
+            Some(&option) => option,
+            None if alias.is_some() => {
+                let name = alias.unwrap();
+                errors.push(deprecated(line_num, line, name));
+                name
+            }
+            None => {
+                let correction: Message = if CONFIG_OPTIONS.contains(&key.as_str()) {
+                    |text| text.wrong_section
+                } else {
+                    |text| text.valid_settings
+                };
+                let correction = (correction, SETTING_NAMES);
+                errors.push(unknown_setting(line_num, line, correction));
+                continue;
+            }
+        };
+        match toml_rhs(value, line_num, line) {
+            Ok(rhs) => values.push((key, rhs, line_num, line)),
+            Err(e) => errors.push(e)
+        }
+    }
+}
+
+fn toml_settings<'a>(values: &'a [(&'static str, String, usize, &'a str)]) -> Settings<'a> {
+    values
+        .iter()
+        .map(|&(key, ref rhs, line_num, line)| {
+            (key, (rhs.as_str(), line_num, line, Source::Config))
+        })
+        .collect()
+}
+
+// The main section of a TOML config that a setting belongs in.
+fn toml_section(key: &str) -> &'static str {
+    if TOML_GAME_OPTIONS.contains(&key) {
+        "game"
+    } else if TOML_KEYS_OPTIONS.contains(&key) {
+        "keys"
+    } else if TOML_COLORS_OPTIONS.contains(&key) {
+        "colors"
+    } else {
+        "appearance"
+    }
+}
+
+// The comment after a TOML value, if there is one, skipping any '#'s inside strings.
+fn toml_comment(rhs: &str) -> Option<&str> {
+    let mut quote = None;
+    let mut escaped = false;
+    for (i, c) in rhs.char_indices() {
+        match quote {
+            Some('"') if escaped => escaped = false,
+            Some('"') if c == '\\' => escaped = true,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == '"' || c == '\'' => quote = Some(c),
+            None if c == '#' => return Some(&rhs[i..]),
+            None => {}
+        }
+    }
+    None
+}
+
+// Convert a TOML value into the text the legacy format would have for it.
+fn toml_rhs(value: &Value, line_num: usize, line: &str) -> Result<String, ParseError> {
+    match value {
+        Value::String(string) => Ok(string.clone()),
+        Value::Integer(integer) => Ok(integer.to_string()),
+        Value::Boolean(boolean) => Ok(boolean.to_string()),
         _ => Err(ParseError::new(
             ParseErrorKind::InvalidValue,
             line_num,
             line,
-            Some("Accepted boolean values: 1, t, true, 0, f, false")
+            Some(|text| text.toml_value_types)
         ))
     }
 }
 
+// The reverse of `toml_rhs`, using the native TOML types where a setting has one.
+fn toml_value(key: &str, rhs: &str) -> Value {
+    if TOML_INTEGER_SETTINGS.contains(&key) {
+        if let Ok(integer) = rhs.parse() {
+            return Value::Integer(integer);
+        }
+    } else if TOML_BOOLEAN_SETTINGS.contains(&key) {
+        if let Ok(boolean) = parse_bool(rhs, 0, "") {
+            return Value::Boolean(boolean);
+        }
+    }
+    Value::String(rhs.to_owned())
+}
+
+#[derive(Clone)]
 pub struct GameConfig {
     // Required game settings
     pub(crate) fps_limiter: Option<u64>,
     pub(crate) board_width: usize,
     pub(crate) board_height: usize,
+    // Rows above the visible board that pieces spawn in.
+    pub(crate) hidden_rows: usize,
+    // Set for board sizes that are `auto`, which are worked out by `fit_board` at startup and only
+    // have placeholder values until then.
+    pub(crate) auto_board_width: bool,
+    pub(crate) auto_board_height: bool,
     pub(crate) mode: Mode,
-    pub(crate) left: KeyEvent,
-    pub(crate) right: KeyEvent,
-    pub(crate) rot_cw: KeyEvent,
-    pub(crate) rot_acw: KeyEvent,
-    pub(crate) soft_drop: KeyEvent,
-    pub(crate) hard_drop: Option<KeyEvent>,
-    pub(crate) hold: Option<KeyEvent>,
+    // Every key that does each action. Hard drop, sonic drop, hold, and the stats toggle can be
+    // left empty to turn them off.
+    pub(crate) left: Vec<Key>,
+    pub(crate) right: Vec<Key>,
+    pub(crate) rot_cw: Vec<Key>,
+    pub(crate) rot_acw: Vec<Key>,
+    pub(crate) soft_drop: Vec<Key>,
+    pub(crate) hard_drop: Vec<Key>,
+    // Drops the piece as far as it'll go without locking it, like in TGM.
+    pub(crate) sonic_drop: Vec<Key>,
+    pub(crate) hold: Vec<Key>,
+    pub(crate) use_item: Vec<Key>,
+    // Picks which opponent attacks go to in a network game, in place of `targeting`.
+    pub(crate) switch_target: Vec<Key>,
+    pub(crate) pause: Vec<Key>,
+    pub(crate) quit: Vec<Key>,
+    pub(crate) toggle_stats: Vec<Key>,
+    // Shows where the built in AI would put the active piece.
+    pub(crate) toggle_hint: Vec<Key>,
+    // Shows frame timing and where the active piece is, for tracking down slow terminals.
+    pub(crate) toggle_debug: Vec<Key>,
+    // Freezes the game so that it only moves on a tick at a time when `frame_step` is pressed.
+    pub(crate) toggle_frame_step: Vec<Key>,
+    pub(crate) frame_step: Vec<Key>,
+    // Go back a few seconds, or to before the last piece was placed. Only work in practice games.
+    pub(crate) rewind: Vec<Key>,
+    pub(crate) undo: Vec<Key>,
+    // Saves what's on the screen to share a position without a graphical screenshot.
+    pub(crate) screenshot: Vec<Key>,
+    // Menus go left and right with the movement keys, and use these for everything else.
+    pub(crate) menu_up: Vec<Key>,
+    pub(crate) menu_down: Vec<Key>,
+    pub(crate) menu_select: Vec<Key>,
+    pub(crate) menu_back: Vec<Key>,
+    // The set of keys above in use, and every set there is with the main keys first as the
+    // default.
+    pub(crate) key_bindings: String,
+    pub(crate) bindings: Vec<Bindings>,
     // Optional gameplay settings
     pub(crate) ghost_tetromino_character: Option<char>,
-    pub(crate) ghost_tetromino_color: Option<Color>,
+    // `auto` draws the ghost in a dimmed version of the active piece's color.
+    pub(crate) ghost_tetromino_color: Option<GhostColor>,
     pub(crate) cascade: bool,
+    pub(crate) big_mode: bool,
+    // How many times hold can be used on each piece, or `None` for no limit.
+    pub(crate) hold_limit: Option<usize>,
+    // Initial rotation and hold: rotate and hold keys that are held down as a piece spawns apply
+    // to it straight away, and holding them down doesn't repeat them.
+    pub(crate) irs: bool,
+    // Whether the last of two opposite moves or rotations on the same frame wins, or neither
+    // happens.
+    pub(crate) input_conflicts: InputConflicts,
+    // Milliseconds between a piece locking and the next one coming into play.
+    pub(crate) entry_delay: Option<u64>,
+    // Frames (logic ticks) the next piece is held back after a lock that clears lines.
+    pub(crate) line_clear_delay: u32,
+    // Milliseconds a piece can rest on the stack before locking, or `None` to lock it as soon as
+    // it lands (after master mode's own delay in master mode).
+    pub(crate) lock_delay: Option<u64>,
+    // Moves and rotations that can restart the lock delay before the piece falls again, or `None`
+    // for no limit.
+    pub(crate) lock_resets: Option<usize>,
+    // Whether soft dropping a piece that's resting on the stack locks it straight away, or `None`
+    // for whatever the mode does (only master mode does).
+    pub(crate) soft_drop_lock: Option<bool>,
     pub(crate) const_level: Option<usize>,
-    // Optional game appearance setting
+    pub(crate) lines_per_level: usize,
+    pub(crate) level_goal: LevelGoal,
+    // Append a record of each game to a file in the data directory.
+    pub(crate) stats_export: Option<StatsExport>,
+    // Print each finished game as a fumen, with a page for every piece placed, to share it.
+    pub(crate) fumen_export: bool,
+    // Percent chance that each garbage row has its hole in the same column as the last one.
+    pub(crate) garbage_hole_repeat: usize,
+    // Some locked pieces carry items, which are picked up by clearing their line and used with
+    // `use_item`.
+    pub(crate) items: bool,
+    pub(crate) targeting: Targeting,
+    // Whether network games hosted here are played with rollback netcode, and how many ticks
+    // after they're pressed this player's inputs count in them.
+    pub(crate) rollback: bool,
+    pub(crate) input_delay: u64,
+    // Milliseconds a bot opponent waits between placing pieces. The built-in AI goes at the speed
+    // of its difficulty instead.
+    pub(crate) bot_delay: u64,
+    pub(crate) ai_difficulty: AiDifficulty,
+    pub(crate) ai_style: Style,
+    // What this player gives up in versus games, to even things out with a stronger opponent.
+    pub(crate) handicap_garbage: usize,
+    pub(crate) handicap_gravity: isize,
+    pub(crate) handicap_preview: Option<usize>,
+    // Percent of each attack that's sent.
+    pub(crate) handicap_attack: u64,
+    // Seconds counted down before play starts or picks up again after a pause.
+    pub(crate) countdown: u64,
+    // Percent of normal speed that the whole game runs at, for practicing in slow motion or at
+    // higher gravity. Anything but 100 makes the game practice, and versus games ignore it.
+    pub(crate) game_speed: u32,
+    // Play sound cues. Does nothing unless built with the `sound` feature.
+    pub(crate) sound: bool,
+    // Announce each new piece and a dangerously high stack with a tone, or with the terminal bell
+    // if there's no sound.
+    pub(crate) accessibility_cues: bool,
+    // Where to send results and fetch the top entries from, and the name results are sent under.
+    // Does nothing unless built with the `online` feature.
+    pub(crate) leaderboard_url: Option<String>,
+    pub(crate) leaderboard_name: String,
+    // Where the state of the game is written out every second for streaming overlays to show.
+    pub(crate) mirror_file: Option<String>,
+    // The Discord application the game is shown as activity under. Does nothing unless built with
+    // the `discord` feature.
+    pub(crate) discord_app_id: Option<u64>,
+    // What language menus and the side panel are shown in.
+    pub(crate) language: Language,
+    // Optional game appearance settings
     pub(crate) monochrome: Option<Color>,
+    pub(crate) color_support: Option<ColorSupport>,
+    pub(crate) theme: Option<String>,
     // Optional board appearance settings
     pub(crate) border_color: Color,
     pub(crate) top_border_character: char,
@@ -538,13 +2569,44 @@ pub struct GameConfig {
     // Optional block appearance settings
     pub(crate) block_character: char,
     pub(crate) block_size: usize,
+    pub(crate) renderer: Renderer,
+    // Pattern drawn in empty squares with the block renderer, in its own character and color.
+    pub(crate) grid: Option<Grid>,
+    pub(crate) grid_character: char,
+    pub(crate) grid_color: Color,
+    // Shake the board on Tetrises and flash its border on T-spin clears.
+    pub(crate) effects: bool,
+    // Percent of the way up the board past which the stack turns the border red, or 0 for never,
+    // and whether the red border pulses.
+    pub(crate) danger_height: usize,
+    pub(crate) danger_pulse: bool,
+    // Show a speedrun timer with split times, compared against the best run of the same kind.
+    pub(crate) splits: bool,
+    // Replay the best run beside the board in challenges that are timed.
+    pub(crate) ghost_race: bool,
+    // Keep the terminal's title up to date with the game, and send a desktop notification through
+    // the terminal when a game ends with a new personal best.
+    pub(crate) terminal_title: bool,
+    // ANSI art drawn dimmed behind the game, showing around the board and through its empty
+    // squares.
+    pub(crate) background_art: Option<String>,
+    pub(crate) palette: Option<Palette>,
     pub(crate) i_color: Color,
     pub(crate) j_color: Color,
     pub(crate) l_color: Color,
     pub(crate) s_color: Color,
     pub(crate) z_color: Color,
     pub(crate) t_color: Color,
-    pub(crate) o_color: Color
+    pub(crate) o_color: Color,
+    // Per-piece block characters, falling back on `block_character` when unset. Mostly useful for
+    // telling pieces apart in monochrome.
+    pub(crate) i_character: Option<char>,
+    pub(crate) j_character: Option<char>,
+    pub(crate) l_character: Option<char>,
+    pub(crate) s_character: Option<char>,
+    pub(crate) z_character: Option<char>,
+    pub(crate) t_character: Option<char>,
+    pub(crate) o_character: Option<char>
 }
 
 impl GameConfig {
@@ -553,19 +2615,77 @@ impl GameConfig {
             fps_limiter: D_FPS_LIMITER,
             board_width: D_BOARD_WIDTH,
             board_height: D_BOARD_HEIGHT,
+            hidden_rows: D_HIDDEN_ROWS,
+            auto_board_width: false,
+            auto_board_height: false,
             mode: D_MODE,
-            left: D_LEFT,
-            right: D_RIGHT,
-            rot_cw: D_ROT_CW,
-            rot_acw: D_ROT_ACW,
-            soft_drop: D_SOFT_DROP,
-            hard_drop: D_HARD_DROP,
-            hold: D_HOLD,
+            left: D_LEFT.to_vec(),
+            right: D_RIGHT.to_vec(),
+            rot_cw: D_ROT_CW.to_vec(),
+            rot_acw: D_ROT_ACW.to_vec(),
+            soft_drop: D_SOFT_DROP.to_vec(),
+            hard_drop: D_HARD_DROP.to_vec(),
+            sonic_drop: D_SONIC_DROP.to_vec(),
+            hold: D_HOLD.to_vec(),
+            use_item: D_USE_ITEM.to_vec(),
+            switch_target: D_SWITCH_TARGET.to_vec(),
+            pause: D_PAUSE.to_vec(),
+            quit: D_QUIT.to_vec(),
+            toggle_stats: D_TOGGLE_STATS.to_vec(),
+            toggle_hint: D_TOGGLE_HINT.to_vec(),
+            toggle_debug: D_TOGGLE_DEBUG.to_vec(),
+            toggle_frame_step: D_TOGGLE_FRAME_STEP.to_vec(),
+            frame_step: D_FRAME_STEP.to_vec(),
+            rewind: D_REWIND.to_vec(),
+            undo: D_UNDO.to_vec(),
+            screenshot: D_SCREENSHOT.to_vec(),
+            menu_up: D_MENU_UP.to_vec(),
+            menu_down: D_MENU_DOWN.to_vec(),
+            menu_select: D_MENU_SELECT.to_vec(),
+            menu_back: D_MENU_BACK.to_vec(),
+            key_bindings: D_KEY_BINDINGS.to_string(),
+            bindings: Vec::new(),
             ghost_tetromino_character: D_GHOST_TETROMINO_CHARACTER,
             ghost_tetromino_color: D_GHOST_TETROMINO_COLOR,
             cascade: D_CASCADE,
+            big_mode: D_BIG_MODE,
+            hold_limit: D_HOLD_LIMIT,
+            irs: D_IRS,
+            input_conflicts: D_INPUT_CONFLICTS,
+            entry_delay: D_ENTRY_DELAY,
+            line_clear_delay: D_LINE_CLEAR_DELAY,
+            lock_delay: D_LOCK_DELAY,
+            lock_resets: D_LOCK_RESETS,
+            soft_drop_lock: D_SOFT_DROP_LOCK,
             const_level: D_CONST_LEVEL,
+            lines_per_level: D_LINES_PER_LEVEL,
+            level_goal: D_LEVEL_GOAL,
+            stats_export: D_STATS_EXPORT,
+            fumen_export: D_FUMEN_EXPORT,
+            garbage_hole_repeat: D_GARBAGE_HOLE_REPEAT,
+            items: D_ITEMS,
+            targeting: D_TARGETING,
+            rollback: D_ROLLBACK,
+            input_delay: D_INPUT_DELAY,
+            bot_delay: D_BOT_DELAY,
+            ai_difficulty: D_AI_DIFFICULTY,
+            ai_style: D_AI_STYLE,
+            handicap_garbage: D_HANDICAP_GARBAGE,
+            handicap_gravity: D_HANDICAP_GRAVITY,
+            handicap_preview: D_HANDICAP_PREVIEW,
+            handicap_attack: D_HANDICAP_ATTACK,
+            countdown: D_COUNTDOWN,
+            game_speed: D_GAME_SPEED,
+            sound: D_SOUND,
+            accessibility_cues: D_ACCESSIBILITY_CUES,
+            leaderboard_url: D_LEADERBOARD_URL,
+            leaderboard_name: D_LEADERBOARD_NAME.to_string(),
+            mirror_file: D_MIRROR_FILE,
+            discord_app_id: D_DISCORD_APP_ID,
+            language: D_LANGUAGE,
             monochrome: D_MONOCHROME,
+            color_support: D_COLOR_SUPPORT,
+            theme: D_THEME,
             border_color: D_BORDER_COLOR,
             top_border_character: D_TOP_BORDER_CHARACTER,
             tl_corner_character: D_TL_CORNER_CHARACTER,
@@ -578,84 +2698,414 @@ impl GameConfig {
             background_color: D_BACKGROUND_COLOR,
             block_character: D_BLOCK_CHARACTER,
             block_size: D_BLOCK_SIZE,
+            renderer: D_RENDERER,
+            grid: D_GRID,
+            grid_character: D_GRID_CHARACTER,
+            grid_color: D_GRID_COLOR,
+            effects: D_EFFECTS,
+            danger_height: D_DANGER_HEIGHT,
+            danger_pulse: D_DANGER_PULSE,
+            splits: D_SPLITS,
+            ghost_race: D_GHOST_RACE,
+            terminal_title: D_TERMINAL_TITLE,
+            background_art: D_BACKGROUND_ART,
+            palette: D_PALETTE,
             i_color: D_I_COLOR,
             j_color: D_J_COLOR,
             l_color: D_L_COLOR,
             s_color: D_S_COLOR,
             z_color: D_Z_COLOR,
             t_color: D_T_COLOR,
-            o_color: D_O_COLOR
+            o_color: D_O_COLOR,
+            i_character: D_I_CHARACTER,
+            j_character: D_J_CHARACTER,
+            l_character: D_L_CHARACTER,
+            s_character: D_S_CHARACTER,
+            z_character: D_Z_CHARACTER,
+            t_character: D_T_CHARACTER,
+            o_character: D_O_CHARACTER
+        }
+    }
+
+    // Each line in the config file is put in a `HashMap<&str, (&str, usize, &str)>` where the key
+    // value is the setting name and the tuple type contains the RHS of the setting line, the line
+    // number, and the complete line (the last two are for error message purposes). If any line
+    // with a LHS that is not one of the valid setting names or a duplicate setting name is
+    // encountered, the config file is considered invalid. After that, we try to get the value for
+    // each "valid" key (each setting name) and parse it into the appropriate data type. Once that's
+    // done for each setting, we check a case where the config might be invalid, as well as two
+    // where some values might need to be adjusted. After that, we return the complete config, or
+    // every problem that was found along the way so they can all be fixed in one go.
+    pub fn parse(
+        s: &str,
+        overrides: &[(String, String)],
+        mode: Option<&str>
+    ) -> Result<Self, ParseErrors> {
+        all_errors(Self::check(s, overrides, mode))
+    }
+
+    pub fn parse_toml(
+        s: &str,
+        overrides: &[(String, String)],
+        profile: Option<&str>,
+        mode: Option<&str>
+    ) -> Result<Self, ParseErrors> {
+        all_errors(Self::check_toml(s, overrides, profile, mode))
+    }
+
+    // Like `parse`, but carries on past errors to find all of them. Broken settings are left at
+    // their defaults in the returned config. If `mode` is given, the settings in its `[<mode>]`
+    // section replace the ones at the top of the file.
+    pub fn check(
+        s: &str,
+        overrides: &[(String, String)],
+        mode: Option<&str>
+    ) -> (Self, Vec<ParseError>) {
+        let mut errors = Vec::new();
+        let mut settings = HashMap::with_capacity(CONFIG_OPTIONS.len());
+        parse_lines(
+            s,
+            &CONFIG_OPTIONS,
+            (|text| text.valid_settings, SETTING_NAMES),
+            Source::Config,
+            None,
+            &mut settings,
+            &mut errors
+        );
+        if mode.is_some() {
+            let mut mode_settings = HashMap::new();
+            parse_lines(
+                s,
+                &CONFIG_OPTIONS,
+                (|text| text.valid_settings, SETTING_NAMES),
+                Source::Config,
+                mode,
+                &mut mode_settings,
+                &mut errors
+            );
+            settings.extend(mode_settings);
+        }
+        // Sections named `bindings.<name>` are sets of keys rather than modes.
+        let mut sections = Vec::new();
+        for name in s.lines().filter_map(section_header) {
+            if let Some(bindings) = name.strip_prefix("bindings.") {
+                let mut section = HashMap::new();
+                parse_lines(
+                    s,
+                    &BINDING_OPTIONS,
+                    (|text| text.bindings_keys_only, ""),
+                    Source::Config,
+                    Some(name),
+                    &mut section,
+                    &mut errors
+                );
+                sections.push((bindings, section));
+            }
+        }
+        let (mut config, setting_errors) = Self::from_settings(settings.clone(), overrides);
+        errors.extend(setting_errors);
+        config.add_bindings(&settings, sections, overrides, &mut errors);
+        sort_errors(&mut errors);
+        (config, errors)
+    }
+
+    // TOML settings are turned back into the same text as in the legacy format and go through the
+    // same checks, with the line numbers found by searching the file for each setting's name. If
+    // `profile` is given, the settings in its `[profile.<name>]` section replace the main ones, and
+    // if `mode` is, the ones in its `[mode.<name>]` sections replace both.
+    pub fn check_toml(
+        s: &str,
+        overrides: &[(String, String)],
+        profile: Option<&str>,
+        mode: Option<&str>
+    ) -> (Self, Vec<ParseError>) {
+        let mut errors = Vec::new();
+        let toml_config = toml::from_str::<TomlConfig>(s).unwrap_or_else(|e| {
+            let line_num = e.line_col().map_or(0, |(line_num, _)| line_num);
+            errors.push(ParseError::new(
+                ParseErrorKind::InvalidToml(e.to_string()),
+                line_num,
+                s.lines().nth(line_num).unwrap_or(""),
+                None
+            ));
+            TomlConfig::default()
+        });
+        let mut configs = vec![(&toml_config, 0)];
+        if let Some(name) = profile {
+            match toml_config.profile.get(name) {
+                Some(profile_config) => {
+                    let header = format!("[profile.{}", name);
+                    let start = s
+                        .lines()
+                        .position(|line| line.trim_start().starts_with(&header))
+                        .unwrap_or(0);
+                    configs.push((profile_config, start));
+                }
+                None => errors.push(
+                    ParseError::new(
+                        ParseErrorKind::InvalidValue,
+                        0,
+                        &format!("--profile {}", name),
+                        Some(|text| text.missing_profile)
+                    )
+                    .with_source(Source::Arguments)
+                )
+            }
+        }
+        // Most modes won't have a section, so there's nothing to say if there isn't one. A profile
+        // can have its own, which goes over the main config's.
+        if let Some(name) = mode {
+            let mut parents = vec![(&toml_config, String::new())];
+            let profile_config =
+                profile.and_then(|name| toml_config.profile.get(name).map(|config| (name, config)));
+            if let Some((profile, profile_config)) = profile_config {
+                parents.push((profile_config, format!("profile.{}.", profile)));
+            }
+            for (parent, prefix) in parents {
+                if let Some(mode_config) = parent.mode.get(name) {
+                    let header = format!("[{}mode.{}", prefix, name);
+                    let start = s
+                        .lines()
+                        .position(|line| line.trim_start().starts_with(&header))
+                        .unwrap_or(0);
+                    configs.push((mode_config, start));
+                }
+            }
+        }
+        // `settings` borrows the converted values, so they're kept here. Later values replace
+        // earlier ones, so profile and mode settings win.
+        let mut values = Vec::with_capacity(CONFIG_OPTIONS.len());
+        for &(config, start) in configs.iter() {
+            for &(table, options) in config.sections().iter() {
+                toml_values(s, table, options, start, &mut values, &mut errors);
+            }
         }
+        let binding_values = toml_config
+            .bindings
+            .iter()
+            .map(|(name, table)| {
+                let header = format!("[bindings.{}]", name);
+                let start = s
+                    .lines()
+                    .position(|line| line.trim() == header)
+                    .unwrap_or(0);
+                let mut values = Vec::new();
+                toml_values(s, table, &BINDING_OPTIONS, start, &mut values, &mut errors);
+                (name, values)
+            })
+            .collect::<Vec<_>>();
+        let settings = toml_settings(&values);
+        let sections = binding_values
+            .iter()
+            .map(|(name, values)| (name.as_str(), toml_settings(values)))
+            .collect();
+        let (mut config, setting_errors) = Self::from_settings(settings.clone(), overrides);
+        errors.extend(setting_errors);
+        config.add_bindings(&settings, sections, overrides, &mut errors);
+        sort_errors(&mut errors);
+        (config, errors)
     }
 
-    // Each line in the config file is put in a `HashMap<&str, (&str, usize, &str)>` where the key
-    // value is the setting name and the tuple type contains the RHS of the setting line, the line
-    // number, and the complete line (the last two are for error message purposes). If any line
-    // with a LHS that is not one of the valid setting names or a duplicate setting name is
-    // encountered, the config file is considered invalid. After that, we try to get the value for
-    // each "valid" key (each setting name) and parse it into the appropriate data type. Once that's
-    // done for each setting, we check a case where the config might be invalid, as well as two
-    // where some values might need to be adjusted. After that, we return the complete config.
-    pub fn parse(s: &str) -> Result<Self, ParseError> {
-        let mut settings = HashMap::with_capacity(35);
-        for (num, line) in s.lines().enumerate() {
-            // Skip blank lines
-            if line.len() == 0 {
-                continue;
-            }
-            // Skip comment lines
-            if let Some('#') = line.chars().take(1).next() {
-                continue;
+    // Each set of keys is parsed over the rest of the config, so that it only needs the keys it
+    // changes and they're checked the same way as the main ones. Problems outside of the set have
+    // already been found with the main keys.
+    fn add_bindings(
+        &mut self,
+        settings: &Settings,
+        sections: Vec<(&str, Settings)>,
+        overrides: &[(String, String)],
+        errors: &mut Vec<ParseError>
+    ) {
+        self.bindings = vec![Bindings::of(D_KEY_BINDINGS, self)];
+        for (name, section) in sections {
+            let lines = section
+                .values()
+                .map(|&(_, line_num, _, _)| line_num)
+                .collect::<Vec<_>>();
+            let mut combined = settings.clone();
+            combined.extend(section);
+            let (mut config, section_errors) = Self::from_settings(combined, overrides);
+            errors.extend(
+                section_errors
+                    .into_iter()
+                    .filter(|e| !e.in_arguments && e.file.is_none() && lines.contains(&e.line_num))
+            );
+            self.bindings.push(Bindings::of(name, &mut config));
+        }
+        let name = self.key_bindings.clone();
+        if name == D_KEY_BINDINGS || self.use_bindings(&name) {
+            return;
+        }
+        let correction: Option<Message> = Some(|text| text.missing_bindings);
+        errors.push(match settings.get("key_bindings") {
+            Some(&(_, line_num, line, source)) => {
+                ParseError::new(ParseErrorKind::InvalidValue, line_num, line, correction)
+                    .with_source(source)
             }
-            // Split into LHS and RHS at '='
-            let mut sections = line.split('=');
-            // Each valid line has a LHS
-            let lhs = sections
-                .next()
-                .ok_or_else(|| ParseError::new(ParseErrorKind::InvalidLineFormat, num, line, None))?
-                .trim();
-            // LHS length must be > 0
-            if lhs.len() == 0 {
-                return Err(ParseError::new(
-                    ParseErrorKind::InvalidLineFormat,
-                    num,
-                    line,
-                    Some("There must be a setting name on the left side of the equals sign.")
-                ));
-            }
-            // Each valid line has a RHS
-            let rhs = sections
-                .next()
-                .ok_or_else(|| ParseError::new(ParseErrorKind::InvalidLineFormat, num, line, None))?
-                .trim();
-            // RHS length must be > 0
-            if rhs.len() == 0 {
-                return Err(ParseError::new(
-                    ParseErrorKind::InvalidLineFormat,
-                    num,
-                    line,
-                    Some("There must be a value on the right side of the equals sign.")
-                ));
-            }
-            // Check that the LHS is a valid setting name
-            if CONFIG_OPTIONS.contains(&lhs) {
-                if settings.insert(lhs, (rhs, num, line)).is_some() {
-                    return Err(ParseError::new(
-                        ParseErrorKind::DuplicateSetting,
-                        num,
-                        line,
-                        None
-                    ));
+            None => ParseError::new(
+                ParseErrorKind::InvalidValue,
+                0,
+                &format!("--key-bindings {}", name),
+                correction
+            )
+            .with_source(Source::Arguments)
+        });
+        self.key_bindings = D_KEY_BINDINGS.to_string();
+    }
+
+    // Switch to the set of keys called `name`, keeping any changes made to the default keys for
+    // switching back to them. Returns whether there's a set with that name.
+    pub fn use_bindings(&mut self, name: &str) -> bool {
+        let bindings = match self.bindings.iter().find(|bindings| bindings.name == name) {
+            Some(bindings) => bindings.clone(),
+            None => return false
+        };
+        if self.key_bindings == D_KEY_BINDINGS {
+            let default = Bindings::of(D_KEY_BINDINGS, self);
+            self.bindings[0] = default;
+        }
+        bindings.apply(self);
+        self.key_bindings = name.to_owned();
+        true
+    }
+
+    pub fn is_default_bindings(&self) -> bool {
+        self.key_bindings == D_KEY_BINDINGS
+    }
+
+    // Only the keys that presets change are needed here.
+    fn keys(&self, setting: &str) -> &[Key] {
+        match setting {
+            "move_left" => &self.left,
+            "move_right" => &self.right,
+            "rotate_clockwise" => &self.rot_cw,
+            "rotate_anticlockwise" => &self.rot_acw,
+            "soft_drop" => &self.soft_drop,
+            "hard_drop" => &self.hard_drop,
+            "sonic_drop" => &self.sonic_drop,
+            "hold" => &self.hold,
+            "toggle_hint" => &self.toggle_hint,
+            _ => unreachable!()
+        }
+    }
+
+    fn keys_mut(&mut self, setting: &str) -> &mut Vec<Key> {
+        match setting {
+            "move_left" => &mut self.left,
+            "move_right" => &mut self.right,
+            "rotate_clockwise" => &mut self.rot_cw,
+            "rotate_anticlockwise" => &mut self.rot_acw,
+            "soft_drop" => &mut self.soft_drop,
+            "hard_drop" => &mut self.hard_drop,
+            "sonic_drop" => &mut self.sonic_drop,
+            "hold" => &mut self.hold,
+            "use_item" => &mut self.use_item,
+            "switch_target" => &mut self.switch_target,
+            "pause" => &mut self.pause,
+            "quit" => &mut self.quit,
+            "toggle_stats" => &mut self.toggle_stats,
+            "toggle_hint" => &mut self.toggle_hint,
+            "toggle_debug" => &mut self.toggle_debug,
+            "toggle_frame_step" => &mut self.toggle_frame_step,
+            "frame_step" => &mut self.frame_step,
+            "rewind" => &mut self.rewind,
+            "undo" => &mut self.undo,
+            "screenshot" => &mut self.screenshot,
+            "menu_up" => &mut self.menu_up,
+            "menu_down" => &mut self.menu_down,
+            "menu_select" => &mut self.menu_select,
+            "menu_back" => &mut self.menu_back,
+            _ => unreachable!()
+        }
+    }
+
+    // `overrides` are (setting name, value) pairs from the command line. They replace whatever the
+    // config file has for the same settings.
+    fn from_settings(
+        settings: Settings,
+        overrides: &[(String, String)]
+    ) -> (Self, Vec<ParseError>) {
+        let mut errors = Vec::new();
+        // Theme file contents and override arguments are borrowed by `settings`, so they have to
+        // outlive it.
+        let theme_path: String;
+        let theme_contents: String;
+        let override_lines = overrides
+            .iter()
+            .map(|(name, value)| format!("--{} {}", name.replace('_', "-"), value))
+            .collect::<Vec<_>>();
+        let mut settings = settings;
+        for (num, ((name, value), line)) in overrides.iter().zip(override_lines.iter()).enumerate()
+        {
+            let alias = setting_alias(name);
+            match CONFIG_OPTIONS.iter().find(|&option| option == name) {
+                Some(&option) => {
+                    settings.insert(
+                        option,
+                        (value.as_str(), num, line.as_str(), Source::Arguments)
+                    );
                 }
-            } else {
-                return Err({
+                None if alias.is_some() => {
+                    let option = alias.unwrap();
+                    errors.push(deprecated(num, line, option).with_source(Source::Arguments));
+                    settings.insert(
+                        option,
+                        (value.as_str(), num, line.as_str(), Source::Arguments)
+                    );
+                }
+                None => errors.push(
                     ParseError::new(
                         ParseErrorKind::UnknownSetting,
                         num,
                         line,
-                        Some(VALID_SETTINGS)
+                        Some(|text| text.unknown_option)
+                    )
+                    .with_source(Source::Arguments)
+                )
+            }
+        }
+        // Settings from the theme file only fill in what the config doesn't set itself.
+        let theme =
+            opt_general_parse::<String>(&settings, "theme", D_THEME, parse_theme_name, &mut errors);
+        if let Some(ref name) = theme {
+            let &(_, line_num, line, source) = settings.get("theme").unwrap();
+            let file_name = format!("{}.theme", name);
+            let theme_paths = paths::config_search_dirs()
+                .into_iter()
+                .map(|dir| dir.join(THEMES_DIR).join(&file_name))
+                .collect::<Vec<_>>();
+            theme_path = theme_paths
+                .iter()
+                .find(|path| path.exists())
+                .unwrap_or(&theme_paths[0])
+                .display()
+                .to_string();
+            match read_to_string(&theme_path) {
+                Ok(contents) => {
+                    theme_contents = contents;
+                    let mut theme_settings = HashMap::with_capacity(THEME_OPTIONS.len());
+                    parse_lines(
+                        &theme_contents,
+                        &THEME_OPTIONS,
+                        (|text| text.valid_theme_settings, THEME_SETTING_NAMES),
+                        Source::Theme(&theme_path),
+                        None,
+                        &mut theme_settings,
+                        &mut errors
+                    );
+                    for (key, value) in theme_settings {
+                        settings.entry(key).or_insert(value);
+                    }
+                }
+                Err(_) => errors.push(
+                    ParseError::new(
+                        ParseErrorKind::InvalidValue,
+                        line_num,
+                        line,
+                        Some(|text| text.theme_unreadable)
                     )
-                });
+                    .with_source(source)
+                )
             }
         }
         // Get a value for each setting.
@@ -664,148 +3114,752 @@ impl GameConfig {
             "fps_limiter",
             D_FPS_LIMITER,
             30..,
-            "Failed to parse FPS_LIMITER value.",
-            "FPS_LIMITER value is not greater than or equal to 30."
-        )?;
-        let board_width = parse_num_range::<usize, RangeFrom<usize>>(
+            |text| text.fps_limiter_unparsed,
+            |text| text.fps_limiter_range,
+            &mut errors
+        );
+        let is_auto = |key| {
+            settings
+                .get(key)
+                .is_some_and(|&(rhs, ..)| rhs.eq_ignore_ascii_case(AUTO_BOARD_SIZE))
+        };
+        let (auto_board_width, auto_board_height) =
+            (is_auto("board_width"), is_auto("board_height"));
+        let board_width = if auto_board_width {
+            D_BOARD_WIDTH
+        } else {
+            parse_num_range::<usize, RangeFrom<usize>>(
+                &settings,
+                "board_width",
+                D_BOARD_WIDTH,
+                1..,
+                |text| text.board_width_unparsed,
+                |text| text.board_width_range,
+                &mut errors
+            )
+        };
+        let board_height = if auto_board_height {
+            D_BOARD_HEIGHT
+        } else {
+            parse_num_range::<usize, RangeFrom<usize>>(
+                &settings,
+                "board_height",
+                D_BOARD_HEIGHT,
+                1..,
+                |text| text.board_height_unparsed,
+                |text| text.board_height_range,
+                &mut errors
+            )
+        };
+        let hidden_rows = parse_num_range::<usize, RangeInclusive<usize>>(
             &settings,
-            "board_width",
-            D_BOARD_WIDTH,
-            1..,
-            "Failed to parse board width value.",
-            "Board width value is not greater than or equal to 1."
-        )?;
-        let board_height = parse_num_range::<usize, RangeFrom<usize>>(
+            "hidden_rows",
+            D_HIDDEN_ROWS,
+            0..=MAX_HIDDEN_ROWS,
+            |text| text.hidden_rows_unparsed,
+            |text| text.hidden_rows_range,
+            &mut errors
+        );
+        let mode = general_parse::<Mode>(&settings, "mode", D_MODE, parse_mode, &mut errors);
+        let left = general_parse(
             &settings,
-            "board_height",
-            D_BOARD_HEIGHT,
-            1..,
-            "Failed to parse board height value.",
-            "Board height value is not greater than or equal to 1."
-        )?;
-        let mode = general_parse::<Mode>(&settings, "mode", D_MODE, parse_mode)?;
-        let left = general_parse::<KeyEvent>(&settings, "left", D_LEFT, parse_keyevent)?;
-        let right = general_parse::<KeyEvent>(&settings, "right", D_RIGHT, parse_keyevent)?;
-        let rot_cw = general_parse::<KeyEvent>(&settings, "rot_cw", D_ROT_CW, parse_keyevent)?;
-        let rot_acw = general_parse::<KeyEvent>(&settings, "rot_acw", D_ROT_ACW, parse_keyevent)?;
-        let soft_drop =
-            general_parse::<KeyEvent>(&settings, "soft_drop", D_SOFT_DROP, parse_keyevent)?;
-        let mut hard_drop =
-            opt_general_parse::<KeyEvent>(&settings, "hard_drop", D_HARD_DROP, parse_keyevent)?;
-        let mut hold = opt_general_parse::<KeyEvent>(&settings, "hold", D_HOLD, parse_keyevent)?;
-        let mut ghost_tetromino_character = opt_general_parse::<char>(
+            "move_left",
+            D_LEFT.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let right = general_parse(
+            &settings,
+            "move_right",
+            D_RIGHT.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let rot_cw = general_parse(
+            &settings,
+            "rotate_clockwise",
+            D_ROT_CW.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let rot_acw = general_parse(
+            &settings,
+            "rotate_anticlockwise",
+            D_ROT_ACW.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let soft_drop = general_parse(
+            &settings,
+            "soft_drop",
+            D_SOFT_DROP.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let hard_drop = general_parse(
+            &settings,
+            "hard_drop",
+            D_HARD_DROP.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let sonic_drop = general_parse(
+            &settings,
+            "sonic_drop",
+            D_SONIC_DROP.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let hold = general_parse(
+            &settings,
+            "hold",
+            D_HOLD.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let use_item = general_parse(
+            &settings,
+            "use_item",
+            D_USE_ITEM.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let switch_target = general_parse(
+            &settings,
+            "switch_target",
+            D_SWITCH_TARGET.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let pause = general_parse(
+            &settings,
+            "pause",
+            D_PAUSE.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let quit = general_parse(&settings, "quit", D_QUIT.to_vec(), parse_keys, &mut errors);
+        let toggle_stats = general_parse(
+            &settings,
+            "toggle_stats",
+            D_TOGGLE_STATS.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let toggle_hint = general_parse(
+            &settings,
+            "toggle_hint",
+            D_TOGGLE_HINT.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let toggle_debug = general_parse(
+            &settings,
+            "toggle_debug",
+            D_TOGGLE_DEBUG.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let toggle_frame_step = general_parse(
+            &settings,
+            "toggle_frame_step",
+            D_TOGGLE_FRAME_STEP.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let frame_step = general_parse(
+            &settings,
+            "frame_step",
+            D_FRAME_STEP.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let rewind = general_parse(
+            &settings,
+            "rewind",
+            D_REWIND.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let undo = general_parse(
+            &settings,
+            "undo",
+            D_UNDO.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let screenshot = general_parse(
+            &settings,
+            "screenshot",
+            D_SCREENSHOT.to_vec(),
+            parse_opt_keys,
+            &mut errors
+        );
+        let menu_up = general_parse(
+            &settings,
+            "menu_up",
+            D_MENU_UP.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let menu_down = general_parse(
+            &settings,
+            "menu_down",
+            D_MENU_DOWN.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let menu_select = general_parse(
+            &settings,
+            "menu_select",
+            D_MENU_SELECT.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let menu_back = general_parse(
+            &settings,
+            "menu_back",
+            D_MENU_BACK.to_vec(),
+            parse_keys,
+            &mut errors
+        );
+        let key_bindings = general_parse(
+            &settings,
+            "key_bindings",
+            D_KEY_BINDINGS.to_string(),
+            parse_key_bindings,
+            &mut errors
+        );
+        let ghost_tetromino_character = opt_general_parse::<char>(
             &settings,
             "ghost_tetromino_character",
             D_GHOST_TETROMINO_CHARACTER,
-            parse_char
-        )?;
-        let mut ghost_tetromino_color = opt_general_parse::<Color>(
+            parse_char,
+            &mut errors
+        );
+        let ghost_tetromino_color = opt_general_parse::<GhostColor>(
             &settings,
             "ghost_tetromino_color",
             D_GHOST_TETROMINO_COLOR,
-            parse_color
-        )?;
-        let cascade = general_parse::<bool>(&settings, "cascade", D_CASCADE, parse_bool)?;
+            parse_ghost_color,
+            &mut errors
+        );
+        let cascade =
+            general_parse::<bool>(&settings, "cascade", D_CASCADE, parse_bool, &mut errors);
+        let big_mode =
+            general_parse::<bool>(&settings, "big_mode", D_BIG_MODE, parse_bool, &mut errors);
+        let hold_limit = general_parse(
+            &settings,
+            "hold_limit",
+            D_HOLD_LIMIT,
+            parse_hold_limit,
+            &mut errors
+        );
+        let irs = general_parse::<bool>(&settings, "irs", D_IRS, parse_bool, &mut errors);
+        let input_conflicts = general_parse(
+            &settings,
+            "input_conflicts",
+            D_INPUT_CONFLICTS,
+            parse_input_conflicts,
+            &mut errors
+        );
+        let entry_delay = opt_parse_num_range::<u64, RangeInclusive<u64>>(
+            &settings,
+            "entry_delay",
+            D_ENTRY_DELAY,
+            0..=MAX_ENTRY_DELAY,
+            |text| text.entry_delay_unparsed,
+            |text| text.entry_delay_range,
+            &mut errors
+        );
+        let line_clear_delay = parse_num_range::<u32, RangeInclusive<u32>>(
+            &settings,
+            "line_clear_delay",
+            D_LINE_CLEAR_DELAY,
+            0..=MAX_LINE_CLEAR_DELAY,
+            |text| text.line_clear_delay_unparsed,
+            |text| text.line_clear_delay_range,
+            &mut errors
+        );
+        let lock_delay = opt_parse_num_range::<u64, RangeInclusive<u64>>(
+            &settings,
+            "lock_delay",
+            D_LOCK_DELAY,
+            0..=MAX_LOCK_DELAY,
+            |text| text.lock_delay_unparsed,
+            |text| text.lock_delay_range,
+            &mut errors
+        );
+        let lock_resets = general_parse(
+            &settings,
+            "lock_resets",
+            D_LOCK_RESETS,
+            parse_lock_resets,
+            &mut errors
+        );
+        let soft_drop_lock = general_parse(
+            &settings,
+            "soft_drop_lock",
+            D_SOFT_DROP_LOCK,
+            parse_soft_drop_lock,
+            &mut errors
+        );
         let const_level = opt_parse_num_range::<usize, RangeFrom<usize>>(
             &settings,
             "const_level",
             D_CONST_LEVEL,
             1..,
-            "Failed to parse constant level value.",
-            "Level value was not greater than or equal to 1."
-        )?;
-        let monochrome =
-            opt_general_parse::<Color>(&settings, "monochrome", D_MONOCHROME, parse_color)?;
-        let border_color =
-            general_parse::<Color>(&settings, "border_color", D_BORDER_COLOR, parse_color)?;
+            |text| text.const_level_unparsed,
+            |text| text.const_level_range,
+            &mut errors
+        );
+        let lines_per_level = parse_num_range::<usize, RangeFrom<usize>>(
+            &settings,
+            "lines_per_level",
+            D_LINES_PER_LEVEL,
+            1..,
+            |text| text.lines_per_level_unparsed,
+            |text| text.lines_per_level_range,
+            &mut errors
+        );
+        let level_goal = general_parse(
+            &settings,
+            "level_goal",
+            D_LEVEL_GOAL,
+            parse_level_goal,
+            &mut errors
+        );
+        let stats_export = opt_general_parse(
+            &settings,
+            "stats_export",
+            D_STATS_EXPORT,
+            parse_stats_export,
+            &mut errors
+        );
+        let fumen_export = general_parse::<bool>(
+            &settings,
+            "fumen_export",
+            D_FUMEN_EXPORT,
+            parse_bool,
+            &mut errors
+        );
+        let garbage_hole_repeat = parse_num_range::<usize, RangeInclusive<usize>>(
+            &settings,
+            "garbage_hole_repeat",
+            D_GARBAGE_HOLE_REPEAT,
+            0..=100,
+            |text| text.garbage_hole_repeat_unparsed,
+            |text| text.garbage_hole_repeat_range,
+            &mut errors
+        );
+        let items = general_parse::<bool>(&settings, "items", D_ITEMS, parse_bool, &mut errors);
+        let targeting = general_parse(
+            &settings,
+            "targeting",
+            D_TARGETING,
+            parse_targeting,
+            &mut errors
+        );
+        let rollback =
+            general_parse::<bool>(&settings, "rollback", D_ROLLBACK, parse_bool, &mut errors);
+        let input_delay = parse_num_range::<u64, RangeInclusive<u64>>(
+            &settings,
+            "input_delay",
+            D_INPUT_DELAY,
+            0..=MAX_INPUT_DELAY,
+            |text| text.input_delay_unparsed,
+            |text| text.input_delay_range,
+            &mut errors
+        );
+        let bot_delay = parse_num_range::<u64, RangeFrom<u64>>(
+            &settings,
+            "bot_delay",
+            D_BOT_DELAY,
+            0..,
+            |text| text.bot_delay_unparsed,
+            |text| text.bot_delay_range,
+            &mut errors
+        );
+        let ai_difficulty = general_parse(
+            &settings,
+            "ai_difficulty",
+            D_AI_DIFFICULTY,
+            parse_ai_difficulty,
+            &mut errors
+        );
+        let ai_style = general_parse(
+            &settings,
+            "ai_style",
+            D_AI_STYLE,
+            parse_ai_style,
+            &mut errors
+        );
+        let handicap_garbage = parse_num_range::<usize, RangeInclusive<usize>>(
+            &settings,
+            "handicap_garbage",
+            D_HANDICAP_GARBAGE,
+            0..=MAX_HANDICAP_GARBAGE,
+            |text| text.handicap_garbage_unparsed,
+            |text| text.handicap_garbage_range,
+            &mut errors
+        );
+        let handicap_gravity = parse_num_range::<isize, RangeInclusive<isize>>(
+            &settings,
+            "handicap_gravity",
+            D_HANDICAP_GRAVITY,
+            -MAX_HANDICAP_GRAVITY..=MAX_HANDICAP_GRAVITY,
+            |text| text.handicap_gravity_unparsed,
+            |text| text.handicap_gravity_range,
+            &mut errors
+        );
+        let handicap_preview = opt_parse_num_range::<usize, RangeFrom<usize>>(
+            &settings,
+            "handicap_preview",
+            D_HANDICAP_PREVIEW,
+            0..,
+            |text| text.handicap_preview_unparsed,
+            |text| text.handicap_preview_range,
+            &mut errors
+        );
+        let handicap_attack = parse_num_range::<u64, RangeInclusive<u64>>(
+            &settings,
+            "handicap_attack",
+            D_HANDICAP_ATTACK,
+            0..=MAX_HANDICAP_ATTACK,
+            |text| text.handicap_attack_unparsed,
+            |text| text.handicap_attack_range,
+            &mut errors
+        );
+        let countdown = parse_num_range::<u64, RangeInclusive<u64>>(
+            &settings,
+            "countdown",
+            D_COUNTDOWN,
+            0..=MAX_COUNTDOWN,
+            |text| text.countdown_unparsed,
+            |text| text.countdown_range,
+            &mut errors
+        );
+        let game_speed = parse_num_range::<u32, RangeInclusive<u32>>(
+            &settings,
+            "game_speed",
+            D_GAME_SPEED,
+            10..=400,
+            |text| text.game_speed_unparsed,
+            |text| text.game_speed_range,
+            &mut errors
+        );
+        let sound = general_parse::<bool>(&settings, "sound", D_SOUND, parse_bool, &mut errors);
+        let accessibility_cues = general_parse::<bool>(
+            &settings,
+            "accessibility_cues",
+            D_ACCESSIBILITY_CUES,
+            parse_bool,
+            &mut errors
+        );
+        let leaderboard_url = opt_general_parse::<String>(
+            &settings,
+            "leaderboard_url",
+            D_LEADERBOARD_URL,
+            parse_leaderboard_url,
+            &mut errors
+        );
+        let leaderboard_name = general_parse::<String>(
+            &settings,
+            "leaderboard_name",
+            D_LEADERBOARD_NAME.to_string(),
+            parse_leaderboard_name,
+            &mut errors
+        );
+        let mirror_file = opt_general_parse::<String>(
+            &settings,
+            "mirror_file",
+            D_MIRROR_FILE,
+            parse_mirror_file,
+            &mut errors
+        );
+        let discord_app_id = opt_general_parse::<u64>(
+            &settings,
+            "discord_app_id",
+            D_DISCORD_APP_ID,
+            parse_discord_app_id,
+            &mut errors
+        );
+        let language = general_parse(
+            &settings,
+            "language",
+            D_LANGUAGE,
+            parse_language,
+            &mut errors
+        );
+        let monochrome = opt_general_parse::<Color>(
+            &settings,
+            "monochrome",
+            D_MONOCHROME,
+            parse_color,
+            &mut errors
+        );
+        let color_support = general_parse::<Option<ColorSupport>>(
+            &settings,
+            "color_support",
+            D_COLOR_SUPPORT,
+            parse_color_support,
+            &mut errors
+        );
+        let border_color = general_parse::<Color>(
+            &settings,
+            "border_color",
+            D_BORDER_COLOR,
+            parse_color,
+            &mut errors
+        );
         let top_border_character = general_parse::<char>(
             &settings,
             "top_border_character",
             D_TOP_BORDER_CHARACTER,
-            parse_char
-        )?;
+            parse_char,
+            &mut errors
+        );
         let tl_corner_character = general_parse::<char>(
             &settings,
             "tl_corner_character",
             D_TL_CORNER_CHARACTER,
-            parse_char
-        )?;
+            parse_char,
+            &mut errors
+        );
         let left_border_character = general_parse::<char>(
             &settings,
             "left_border_character",
             D_LEFT_BORDER_CHARACTER,
-            parse_char
-        )?;
+            parse_char,
+            &mut errors
+        );
         let bl_corner_character = general_parse::<char>(
             &settings,
             "bl_corner_character",
             D_BL_CORNER_CHARACTER,
-            parse_char
-        )?;
+            parse_char,
+            &mut errors
+        );
         let bottom_border_character = general_parse::<char>(
             &settings,
             "bottom_border_character",
             D_BOTTOM_BORDER_CHARACTER,
-            parse_char
-        )?;
+            parse_char,
+            &mut errors
+        );
         let br_corner_character = general_parse::<char>(
             &settings,
             "br_corner_character",
             D_BR_CORNER_CHARACTER,
-            parse_char
-        )?;
+            parse_char,
+            &mut errors
+        );
         let right_border_character = general_parse::<char>(
             &settings,
             "right_border_character",
             D_RIGHT_BORDER_CHARACTER,
-            parse_char
-        )?;
+            parse_char,
+            &mut errors
+        );
         let tr_corner_character = general_parse::<char>(
             &settings,
             "tr_corner_character",
             D_TR_CORNER_CHARACTER,
-            parse_char
-        )?;
+            parse_char,
+            &mut errors
+        );
         let background_color = general_parse::<Color>(
             &settings,
             "background_color",
             D_BACKGROUND_COLOR,
-            parse_color
-        )?;
-        let block_character =
-            general_parse::<char>(&settings, "block_character", D_BLOCK_CHARACTER, parse_char)?;
+            parse_color,
+            &mut errors
+        );
+        let block_character = general_parse::<char>(
+            &settings,
+            "block_character",
+            D_BLOCK_CHARACTER,
+            parse_char,
+            &mut errors
+        );
         let block_size = parse_num_range::<usize, RangeFrom<usize>>(
             &settings,
             "block_size",
             D_BLOCK_SIZE,
             1..,
-            "Failed to parse block size value.",
-            "Block size must be greater than or equal to 1."
-        )?;
-        let mut i_color = general_parse(&settings, "i_color", D_I_COLOR, parse_color)?;
-        let mut j_color = general_parse(&settings, "j_color", D_J_COLOR, parse_color)?;
-        let mut l_color = general_parse(&settings, "l_color", D_L_COLOR, parse_color)?;
-        let mut s_color = general_parse(&settings, "s_color", D_S_COLOR, parse_color)?;
-        let mut z_color = general_parse(&settings, "z_color", D_Z_COLOR, parse_color)?;
-        let mut t_color = general_parse(&settings, "t_color", D_T_COLOR, parse_color)?;
-        let mut o_color = general_parse(&settings, "o_color", D_O_COLOR, parse_color)?;
-        if board_width <= (block_size * 4) || board_height <= (block_size * 4) {
+            |text| text.block_size_unparsed,
+            |text| text.block_size_range,
+            &mut errors
+        );
+        let renderer = general_parse(
+            &settings,
+            "renderer",
+            D_RENDERER,
+            parse_renderer,
+            &mut errors
+        );
+        let grid = opt_general_parse::<Grid>(&settings, "grid", D_GRID, parse_grid, &mut errors);
+        let grid_character = general_parse::<char>(
+            &settings,
+            "grid_character",
+            D_GRID_CHARACTER,
+            parse_char,
+            &mut errors
+        );
+        let grid_color = general_parse::<Color>(
+            &settings,
+            "grid_color",
+            D_GRID_COLOR,
+            parse_color,
+            &mut errors
+        );
+        let effects =
+            general_parse::<bool>(&settings, "effects", D_EFFECTS, parse_bool, &mut errors);
+        let danger_height = parse_num_range::<usize, RangeInclusive<usize>>(
+            &settings,
+            "danger_height",
+            D_DANGER_HEIGHT,
+            0..=100,
+            |text| text.danger_height_unparsed,
+            |text| text.danger_height_range,
+            &mut errors
+        );
+        let danger_pulse = general_parse::<bool>(
+            &settings,
+            "danger_pulse",
+            D_DANGER_PULSE,
+            parse_bool,
+            &mut errors
+        );
+        let splits = general_parse::<bool>(&settings, "splits", D_SPLITS, parse_bool, &mut errors);
+        let ghost_race = general_parse::<bool>(
+            &settings,
+            "ghost_race",
+            D_GHOST_RACE,
+            parse_bool,
+            &mut errors
+        );
+        let terminal_title = general_parse::<bool>(
+            &settings,
+            "terminal_title",
+            D_TERMINAL_TITLE,
+            parse_bool,
+            &mut errors
+        );
+        let background_art = opt_general_parse::<String>(
+            &settings,
+            "background_art",
+            D_BACKGROUND_ART,
+            parse_background_art,
+            &mut errors
+        );
+        let palette = opt_general_parse::<Palette>(
+            &settings,
+            "palette",
+            D_PALETTE,
+            parse_palette,
+            &mut errors
+        );
+        // Palette colors replace the defaults, so explicit piece colors still win.
+        let [d_i, d_j, d_l, d_s, d_z, d_t, d_o] = palette.map_or(
+            [
+                D_I_COLOR, D_J_COLOR, D_L_COLOR, D_S_COLOR, D_Z_COLOR, D_T_COLOR, D_O_COLOR
+            ],
+            Palette::colors
+        );
+        let mut i_color = general_parse(&settings, "i_color", d_i, parse_color, &mut errors);
+        let mut j_color = general_parse(&settings, "j_color", d_j, parse_color, &mut errors);
+        let mut l_color = general_parse(&settings, "l_color", d_l, parse_color, &mut errors);
+        let mut s_color = general_parse(&settings, "s_color", d_s, parse_color, &mut errors);
+        let mut z_color = general_parse(&settings, "z_color", d_z, parse_color, &mut errors);
+        let mut t_color = general_parse(&settings, "t_color", d_t, parse_color, &mut errors);
+        let mut o_color = general_parse(&settings, "o_color", d_o, parse_color, &mut errors);
+        let i_character = opt_general_parse(
+            &settings,
+            "i_character",
+            D_I_CHARACTER,
+            parse_char,
+            &mut errors
+        );
+        let j_character = opt_general_parse(
+            &settings,
+            "j_character",
+            D_J_CHARACTER,
+            parse_char,
+            &mut errors
+        );
+        let l_character = opt_general_parse(
+            &settings,
+            "l_character",
+            D_L_CHARACTER,
+            parse_char,
+            &mut errors
+        );
+        let s_character = opt_general_parse(
+            &settings,
+            "s_character",
+            D_S_CHARACTER,
+            parse_char,
+            &mut errors
+        );
+        let z_character = opt_general_parse(
+            &settings,
+            "z_character",
+            D_Z_CHARACTER,
+            parse_char,
+            &mut errors
+        );
+        let t_character = opt_general_parse(
+            &settings,
+            "t_character",
+            D_T_CHARACTER,
+            parse_char,
+            &mut errors
+        );
+        let o_character = opt_general_parse(
+            &settings,
+            "o_character",
+            D_O_CHARACTER,
+            parse_char,
+            &mut errors
+        );
+        // Big pieces are twice as wide, so the board needs room for a big I piece, and an even width
+        // so that they can reach both walls.
+        if big_mode
+            && !auto_board_width
+            && (board_width < BIG_MODE_MIN_WIDTH || !board_width.is_multiple_of(2))
+        {
+            if let Some(&(_, line_num, line, source)) = settings.get("big_mode") {
+                errors.push(
+                    ParseError::new(
+                        ParseErrorKind::InvalidValue,
+                        line_num,
+                        line,
+                        Some(|text| text.big_mode_width)
+                    )
+                    .with_source(source)
+                );
+            }
+        }
+        // Automatic sizes are always made big enough later on.
+        if (!auto_board_width && board_width <= (block_size * 4))
+            || (!auto_board_height && board_height <= (block_size * 4))
+        {
             // The board must be at least as wide and tall as an I piece for any given block size.
-            let (line_num, line) = if let Some(&(_, line_num, line)) = settings.get("block_size") {
-                (line_num, line)
-            } else if let Some(&(_, line_num, line)) = settings.get("board_height") {
-                (line_num, line)
-            } else if let Some(&(_, line_num, line)) = settings.get("board_width") {
-                (line_num, line)
-            } else {
-                unreachable!()
-            };
-            return Err(ParseError::new(
-                ParseErrorKind::InvalidValue,
-                line_num,
-                line,
-                Some("Board dimensions must be greater than or equal to block size.")
-            ));
+            let &(_, line_num, line, source) = settings
+                .get("block_size")
+                .or_else(|| settings.get("board_height"))
+                .or_else(|| settings.get("board_width"))
+                .unwrap();
+            errors.push(
+                ParseError::new(
+                    ParseErrorKind::InvalidValue,
+                    line_num,
+                    line,
+                    Some(|text| text.board_smaller_than_block)
+                )
+                .with_source(source)
+            );
         } else if monochrome.is_some() {
             // Monochrome setting overrides piece colors, but not border or background colors.
             i_color = monochrome.unwrap();
@@ -815,19 +3869,14 @@ impl GameConfig {
             z_color = monochrome.unwrap();
             t_color = monochrome.unwrap();
             o_color = monochrome.unwrap();
-        } else {
-            // Classic mode doesn't have the ghost tetromino, hard drops, or holds.
-            if mode == Mode::Classic {
-                hard_drop = None;
-                hold = None;
-                ghost_tetromino_character = None;
-                ghost_tetromino_color = None;
-            }
         }
-        Ok(GameConfig {
+        let config = GameConfig {
             fps_limiter,
             board_width,
             board_height,
+            hidden_rows,
+            auto_board_width,
+            auto_board_height,
             mode,
             left,
             right,
@@ -835,12 +3884,67 @@ impl GameConfig {
             rot_acw,
             soft_drop,
             hard_drop,
+            sonic_drop,
             hold,
+            use_item,
+            switch_target,
+            pause,
+            quit,
+            toggle_stats,
+            toggle_hint,
+            toggle_debug,
+            toggle_frame_step,
+            frame_step,
+            rewind,
+            undo,
+            screenshot,
+            menu_up,
+            menu_down,
+            menu_select,
+            menu_back,
+            key_bindings,
+            bindings: Vec::new(),
             ghost_tetromino_character,
             ghost_tetromino_color,
             cascade,
+            big_mode,
+            hold_limit,
+            irs,
+            input_conflicts,
+            entry_delay,
+            line_clear_delay,
+            lock_delay,
+            lock_resets,
+            soft_drop_lock,
             const_level,
+            lines_per_level,
+            level_goal,
+            stats_export,
+            fumen_export,
+            garbage_hole_repeat,
+            items,
+            targeting,
+            rollback,
+            input_delay,
+            bot_delay,
+            ai_difficulty,
+            ai_style,
+            handicap_garbage,
+            handicap_gravity,
+            handicap_preview,
+            handicap_attack,
+            countdown,
+            game_speed,
+            sound,
+            accessibility_cues,
+            leaderboard_url,
+            leaderboard_name,
+            mirror_file,
+            discord_app_id,
+            language,
             monochrome,
+            color_support,
+            theme,
             border_color,
             top_border_character,
             tl_corner_character,
@@ -853,29 +3957,447 @@ impl GameConfig {
             background_color,
             block_character,
             block_size,
+            renderer,
+            grid,
+            grid_character,
+            grid_color,
+            effects,
+            danger_height,
+            danger_pulse,
+            splits,
+            ghost_race,
+            terminal_title,
+            background_art,
+            palette,
             i_color,
             j_color,
             l_color,
             s_color,
             z_color,
             t_color,
-            o_color
-        })
+            o_color,
+            i_character,
+            j_character,
+            l_character,
+            s_character,
+            z_character,
+            t_character,
+            o_character
+        };
+        (config, errors)
+    }
+
+    // Work out the `auto` board sizes as the biggest that fit on a terminal of this many columns
+    // and rows, beside the panel and inside the border. Boards are never made smaller than a block
+    // size allows, even if that means they don't fit.
+    pub fn fit_board(&mut self, columns: u16, rows: u16) {
+        // How many board squares fit across and down in each character.
+        let (across, down) = match self.renderer {
+            Renderer::Blocks => (1, 1),
+            Renderer::Braille => (2, 4)
+        };
+        let smallest = self.block_size * 4 + 1;
+        let inner_columns = (columns as usize).saturating_sub(PANEL_WIDTH + 2);
+        let inner_rows = (rows as usize).saturating_sub(2);
+        if self.auto_board_width {
+            let width = (inner_columns * across / self.block_size).max(smallest);
+            // Big mode needs an even width, which is rounded down unless that makes it too small.
+            self.board_width = if self.big_mode {
+                let even = width - width % 2;
+                if even < smallest {
+                    even + 2
+                } else {
+                    even.max(BIG_MODE_MIN_WIDTH)
+                }
+            } else {
+                width
+            };
+        }
+        if self.auto_board_height {
+            self.board_height = (inner_rows * down / self.block_size).max(smallest);
+        }
+    }
+
+    // How the built-in AI plays.
+    pub fn ai_personality(&self) -> Personality {
+        self.ai_difficulty.personality(self.ai_style)
+    }
+
+    // The settings the game itself needs to know about.
+    pub fn rules(&self) -> Rules {
+        Rules {
+            board_width: self.board_width,
+            board_height: self.board_height,
+            hidden_rows: self.hidden_rows,
+            mode: self.mode,
+            cascade: self.cascade,
+            big: self.big_mode,
+            hold_limit: self.hold_limit,
+            lines_per_level: self.lines_per_level,
+            const_level: self.const_level,
+            level_goal: self.level_goal,
+            entry_delay: self.entry_delay,
+            line_clear_delay: self.line_clear_delay,
+            lock_delay: self.lock_delay,
+            lock_resets: self.lock_resets,
+            soft_drop_lock: self.soft_drop_lock,
+            garbage_hole_repeat: self.garbage_hole_repeat,
+            items: self.items,
+            handicap: Handicap {
+                garbage_rows: self.handicap_garbage,
+                gravity_offset: self.handicap_gravity,
+                preview: self.handicap_preview,
+                attack_percent: self.handicap_attack
+            }
+        }
+    }
+
+    pub fn set_rules(&mut self, rules: &Rules) {
+        self.board_width = rules.board_width;
+        self.board_height = rules.board_height;
+        self.hidden_rows = rules.hidden_rows;
+        self.mode = rules.mode;
+        self.cascade = rules.cascade;
+        self.big_mode = rules.big;
+        self.hold_limit = rules.hold_limit;
+        self.lines_per_level = rules.lines_per_level;
+        self.const_level = rules.const_level;
+        self.level_goal = rules.level_goal;
+        self.entry_delay = rules.entry_delay;
+        self.line_clear_delay = rules.line_clear_delay;
+        self.lock_delay = rules.lock_delay;
+        self.lock_resets = rules.lock_resets;
+        self.soft_drop_lock = rules.soft_drop_lock;
+        self.garbage_hole_repeat = rules.garbage_hole_repeat;
+        self.items = rules.items;
+        self.handicap_garbage = rules.handicap.garbage_rows;
+        self.handicap_gravity = rules.handicap.gravity_offset;
+        self.handicap_preview = rules.handicap.preview;
+        self.handicap_attack = rules.handicap.attack_percent;
+    }
+
+    pub fn piece_color(&self, tetromino: Tetromino) -> Color {
+        match tetromino {
+            Tetromino::I => self.i_color,
+            Tetromino::J => self.j_color,
+            Tetromino::L => self.l_color,
+            Tetromino::S => self.s_color,
+            Tetromino::Z => self.z_color,
+            Tetromino::T => self.t_color,
+            Tetromino::O => self.o_color
+        }
+    }
+
+    pub fn piece_character(&self, tetromino: Tetromino) -> char {
+        match tetromino {
+            Tetromino::I => self.i_character,
+            Tetromino::J => self.j_character,
+            Tetromino::L => self.l_character,
+            Tetromino::S => self.s_character,
+            Tetromino::Z => self.z_character,
+            Tetromino::T => self.t_character,
+            Tetromino::O => self.o_character
+        }
+        .unwrap_or(self.block_character)
+    }
+
+    // Copy over everything that only changes how the game looks. Used to reload the config
+    // without restarting.
+    pub fn apply_appearance(&mut self, other: GameConfig) {
+        self.ghost_tetromino_character = other.ghost_tetromino_character;
+        self.ghost_tetromino_color = other.ghost_tetromino_color;
+        self.monochrome = other.monochrome;
+        self.color_support = other.color_support;
+        self.theme = other.theme;
+        self.border_color = other.border_color;
+        self.top_border_character = other.top_border_character;
+        self.tl_corner_character = other.tl_corner_character;
+        self.left_border_character = other.left_border_character;
+        self.bl_corner_character = other.bl_corner_character;
+        self.bottom_border_character = other.bottom_border_character;
+        self.br_corner_character = other.br_corner_character;
+        self.right_border_character = other.right_border_character;
+        self.tr_corner_character = other.tr_corner_character;
+        self.background_color = other.background_color;
+        self.block_character = other.block_character;
+        // The board size isn't reloaded, so the new block size has to fit the current one.
+        if self.board_width > other.block_size * 4 && self.board_height > other.block_size * 4 {
+            self.block_size = other.block_size;
+        }
+        self.language = other.language;
+        self.renderer = other.renderer;
+        self.grid = other.grid;
+        self.grid_character = other.grid_character;
+        self.grid_color = other.grid_color;
+        self.effects = other.effects;
+        self.danger_height = other.danger_height;
+        self.danger_pulse = other.danger_pulse;
+        self.splits = other.splits;
+        self.ghost_race = other.ghost_race;
+        self.terminal_title = other.terminal_title;
+        self.background_art = other.background_art;
+        self.palette = other.palette;
+        self.i_color = other.i_color;
+        self.j_color = other.j_color;
+        self.l_color = other.l_color;
+        self.s_color = other.s_color;
+        self.z_color = other.z_color;
+        self.t_color = other.t_color;
+        self.o_color = other.o_color;
+        self.i_character = other.i_character;
+        self.j_character = other.j_character;
+        self.l_character = other.l_character;
+        self.s_character = other.s_character;
+        self.z_character = other.z_character;
+        self.t_character = other.t_character;
+        self.o_character = other.o_character;
+    }
+
+    // Serialized from the legacy format so that there's only one place listing how each setting is
+    // written out.
+    pub fn to_toml(&self) -> String {
+        let settings = self.setting_values();
+        let section = |options: &[&str]| {
+            options
+                .iter()
+                .map(|&key| (key.to_owned(), toml_value(key, &settings[key])))
+                .collect::<Table>()
+        };
+        let toml_config = TomlConfig {
+            game: section(&TOML_GAME_OPTIONS),
+            keys: section(&TOML_KEYS_OPTIONS),
+            colors: section(&TOML_COLORS_OPTIONS),
+            appearance: section(&TOML_APPEARANCE_OPTIONS),
+            profile: BTreeMap::new(),
+            mode: BTreeMap::new(),
+            bindings: BTreeMap::new()
+        };
+        toml::to_string(&toml_config).unwrap()
+    }
+
+    // The config with a comment above each setting saying what it does, what it accepts, and what
+    // its default is, for writing out as a new config file.
+    pub fn to_commented_toml(&self) -> String {
+        let sections = [
+            ("game", &TOML_GAME_OPTIONS[..]),
+            ("keys", &TOML_KEYS_OPTIONS[..]),
+            ("colors", &TOML_COLORS_OPTIONS[..]),
+            ("appearance", &TOML_APPEARANCE_OPTIONS[..])
+        ];
+        let mut out = format!("{}# Switches are true or false.\n", COMMENTED_HEADER);
+        for &(section, options) in sections.iter() {
+            out += &format!("\n[{}]\n", section);
+            out +=
+                &self.commented_settings(options, |key, value| toml_value(key, value).to_string());
+        }
+        out
+    }
+
+    // The same in the legacy format.
+    pub fn to_commented_string(&self) -> String {
+        let mut out = format!("{}# Switches are t or f.\n\n", COMMENTED_HEADER);
+        out += &self.commented_settings(&CONFIG_OPTIONS, |_, value| value.to_owned());
+        out
+    }
+
+    // `options` with their comments, each written by `write` and followed by a blank line.
+    fn commented_settings<F: Fn(&str, &str) -> String>(
+        &self,
+        options: &[&str],
+        write: F
+    ) -> String {
+        let (values, defaults) = (
+            self.setting_values(),
+            GameConfig::default().setting_values()
+        );
+        let mut out = String::new();
+        for &key in options.iter() {
+            let help = SETTING_HELP
+                .iter()
+                .find(|&&(name, _)| name == key)
+                .map_or("", |&(_, help)| help);
+            out += &format!(
+                "# {}\n# Default: {}\n{} = {}\n\n",
+                help,
+                write(key, &defaults[key]),
+                key,
+                write(key, &values[key])
+            );
+        }
+        out
+    }
+
+    // Each setting's text in the legacy format.
+    fn setting_values(&self) -> HashMap<String, String> {
+        self.to_string()
+            .lines()
+            .filter_map(|line| {
+                let mut sides = line.splitn(2, " = ");
+                Some((sides.next()?.to_owned(), sides.next()?.to_owned()))
+            })
+            .collect()
+    }
+
+    // Settings whose text differs from `before`'s, in the order they're listed in.
+    fn changed_settings(&self, before: &GameConfig) -> Vec<(&'static str, String)> {
+        let (after, before) = (self.setting_values(), before.setting_values());
+        CONFIG_OPTIONS
+            .iter()
+            .filter(|&&key| after.get(key) != before.get(key))
+            .map(|&key| (key, after[key].clone()))
+            .collect()
+    }
+
+    // The text of a legacy config with this config's settings in place of the ones it was parsed
+    // into, which are given as `before`. Only lines for settings that have changed are replaced,
+    // so comments, blank lines, and the order of settings are kept. Changed settings that aren't
+    // in the file yet go at the end of the settings before any mode sections, which are left
+    // alone.
+    pub fn rewrite(&self, before: &GameConfig, contents: &str) -> String {
+        let mut changed = self.changed_settings(before);
+        let all = contents.lines().collect::<Vec<_>>();
+        let sections = all
+            .iter()
+            .position(|line| section_header(line).is_some())
+            .unwrap_or(all.len());
+        let mut lines = Vec::new();
+        for &line in &all[..sections] {
+            let key = match line.split_once('=') {
+                Some((lhs, _)) if !line.starts_with('#') => lhs.trim(),
+                _ => ""
+            };
+            // Old names are replaced along with the value.
+            let key = setting_alias(key).unwrap_or(key);
+            match changed
+                .iter()
+                .position(|&(changed_key, _)| changed_key == key)
+            {
+                Some(i) => {
+                    let (key, value) = changed.remove(i);
+                    lines.push(format!("{} = {}", key, value));
+                }
+                None => lines.push(line.to_owned())
+            }
+        }
+        // Blank lines before the first section stay between it and the settings.
+        let end = if sections < all.len() {
+            lines
+                .iter()
+                .rposition(|line: &String| !line.trim().is_empty())
+                .map_or(0, |i| i + 1)
+        } else {
+            lines.len()
+        };
+        let added = changed
+            .into_iter()
+            .map(|(key, value)| format!("{} = {}", key, value));
+        lines.splice(end..end, added);
+        lines.extend(all[sections..].iter().map(|&line| line.to_owned()));
+        lines.join("\n") + "\n"
     }
 
-    pub fn write_to_file(&self, file: &mut File) -> IoResult<()> {
-        file.write_all(self.to_string().as_bytes())
+    // The same for a TOML config. Settings are only looked for in the four main sections, so
+    // profiles are left alone, and comments after a value are kept. Changed settings that aren't
+    // in the file go at the end of their section, which is added if it isn't there either.
+    pub fn rewrite_toml(&self, before: &GameConfig, contents: &str) -> String {
+        let mut changed = self.changed_settings(before);
+        let mut lines = Vec::new();
+        let mut section = "";
+        // Where the last line of each main section is, for adding settings after.
+        let mut section_ends = HashMap::new();
+        for line in contents.lines() {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') {
+                section = trimmed.trim_matches(|c| c == '[' || c == ']').trim();
+                lines.push(line.to_owned());
+                section_ends.insert(section, lines.len());
+                continue;
+            }
+            let setting = match line.split_once('=') {
+                Some((lhs, rhs)) if !trimmed.starts_with('#') => {
+                    let key = lhs.trim().trim_matches(|c| c == '"' || c == '\'');
+                    let key = setting_alias(key).unwrap_or(key);
+                    changed
+                        .iter()
+                        .position(|&(changed_key, _)| {
+                            changed_key == key && toml_section(key) == section
+                        })
+                        .map(|i| (lhs, rhs, i))
+                }
+                _ => None
+            };
+            match setting {
+                Some((lhs, rhs, i)) => {
+                    let (key, value) = changed.remove(i);
+                    let comment = toml_comment(rhs).map_or(String::new(), |c| format!(" {}", c));
+                    let indent = &lhs[..lhs.len() - lhs.trim_start().len()];
+                    lines.push(format!(
+                        "{}{} = {}{}",
+                        indent,
+                        key,
+                        toml_value(key, &value),
+                        comment
+                    ));
+                }
+                None => lines.push(line.to_owned())
+            }
+            if !trimmed.is_empty() {
+                section_ends.insert(section, lines.len());
+            }
+        }
+        let mut added = Vec::<(&str, Vec<String>)>::new();
+        for (key, value) in changed {
+            let section = toml_section(key);
+            let line = format!("{} = {}", key, toml_value(key, &value));
+            match added.iter_mut().find(|(name, _)| *name == section) {
+                Some((_, section_lines)) => section_lines.push(line),
+                None => added.push((section, vec![line]))
+            }
+        }
+        let mut output = Vec::with_capacity(lines.len());
+        for (i, line) in lines.into_iter().enumerate() {
+            output.push(line);
+            for (section, section_lines) in added.iter_mut() {
+                if section_ends.get(section) == Some(&(i + 1)) {
+                    output.append(section_lines);
+                }
+            }
+        }
+        // Anything left over is for a section the file doesn't have.
+        for (section, section_lines) in added {
+            if section_lines.is_empty() {
+                continue;
+            }
+            if !output.is_empty() {
+                output.push(String::new());
+            }
+            output.push(format!("[{}]", section));
+            output.extend(section_lines);
+        }
+        output.join("\n") + "\n"
     }
 }
 
 impl Display for GameConfig {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        // The main keys are written whichever set is in use, since that's what the file has for
+        // them.
+        let mut defaults;
+        let keys = match self.bindings.first() {
+            Some(default) if !self.is_default_bindings() => {
+                defaults = self.clone();
+                default.apply(&mut defaults);
+                &defaults
+            }
+            _ => self
+        };
         write!(
             f,
             "\
              fps_limiter = {}\n\
              board_width = {}\n\
              board_height = {}\n\
+             hidden_rows = {}\n\
              mode = {}\n\
              move_left = {}\n\
              move_right = {}\n\
@@ -883,12 +4405,66 @@ impl Display for GameConfig {
              rotate_anticlockwise = {}\n\
              soft_drop = {}\n\
              hard_drop = {}\n\
+             sonic_drop = {}\n\
              hold = {}\n\
+             use_item = {}\n\
+             switch_target = {}\n\
+             pause = {}\n\
+             quit = {}\n\
+             toggle_stats = {}\n\
+             toggle_hint = {}\n\
+             toggle_debug = {}\n\
+             toggle_frame_step = {}\n\
+             frame_step = {}\n\
+             rewind = {}\n\
+             undo = {}\n\
+             screenshot = {}\n\
+             menu_up = {}\n\
+             menu_down = {}\n\
+             menu_select = {}\n\
+             menu_back = {}\n\
+             key_bindings = {}\n\
              ghost_tetromino_character = {}\n\
              ghost_tetromino_color = {}\n\
              cascade = {}\n\
+             big_mode = {}\n\
+             hold_limit = {}\n\
+             irs = {}\n\
+             input_conflicts = {}\n\
+             entry_delay = {}\n\
+             line_clear_delay = {}\n\
+             lock_delay = {}\n\
+             lock_resets = {}\n\
+             soft_drop_lock = {}\n\
              const_level = {}\n\
+             lines_per_level = {}\n\
+             level_goal = {}\n\
+             stats_export = {}\n\
+             fumen_export = {}\n\
+             garbage_hole_repeat = {}\n\
+             items = {}\n\
+             targeting = {}\n\
+             rollback = {}\n\
+             input_delay = {}\n\
+             bot_delay = {}\n\
+             ai_difficulty = {}\n\
+             ai_style = {}\n\
+             handicap_garbage = {}\n\
+             handicap_gravity = {}\n\
+             handicap_preview = {}\n\
+             handicap_attack = {}\n\
+             countdown = {}\n\
+             game_speed = {}\n\
+             sound = {}\n\
+             accessibility_cues = {}\n\
+             leaderboard_url = {}\n\
+             leaderboard_name = {}\n\
+             mirror_file = {}\n\
+             discord_app_id = {}\n\
+             language = {}\n\
              monochrome = {}\n\
+             color_support = {}\n\
+             theme = {}\n\
              border_color = {}\n\
              top_border_character = {}\n\
              tl_corner_character = {}\n\
@@ -901,29 +4477,107 @@ impl Display for GameConfig {
              background_color = {}\n\
              block_character = {}\n\
              block_size = {}\n\
+             renderer = {}\n\
+             grid = {}\n\
+             grid_character = {}\n\
+             grid_color = {}\n\
+             effects = {}\n\
+             danger_height = {}\n\
+             danger_pulse = {}\n\
+             splits = {}\n\
+             ghost_race = {}\n\
+             terminal_title = {}\n\
+             background_art = {}\n\
+             palette = {}\n\
              i_color = {}\n\
              j_color = {}\n\
              l_color = {}\n\
              s_color = {}\n\
              z_color = {}\n\
              t_color = {}\n\
-             o_color = {}\n",
+             o_color = {}\n\
+             i_character = {}\n\
+             j_character = {}\n\
+             l_character = {}\n\
+             s_character = {}\n\
+             z_character = {}\n\
+             t_character = {}\n\
+             o_character = {}\n",
             opt_u64_string(&self.fps_limiter),
-            self.board_width,
-            self.board_height,
+            board_size_string(self.board_width, self.auto_board_width),
+            board_size_string(self.board_height, self.auto_board_height),
+            self.hidden_rows,
             self.mode,
-            keyevent_string(&self.left),
-            keyevent_string(&self.right),
-            keyevent_string(&self.rot_cw),
-            keyevent_string(&self.rot_acw),
-            keyevent_string(&self.soft_drop),
-            opt_keyevent_string(&self.hard_drop),
-            opt_keyevent_string(&self.hold),
+            keys_string(&keys.left),
+            keys_string(&keys.right),
+            keys_string(&keys.rot_cw),
+            keys_string(&keys.rot_acw),
+            keys_string(&keys.soft_drop),
+            keys_string(&keys.hard_drop),
+            keys_string(&keys.sonic_drop),
+            keys_string(&keys.hold),
+            keys_string(&keys.use_item),
+            keys_string(&keys.switch_target),
+            keys_string(&keys.pause),
+            keys_string(&keys.quit),
+            keys_string(&keys.toggle_stats),
+            keys_string(&keys.toggle_hint),
+            keys_string(&keys.toggle_debug),
+            keys_string(&keys.toggle_frame_step),
+            keys_string(&keys.frame_step),
+            keys_string(&keys.rewind),
+            keys_string(&keys.undo),
+            keys_string(&keys.screenshot),
+            keys_string(&keys.menu_up),
+            keys_string(&keys.menu_down),
+            keys_string(&keys.menu_select),
+            keys_string(&keys.menu_back),
+            self.key_bindings,
             opt_char_string(&self.ghost_tetromino_character),
-            opt_color_string(&self.ghost_tetromino_color),
+            self.ghost_tetromino_color
+                .map_or("none".to_string(), |color| color.to_string()),
             bool_string(&self.cascade),
+            bool_string(&self.big_mode),
+            count_string(&self.hold_limit),
+            bool_string(&self.irs),
+            self.input_conflicts,
+            opt_u64_string(&self.entry_delay),
+            self.line_clear_delay,
+            opt_u64_string(&self.lock_delay),
+            count_string(&self.lock_resets),
+            self.soft_drop_lock
+                .map_or("auto".to_string(), |lock| bool_string(&lock)),
             opt_usize_string(&self.const_level),
+            self.lines_per_level,
+            self.level_goal,
+            self.stats_export
+                .map_or("none".to_string(), |export| export.to_string()),
+            bool_string(&self.fumen_export),
+            self.garbage_hole_repeat,
+            bool_string(&self.items),
+            self.targeting,
+            bool_string(&self.rollback),
+            self.input_delay,
+            self.bot_delay,
+            self.ai_difficulty,
+            self.ai_style,
+            self.handicap_garbage,
+            self.handicap_gravity,
+            opt_usize_string(&self.handicap_preview),
+            self.handicap_attack,
+            self.countdown,
+            self.game_speed,
+            bool_string(&self.sound),
+            bool_string(&self.accessibility_cues),
+            self.leaderboard_url.as_ref().map_or("none", String::as_str),
+            self.leaderboard_name,
+            self.mirror_file.as_ref().map_or("none", String::as_str),
+            self.discord_app_id
+                .map_or("none".to_string(), |id| id.to_string()),
+            self.language,
             opt_color_string(&self.monochrome),
+            color_support_string(&self.color_support),
+            self.theme.as_ref().map_or("none", String::as_str),
             color_string(&self.border_color),
             self.top_border_character,
             self.tl_corner_character,
@@ -936,13 +4590,33 @@ impl Display for GameConfig {
             color_string(&self.background_color),
             self.block_character,
             self.block_size,
+            self.renderer,
+            self.grid
+                .map_or("none".to_string(), |grid| grid.to_string()),
+            self.grid_character,
+            color_string(&self.grid_color),
+            bool_string(&self.effects),
+            self.danger_height,
+            bool_string(&self.danger_pulse),
+            bool_string(&self.splits),
+            bool_string(&self.ghost_race),
+            bool_string(&self.terminal_title),
+            self.background_art.as_ref().map_or("none", String::as_str),
+            opt_palette_string(&self.palette),
             color_string(&self.i_color),
             color_string(&self.j_color),
             color_string(&self.l_color),
             color_string(&self.s_color),
             color_string(&self.z_color),
             color_string(&self.t_color),
-            color_string(&self.o_color)
+            color_string(&self.o_color),
+            opt_char_string(&self.i_character),
+            opt_char_string(&self.j_character),
+            opt_char_string(&self.l_character),
+            opt_char_string(&self.s_character),
+            opt_char_string(&self.z_character),
+            opt_char_string(&self.t_character),
+            opt_char_string(&self.o_character)
         )
     }
 }
@@ -955,30 +4629,14 @@ fn opt_u64_string(opt_u64: &Option<u64>) -> String {
     }
 }
 
-fn keyevent_string(keyevent: &KeyEvent) -> String {
-    match keyevent {
-        KeyEvent::Char(c) => match c {
-            ' ' => "space".to_string(),
-            _ => format!("{}", c)
-        },
-        KeyEvent::Left => "left".to_string(),
-        KeyEvent::Right => "right".to_string(),
-        KeyEvent::Up => "up".to_string(),
-        KeyEvent::Down => "down".to_string(),
-        KeyEvent::ShiftLeft => "lshift".to_string(),
-        KeyEvent::ShiftRight => "rshift".to_string(),
-        KeyEvent::CtrlLeft => "lctrl".to_string(),
-        KeyEvent::CtrlRight => "rctrl".to_string(),
-        KeyEvent::Esc => "esc".to_string(),
-        _ => unreachable!()
-    }
-}
-
-fn opt_keyevent_string(opt_keyevent: &Option<KeyEvent>) -> String {
-    if let Some(ref keyevent) = opt_keyevent {
-        keyevent_string(keyevent)
-    } else {
+pub(crate) fn keys_string(keys: &[Key]) -> String {
+    if keys.is_empty() {
         "none".to_string()
+    } else {
+        keys.iter()
+            .map(Key::to_string)
+            .collect::<Vec<_>>()
+            .join(",")
     }
 }
 
@@ -990,7 +4648,7 @@ fn opt_char_string(opt_char: &Option<char>) -> String {
     }
 }
 
-fn color_string(color: &Color) -> String {
+pub(crate) fn color_string(color: &Color) -> String {
     match color {
         Color::Rgb { r, g, b } => format!("rgb {},{},{}", r, g, b),
         Color::AnsiValue(ansivalue) => format!("ansi {}", ansivalue),
@@ -1006,10 +4664,36 @@ fn opt_color_string(opt_color: &Option<Color>) -> String {
     }
 }
 
+fn color_support_string(color_support: &Option<ColorSupport>) -> String {
+    match color_support {
+        None => "auto",
+        Some(ColorSupport::TrueColor) => "truecolor",
+        Some(ColorSupport::Ansi256) => "256",
+        Some(ColorSupport::Ansi16) => "16"
+    }
+    .to_string()
+}
+
+fn opt_palette_string(opt_palette: &Option<Palette>) -> String {
+    if let Some(palette) = opt_palette {
+        palette.to_string()
+    } else {
+        "none".to_string()
+    }
+}
+
 fn bool_string(b: &bool) -> String {
     if *b { "t" } else { "f" }.to_string()
 }
 
+fn board_size_string(size: usize, auto: bool) -> String {
+    if auto {
+        AUTO_BOARD_SIZE.to_string()
+    } else {
+        size.to_string()
+    }
+}
+
 fn opt_usize_string(opt_usize: &Option<usize>) -> String {
     if let Some(num) = opt_usize {
         format!("{}", num)
@@ -1017,3 +4701,230 @@ fn opt_usize_string(opt_usize: &Option<usize>) -> String {
         "none".to_string()
     }
 }
+
+// A count where `None` means no limit.
+fn count_string(count: &Option<usize>) -> String {
+    count.map_or("inf".to_string(), |count| count.to_string())
+}
+
+// Writing a config out as TOML and reading it back in shouldn't change anything.
+#[test]
+fn test_toml_round_trip() {
+    let config = GameConfig::default();
+    let parsed = GameConfig::parse_toml(&config.to_toml(), &[], None, None).unwrap();
+    assert_eq!(parsed.to_string(), config.to_string());
+}
+
+// Checking a config should find every problem with it, not just the first.
+#[test]
+fn test_check_reports_every_error() {
+    let (config, errors) =
+        GameConfig::check("board_width = 0\nfoo = 1\nmode = m\nmode = c\n", &[], None);
+    assert_eq!(errors.len(), 3);
+    assert_eq!(config.board_width, D_BOARD_WIDTH);
+    assert!(config.mode == Mode::Modern);
+}
+
+// Parsing should fail with every problem too, each on its own line number.
+#[test]
+fn test_parse_reports_every_error() {
+    let errors = match GameConfig::parse("board_width = 0\nfoo = 1\n", &[], None) {
+        Ok(_) => panic!("Expected the config to be invalid"),
+        Err(errors) => errors
+    };
+    assert_eq!(errors.0.len(), 2);
+    let message = errors.to_string();
+    assert!(message.contains("Error on line 1"));
+    assert!(message.contains("Error on line 2"));
+}
+
+// New configs should have help for every setting, and load back as the defaults.
+#[test]
+fn test_commented_config() {
+    for &option in CONFIG_OPTIONS.iter() {
+        assert!(SETTING_HELP.iter().any(|&(name, _)| name == option));
+    }
+    let config = GameConfig::default();
+    let toml = GameConfig::parse_toml(&config.to_commented_toml(), &[], None, None).unwrap();
+    assert_eq!(toml.to_string(), config.to_string());
+    let legacy = GameConfig::parse(&config.to_commented_string(), &[], None).unwrap();
+    assert_eq!(legacy.to_string(), config.to_string());
+}
+
+// Only unknown settings in the file can be skipped by a lenient config.
+#[test]
+fn test_unknown_settings() {
+    let overrides = [("foo".to_string(), "1".to_string())];
+    let (_, errors) = GameConfig::check("foo = 1\nboard_width = 0\n", &overrides, None);
+    let unknown = errors
+        .iter()
+        .map(ParseError::is_unknown_setting)
+        .collect::<Vec<_>>();
+    assert_eq!(unknown, vec![true, false, false]);
+    assert_eq!(
+        errors[0].warning(&ENGLISH),
+        "Warning: skipping unknown setting on line 1: foo = 1"
+    );
+}
+
+// Key settings should work under their full names and, with a warning, their old short ones.
+#[test]
+fn test_setting_aliases() {
+    let (config, errors) = GameConfig::check("move_left = a\nrot_cw = x\n", &[], None);
+    assert_eq!(config.left, vec![Key::plain(KeyCode::Char('a'))]);
+    assert_eq!(config.rot_cw, vec![Key::plain(KeyCode::Char('x'))]);
+    assert_eq!(errors.len(), 1);
+    assert!(errors[0].is_deprecated());
+    let toml = "[keys]\nright = \"d\"\nrotate_anticlockwise = \"z\"\n";
+    let config = GameConfig::parse_toml(toml, &[], None, None).unwrap();
+    assert_eq!(config.right, vec![Key::plain(KeyCode::Char('d'))]);
+    assert_eq!(config.rot_acw, vec![Key::plain(KeyCode::Char('z'))]);
+    let (_, errors) = GameConfig::check("left = a\nmove_left = b\n", &[], None);
+    assert!(matches!(errors[1].kind, ParseErrorKind::DuplicateSetting));
+}
+
+// Rewriting a config should only touch the settings that changed.
+#[test]
+fn test_rewrite() {
+    let contents = "# Comment\n\nboard_width = 10\nsound = t\n";
+    let before = GameConfig::parse(contents, &[], None).unwrap();
+    let mut config = before.clone();
+    config.sound = false;
+    config.cascade = true;
+    assert_eq!(
+        config.rewrite(&before, contents),
+        "# Comment\n\nboard_width = 10\nsound = f\ncascade = t\n"
+    );
+}
+
+// The same for TOML, keeping comments after values and leaving profiles alone.
+#[test]
+fn test_rewrite_toml() {
+    let contents = "\
+        [game]\n\
+        sound = true # Comment\n\
+        \n\
+        [keys]\n\
+        hold = \"c\"\n\
+        \n\
+        [profile.quiet.game]\n\
+        sound = false\n";
+    let before = GameConfig::parse_toml(contents, &[], None, None).unwrap();
+    let mut config = before.clone();
+    config.sound = false;
+    config.cascade = true;
+    config.border_color = Color::Rgb { r: 1, g: 2, b: 3 };
+    assert_eq!(
+        config.rewrite_toml(&before, contents),
+        "\
+         [game]\n\
+         sound = false # Comment\n\
+         cascade = true\n\
+         \n\
+         [keys]\n\
+         hold = \"c\"\n\
+         \n\
+         [profile.quiet.game]\n\
+         sound = false\n\
+         \n\
+         [colors]\n\
+         border_color = \"rgb 1,2,3\"\n"
+    );
+}
+
+// Automatic board sizes should fill the terminal beside the panel, and be written back as `auto`.
+#[test]
+fn test_fit_board() {
+    let (mut config, errors) =
+        GameConfig::check("board_width = auto\nboard_height = AUTO\n", &[], None);
+    assert!(errors.is_empty());
+    config.fit_board(80, 24);
+    assert_eq!((config.board_width, config.board_height), (61, 22));
+    assert!(config.to_string().contains("board_width = auto\n"));
+    config.renderer = Renderer::Braille;
+    config.block_size = 2;
+    config.fit_board(80, 24);
+    assert_eq!((config.board_width, config.board_height), (61, 44));
+    // Big mode rounds the width down to an even one, and won't take an odd one from the file.
+    config.big_mode = true;
+    config.fit_board(80, 24);
+    assert_eq!(config.board_width, 60);
+    let (_, errors) = GameConfig::check("big_mode = t\nboard_width = 11\n", &[], None);
+    assert_eq!(errors.len(), 1);
+}
+
+// Key lists should parse each key, with the comma key written out by name inside a list.
+#[test]
+fn test_parse_keys() {
+    let keys = parse_keys("left, ctrl+a,comma", 1, "").unwrap();
+    let expected = vec![
+        Key::plain(KeyCode::Left),
+        Key::new(KeyCode::Char('a'), KeyModifiers::CONTROL),
+        Key::plain(KeyCode::Char(',')),
+    ];
+    assert_eq!(keys, expected);
+    assert_eq!(keys_string(&keys), "left,ctrl+a,comma");
+    assert_eq!(
+        parse_keys(",", 1, "").unwrap(),
+        vec![Key::plain(KeyCode::Char(','))]
+    );
+    assert!(parse_keys("left,,a", 1, "").is_err());
+    assert!(parse_opt_keys("none", 1, "").unwrap().is_empty());
+}
+
+// A mode's section goes over the settings at the top of the file, other modes' sections are left
+// out, and rewriting only touches the top.
+#[test]
+fn test_mode_sections() {
+    let contents = "sound = t\ncascade = f\n\n[sprint]\ncascade = t\n\n[ultra]\nsound = f\n";
+    let sprint = GameConfig::parse(contents, &[], Some("sprint")).unwrap();
+    assert!(sprint.sound && sprint.cascade);
+    let plain = GameConfig::parse(contents, &[], None).unwrap();
+    assert!(plain.sound && !plain.cascade);
+    let mut config = plain.clone();
+    config.sound = false;
+    config.fumen_export = true;
+    assert_eq!(
+        config.rewrite(&plain, contents),
+        "sound = f\ncascade = f\nfumen_export = t\n\n[sprint]\ncascade = t\n\n[ultra]\nsound = f\n"
+    );
+    let toml = "[game]\nsound = true\n\n[mode.sprint.game]\ncascade = true\n";
+    let sprint = GameConfig::parse_toml(toml, &[], None, Some("sprint")).unwrap();
+    assert!(sprint.sound && sprint.cascade);
+}
+
+// A bindings section only needs the keys it changes, switching back restores the main ones, and
+// saving always writes the main ones.
+#[test]
+fn test_key_bindings() {
+    let contents = "move_left = j\nkey_bindings = leftie\n\n[bindings.leftie]\nmove_left = a\n";
+    let mut config = GameConfig::parse(contents, &[], None).unwrap();
+    assert_eq!(config.left, vec![Key::plain(KeyCode::Char('a'))]);
+    assert!(!config.is_default_bindings());
+    assert!(config.to_string().contains("move_left = j\n"));
+    assert!(config.use_bindings("default"));
+    assert_eq!(config.left, vec![Key::plain(KeyCode::Char('j'))]);
+    assert!(!config.use_bindings("missing"));
+    let toml = "[keys]\nkey_bindings = \"leftie\"\n\n[bindings.leftie]\nmove_left = \"a\"\n";
+    let config = GameConfig::parse_toml(toml, &[], None, None).unwrap();
+    assert_eq!(config.left, vec![Key::plain(KeyCode::Char('a'))]);
+    let (_, errors) = GameConfig::check("key_bindings = missing\n", &[], None);
+    assert_eq!(errors.len(), 1);
+}
+
+// Corrections are given in the language the errors are reported in, with any names filled in.
+#[test]
+fn test_localized_corrections() {
+    use crate::locale::SPANISH;
+
+    let (_, errors) = GameConfig::check("rot_cw = x\nboard_width = 0\nfoo = 1\n", &[], None);
+    assert!(errors[0]
+        .warning(&SPANISH)
+        .ends_with("\nEste ajuste ahora se llama rotate_clockwise."));
+    assert!(errors[1]
+        .report(&SPANISH)
+        .ends_with("\nEl ancho del tablero no es mayor o igual que 1."));
+    assert!(errors[2]
+        .report(&ENGLISH)
+        .contains("\nValid settings:\nfps_limiter, board_width,"));
+}
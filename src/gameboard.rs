@@ -1,85 +1,1349 @@
-use crossterm::Color;
-use rand::{thread_rng, rngs::ThreadRng, Rng};
+use rand::seq::SliceRandom;
+use rand::{thread_rng, Error as RandError, Rng, RngCore, SeedableRng};
+use rand_chacha::ChaCha20Rng;
 
-use crate::game_config::{GameConfig, Mode};
+use crate::action::Action;
+use crate::ai;
+use crate::challenge::{Challenge, Outcome};
+use crate::fumen::Position;
+use crate::item::{Item, CLEAR_ROWS, ITEMS, ITEM_CHANCE, SHRINK_TICKS};
+use crate::rules::{LevelGoal, Randomizer, Rules};
+use crate::stats::Stats;
 use crate::tetromino::Tetromino;
+use crate::timing::TICK_RATE;
 use std::hint::unreachable_unchecked;
+use std::mem;
 
-struct Cell {
-    character: char,
-    colour: Color,
+// Lines a clear of one to four lines counts as towards a variable level goal.
+const GOAL_LINES: [usize; 4] = [1, 3, 5, 8];
+// A filled square of the board. How it looks is up to the frontend.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Cell {
+    // The piece this was part of, if any. Garbage doesn't belong to one.
+    pub tetromino: Option<Tetromino>,
+    // The item clearing this square's row picks up, in a game with items.
+    #[serde(default)]
+    pub item: Option<Item>
 }
 
 impl Cell {
-    fn new(character: char, colour: Color) -> Self {
-        Cell { character, colour }
+    pub fn garbage() -> Self {
+        Cell {
+            tetromino: None,
+            item: None
+        }
+    }
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct ActivePiece {
+    pub tetromino: Tetromino,
+    pub rotation: usize,
+    pub x: isize,
+    pub y: isize,
+    // How many squares across and down each mino covers: 1 normally, and 2 in big mode.
+    pub scale: isize
+}
+
+impl ActivePiece {
+    // Big pieces spawn on an even column, since they only ever move two columns at a time and
+    // would otherwise never reach the edges of the board.
+    fn new(tetromino: Tetromino, board_width: usize, scale: isize) -> Self {
+        ActivePiece {
+            tetromino,
+            rotation: 0,
+            x: (board_width as isize - 4 * scale) / 2 / scale * scale,
+            y: 0,
+            scale
+        }
+    }
+
+    // Board coordinates of every square the piece covers.
+    pub fn cells(&self) -> Vec<(isize, isize)> {
+        self.squares().collect()
+    }
+
+    // The same as `cells`, without collecting them, for checking fits as cheaply as possible.
+    fn squares(&self) -> impl Iterator<Item = (isize, isize)> {
+        let (x, y, scale) = (self.x, self.y, self.scale);
+        IntoIterator::into_iter(self.tetromino.minos(self.rotation)).flat_map(move |(mx, my)| {
+            (0..scale * scale)
+                .map(move |i| (x + mx * scale + i % scale, y + my * scale + i / scale))
+        })
+    }
+
+    pub(crate) fn shifted(&self, dx: isize, dy: isize) -> Self {
+        ActivePiece {
+            x: self.x + dx,
+            y: self.y + dy,
+            ..*self
+        }
+    }
+
+    // Rotated in place, without any wall kicks.
+    fn rotated(&self, turns: usize) -> Self {
+        ActivePiece {
+            rotation: (self.rotation + turns) & 3,
+            ..*self
+        }
+    }
+
+    // The piece's cells moved up against the top of the board, so that placements which only differ
+    // in height compare equal. Pieces like I, S, and Z look the same in two orientations.
+    fn footprint(&self) -> Vec<(isize, isize)> {
+        let mut cells = self.cells();
+        let top = cells.iter().map(|&(_, y)| y).min().unwrap();
+        for cell in cells.iter_mut() {
+            cell.1 -= top;
+        }
+        cells.sort();
+        cells
     }
 }
 
-struct GameBoard {
-    width: usize,
-    height: usize,
-    cells: Vec<Option<Cell>>,
-    active_piece: [usize; 4]
+// The visible rows are numbered from 0 at the top, and the hidden rows above them count up from -1.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GameBoard {
+    pub width: usize,
+    pub height: usize,
+    // Rows above the visible board that pieces spawn and rotate in, and the stack can reach into.
+    pub hidden: usize,
+    cells: Vec<Option<Cell>>
 }
 
 impl GameBoard {
-    fn new(width: usize, height: usize) -> Self {
+    fn new(width: usize, height: usize, hidden: usize) -> Self {
         GameBoard {
             width,
             height,
-            cells: Vec::with_capacity(width * height),
-            active_piece: [0; 4]
+            hidden,
+            cells: vec![None; width * (height + hidden)]
         }
     }
 
-    // Placeholder until I get around to learning how to use crossterm better
-    fn draw(&self) {
+    fn index(&self, x: isize, y: isize) -> usize {
+        (y + self.hidden as isize) as usize * self.width + x as usize
+    }
+
+    // Visible rows only.
+    pub fn get(&self, x: usize, y: usize) -> Option<Cell> {
+        self.cells[self.index(x as isize, y as isize)]
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.cells.iter().all(Option::is_none)
+    }
 
+    // How many rows from the floor to the top of the stack.
+    pub fn stack_height(&self) -> usize {
+        (0..self.height)
+            .find(|&y| (0..self.width).any(|x| self.get(x, y).is_some()))
+            .map_or(0, |top| self.height - top)
     }
+
+    // Any row, hidden ones included.
+    pub(crate) fn cell(&self, x: usize, y: isize) -> Option<Cell> {
+        self.cells[self.index(x as isize, y)]
+    }
+
+    // A position is free if it's inside the walls and floor and not already filled. Anything above
+    // the hidden rows counts as free so pieces can rotate out of the top.
+    pub(crate) fn is_free(&self, x: isize, y: isize) -> bool {
+        x >= 0
+            && (x as usize) < self.width
+            && y < self.height as isize
+            && (y < -(self.hidden as isize) || self.cells[self.index(x, y)].is_none())
+    }
+
+    pub(crate) fn fits(&self, piece: &ActivePiece) -> bool {
+        piece.squares().all(|(x, y)| self.is_free(x, y))
+    }
+
+    // Push the stack up by a solid row for each hole column, with the last row at the bottom.
+    // Returns false if anything was pushed out of the top of the board.
+    fn insert_garbage(&mut self, holes: &[usize], cell: Cell) -> bool {
+        let rows = holes.len().min(self.height + self.hidden);
+        let overflowed = self.cells[..rows * self.width].iter().any(Option::is_some);
+        self.cells.drain(..rows * self.width);
+        for &hole in holes[holes.len() - rows..].iter() {
+            self.cells
+                .extend((0..self.width).map(|x| if x == hole { None } else { Some(cell) }));
+        }
+        !overflowed
+    }
+
+    // Take rows off the bottom of the board, moving everything above them down.
+    fn remove_bottom_rows(&mut self, rows: usize) {
+        let removed = rows.min(self.height + self.hidden) * self.width;
+        let kept = self.cells.len() - removed;
+        self.cells.truncate(kept);
+        let mut cells = vec![None; removed];
+        cells.append(&mut self.cells);
+        self.cells = cells;
+    }
+
+    // Move the holes in every row that's only garbage to random columns.
+    fn scramble_garbage<R: Rng>(&mut self, rng: &mut R) {
+        for row in self.cells.chunks_mut(self.width) {
+            let garbage = row.iter().flatten().all(|cell| cell.tetromino.is_none());
+            if garbage && row.iter().any(Option::is_some) {
+                row.shuffle(rng);
+            }
+        }
+    }
+
+    pub(crate) fn place(&mut self, piece: &ActivePiece, cell: Cell) {
+        for (x, y) in piece.squares() {
+            if y >= -(self.hidden as isize) {
+                let index = self.index(x, y);
+                self.cells[index] = Some(cell);
+            }
+        }
+    }
+
+    // Remove every full row, shifting everything above it down. Returns the number of rows
+    // removed.
+    pub(crate) fn clear_full_rows(&mut self) -> usize {
+        let width = self.width;
+        let mut remaining = Vec::with_capacity(self.cells.len());
+        let mut cleared = 0;
+        for row in self.cells.chunks(width) {
+            if row.iter().all(Option::is_some) {
+                cleared += 1;
+            } else {
+                remaining.extend_from_slice(row);
+            }
+        }
+        if cleared > 0 {
+            self.cells = vec![None; cleared * width];
+            self.cells.append(&mut remaining);
+        }
+        cleared
+    }
+
+    // Cascade gravity: every group of orthogonally connected blocks falls as a unit until it lands
+    // on something. Groups are recomputed after each step since falling groups can merge.
+    fn settle(&mut self) {
+        while self.drop_one_group() {}
+    }
+
+    fn drop_one_group(&mut self) -> bool {
+        let groups = self.label_groups();
+        let group_count = groups.iter().filter_map(|&g| g).max().map_or(0, |g| g + 1);
+        for group in 0..group_count {
+            let can_fall = groups.iter().enumerate().all(|(ind, &g)| {
+                if g != Some(group) {
+                    return true;
+                }
+                let below = ind + self.width;
+                below < self.cells.len() && (self.cells[below].is_none() || groups[below] == g)
+            });
+            if can_fall {
+                // Move from the bottom up so no cell is overwritten before it's moved.
+                for ind in (0..self.cells.len()).rev() {
+                    if groups[ind] == Some(group) {
+                        self.cells[ind + self.width] = self.cells[ind].take();
+                    }
+                }
+                return true;
+            }
+        }
+        false
+    }
+
+    fn label_groups(&self) -> Vec<Option<usize>> {
+        let mut groups = vec![None; self.cells.len()];
+        let mut next_group = 0;
+        let mut stack = Vec::new();
+        for start in 0..self.cells.len() {
+            if self.cells[start].is_none() || groups[start].is_some() {
+                continue;
+            }
+            groups[start] = Some(next_group);
+            stack.push(start);
+            while let Some(ind) = stack.pop() {
+                let (x, y) = (ind % self.width, ind / self.width);
+                let mut neighbours = Vec::with_capacity(4);
+                if x > 0 {
+                    neighbours.push(ind - 1);
+                }
+                if x + 1 < self.width {
+                    neighbours.push(ind + 1);
+                }
+                if y > 0 {
+                    neighbours.push(ind - self.width);
+                }
+                if y + 1 < self.height + self.hidden {
+                    neighbours.push(ind + self.width);
+                }
+                for neighbour in neighbours {
+                    if self.cells[neighbour].is_some() && groups[neighbour].is_none() {
+                        groups[neighbour] = Some(next_group);
+                        stack.push(neighbour);
+                    }
+                }
+            }
+            next_group += 1;
+        }
+        groups
+    }
+}
+
+// A random number generator that can be saved. Only the seed it started from and how far along
+// its stream it's got are written out, since that's all it takes to carry on from the same place.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(from = "RngPosition", into = "RngPosition")]
+struct SeededRng {
+    seed: u64,
+    rng: ChaCha20Rng
+}
+
+#[derive(Serialize, Deserialize)]
+struct RngPosition {
+    seed: u64,
+    position: u64
+}
+
+impl SeededRng {
+    fn new(seed: u64) -> Self {
+        let mut rng = ChaCha20Rng::seed_from_u64(seed);
+        // `get_word_pos` overflows on a generator that hasn't made anything yet, which an unused
+        // garbage generator never has. Moving to the start of the stream fills the first block
+        // without changing what comes out.
+        rng.set_word_pos(0);
+        SeededRng { seed, rng }
+    }
+}
+
+impl From<RngPosition> for SeededRng {
+    fn from(saved: RngPosition) -> Self {
+        let mut rng = SeededRng::new(saved.seed);
+        rng.rng.set_word_pos(saved.position as u128);
+        rng
+    }
+}
+
+impl From<SeededRng> for RngPosition {
+    fn from(rng: SeededRng) -> Self {
+        RngPosition {
+            seed: rng.seed,
+            position: rng.rng.get_word_pos() as u64
+        }
+    }
+}
+
+impl RngCore for SeededRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        self.rng.fill_bytes(dest)
+    }
+
+    fn try_fill_bytes(&mut self, dest: &mut [u8]) -> Result<(), RandError> {
+        self.rng.try_fill_bytes(dest)
+    }
+}
+
+// Read a seed written by `Game::seed_text`.
+pub fn parse_seed(text: &str) -> Option<u64> {
+    u64::from_str_radix(text, 16).ok()
 }
 
+// Everything about a game in progress, all of which can be saved and picked up again later.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct Game {
-    config: GameConfig,
-    board: GameBoard,
-    rng: ThreadRng,
+    pub rules: Rules,
+    pub board: GameBoard,
+    rng: SeededRng,
+    // Kept apart from `rng` so that garbage and items don't change which pieces come next.
+    garbage_rng: SeededRng,
+    // Column of the hole in the last garbage row added.
+    garbage_hole: Option<usize>,
+    // Every piece in the game comes from this, so the same seed always deals the same pieces.
+    pub seed: u64,
     sequence: [Tetromino; 7],
+    next_sequence: [Tetromino; 7],
     sequence_ind: usize,
-    score: u64,
-    preview: Option<[Tetromino; 4]>,
-    hold: Option<Tetromino>,
-    level: usize,
-    lines_cleared: usize
+    // Pieces to deal before going on with the sequence, e.g. from a practice position's queue.
+    #[serde(default)]
+    queued: Vec<Tetromino>,
+    pub active_piece: ActivePiece,
+    gravity_counter: u32,
+    // Countdowns until the active piece comes into play and, in master mode, until it locks.
+    entry_delay: u32,
+    lock_delay: u32,
+    // Times the lock delay has been restarted by a move or rotation since the piece last fell.
+    lock_resets: usize,
+    // What was pressed during the entry delay outside master mode, to be done once it's over.
+    #[serde(skip)]
+    buffered: Vec<Action>,
+    pub score: u64,
+    pub preview: Vec<Tetromino>,
+    pub hold: Option<Tetromino>,
+    // Holds used on the active piece.
+    holds_used: usize,
+    pub level: usize,
+    pub lines_cleared: usize,
+    // Lines counted towards the next level with a variable goal.
+    goal_lines: usize,
+    pub game_over: bool,
+    pub paused: bool,
+    // Set when the game ended because the opponent topped out, or master mode or a challenge was
+    // completed.
+    pub won: bool,
+    pub stats: Stats,
+    // Whether the suggested placement for the active piece is shown, and where it is.
+    pub show_hint: bool,
+    #[serde(skip)]
+    pub hint: Option<ActivePiece>,
+    // Whether the active piece's last successful move was a rotation, for spotting T-spins.
+    last_rotated: bool,
+    // How many pieces have come into play, counting those swapped in by hold, so that inputs can be
+    // matched with the piece they went to.
+    #[serde(default)]
+    pub spawned: u64,
+    // The last piece to lock, by `spawned`, and the fewest moves and rotations it could have
+    // taken. Finesse is checked against the inputs it actually took, which the frontend logs.
+    #[serde(skip)]
+    pub finesse_check: Option<(u64, u32)>,
+    // Whether there's an opponent to send attack to and take garbage from.
+    pub versus: bool,
+    // Garbage sent by the opponent that hasn't been cancelled out or added to the board yet.
+    pub pending_garbage: u64,
+    // Lines of attack waiting to be sent to the opponent.
+    pub outgoing_attack: u64,
+    // The item picked up and waiting to be used, items used on the opponent that haven't been sent
+    // yet, and ticks left with the preview cut down by the opponent's item.
+    #[serde(default)]
+    pub item: Option<Item>,
+    #[serde(default)]
+    pub outgoing_items: Vec<Item>,
+    #[serde(default)]
+    pub preview_shrunk: u64,
+    // How many pieces have been taken from the sequence, and how many garbage rows have come in,
+    // so that bots can tell what's changed.
+    pub dealt: u64,
+    pub garbage_rows: u64,
+    // The objective the game is played for, if any. The game ends as soon as it's met or failed.
+    pub challenge: Option<Challenge>,
+    // Set when the game was started from a practice position rather than an empty board.
+    #[serde(default)]
+    pub practice: bool
 }
 
 impl Game {
-    pub fn new(config: GameConfig) -> Self {
-        let mut rng = thread_rng();
-        let board = GameBoard::new(config.board_width, config.board_height);
-        let sequence = decode_sequence_number(rng.gen_range(0, 5040));
-        let preview = match config.mode {
-            Mode::Modern => Some({
-                let mut preview = [Tetromino::I; 4];
-                preview.copy_from_slice(&sequence[0..4]);
-                preview
-            }),
-            Mode::Classic => None
-        };
-        Game {
-            config,
+    pub fn new(rules: Rules) -> Self {
+        Game::with_seed(rules, thread_rng().gen())
+    }
+
+    pub fn with_seed(rules: Rules, seed: u64) -> Self {
+        let mut rng = SeededRng::new(seed);
+        let board = GameBoard::new(rules.board_width, rules.board_height, rules.hidden_rows);
+        let randomizer = rules.rule_set().randomizer();
+        let sequence = deal(randomizer, &mut rng);
+        let next_sequence = deal(randomizer, &mut rng);
+        let preview = vec![Tetromino::I; rules.rule_set().preview_count()];
+        let active_piece = ActivePiece::new(sequence[0], rules.board_width, rules.scale());
+        let mut game = Game {
+            rules,
             board,
             rng,
+            garbage_rng: SeededRng::new(!seed),
+            garbage_hole: None,
+            seed,
             sequence,
-            sequence_ind: 0,
+            next_sequence,
+            sequence_ind: 1,
+            queued: Vec::new(),
+            active_piece,
+            gravity_counter: 0,
+            entry_delay: 0,
+            lock_delay: 0,
+            lock_resets: 0,
+            buffered: Vec::new(),
             score: 0,
             preview,
             hold: None,
+            holds_used: 0,
             level: 0,
-            lines_cleared: 0
+            lines_cleared: 0,
+            goal_lines: 0,
+            game_over: false,
+            paused: false,
+            won: false,
+            stats: Stats::new(),
+            show_hint: false,
+            hint: None,
+            last_rotated: false,
+            spawned: 0,
+            finesse_check: None,
+            versus: false,
+            pending_garbage: 0,
+            outgoing_attack: 0,
+            item: None,
+            outgoing_items: Vec::new(),
+            preview_shrunk: 0,
+            dealt: 0,
+            garbage_rows: 0,
+            challenge: None,
+            practice: false
+        };
+        game.active_piece = game.spawn_position(game.active_piece.tetromino);
+        game.update_preview();
+        if let Some(level) = game.rules.const_level.filter(|_| game.level_fixed()) {
+            game.level = level;
+        }
+        if game.twenty_g() {
+            game.entry_delay = game.entry_delay_ticks();
+        }
+        game
+    }
+
+    // The seed the way it's shown to players and written to stats, which `parse_seed` reads back.
+    pub fn seed_text(&self) -> String {
+        format!("{:016x}", self.seed)
+    }
+
+    // Set the game up to practice a position, with its queue dealt before the usual pieces. Only
+    // makes sense before the game has started.
+    pub fn start_from(&mut self, position: Position) -> Result<(), String> {
+        position.check(&self.rules)?;
+        let bottom = self.board.height as isize - 1;
+        for (i, row) in position.rows.iter().enumerate() {
+            for (x, &cell) in row.iter().enumerate() {
+                let index = self.board.index(x as isize, bottom - i as isize);
+                self.board.cells[index] = cell;
+            }
+        }
+        self.hold = position.hold;
+        self.queued = position.queue;
+        self.practice = true;
+        let first = if self.queued.is_empty() {
+            self.active_piece.tetromino
+        } else {
+            self.queued.remove(0)
+        };
+        self.spawn(first);
+        self.update_preview();
+        Ok(())
+    }
+
+    pub fn handle_action(&mut self, action: Action) {
+        if action == Action::ToggleHint {
+            self.show_hint = !self.show_hint;
+            self.update_hint();
+            return;
+        }
+        if self.game_over {
+            return;
+        }
+        if action == Action::Pause {
+            self.paused = !self.paused;
+            return;
+        } else if self.paused {
+            return;
+        }
+        if !self.piece_in_play() {
+            self.buffered.push(action);
+            return;
+        }
+        match action {
+            Action::MoveLeft => {
+                self.try_move(-self.active_piece.scale, 0);
+            }
+            Action::MoveRight => {
+                self.try_move(self.active_piece.scale, 0);
+            }
+            Action::RotateClockwise => self.try_rotate(true),
+            Action::RotateAnticlockwise => self.try_rotate(false),
+            // Pieces are already on the stack at 20G, so soft drop locks them at once if it locks
+            // them at all.
+            Action::SoftDrop if self.twenty_g() && self.soft_drop_locks() => {
+                while self.try_move(0, 1) {
+                    self.award_drop(1);
+                }
+                self.lock();
+            }
+            // With soft drop locking, a piece that's already resting locks rather than waiting out
+            // its lock delay.
+            Action::SoftDrop => {
+                if self.try_move(0, 1) {
+                    self.award_drop(1);
+                    self.gravity_counter = 0;
+                } else if self.soft_drop_locks() {
+                    self.lock();
+                }
+            }
+            Action::HardDrop | Action::SonicDrop if !self.rules.rule_set().has_hard_drop() => {}
+            Action::HardDrop => {
+                while self.try_move(0, 1) {
+                    self.award_drop(2);
+                }
+                self.lock();
+            }
+            // The piece is left to lock on the next gravity step, so there's still time to slide
+            // it along the stack.
+            Action::SonicDrop => {
+                self.entry_delay = 0;
+                while self.try_move(0, 1) {
+                    self.award_drop(1);
+                    self.gravity_counter = 0;
+                }
+            }
+            Action::Hold => self.hold(),
+            Action::UseItem => self.use_item(),
+            Action::Pause
+            | Action::Quit
+            | Action::ToggleStats
+            | Action::ToggleHint
+            | Action::ToggleDebug
+            | Action::ToggleFrameStep
+            | Action::FrameStep
+            | Action::Rewind
+            | Action::Undo
+            | Action::Screenshot
+            | Action::SwitchTarget => {}
+        }
+        if self.twenty_g() && self.entry_delay == 0 && !self.game_over {
+            self.fall_to_stack();
+        }
+    }
+
+    // Play one tick given nothing but what was pressed on it. With the game's state, that's all
+    // it takes to know what happens next, which is what lets a network game guess at an
+    // opponent's inputs and play the tick again when the real ones arrive.
+    pub fn advance(&mut self, actions: &[Action]) {
+        for &action in actions {
+            self.handle_action(action);
+        }
+        self.tick();
+    }
+
+    // Advance the game by one logic tick.
+    pub fn tick(&mut self) {
+        if self.game_over || self.paused {
+            return;
+        }
+        self.stats.ticks += 1;
+        self.preview_shrunk = self.preview_shrunk.saturating_sub(1);
+        if self.stats.ticks.is_multiple_of(TICK_RATE) {
+            self.stats.record_sample(self.board.stack_height());
+        }
+        self.check_challenge();
+        if self.game_over {
+            return;
+        }
+        // Anything pressed while waiting is done in order as soon as the piece is in play, even if
+        // a hard drop in there brings on another wait.
+        if !self.piece_in_play() {
+            self.entry_delay -= 1;
+            if self.entry_delay == 0 {
+                for action in mem::take(&mut self.buffered) {
+                    self.handle_action(action);
+                }
+            }
+            return;
+        }
+        match self.rules.rule_set().gravity(self.gravity_level()) {
+            None if self.entry_delay > 0 => {
+                self.entry_delay -= 1;
+                return;
+            }
+            None => self.fall_to_stack(),
+            Some(gravity) => {
+                self.gravity_counter += 1;
+                if self.gravity_counter >= gravity {
+                    self.gravity_counter = 0;
+                    // Without a lock delay, a piece locks when gravity can't move it any further.
+                    if !self.try_move(0, 1) && self.lock_delay_ticks().is_none() {
+                        self.lock();
+                        return;
+                    }
+                }
+            }
+        }
+        if let Some(ticks) = self.lock_delay_ticks() {
+            if !self.board.fits(&self.active_piece.shifted(0, 1)) {
+                self.lock_delay += 1;
+                if self.lock_delay >= ticks {
+                    self.lock();
+                }
+            }
+        }
+    }
+
+    // Get a game loaded from a save ready to carry on, paused so that the player has time to get
+    // ready. Returns false if the save doesn't make sense as a game in progress.
+    pub fn resume(&mut self) -> bool {
+        let board = &self.board;
+        if board.cells.len() != board.width * (board.height + board.hidden)
+            || self.sequence_ind >= 7
+            || self.game_over
+            || !board.fits(&self.active_piece)
+        {
+            return false;
+        }
+        self.paused = true;
+        self.update_hint();
+        true
+    }
+
+    // The game stops as a win once the opponent tops out.
+    pub fn win(&mut self) {
+        if !self.game_over {
+            info!("Won with {} points", self.score);
+            self.game_over = true;
+            self.won = true;
+        }
+    }
+
+    // Add rows of garbage to the bottom of the board. Each row's hole stays in the same column as the
+    // last one with the `garbage_hole_repeat` chance, and moves to a different column otherwise. The
+    // active piece is pushed up along with the stack if it's in the way.
+    pub fn add_garbage(&mut self, lines: usize) {
+        if lines == 0 || self.game_over || !self.raise_garbage(lines) {
+            return;
+        }
+        for _ in 0..lines {
+            if self.board.fits(&self.active_piece) {
+                break;
+            }
+            self.active_piece.y -= 1;
+        }
+        if !self.board.fits(&self.active_piece) {
+            info!("Topped out with garbage pushing into the active piece");
+            self.game_over = true;
+        }
+        self.update_hint();
+    }
+
+    // Take an item the opponent used on this game.
+    pub fn receive_item(&mut self, item: Item) {
+        if self.game_over {
+            return;
+        }
+        debug!("Hit by {:?}", item);
+        match item {
+            Item::ShrinkPreview => self.preview_shrunk = SHRINK_TICKS,
+            Item::ScrambleGarbage => {
+                self.board.scramble_garbage(&mut self.garbage_rng);
+                self.make_room(self.board.height + self.board.hidden);
+            }
+            Item::ClearRows => {}
+        }
+    }
+
+    // The preview as it's shown, which is only the next piece while it's cut down, and no more
+    // than a versus handicap allows otherwise.
+    pub fn shown_preview(&self) -> &[Tetromino] {
+        let limit = match self.rules.handicap.preview {
+            _ if self.preview_shrunk > 0 => 1,
+            Some(limit) if self.versus => limit,
+            _ => self.preview.len()
+        };
+        &self.preview[..self.preview.len().min(limit)]
+    }
+
+    // Start playing against an opponent, with the handicap's garbage already under the stack.
+    pub fn start_versus(&mut self) {
+        self.versus = true;
+        let rows = self.rules.handicap.garbage_rows;
+        if rows > 0 {
+            self.add_garbage(rows);
+        }
+    }
+
+    // Use the item that's been picked up. Attacks only go anywhere in versus games.
+    fn use_item(&mut self) {
+        let item = match self.item.take() {
+            Some(item) => item,
+            None => return
+        };
+        debug!("Used {:?}", item);
+        if item.is_attack() {
+            if self.versus {
+                self.outgoing_items.push(item);
+            }
+            return;
+        }
+        self.board.remove_bottom_rows(CLEAR_ROWS);
+        self.make_room(CLEAR_ROWS);
+    }
+
+    // After the stack has moved under it, move the active piece up by as many as `rows` until it
+    // fits again. The game is over if it can't.
+    fn make_room(&mut self, rows: usize) {
+        for _ in 0..rows {
+            if self.board.fits(&self.active_piece) {
+                break;
+            }
+            self.active_piece.y -= 1;
+        }
+        if !self.board.fits(&self.active_piece) {
+            info!("Topped out with the stack moving into the active piece");
+            self.game_over = true;
+        }
+        self.update_hint();
+    }
+
+    // Put the garbage rows in under the stack, leaving the active piece alone. Returns false and
+    // ends the game if the stack was pushed out of the top.
+    fn raise_garbage(&mut self, lines: usize) -> bool {
+        let width = self.board.width;
+        let mut holes = Vec::with_capacity(lines);
+        for _ in 0..lines {
+            let hole = match self.garbage_hole {
+                Some(last)
+                    if self.garbage_rng.gen_range(0, 100) < self.rules.garbage_hole_repeat =>
+                {
+                    last
+                }
+                Some(last) if width > 1 => {
+                    let hole = self.garbage_rng.gen_range(0, width - 1);
+                    if hole >= last {
+                        hole + 1
+                    } else {
+                        hole
+                    }
+                }
+                _ => self.garbage_rng.gen_range(0, width)
+            };
+            self.garbage_hole = Some(hole);
+            holes.push(hole);
+        }
+        self.garbage_rows += lines as u64;
+        debug!("Raised {} garbage rows with holes at {:?}", lines, holes);
+        if !self.board.insert_garbage(&holes, Cell::garbage()) {
+            info!("Topped out with garbage pushing the stack off the board");
+            self.game_over = true;
+            return false;
+        }
+        true
+    }
+
+    // Put a piece straight into its final position and lock it there, for bots. Holds first if the
+    // piece is the held or next one instead of the active one. Returns false if it can't go there.
+    pub fn play_placement(&mut self, piece: ActivePiece) -> bool {
+        if self.game_over {
+            return false;
+        }
+        if piece.tetromino != self.active_piece.tetromino {
+            self.hold();
+        }
+        if piece.tetromino != self.active_piece.tetromino || !self.board.fits(&piece) {
+            return false;
+        }
+        self.active_piece = piece;
+        self.lock();
+        true
+    }
+
+    // The active piece followed by the preview.
+    pub fn queue(&self) -> Vec<Tetromino> {
+        let mut queue = vec![self.active_piece.tetromino];
+        queue.extend(self.preview.iter());
+        queue
+    }
+
+    // Where the active piece would land if it were hard dropped.
+    pub fn ghost_piece(&self) -> ActivePiece {
+        let mut ghost = self.active_piece;
+        while self.board.fits(&ghost.shifted(0, 1)) {
+            ghost.y += 1;
+        }
+        ghost
+    }
+
+    fn try_move(&mut self, dx: isize, dy: isize) -> bool {
+        let moved = self.active_piece.shifted(dx, dy);
+        if self.board.fits(&moved) {
+            self.active_piece = moved;
+            self.last_rotated = false;
+            // Falling always restarts the lock delay and gives back any resets used up.
+            if dy > 0 {
+                self.lock_delay = 0;
+                self.lock_resets = 0;
+            } else {
+                self.move_reset();
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    // Master mode's 20G gravity: the piece falls as far as it can at once, and gets a fresh lock
+    // delay whenever it does.
+    fn fall_to_stack(&mut self) {
+        while self.try_move(0, 1) {}
+    }
+
+    // A move or rotation on the stack restarts the lock delay, as long as the piece has resets
+    // left. With no resets, only falling does (TGM's step reset).
+    fn move_reset(&mut self) {
+        if self.lock_delay > 0
+            && self
+                .rules
+                .lock_resets
+                .is_none_or(|limit| self.lock_resets < limit)
+        {
+            self.lock_delay = 0;
+            self.lock_resets += 1;
+        }
+    }
+
+    // Classic mode has no wall kicks, so a rotation into a wall or the stack simply fails.
+    fn try_rotate(&mut self, clockwise: bool) {
+        let piece = self.active_piece;
+        let rotation = if clockwise {
+            (piece.rotation + 1) & 3
+        } else {
+            (piece.rotation + 3) & 3
+        };
+        let kicks = self
+            .rules
+            .rule_set()
+            .kicks(piece.tetromino, piece.rotation, clockwise);
+        for &(dx, dy) in kicks {
+            let rotated = ActivePiece {
+                rotation,
+                x: piece.x + dx * piece.scale,
+                y: piece.y + dy * piece.scale,
+                ..piece
+            };
+            if self.board.fits(&rotated) {
+                self.active_piece = rotated;
+                self.last_rotated = true;
+                self.move_reset();
+                return;
+            }
+        }
+    }
+
+    fn hold(&mut self) {
+        if !self.can_hold() {
+            return;
+        }
+        let current = self.active_piece.tetromino;
+        let next = match self.hold.take() {
+            Some(held) => held,
+            None => self.next_tetromino()
+        };
+        self.hold = Some(current);
+        self.spawn(next);
+        self.holds_used += 1;
+        self.stats.holds += 1;
+    }
+
+    // How many more times the active piece can be held, or `None` if there's no limit.
+    pub fn holds_left(&self) -> Option<usize> {
+        self.rules
+            .hold_limit
+            .map(|limit| limit.saturating_sub(self.holds_used))
+    }
+
+    pub fn can_hold(&self) -> bool {
+        self.rules.rule_set().has_hold() && self.holds_left() != Some(0)
+    }
+
+    // Uses the three corner rule: the last move has to have been a rotation, and at least three of
+    // the four squares diagonal to the T's center have to be filled or out of bounds.
+    fn is_t_spin(&self) -> bool {
+        let piece = self.active_piece;
+        if piece.tetromino != Tetromino::T || !self.last_rotated {
+            return false;
+        }
+        [(0, 0), (2, 0), (0, 2), (2, 2)]
+            .iter()
+            .filter(|&&(dx, dy)| {
+                !self
+                    .board
+                    .is_free(piece.x + dx * piece.scale, piece.y + dy * piece.scale)
+            })
+            .count()
+            >= 3
+    }
+
+    // The fewest moves and rotations that get a piece from where it spawned to the active piece's
+    // columns and orientation, without any help from gravity. `None` if it can't be done that way,
+    // e.g. for tucks and spins.
+    fn finesse_optimal(&self) -> Option<u32> {
+        let target = self.active_piece.footprint();
+        let piece = self.active_piece;
+        let start = self.spawn_position(piece.tetromino);
+        let mut seen = vec![(start.x, start.rotation)];
+        let mut frontier = vec![start];
+        let mut depth = 0;
+        while !frontier.is_empty() {
+            if frontier.iter().any(|piece| piece.footprint() == target) {
+                return Some(depth);
+            }
+            let mut next = Vec::new();
+            for piece in frontier {
+                let candidates = [
+                    piece.shifted(-piece.scale, 0),
+                    piece.shifted(piece.scale, 0),
+                    piece.rotated(1),
+                    piece.rotated(3)
+                ];
+                for candidate in candidates.iter() {
+                    let key = (candidate.x, candidate.rotation);
+                    if self.board.fits(candidate) && !seen.contains(&key) {
+                        seen.push(key);
+                        next.push(*candidate);
+                    }
+                }
+            }
+            frontier = next;
+            depth += 1;
+        }
+        None
+    }
+
+    fn lock(&mut self) {
+        self.finesse_check = self
+            .finesse_optimal()
+            .map(|optimal| (self.spawned, optimal));
+        let t_spin = self.is_t_spin();
+        let piece = self.active_piece;
+        let cell = Cell {
+            tetromino: Some(piece.tetromino),
+            item: None
+        };
+        self.board.place(&piece, cell);
+        if self.rules.items {
+            self.mark_item(&piece);
+        }
+        self.collect_item();
+        let mut cleared = self.board.clear_full_rows();
+        debug!(
+            "Locked {:?} at ({}, {}) rotation {}, clearing {} lines{}",
+            piece.tetromino,
+            piece.x,
+            piece.y,
+            piece.rotation,
+            cleared,
+            if t_spin { " with a T-spin" } else { "" }
+        );
+        let (attack_before, cleared_any) = (self.stats.attack, cleared > 0);
+        self.stats.record_lock(cleared, t_spin);
+        self.award_lines(cleared, t_spin);
+        while self.rules.cascade && cleared > 0 {
+            self.board.settle();
+            self.collect_item();
+            cleared = self.board.clear_full_rows();
+            self.award_lines(cleared, false);
+        }
+        if self.versus {
+            self.exchange_garbage(self.stats.attack - attack_before, cleared_any);
+        }
+        self.advance_level();
+        self.check_challenge();
+        if self.game_over {
+            return;
+        }
+        // A piece locked entirely in the hidden rows tops out too (lock out), unless it cleared
+        // lines.
+        if self.board.hidden > 0 && !cleared_any && piece.squares().all(|(_, y)| y < 0) {
+            info!("Topped out locking {:?} above the board", piece.tetromino);
+            self.game_over = true;
+            return;
+        }
+        let next = self.next_tetromino();
+        self.entry_delay = self.entry_delay_ticks();
+        if cleared_any {
+            self.entry_delay += self.rules.line_clear_delay;
+        }
+        self.spawn(next);
+        self.holds_used = 0;
+    }
+
+    // Every so often, mark one of a locked piece's squares with an item. Attacks are only dealt
+    // when there's an opponent to use them on.
+    fn mark_item(&mut self, piece: &ActivePiece) {
+        if self.garbage_rng.gen_range(0, 100) >= ITEM_CHANCE {
+            return;
+        }
+        let items = if self.versus { &ITEMS[..] } else { &ITEMS[..1] };
+        let item = items[self.garbage_rng.gen_range(0, items.len())];
+        let hidden = -(self.board.hidden as isize);
+        let squares = piece
+            .cells()
+            .into_iter()
+            .filter(|&(_, y)| y >= hidden)
+            .collect::<Vec<_>>();
+        if let Some(&(x, y)) = squares.choose(&mut self.garbage_rng) {
+            let index = self.board.index(x, y);
+            if let Some(ref mut cell) = self.board.cells[index] {
+                cell.item = Some(item);
+            }
+        }
+    }
+
+    // A full row about to be cleared gives up an item it has, unless one's already waiting to be
+    // used.
+    fn collect_item(&mut self) {
+        if self.item.is_some() {
+            return;
+        }
+        let item = self
+            .board
+            .cells
+            .chunks(self.board.width)
+            .filter(|row| row.iter().all(Option::is_some))
+            .flatten()
+            .find_map(|cell| cell.and_then(|cell| cell.item));
+        if let Some(item) = item {
+            info!("Picked up {:?}", item);
+            self.item = Some(item);
+        }
+    }
+
+    // Ticks from a piece being dealt until it comes into play.
+    fn entry_delay_ticks(&self) -> u32 {
+        match self.rules.entry_delay {
+            None => self.rules.rule_set().entry_delay(),
+            Some(millis) => (millis * TICK_RATE / 1000) as u32
+        }
+    }
+
+    // Ticks a piece can rest on the stack before it locks, or `None` to lock as soon as gravity
+    // can't move it.
+    fn lock_delay_ticks(&self) -> Option<u32> {
+        match self.rules.lock_delay {
+            None => self.rules.rule_set().lock_delay(),
+            millis => millis.map(|millis| (millis * TICK_RATE / 1000) as u32)
+        }
+    }
+
+    fn soft_drop_locks(&self) -> bool {
+        self.rules
+            .soft_drop_lock
+            .unwrap_or_else(|| self.rules.rule_set().soft_drop_locks())
+    }
+
+    // Whether pieces drop straight onto the stack.
+    fn twenty_g(&self) -> bool {
+        self.rules
+            .rule_set()
+            .gravity(self.gravity_level())
+            .is_none()
+    }
+
+    // The level gravity goes by, which a versus handicap can move away from the real one.
+    fn gravity_level(&self) -> usize {
+        if !self.versus {
+            return self.level;
+        }
+        (self.level as isize + self.rules.handicap.gravity_offset).max(0) as usize
+    }
+
+    // The next piece is normally kept out of sight until its entry delay is over. At 20G it waits
+    // at the top where it can be rotated, since it'd be on the stack as soon as it came into play.
+    pub fn piece_in_play(&self) -> bool {
+        self.twenty_g() || self.entry_delay == 0
+    }
+
+    // Attack cancels out garbage that's waiting to come in before anything is sent, and whatever
+    // is still waiting comes in once a piece locks without clearing anything.
+    fn exchange_garbage(&mut self, attack: u64, cleared_any: bool) {
+        let attack = (attack * self.rules.handicap.attack_percent + 50) / 100;
+        if attack > 0 {
+            let cancelled = attack.min(self.pending_garbage);
+            self.pending_garbage -= cancelled;
+            self.outgoing_attack += attack - cancelled;
+        } else if !cleared_any && self.pending_garbage > 0 {
+            let lines = self.pending_garbage as usize;
+            self.pending_garbage = 0;
+            self.raise_garbage(lines);
+        }
+    }
+
+    fn award_drop(&mut self, points: u64) {
+        self.score += points;
+        self.stats.drop_score += points;
+    }
+
+    fn award_lines(&mut self, lines: usize, t_spin: bool) {
+        if lines == 0 {
+            return;
+        }
+        let rule_set = self.rules.rule_set();
+        let points = rule_set.line_clear_score(lines, self.level);
+        self.score += points;
+        self.stats.record_clear_score(lines, t_spin, points);
+        self.lines_cleared += lines;
+        // Levels that go up a line at a time are only logged when they pass a level stop.
+        if let Some(level) = rule_set.level_after_clear(self.level, lines) {
+            if rule_set.level_stop(level) != rule_set.level_stop(self.level) {
+                info!("Reached level {}", level);
+            }
+            self.level = level;
+            return;
+        }
+        if self.level_fixed() {
+            return;
+        }
+        let per_level = self.rules.lines_per_level.max(1);
+        let level = match self.rules.level_goal {
+            LevelGoal::Fixed => self.lines_cleared / per_level,
+            LevelGoal::Variable => {
+                self.goal_lines += GOAL_LINES[lines.min(4) - 1];
+                let mut level = self.level;
+                while self.goal_lines >= Self::variable_goal(per_level, level) {
+                    self.goal_lines -= Self::variable_goal(per_level, level);
+                    level += 1;
+                }
+                level
+            }
+        };
+        if level != self.level {
+            info!("Reached level {}", level);
+        }
+        self.level = level;
+    }
+
+    // Whether the level is held at `const_level`, which not every mode allows.
+    pub fn level_fixed(&self) -> bool {
+        self.rules.const_level.is_some() && self.rules.rule_set().allows_const_level()
+    }
+
+    // Lines needed to go up from `level` with a variable goal.
+    fn variable_goal(per_level: usize, level: usize) -> usize {
+        (per_level * (level + 1) / 2).max(1)
+    }
+
+    // In modes where every piece counts for a level, go up one for the piece that just locked.
+    // Reaching the mode's final level completes the game.
+    fn advance_level(&mut self) {
+        let rule_set = self.rules.rule_set();
+        self.level = rule_set.advance_level(self.level);
+        if rule_set
+            .final_level()
+            .is_some_and(|level| self.level >= level)
+        {
+            info!(
+                "Completed {} mode with grade {}",
+                self.rules.mode,
+                self.grade().unwrap_or("-")
+            );
+            self.won = true;
+            self.game_over = true;
+        }
+    }
+
+    fn check_challenge(&mut self) {
+        let outcome = match self.challenge {
+            Some(ref challenge) => challenge.check(self),
+            None => return
+        };
+        match outcome {
+            Outcome::Succeeded => {
+                info!("Challenge complete");
+                self.won = true;
+                self.game_over = true;
+            }
+            Outcome::Failed => {
+                info!("Challenge failed");
+                self.game_over = true;
+            }
+            Outcome::InProgress => {}
+        }
+    }
+
+    // The grade the score so far is worth, in modes that grade.
+    pub fn grade(&self) -> Option<&'static str> {
+        self.rules.rule_set().grade(self.score, self.level)
+    }
+
+    // The level the game is held at until a line is cleared, in modes that stop.
+    pub fn level_stop(&self) -> Option<usize> {
+        self.rules.rule_set().level_stop(self.level)
+    }
+
+    // Where pieces come in. With hidden rows they appear above the visible board and drop straight
+    // into its top row if there's room, as in guideline games.
+    pub(crate) fn spawn_position(&self, tetromino: Tetromino) -> ActivePiece {
+        let mut piece = ActivePiece::new(tetromino, self.board.width, self.rules.scale());
+        if self.board.hidden > 0 {
+            piece.y = -(self.board.hidden.min(2) as isize);
+            if self.board.fits(&piece.shifted(0, 1)) {
+                piece.y += 1;
+            }
+        }
+        piece
+    }
+
+    fn spawn(&mut self, tetromino: Tetromino) {
+        self.active_piece = self.spawn_position(tetromino);
+        self.gravity_counter = 0;
+        if self.twenty_g() {
+            self.entry_delay = self.entry_delay_ticks();
+        }
+        self.lock_delay = 0;
+        self.lock_resets = 0;
+        self.last_rotated = false;
+        self.spawned += 1;
+        if !self.board.fits(&self.active_piece) {
+            info!("Topped out with no room for {:?}", tetromino);
+            self.game_over = true;
+        }
+        self.update_hint();
+    }
+
+    // The hint is only worked out when the active piece or the board changes, since searching
+    // every placement each frame would be a waste.
+    fn update_hint(&mut self) {
+        self.hint = if self.show_hint && !self.game_over {
+            ai::best_placement(self)
+        } else {
+            None
+        };
+    }
+
+    fn next_tetromino(&mut self) -> Tetromino {
+        self.dealt += 1;
+        if !self.queued.is_empty() {
+            let next = self.queued.remove(0);
+            self.update_preview();
+            return next;
+        }
+        let next = self.sequence[self.sequence_ind];
+        self.sequence_ind += 1;
+        if self.sequence_ind == 7 {
+            self.sequence = self.next_sequence;
+            self.next_sequence = deal(self.rules.rule_set().randomizer(), &mut self.rng);
+            self.sequence_ind = 0;
+        }
+        self.update_preview();
+        next
+    }
+
+    fn update_preview(&mut self) {
+        let upcoming = self
+            .queued
+            .iter()
+            .chain(self.sequence[self.sequence_ind..].iter())
+            .chain(self.next_sequence.iter());
+        for (slot, &tetromino) in self.preview.iter_mut().zip(upcoming) {
+            *slot = tetromino;
         }
     }
 }
 
+// The next seven pieces, dealt the way the mode's rule set says.
+fn deal(randomizer: Randomizer, rng: &mut SeededRng) -> [Tetromino; 7] {
+    match randomizer {
+        Randomizer::Bag => decode_sequence_number(rng.gen_range(0, 5040))
+    }
+}
+
 // Generate the piece sequence by the following algorithm:
 // input: sequence_number: usize (sn), usage_map: [bool; 7] (um, true = used, false = unused)
 // for piece n:
@@ -172,7 +1436,7 @@ fn test_sequence_decode() {
                         "Duplicate tetromino in sequence for sn {}: {:?}",
                         n, sequence
                     );
-                    panic!(message);
+                    panic!("{}", message);
                 }
             }
         }
@@ -183,8 +1447,8 @@ fn test_sequence_decode() {
 #[test]
 fn test_no_duplicate_sequences() {
     let mut sequences = [[Tetromino::I; 7]; 5040];
-    for n in 0..5040 {
-        sequences[n] = decode_sequence_number(n as u16);
+    for (n, sequence) in sequences.iter_mut().enumerate() {
+        *sequence = decode_sequence_number(n as u16);
     }
     for i in 0..5039 {
         for j in i + 1..5040 {
@@ -193,6 +1457,7 @@ fn test_no_duplicate_sequences() {
                     "Duplicate sequence for sns {} and {}: {:?}",
                     i, j, sequences[i]
                 );
+                panic!("{}", message);
             }
         }
     }
@@ -207,4 +1472,563 @@ fn find_nth_unused(usage_map: [bool; 7], mut n: usize) -> u16 {
         ind += 1;
     }
     ind as u16
-}
\ No newline at end of file
+}
+
+// A sonic drop should land the piece where the ghost shows without locking it.
+#[test]
+fn test_sonic_drop_does_not_lock() {
+    let mut game = Game::new(Rules::default());
+    let landing = game.ghost_piece();
+    game.handle_action(Action::SonicDrop);
+    assert_eq!(game.active_piece.y, landing.y);
+    assert!((0..game.board.width).all(|x| game.board.get(x, game.board.height - 1).is_none()));
+}
+
+// A big piece covers four squares per mino, and a line can clear through the middle of it.
+#[test]
+fn test_big_piece_clears_half() {
+    let rules = Rules {
+        big: true,
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    game.active_piece = ActivePiece {
+        tetromino: Tetromino::O,
+        ..game.active_piece
+    };
+    assert_eq!(game.active_piece.cells().len(), 16);
+    let bottom = game.board.height - 1;
+    let covered = game.ghost_piece().cells();
+    for x in 0..game.board.width {
+        if !covered.contains(&(x as isize, bottom as isize)) {
+            let index = game.board.index(x as isize, bottom as isize);
+            game.board.cells[index] = Some(Cell::garbage());
+        }
+    }
+    game.handle_action(Action::HardDrop);
+    assert_eq!(game.lines_cleared, 1);
+    assert_eq!(game.board.cells.iter().flatten().count(), 12);
+}
+
+// Big pieces can reach both walls of an even board, so lines can be cleared on an empty one.
+#[test]
+fn test_big_pieces_fill_board() {
+    use std::cmp::Ordering;
+
+    let rules = Rules {
+        big: true,
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    // Two Os against the walls with an upright I between them.
+    for &(tetromino, rotate, left) in &[
+        (Tetromino::O, false, 0),
+        (Tetromino::O, false, 6),
+        (Tetromino::I, true, 4)
+    ] {
+        game.active_piece = ActivePiece {
+            tetromino,
+            rotation: 0,
+            ..game.active_piece
+        };
+        if rotate {
+            game.handle_action(Action::RotateClockwise);
+        }
+        for _ in 0..game.board.width {
+            let x = game.active_piece.cells().iter().map(|&(x, _)| x).min();
+            match x.unwrap().cmp(&left) {
+                Ordering::Greater => game.handle_action(Action::MoveLeft),
+                Ordering::Less => game.handle_action(Action::MoveRight),
+                Ordering::Equal => break
+            }
+        }
+        game.handle_action(Action::HardDrop);
+    }
+    assert_eq!(game.lines_cleared, 4);
+    assert_eq!(game.board.cells.iter().flatten().count(), 8);
+}
+
+// In master mode a piece rotated during the entry delay lands rotated, falls straight to the
+// floor, and only locks once the lock delay runs out. Levels stop at the end of a section.
+#[test]
+fn test_master_mode() {
+    use crate::rules::{Mode, MASTER_ENTRY_DELAY, MASTER_LOCK_DELAY, MASTER_SECTION};
+
+    let rules = Rules {
+        mode: Mode::Master,
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    let spawn_y = game.active_piece.y;
+    game.handle_action(Action::RotateClockwise);
+    assert_eq!(game.active_piece.rotation, 1);
+    for _ in 0..MASTER_ENTRY_DELAY {
+        game.tick();
+    }
+    assert_eq!(game.active_piece.y, spawn_y);
+    game.tick();
+    assert_eq!(game.active_piece.y, game.ghost_piece().y);
+    assert!(game.active_piece.y > spawn_y);
+    for _ in 2..MASTER_LOCK_DELAY {
+        game.tick();
+    }
+    assert_eq!(game.dealt, 0);
+    game.tick();
+    assert_eq!(game.dealt, 1);
+    assert_eq!(game.level, 1);
+    game.level = MASTER_SECTION - 2;
+    game.handle_action(Action::HardDrop);
+    game.handle_action(Action::HardDrop);
+    assert_eq!(game.level, MASTER_SECTION - 1);
+    assert_eq!(game.level_stop(), Some(MASTER_SECTION - 1));
+    assert_eq!(game.grade(), Some("9"));
+}
+
+// Outside master mode the next piece stays out of play for the entry delay, and what's pressed in
+// the meantime happens once it arrives. Master mode's own entry delay can be turned off.
+#[test]
+fn test_entry_delay() {
+    use crate::rules::Mode;
+
+    let rules = Rules {
+        entry_delay: Some(100),
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    assert!(game.piece_in_play());
+    game.handle_action(Action::HardDrop);
+    assert!(!game.piece_in_play());
+    game.handle_action(Action::RotateClockwise);
+    assert_eq!(game.active_piece.rotation, 0);
+    for _ in 0..TICK_RATE / 10 {
+        game.tick();
+    }
+    assert!(game.piece_in_play());
+    assert_eq!(game.active_piece.rotation, 1);
+    let rules = Rules {
+        mode: Mode::Master,
+        entry_delay: Some(0),
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    game.handle_action(Action::HardDrop);
+    assert!(game.piece_in_play());
+}
+
+// Only a lock that clears lines holds up the next piece for the line clear delay.
+#[test]
+fn test_line_clear_delay() {
+    let rules = Rules {
+        line_clear_delay: 20,
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    game.handle_action(Action::HardDrop);
+    assert!(game.piece_in_play());
+    let bottom = game.board.height - 1;
+    let covered = game.ghost_piece().cells();
+    for x in 0..game.board.width {
+        if !covered.contains(&(x as isize, bottom as isize)) {
+            let index = game.board.index(x as isize, bottom as isize);
+            game.board.cells[index] = Some(Cell::garbage());
+        }
+    }
+    game.handle_action(Action::HardDrop);
+    assert_eq!(game.lines_cleared, 1);
+    for _ in 1..20 {
+        game.tick();
+    }
+    assert!(!game.piece_in_play());
+    game.tick();
+    assert!(game.piece_in_play());
+}
+
+// With a lock delay, a piece on the stack locks once it's rested there long enough, and moving it
+// puts that off only as many times as the resets allow.
+#[test]
+fn test_lock_resets() {
+    let rules = Rules {
+        lock_delay: Some(500),
+        lock_resets: Some(1),
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    game.handle_action(Action::SonicDrop);
+    let ticks = (TICK_RATE / 2) as u32;
+    for _ in 1..ticks {
+        game.tick();
+    }
+    game.handle_action(Action::MoveLeft);
+    for _ in 1..ticks {
+        game.tick();
+    }
+    game.handle_action(Action::MoveRight);
+    assert_eq!(game.dealt, 0);
+    game.tick();
+    assert_eq!(game.dealt, 1);
+}
+
+// Soft dropping a resting piece only locks it when the rules say so, and otherwise leaves it to
+// the lock delay.
+#[test]
+fn test_soft_drop_lock() {
+    let rules = Rules {
+        lock_delay: Some(500),
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules.clone(), 0);
+    game.handle_action(Action::SonicDrop);
+    game.handle_action(Action::SoftDrop);
+    assert_eq!(game.dealt, 0);
+    let rules = Rules {
+        soft_drop_lock: Some(true),
+        ..rules
+    };
+    let mut game = Game::with_seed(rules, 0);
+    game.handle_action(Action::SoftDrop);
+    assert_eq!(game.dealt, 0);
+    game.handle_action(Action::SonicDrop);
+    game.handle_action(Action::SoftDrop);
+    assert_eq!(game.dealt, 1);
+}
+
+// With a variable goal each level takes more lines than the last, and a Tetris counts for eight.
+#[test]
+fn test_variable_goal() {
+    let rules = Rules {
+        level_goal: LevelGoal::Variable,
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    game.award_lines(4, false);
+    assert_eq!(game.level, 1);
+    assert_eq!(game.goal_lines, 3);
+    game.award_lines(2, false);
+    assert_eq!(game.level, 1);
+    game.award_lines(4, false);
+    assert_eq!(game.level, 2);
+    assert_eq!(game.goal_lines, 4);
+}
+
+// A constant level is where the game starts, and clearing lines doesn't move it. Master mode's
+// levels are how it's completed, so they can't be held.
+#[test]
+fn test_const_level() {
+    use crate::rules::Mode;
+
+    let rules = Rules {
+        const_level: Some(15),
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    assert_eq!(game.level, 15);
+    game.award_lines(4, false);
+    assert_eq!(game.level, 15);
+    assert_eq!(game.score, 1200 * 16);
+    let rules = Rules {
+        mode: Mode::Master,
+        const_level: Some(15),
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    assert!(!game.level_fixed());
+    game.award_lines(4, false);
+    assert_eq!(game.level, 4);
+}
+
+// Classic rules have no hold or hard drop, whatever keys are bound to them, and no preview.
+#[test]
+fn test_classic_rules() {
+    use crate::rules::Mode;
+
+    let rules = Rules {
+        mode: Mode::Classic,
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    let piece = game.active_piece;
+    game.handle_action(Action::Hold);
+    game.handle_action(Action::HardDrop);
+    assert!(game.hold.is_none());
+    assert_eq!(game.active_piece.y, piece.y);
+    assert_eq!(game.dealt, 0);
+    assert!(game.preview.is_empty());
+}
+
+// Pieces spawn partly in the hidden rows, and locking one entirely up there tops out.
+#[test]
+fn test_hidden_rows() {
+    let mut game = Game::with_seed(Rules::default(), 0);
+    let rows = |game: &Game| {
+        let cells = game.active_piece.cells();
+        let top = cells.iter().map(|&(_, y)| y).min().unwrap();
+        let bottom = cells.iter().map(|&(_, y)| y).max().unwrap();
+        (top, bottom)
+    };
+    game.active_piece = game.spawn_position(Tetromino::T);
+    assert_eq!(rows(&game), (-1, 0));
+    game.add_garbage(game.board.height);
+    game.active_piece = game.spawn_position(Tetromino::T);
+    assert_eq!(rows(&game), (-2, -1));
+    assert!(!game.game_over);
+    game.handle_action(Action::HardDrop);
+    assert!(game.game_over);
+}
+
+// Hold can be used as many times per piece as the limit allows, and again after a lock.
+#[test]
+fn test_hold_limit() {
+    let rules = Rules {
+        hold_limit: Some(2),
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    game.handle_action(Action::Hold);
+    game.handle_action(Action::Hold);
+    assert!(!game.can_hold());
+    let stuck = game.active_piece.tetromino;
+    game.handle_action(Action::Hold);
+    assert_eq!(game.active_piece.tetromino, stuck);
+    assert_eq!(game.stats.holds, 2);
+    game.handle_action(Action::HardDrop);
+    assert_eq!(game.holds_left(), Some(2));
+}
+
+// Garbage should push the stack up, and always repeat its hole when the chance is 100%.
+#[test]
+fn test_add_garbage() {
+    let rules = Rules {
+        garbage_hole_repeat: 100,
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    let bottom = game.board.height - 1;
+    let index = game.board.index(0, bottom as isize);
+    game.board.cells[index] = Some(Cell::garbage());
+    game.add_garbage(3);
+    assert!(game.board.get(0, bottom - 3).is_some());
+    let hole = (0..game.board.width)
+        .find(|&x| game.board.get(x, bottom).is_none())
+        .unwrap();
+    for y in bottom - 2..=bottom {
+        let filled = (0..game.board.width).filter(|&x| game.board.get(x, y).is_some());
+        assert_eq!(filled.count(), game.board.width - 1);
+        assert!(game.board.get(hole, y).is_none());
+    }
+    assert!(!game.game_over);
+}
+
+// Clearing a marked block picks up its item. Clearing rows takes the bottom of the stack away, and
+// the attacks go to the opponent to cut down their preview for a while and move their garbage
+// holes around.
+#[test]
+fn test_items() {
+    let rules = Rules {
+        items: true,
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules.clone(), 0);
+    let bottom = game.board.height as isize - 1;
+    for x in 0..game.board.width {
+        let index = game.board.index(x as isize, bottom);
+        game.board.cells[index] = Some(Cell::garbage());
+    }
+    let index = game.board.index(4, bottom);
+    game.board.cells[index] = Some(Cell {
+        tetromino: None,
+        item: Some(Item::ClearRows)
+    });
+    game.handle_action(Action::HardDrop);
+    assert_eq!(game.item, Some(Item::ClearRows));
+    game.add_garbage(3);
+    let height = game.board.stack_height();
+    game.handle_action(Action::UseItem);
+    assert_eq!(game.item, None);
+    assert_eq!(game.board.stack_height(), height - CLEAR_ROWS);
+
+    let mut opponent = Game::with_seed(rules, 1);
+    game.versus = true;
+    game.item = Some(Item::ShrinkPreview);
+    game.handle_action(Action::UseItem);
+    assert_eq!(game.outgoing_items, [Item::ShrinkPreview]);
+    opponent.receive_item(Item::ShrinkPreview);
+    assert_eq!(opponent.shown_preview().len(), 1);
+    for _ in 0..SHRINK_TICKS {
+        opponent.tick();
+    }
+    assert_eq!(opponent.shown_preview().len(), opponent.preview.len());
+    opponent.add_garbage(4);
+    opponent.receive_item(Item::ScrambleGarbage);
+    for y in opponent.board.height - 4..opponent.board.height {
+        let filled = (0..opponent.board.width).filter(|&x| opponent.board.get(x, y).is_some());
+        assert_eq!(filled.count(), opponent.board.width - 1);
+    }
+}
+
+// Everything a player can do to the game itself, for turning random bytes into random play.
+#[cfg(test)]
+const PLAY_ACTIONS: [Action; 8] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::RotateClockwise,
+    Action::RotateAnticlockwise,
+    Action::SoftDrop,
+    Action::HardDrop,
+    Action::SonicDrop,
+    Action::Hold
+];
+
+// Play a game from random input with a tick after every action, checking `check` after each one.
+// Each byte is an action, or a few ticks of doing nothing if it's past the end of the actions.
+#[cfg(test)]
+fn play_randomly<F: FnMut(&Game, Option<Tetromino>) -> bool>(
+    rules: Rules,
+    seed: u64,
+    input: &[u8],
+    mut check: F
+) -> bool {
+    let mut game = Game::with_seed(rules, seed);
+    for &byte in input {
+        if game.game_over {
+            break;
+        }
+        let before = (game.active_piece.tetromino, game.stats.pieces);
+        match PLAY_ACTIONS.get(byte as usize % 16) {
+            Some(&action) => game.handle_action(action),
+            None => {
+                for _ in 0..byte % 8 {
+                    game.tick();
+                }
+            }
+        }
+        game.tick();
+        // Nothing can lock more than one piece at once, so the one that was active is the one that
+        // locked.
+        let locked = if game.stats.pieces > before.1 {
+            Some(before.0)
+        } else {
+            None
+        };
+        if !check(&game, locked) {
+            return false;
+        }
+    }
+    true
+}
+
+// Whatever's on the board has something under it, and there's never a full row left behind.
+#[cfg(test)]
+fn rows_settled(board: &GameBoard) -> bool {
+    let filled = |y| {
+        (0..board.width)
+            .filter(|&x| board.get(x, y).is_some())
+            .count()
+    };
+    let first_filled = (0..board.height).find(|&y| filled(y) > 0);
+    match first_filled {
+        Some(top) => (top..board.height).all(|y| filled(y) > 0 && filled(y) < board.width),
+        None => true
+    }
+}
+
+// No amount of random play should leave a floating or full row, with or without cascade gravity
+// or big pieces, or let the active piece overlap the stack while the game is still going.
+#[test]
+fn test_random_play_keeps_board_valid() {
+    fn property(seed: u64, cascade: bool, big: bool, input: Vec<u8>) -> bool {
+        let rules = Rules {
+            cascade,
+            big,
+            ..Rules::default()
+        };
+        play_randomly(rules, seed, &input, |game, _| {
+            rows_settled(&game.board) && (game.game_over || game.board.fits(&game.active_piece))
+        })
+    }
+    quickcheck::quickcheck(property as fn(u64, bool, bool, Vec<u8>) -> bool);
+}
+
+// Cascade gravity on any board should stop, and leave nothing that could still fall.
+#[test]
+fn test_settle_terminates() {
+    fn property(filled: Vec<bool>) -> bool {
+        let mut board = GameBoard::new(10, 20, 2);
+        for (cell, &filled) in board.cells.iter_mut().zip(filled.iter()) {
+            if filled {
+                *cell = Some(Cell::garbage());
+            }
+        }
+        let count = board.cells.iter().flatten().count();
+        board.settle();
+        !board.drop_one_group() && board.cells.iter().flatten().count() == count
+    }
+    quickcheck::quickcheck(property as fn(Vec<bool>) -> bool);
+}
+
+// Holding should never make a piece appear twice or disappear. Every piece dealt so far is either
+// locked, active, or held, and they're the same pieces a game with the same seed deals in order. A
+// piece that locks out ends the game before another is dealt, leaving it as the active piece too,
+// so it's only counted once.
+#[test]
+fn test_hold_never_duplicates() {
+    fn property(seed: u64, input: Vec<u8>) -> bool {
+        let mut dealer = Game::with_seed(Rules::default(), seed);
+        let mut dealt = vec![dealer.active_piece.tetromino];
+        let mut locked = Vec::new();
+        let mut last_dealt = 0;
+        play_randomly(Rules::default(), seed, &input, |game, piece| {
+            locked.extend(piece);
+            let locked_out = game.game_over && piece.is_some() && game.dealt == last_dealt;
+            last_dealt = game.dealt;
+            while dealt.len() < game.dealt as usize + 1 {
+                dealt.push(dealer.next_tetromino());
+            }
+            let mut held = locked.clone();
+            if !locked_out {
+                held.push(game.active_piece.tetromino);
+            }
+            held.extend(game.hold);
+            let mut expected = dealt.clone();
+            held.sort_by_key(|&t| t as u8);
+            expected.sort_by_key(|&t| t as u8);
+            held == expected
+        })
+    }
+    quickcheck::quickcheck(property as fn(u64, Vec<u8>) -> bool);
+}
+
+// A seed read back from how it's shown starts the same game over.
+#[test]
+fn test_seed_text() {
+    let game = Game::with_seed(Rules::default(), 0x00ab_cdef_0123_4567);
+    assert_eq!(game.seed_text(), "00abcdef01234567");
+    let again = Game::with_seed(Rules::default(), parse_seed(&game.seed_text()).unwrap());
+    assert_eq!(again.preview, game.preview);
+    assert_eq!(parse_seed("not a seed"), None);
+}
+
+// A handicap only comes into it once the game is a versus game.
+#[test]
+fn test_handicap_only_in_versus() {
+    use crate::rules::Handicap;
+
+    let rules = Rules {
+        handicap: Handicap {
+            garbage_rows: 3,
+            gravity_offset: 5,
+            preview: Some(1),
+            attack_percent: 50
+        },
+        ..Rules::default()
+    };
+    let mut game = Game::with_seed(rules, 0);
+    assert_eq!(game.board.stack_height(), 0);
+    assert_eq!(game.shown_preview().len(), game.preview.len());
+    assert_eq!(game.gravity_level(), game.level);
+    game.start_versus();
+    assert_eq!(game.board.stack_height(), 3);
+    assert_eq!(game.shown_preview().len(), 1);
+    assert_eq!(game.gravity_level(), game.level + 5);
+    game.exchange_garbage(5, true);
+    assert_eq!(game.outgoing_attack, 3);
+}
@@ -0,0 +1,163 @@
+#[cfg(feature = "gamepad")]
+use gilrs::{Button as GilrsButton, EventType, Gilrs};
+#[cfg(any(feature = "gamepad", test))]
+use std::time::Duration;
+use std::time::Instant;
+
+#[cfg(any(feature = "gamepad", test))]
+use crate::input::Button;
+use crate::input::Key;
+#[cfg(feature = "gamepad")]
+use crate::input::KeyCode;
+
+// Controllers don't repeat held buttons the way terminals repeat held keys, so that's done here at
+// about a terminal's rate. Holding a direction then keeps the piece moving, and `HeldKeys` can tell
+// a held button from a tapped one.
+#[cfg(any(feature = "gamepad", test))]
+const REPEAT_DELAY: Duration = Duration::from_millis(250);
+#[cfg(any(feature = "gamepad", test))]
+const REPEAT_INTERVAL: Duration = Duration::from_millis(33);
+
+// The buttons being held down, when each was pressed or last repeated, and whether it's started
+// repeating yet.
+#[cfg(any(feature = "gamepad", test))]
+#[derive(Default)]
+struct Repeats {
+    held: Vec<(Button, Instant, bool)>
+}
+
+#[cfg(any(feature = "gamepad", test))]
+impl Repeats {
+    fn press(&mut self, button: Button, now: Instant) {
+        self.release(button);
+        self.held.push((button, now, false));
+    }
+
+    fn release(&mut self, button: Button) {
+        self.held.retain(|&(held, _, _)| held != button);
+    }
+
+    // The held buttons that are due to repeat.
+    fn due(&mut self, now: Instant) -> Vec<Button> {
+        let mut due = Vec::new();
+        for held in self.held.iter_mut() {
+            let wait = if held.2 {
+                REPEAT_INTERVAL
+            } else {
+                REPEAT_DELAY
+            };
+            if now.duration_since(held.1) >= wait {
+                held.1 = now;
+                held.2 = true;
+                due.push(held.0);
+            }
+        }
+        due
+    }
+}
+
+// Game controllers, read as key presses of their buttons so that they can be bound alongside the
+// keyboard. Without the `gamepad` feature, or when controllers can't be read, there are never any.
+pub struct Gamepads {
+    #[cfg(feature = "gamepad")]
+    gilrs: Option<Gilrs>,
+    #[cfg(feature = "gamepad")]
+    repeats: Repeats
+}
+
+impl Gamepads {
+    #[cfg(feature = "gamepad")]
+    pub fn connect() -> Self {
+        let gilrs = match Gilrs::new() {
+            Ok(gilrs) => Some(gilrs),
+            Err(e) => {
+                info!("Not reading game controllers: {}", e);
+                None
+            }
+        };
+        Gamepads {
+            gilrs,
+            repeats: Repeats::default()
+        }
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn connect() -> Self {
+        Gamepads {}
+    }
+
+    // The buttons pressed since the last poll, followed by any held ones that are due to repeat.
+    #[cfg(feature = "gamepad")]
+    pub fn poll(&mut self, now: Instant) -> Vec<Key> {
+        let mut pressed = Vec::new();
+        if let Some(ref mut gilrs) = self.gilrs {
+            while let Some(event) = gilrs.next_event() {
+                match event.event {
+                    EventType::ButtonPressed(button, _) => {
+                        if let Some(button) = button_of(button) {
+                            self.repeats.press(button, now);
+                            pressed.push(button);
+                        }
+                    }
+                    EventType::ButtonReleased(button, _) => {
+                        if let Some(button) = button_of(button) {
+                            self.repeats.release(button);
+                        }
+                    }
+                    EventType::Disconnected => self.repeats = Repeats::default(),
+                    _ => {}
+                }
+            }
+        }
+        pressed.extend(self.repeats.due(now));
+        pressed
+            .into_iter()
+            .map(|button| Key::plain(KeyCode::Button(button)))
+            .collect()
+    }
+
+    #[cfg(not(feature = "gamepad"))]
+    pub fn poll(&mut self, _now: Instant) -> Vec<Key> {
+        Vec::new()
+    }
+}
+
+// gilrs calls the bumpers triggers and the triggers second triggers.
+#[cfg(feature = "gamepad")]
+fn button_of(button: GilrsButton) -> Option<Button> {
+    Some(match button {
+        GilrsButton::South => Button::South,
+        GilrsButton::East => Button::East,
+        GilrsButton::North => Button::North,
+        GilrsButton::West => Button::West,
+        GilrsButton::LeftTrigger => Button::LeftBumper,
+        GilrsButton::RightTrigger => Button::RightBumper,
+        GilrsButton::LeftTrigger2 => Button::LeftTrigger,
+        GilrsButton::RightTrigger2 => Button::RightTrigger,
+        GilrsButton::Select => Button::Select,
+        GilrsButton::Start => Button::Start,
+        GilrsButton::Mode => Button::Mode,
+        GilrsButton::LeftThumb => Button::LeftStick,
+        GilrsButton::RightThumb => Button::RightStick,
+        GilrsButton::DPadUp => Button::Up,
+        GilrsButton::DPadDown => Button::Down,
+        GilrsButton::DPadLeft => Button::Left,
+        GilrsButton::DPadRight => Button::Right,
+        _ => return None
+    })
+}
+
+// A held button repeats after a delay and then quickly, and stops once it's let go.
+#[test]
+fn test_gamepad_repeats() {
+    let start = Instant::now();
+    let at = |millis| start + Duration::from_millis(millis);
+    let mut repeats = Repeats::default();
+    repeats.press(Button::Left, at(0));
+    assert!(repeats.due(at(100)).is_empty());
+    assert_eq!(repeats.due(at(250)), [Button::Left]);
+    assert!(repeats.due(at(270)).is_empty());
+    assert_eq!(repeats.due(at(283)), [Button::Left]);
+    repeats.release(Button::Left);
+    assert!(repeats.due(at(500)).is_empty());
+}
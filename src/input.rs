@@ -0,0 +1,693 @@
+use crossterm::KeyEvent;
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+use std::ops::BitOr;
+use std::time::{Duration, Instant};
+pub use tui_tetris::action::Action;
+use tui_tetris::gameboard::Game;
+
+use crate::game_config::{GameConfig, InputConflicts};
+
+// The same model of keys as crossterm's later `KeyCode` and `KeyModifiers`. crossterm 0.11 reports
+// modified keys as their own `KeyEvent` variants instead, which `Key::from_event` converts.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum KeyCode {
+    Char(char),
+    Left,
+    Right,
+    Up,
+    Down,
+    Home,
+    End,
+    PageUp,
+    PageDown,
+    Insert,
+    Delete,
+    Backspace,
+    Enter,
+    Tab,
+    Esc,
+    F(u8),
+    Button(Button)
+}
+
+// Game controller buttons, named for where they are rather than what's printed on them since that
+// changes from one controller to the next. They're bound like any other key, and only do anything
+// with the `gamepad` feature.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Button {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftStick,
+    RightStick,
+    Up,
+    Down,
+    Left,
+    Right
+}
+
+const BUTTON_NAMES: [(Button, &str); 17] = [
+    (Button::South, "pad_south"),
+    (Button::East, "pad_east"),
+    (Button::North, "pad_north"),
+    (Button::West, "pad_west"),
+    (Button::LeftBumper, "pad_lb"),
+    (Button::RightBumper, "pad_rb"),
+    (Button::LeftTrigger, "pad_lt"),
+    (Button::RightTrigger, "pad_rt"),
+    (Button::Select, "pad_select"),
+    (Button::Start, "pad_start"),
+    (Button::Mode, "pad_mode"),
+    (Button::LeftStick, "pad_ls"),
+    (Button::RightStick, "pad_rs"),
+    (Button::Up, "pad_up"),
+    (Button::Down, "pad_down"),
+    (Button::Left, "pad_left"),
+    (Button::Right, "pad_right")
+];
+
+impl KeyCode {
+    // Names for the keys that aren't written as a single character in the config.
+    pub fn from_name(name: &str) -> Option<Self> {
+        let mut chars = name.chars();
+        if let (Some(c), None) = (chars.next(), chars.next()) {
+            return Some(KeyCode::Char(c));
+        }
+        if let Some(&(button, _)) = BUTTON_NAMES
+            .iter()
+            .find(|&&(_, button_name)| button_name.eq_ignore_ascii_case(name))
+        {
+            return Some(KeyCode::Button(button));
+        }
+        Some(match name.to_ascii_lowercase().as_str() {
+            "space" => KeyCode::Char(' '),
+            "comma" => KeyCode::Char(','),
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" => KeyCode::PageUp,
+            "pagedown" => KeyCode::PageDown,
+            "insert" => KeyCode::Insert,
+            "delete" => KeyCode::Delete,
+            "backspace" => KeyCode::Backspace,
+            "enter" => KeyCode::Enter,
+            "tab" => KeyCode::Tab,
+            "esc" => KeyCode::Esc,
+            name => match name.strip_prefix('f').map(str::parse) {
+                Some(Ok(n)) if (1..=12).contains(&n) => KeyCode::F(n),
+                _ => return None
+            }
+        })
+    }
+}
+
+impl Display for KeyCode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char(',') => write!(f, "comma"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            KeyCode::Button(button) => {
+                let name = BUTTON_NAMES
+                    .iter()
+                    .find(|&&(named, _)| named == *button)
+                    .map_or("", |&(_, name)| name);
+                write!(f, "{}", name)
+            }
+        }
+    }
+}
+
+// Bit flags for the modifiers held with a key.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct KeyModifiers(u8);
+
+impl KeyModifiers {
+    pub const NONE: KeyModifiers = KeyModifiers(0);
+    pub const SHIFT: KeyModifiers = KeyModifiers(1);
+    pub const CONTROL: KeyModifiers = KeyModifiers(2);
+    pub const ALT: KeyModifiers = KeyModifiers(4);
+
+    pub fn contains(self, other: KeyModifiers) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+
+impl BitOr for KeyModifiers {
+    type Output = KeyModifiers;
+
+    fn bitor(self, other: KeyModifiers) -> KeyModifiers {
+        KeyModifiers(self.0 | other.0)
+    }
+}
+
+// Modifier names in the order they're written in, e.g. `ctrl+alt+shift+left`.
+const MODIFIER_NAMES: [(&str, KeyModifiers); 3] = [
+    ("ctrl", KeyModifiers::CONTROL),
+    ("alt", KeyModifiers::ALT),
+    ("shift", KeyModifiers::SHIFT)
+];
+
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Key {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers
+}
+
+impl Key {
+    pub const fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        Key { code, modifiers }
+    }
+
+    pub const fn plain(code: KeyCode) -> Self {
+        Key::new(code, KeyModifiers::NONE)
+    }
+
+    pub fn is_button(&self) -> bool {
+        matches!(self.code, KeyCode::Button(_))
+    }
+
+    // Shifted letters come through as capitals, so they're stored as the lower case letter with
+    // shift held. That way `Z` and `shift+z` are the same binding.
+    fn character(c: char, modifiers: KeyModifiers) -> Self {
+        if c.is_uppercase() {
+            Key::new(
+                KeyCode::Char(c.to_lowercase().next().unwrap_or(c)),
+                modifiers | KeyModifiers::SHIFT
+            )
+        } else {
+            Key::new(KeyCode::Char(c), modifiers)
+        }
+    }
+
+    pub fn from_event(event: &KeyEvent) -> Option<Self> {
+        Some(match *event {
+            KeyEvent::Char(c) => Key::character(c, KeyModifiers::NONE),
+            KeyEvent::Alt(c) => Key::character(c, KeyModifiers::ALT),
+            KeyEvent::Ctrl(c) => Key::new(KeyCode::Char(c), KeyModifiers::CONTROL),
+            KeyEvent::Left => Key::plain(KeyCode::Left),
+            KeyEvent::Right => Key::plain(KeyCode::Right),
+            KeyEvent::Up => Key::plain(KeyCode::Up),
+            KeyEvent::Down => Key::plain(KeyCode::Down),
+            KeyEvent::CtrlLeft => Key::new(KeyCode::Left, KeyModifiers::CONTROL),
+            KeyEvent::CtrlRight => Key::new(KeyCode::Right, KeyModifiers::CONTROL),
+            KeyEvent::CtrlUp => Key::new(KeyCode::Up, KeyModifiers::CONTROL),
+            KeyEvent::CtrlDown => Key::new(KeyCode::Down, KeyModifiers::CONTROL),
+            KeyEvent::ShiftLeft => Key::new(KeyCode::Left, KeyModifiers::SHIFT),
+            KeyEvent::ShiftRight => Key::new(KeyCode::Right, KeyModifiers::SHIFT),
+            KeyEvent::ShiftUp => Key::new(KeyCode::Up, KeyModifiers::SHIFT),
+            KeyEvent::ShiftDown => Key::new(KeyCode::Down, KeyModifiers::SHIFT),
+            KeyEvent::Home => Key::plain(KeyCode::Home),
+            KeyEvent::End => Key::plain(KeyCode::End),
+            KeyEvent::PageUp => Key::plain(KeyCode::PageUp),
+            KeyEvent::PageDown => Key::plain(KeyCode::PageDown),
+            KeyEvent::Insert => Key::plain(KeyCode::Insert),
+            KeyEvent::Delete => Key::plain(KeyCode::Delete),
+            KeyEvent::Backspace => Key::plain(KeyCode::Backspace),
+            KeyEvent::Enter => Key::plain(KeyCode::Enter),
+            KeyEvent::Tab => Key::plain(KeyCode::Tab),
+            KeyEvent::BackTab => Key::new(KeyCode::Tab, KeyModifiers::SHIFT),
+            KeyEvent::Esc => Key::plain(KeyCode::Esc),
+            KeyEvent::F(n) => Key::plain(KeyCode::F(n)),
+            KeyEvent::Null => return None
+        })
+    }
+
+    // Keys are written as any modifiers followed by the key, joined with '+'. The names
+    // 'lshift', 'rshift', 'lctrl', and 'rctrl' from older configs meant the arrow key combos that
+    // crossterm used to report under those names.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "lshift" => return Some(Key::new(KeyCode::Left, KeyModifiers::SHIFT)),
+            "rshift" => return Some(Key::new(KeyCode::Right, KeyModifiers::SHIFT)),
+            "lctrl" => return Some(Key::new(KeyCode::Left, KeyModifiers::CONTROL)),
+            "rctrl" => return Some(Key::new(KeyCode::Right, KeyModifiers::CONTROL)),
+            _ => {}
+        }
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = name;
+        'modifiers: loop {
+            for &(modifier_name, modifier) in MODIFIER_NAMES.iter() {
+                let prefix = rest.get(..modifier_name.len() + 1);
+                // A trailing '+' is the plus key rather than another separator.
+                if rest.len() > modifier_name.len() + 1
+                    && prefix
+                        .is_some_and(|p| p.eq_ignore_ascii_case(&format!("{}+", modifier_name)))
+                {
+                    modifiers = modifiers | modifier;
+                    rest = &rest[modifier_name.len() + 1..];
+                    continue 'modifiers;
+                }
+            }
+            break;
+        }
+        match KeyCode::from_name(rest)? {
+            KeyCode::Char(c) => Some(Key::character(c, modifiers)),
+            code => Some(Key::new(code, modifiers))
+        }
+    }
+}
+
+impl Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for &(name, modifier) in MODIFIER_NAMES.iter() {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", self.code)
+    }
+}
+
+// Translate a key press into the game action it's bound to, if any. Ctrl-C always quits since raw
+// mode swallows the usual interrupt signal.
+pub fn map_key(config: &GameConfig, key: &Key) -> Option<Action> {
+    if *key == Key::new(KeyCode::Char('c'), KeyModifiers::CONTROL) {
+        Some(Action::Quit)
+    } else if config.left.contains(key) {
+        Some(Action::MoveLeft)
+    } else if config.right.contains(key) {
+        Some(Action::MoveRight)
+    } else if config.rot_cw.contains(key) {
+        Some(Action::RotateClockwise)
+    } else if config.rot_acw.contains(key) {
+        Some(Action::RotateAnticlockwise)
+    } else if config.soft_drop.contains(key) {
+        Some(Action::SoftDrop)
+    } else if config.hard_drop.contains(key) {
+        Some(Action::HardDrop)
+    } else if config.sonic_drop.contains(key) {
+        Some(Action::SonicDrop)
+    } else if config.hold.contains(key) {
+        Some(Action::Hold)
+    } else if config.use_item.contains(key) {
+        Some(Action::UseItem)
+    } else if config.switch_target.contains(key) {
+        Some(Action::SwitchTarget)
+    } else if config.pause.contains(key) {
+        Some(Action::Pause)
+    } else if config.quit.contains(key) {
+        Some(Action::Quit)
+    } else if config.toggle_stats.contains(key) {
+        Some(Action::ToggleStats)
+    } else if config.toggle_hint.contains(key) {
+        Some(Action::ToggleHint)
+    } else if config.toggle_debug.contains(key) {
+        Some(Action::ToggleDebug)
+    } else if config.toggle_frame_step.contains(key) {
+        Some(Action::ToggleFrameStep)
+    } else if config.frame_step.contains(key) {
+        Some(Action::FrameStep)
+    } else if config.rewind.contains(key) {
+        Some(Action::Rewind)
+    } else if config.undo.contains(key) {
+        Some(Action::Undo)
+    } else if config.screenshot.contains(key) {
+        Some(Action::Screenshot)
+    } else {
+        None
+    }
+}
+
+// What a key does in the pause, settings and high score menus.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum MenuAction {
+    Up,
+    Down,
+    Left,
+    Right,
+    Select,
+    Back
+}
+
+// Translate a key press into a menu action. The menu keys are checked first so that they win over
+// movement keys bound to the same thing.
+pub fn map_menu_key(config: &GameConfig, key: &Key) -> Option<MenuAction> {
+    if config.menu_up.contains(key) {
+        Some(MenuAction::Up)
+    } else if config.menu_down.contains(key) {
+        Some(MenuAction::Down)
+    } else if config.menu_select.contains(key) {
+        Some(MenuAction::Select)
+    } else if config.menu_back.contains(key) {
+        Some(MenuAction::Back)
+    } else if config.left.contains(key) {
+        Some(MenuAction::Left)
+    } else if config.right.contains(key) {
+        Some(MenuAction::Right)
+    } else {
+        None
+    }
+}
+
+// The actions that carry over to a piece as it spawns when their keys are held down with `irs` on,
+// in the order they're applied: a held piece is swapped in before it's rotated.
+pub const INITIAL_ACTIONS: [Action; 3] = [
+    Action::Hold,
+    Action::RotateClockwise,
+    Action::RotateAnticlockwise
+];
+
+// Actions that act on the piece. These are collected over a frame and done together once all of
+// its input has been read, so that ones that conflict can be sorted out first.
+pub const PIECE_ACTIONS: [Action; 8] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::RotateClockwise,
+    Action::RotateAnticlockwise,
+    Action::SoftDrop,
+    Action::HardDrop,
+    Action::SonicDrop,
+    Action::Hold
+];
+
+// Actions that undo each other when they're both pressed on the same frame.
+const OPPOSITES: [(Action, Action); 2] = [
+    (Action::MoveLeft, Action::MoveRight),
+    (Action::RotateClockwise, Action::RotateAnticlockwise)
+];
+
+// Sort out one frame's piece actions. Of two opposites, either the one pressed last wins or both
+// are dropped. Moves and rotations pressed on the same frame as a hard drop always go before it,
+// so that they apply to the piece being dropped rather than the next one.
+pub fn resolve_conflicts(actions: &[Action], conflicts: InputConflicts) -> Vec<Action> {
+    let mut resolved = actions.to_vec();
+    for &(a, b) in OPPOSITES.iter() {
+        if !resolved.contains(&a) || !resolved.contains(&b) {
+            continue;
+        }
+        match conflicts {
+            InputConflicts::Latest => {
+                let latest = resolved
+                    .iter()
+                    .rev()
+                    .find(|&&action| action == a || action == b);
+                let loser = if latest == Some(&a) { b } else { a };
+                resolved.retain(|&action| action != loser);
+            }
+            InputConflicts::Cancel => resolved.retain(|&action| action != a && action != b)
+        }
+    }
+    let mut ordered: Vec<Action> = Vec::with_capacity(resolved.len());
+    for action in resolved {
+        let steers = OPPOSITES.iter().any(|&(a, b)| action == a || action == b);
+        match ordered.iter().position(|&done| done == Action::HardDrop) {
+            Some(drop) if steers => ordered.insert(drop, action),
+            _ => ordered.push(action)
+        }
+    }
+    ordered
+}
+
+// Terminals don't say when a key is let go, only repeat it while it's held down. A key counts as
+// held while its repeats keep coming in less than this far apart.
+const REPEAT_GAP: Duration = Duration::from_millis(100);
+
+// Which actions' keys are being held down, going by key repeats. Nothing shows a key is held until
+// the terminal starts repeating it, so a key only counts once it's been down for the repeat delay.
+#[derive(Default)]
+pub struct HeldKeys {
+    // When each action's key was last seen and whether that was a repeat.
+    last: Vec<(Action, Instant, bool)>
+}
+
+impl HeldKeys {
+    // Note a press of the key for `action`. Returns false if it's a repeat of a held key.
+    pub fn press(&mut self, action: Action, now: Instant) -> bool {
+        match self.last.iter_mut().find(|entry| entry.0 == action) {
+            Some(entry) => {
+                let repeat = now.duration_since(entry.1) < REPEAT_GAP;
+                entry.1 = now;
+                entry.2 = repeat;
+                !repeat
+            }
+            None => {
+                self.last.push((action, now, false));
+                true
+            }
+        }
+    }
+
+    pub fn is_held(&self, action: Action, now: Instant) -> bool {
+        self.last.iter().any(|&(held, at, repeat)| {
+            held == action && repeat && now.duration_since(at) < REPEAT_GAP
+        })
+    }
+}
+
+// How many of the latest inputs the input log keeps.
+const INPUT_LOG_LENGTH: usize = 256;
+
+// An action as it was given to the game, with the frame it came in on and when it was read. Once
+// the game has played it, `ticks` is the game's tick it came on and `piece` is the piece it went to,
+// counted by the game's `spawned`. Actions that came while paused or counting down have neither.
+#[derive(Copy, Clone, Debug)]
+pub struct LoggedInput {
+    pub frame: u64,
+    pub at: Instant,
+    pub action: Action,
+    pub ticks: Option<u64>,
+    pub piece: u64
+}
+
+// Every action given to the game, numbered by frame, so that the debug overlay, the replay, the
+// finesse check and anything else that looks at input all see the same stream. Only the latest
+// few are kept, but they're counted from the start so that readers can pick up where they left off.
+#[derive(Default)]
+pub struct InputLog {
+    frame: u64,
+    pushed: u64,
+    logged: VecDeque<LoggedInput>
+}
+
+impl InputLog {
+    // Called at the start of every frame, before its input is read.
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn push(&mut self, action: Action, at: Instant) {
+        if self.logged.len() == INPUT_LOG_LENGTH {
+            self.logged.pop_front();
+        }
+        self.logged.push_back(LoggedInput {
+            frame: self.frame,
+            at,
+            action,
+            ticks: None,
+            piece: 0
+        });
+        self.pushed += 1;
+    }
+
+    // Note that the game is playing the latest input, as it stands before playing it.
+    pub fn played(&mut self, game: &Game) {
+        if let Some(input) = self.logged.back_mut() {
+            input.ticks = Some(game.stats.ticks);
+            input.piece = game.spawned;
+        }
+    }
+
+    // How many inputs have been pushed, for reading on from with `since`.
+    pub fn pushed(&self) -> u64 {
+        self.pushed
+    }
+
+    // The inputs pushed after the first `pushed`, as far as they're still kept.
+    pub fn since(&self, pushed: u64) -> impl Iterator<Item = &LoggedInput> {
+        let dropped = self.pushed - self.logged.len() as u64;
+        let start = pushed.saturating_sub(dropped).min(self.logged.len() as u64);
+        self.logged.range(start as usize..)
+    }
+
+    // How many moves and rotations the game played on a piece.
+    pub fn piece_moves(&self, piece: u64) -> u32 {
+        self.logged
+            .iter()
+            .filter(|input| input.ticks.is_some() && input.piece == piece)
+            .filter(|input| {
+                matches!(
+                    input.action,
+                    Action::MoveLeft
+                        | Action::MoveRight
+                        | Action::RotateClockwise
+                        | Action::RotateAnticlockwise
+                )
+            })
+            .count() as u32
+    }
+
+    pub fn last(&self) -> Option<&LoggedInput> {
+        self.logged.back()
+    }
+
+    // The inputs that came in on the current frame, in order.
+    pub fn this_frame(&self) -> impl Iterator<Item = &LoggedInput> {
+        let frame = self.frame;
+        let start = self.logged.partition_point(|input| input.frame < frame);
+        self.logged.range(start..)
+    }
+}
+
+// Inputs are kept by frame, and only the latest few are kept.
+#[test]
+fn test_input_log() {
+    let now = Instant::now();
+    let mut log = InputLog::default();
+    log.push(Action::MoveLeft, now);
+    log.next_frame();
+    log.push(Action::RotateClockwise, now);
+    log.push(Action::HardDrop, now);
+    let this_frame = log
+        .this_frame()
+        .map(|input| input.action)
+        .collect::<Vec<_>>();
+    assert_eq!(this_frame, [Action::RotateClockwise, Action::HardDrop]);
+    log.next_frame();
+    assert_eq!(log.this_frame().count(), 0);
+    assert_eq!(log.last().map(|input| input.frame), Some(1));
+    for _ in 0..INPUT_LOG_LENGTH {
+        log.push(Action::Hold, now);
+    }
+    assert_eq!(log.this_frame().count(), INPUT_LOG_LENGTH);
+    assert_eq!(log.logged.len(), INPUT_LOG_LENGTH);
+    assert_eq!(log.since(3).count(), INPUT_LOG_LENGTH);
+    assert_eq!(log.since(log.pushed() - 1).count(), 1);
+    assert_eq!(log.since(log.pushed()).count(), 0);
+}
+
+// Only the moves and rotations the game played on a piece are counted against it.
+#[test]
+fn test_piece_moves() {
+    use tui_tetris::rules::Rules;
+
+    let now = Instant::now();
+    let mut game = Game::with_seed(Rules::default(), 1);
+    let mut log = InputLog::default();
+    for &action in &[Action::MoveLeft, Action::SoftDrop, Action::RotateClockwise] {
+        log.push(action, now);
+        log.played(&game);
+    }
+    log.push(Action::MoveRight, now);
+    assert_eq!(log.piece_moves(game.spawned), 2);
+    game.handle_action(Action::HardDrop);
+    assert_eq!(log.piece_moves(game.spawned), 0);
+}
+
+// A single tap isn't a held key, a run of repeats is, and letting go is noticed once they stop.
+#[test]
+fn test_held_keys() {
+    let start = Instant::now();
+    let at = |millis| start + Duration::from_millis(millis);
+    let mut held = HeldKeys::default();
+    assert!(held.press(Action::RotateClockwise, at(0)));
+    assert!(!held.is_held(Action::RotateClockwise, at(10)));
+    assert!(held.press(Action::RotateClockwise, at(500)));
+    assert!(!held.press(Action::RotateClockwise, at(530)));
+    assert!(held.is_held(Action::RotateClockwise, at(560)));
+    assert!(!held.is_held(Action::Hold, at(560)));
+    assert!(!held.is_held(Action::RotateClockwise, at(700)));
+}
+
+// Key names should survive being written out and read back in.
+#[test]
+fn test_key_names() {
+    for name in &[
+        "ctrl+left",
+        "shift+z",
+        "alt+space",
+        "ctrl+alt++",
+        "comma",
+        "f5",
+        "esc",
+        "pad_south",
+        "pad_lb"
+    ] {
+        assert_eq!(Key::from_name(name).unwrap().to_string(), *name);
+    }
+    assert_eq!(Key::from_name("Z"), Key::from_name("shift+z"));
+    assert_eq!(Key::from_name("lshift").unwrap().to_string(), "shift+left");
+    assert_eq!(
+        Key::from_event(&KeyEvent::Alt('Z')),
+        Key::from_name("alt+shift+z")
+    );
+    assert!(Key::from_name("ctrl+").is_none());
+    assert!(Key::from_name("hyper+left").is_none());
+}
+
+// Menus should follow rebound movement keys, with the menu keys winning when they overlap.
+#[test]
+fn test_menu_keys() {
+    let mut config = GameConfig::default();
+    config.left = vec![
+        Key::plain(KeyCode::Char('a')),
+        Key::plain(KeyCode::Char('j')),
+    ];
+    assert_eq!(
+        map_menu_key(&config, &Key::plain(KeyCode::Char('a'))),
+        Some(MenuAction::Left)
+    );
+    assert_eq!(
+        map_menu_key(&config, &Key::plain(KeyCode::Char('j'))),
+        Some(MenuAction::Down)
+    );
+    assert_eq!(map_menu_key(&config, &Key::plain(KeyCode::Left)), None);
+}
+
+// Opposites either leave the last one pressed or cancel out, and a hard drop waits for the moves
+// and rotations pressed with it.
+#[test]
+fn test_resolve_conflicts() {
+    use Action::*;
+
+    let actions = [MoveLeft, HardDrop, RotateClockwise, MoveRight, Hold];
+    assert_eq!(
+        resolve_conflicts(&actions, InputConflicts::Latest),
+        [RotateClockwise, MoveRight, HardDrop, Hold]
+    );
+    assert_eq!(
+        resolve_conflicts(&actions, InputConflicts::Cancel),
+        [RotateClockwise, HardDrop, Hold]
+    );
+    let actions = [
+        RotateAnticlockwise,
+        RotateClockwise,
+        RotateAnticlockwise,
+        SoftDrop
+    ];
+    assert_eq!(
+        resolve_conflicts(&actions, InputConflicts::Latest),
+        [RotateAnticlockwise, RotateAnticlockwise, SoftDrop]
+    );
+}
@@ -0,0 +1,28 @@
+use crate::timing::TICK_RATE;
+
+// Percent chance that a piece locked in a game with items has one of its blocks marked.
+pub const ITEM_CHANCE: u32 = 20;
+// Rows taken off the bottom of the board by `Item::ClearRows`.
+pub const CLEAR_ROWS: usize = 2;
+// Ticks the opponent's preview is cut down to the next piece by `Item::ShrinkPreview`.
+pub const SHRINK_TICKS: u64 = TICK_RATE * 10;
+
+// Power-ups for the casual item mode. Clearing a line with a marked block in it picks up that
+// block's item, which is then used with its own key. Only `ClearRows` helps the player who uses
+// it; the others are sent to the opponent.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Item {
+    ClearRows,
+    ShrinkPreview,
+    ScrambleGarbage
+}
+
+pub const ITEMS: [Item; 3] = [Item::ClearRows, Item::ShrinkPreview, Item::ScrambleGarbage];
+
+impl Item {
+    // Whether the item is used on the opponent's game rather than the player's own.
+    pub fn is_attack(self) -> bool {
+        self != Item::ClearRows
+    }
+}
@@ -0,0 +1,245 @@
+use crate::serde_json;
+#[cfg(feature = "online")]
+use crate::ureq;
+use crossterm::{Goto, Output, Result as CrosstermResult};
+use std::io::Write;
+#[cfg(feature = "online")]
+use std::time::Duration;
+use tui_tetris::gameboard::{Game, GameBoard};
+use tui_tetris::timing::TICK_RATE;
+
+use crate::color::ColorSupport;
+use crate::game_config::GameConfig;
+use crate::input::Key;
+use crate::locale::{fill, Strings};
+use crate::records::Measure;
+use crate::session::Splits;
+
+// How long to wait on the leaderboard before giving up, so a server that's down doesn't hold up
+// the end of the game for long.
+#[cfg(feature = "online")]
+const TIMEOUT: Duration = Duration::from_secs(5);
+// How many entries the leaderboard screen shows.
+const SHOWN_ENTRIES: usize = 10;
+// Every line of the leaderboard screen is padded to this so that it covers the game panel.
+const SCREEN_WIDTH: usize = 32;
+// FNV-1a, which is simple enough for a server in any language to check the hash with.
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+// A result as it's sent to the leaderboard. Times are in milliseconds, and the replay hash covers
+// the board after every piece, so a result can be checked against a replay of the seed.
+#[derive(Serialize)]
+pub struct Submission {
+    name: String,
+    course: String,
+    measure: &'static str,
+    value: u64,
+    seed: String,
+    replay_hash: String
+}
+
+impl Submission {
+    // Only challenges against the clock or played for time go on the leaderboard, and only once
+    // they count.
+    pub fn new(config: &GameConfig, game: &Game, pages: &[GameBoard]) -> Option<Self> {
+        let measure = Measure::of(game);
+        let value = measure.value(game)?;
+        let (measure, value) = match measure {
+            Measure::Time => ("time", value * 1000 / TICK_RATE),
+            Measure::Score => ("score", value),
+            Measure::Level => return None
+        };
+        Some(Submission {
+            name: config.leaderboard_name.clone(),
+            course: Splits::course(game),
+            measure,
+            value,
+            seed: game.seed_text(),
+            replay_hash: replay_hash(pages)
+        })
+    }
+
+    // POST the result as JSON to `<url>/results`.
+    #[cfg(feature = "online")]
+    pub fn send(&self, url: &str) -> Result<(), LeaderboardError> {
+        let failed = |e: &dyn ToString| LeaderboardError::Failed(e.to_string());
+        let body = serde_json::to_string(self).map_err(|e| failed(&e))?;
+        ureq::post(&format!("{}/results", url.trim_end_matches('/')))
+            .timeout(TIMEOUT)
+            .set("Content-Type", "application/json")
+            .send_string(&body)
+            .map(|_| ())
+            .map_err(|e| failed(&e))
+    }
+
+    #[cfg(not(feature = "online"))]
+    pub fn send(&self, _url: &str) -> Result<(), LeaderboardError> {
+        Err(LeaderboardError::NotBuilt)
+    }
+}
+
+// Why the leaderboard couldn't be reached. Errors from the server and the connection to it are
+// passed on as they come, but not having the online feature is the game's own to explain.
+#[derive(Debug)]
+pub enum LeaderboardError {
+    #[cfg_attr(feature = "online", allow(dead_code))]
+    NotBuilt,
+    #[cfg_attr(not(feature = "online"), allow(dead_code))]
+    Failed(String)
+}
+
+impl LeaderboardError {
+    pub fn message(&self, text: &Strings) -> String {
+        match self {
+            LeaderboardError::NotBuilt => text.online_not_built.to_string(),
+            LeaderboardError::Failed(ref e) => fill(text.leaderboard_failed, &[e])
+        }
+    }
+}
+
+// One place on the leaderboard, as it comes back from the server.
+#[derive(Clone, Deserialize)]
+pub struct Entry {
+    pub(crate) name: String,
+    pub(crate) value: u64
+}
+
+// The top entries for the kind of game being played, shown from the pause menu.
+pub struct Leaderboard {
+    course: String,
+    measure: Measure,
+    entries: Result<Vec<Entry>, LeaderboardError>
+}
+
+impl Leaderboard {
+    // Fetch the entries straight away. Whatever goes wrong is shown in their place.
+    pub fn load(url: &str, game: &Game) -> Self {
+        let course = Splits::course(game);
+        Leaderboard {
+            entries: fetch(url, &course),
+            measure: Measure::of(game),
+            course
+        }
+    }
+
+    pub fn draw<W: Write>(
+        &self,
+        config: &GameConfig,
+        color_support: ColorSupport,
+        out: &mut W,
+        x: u16
+    ) -> CrosstermResult<()> {
+        let text = config.language.strings();
+        let line = |s: &str| format!("{:width$}", s, width = SCREEN_WIDTH);
+        let mut lines = vec![
+            line(text.leaderboard),
+            line(&self.course.to_uppercase()),
+            line(""),
+        ];
+        match self.entries {
+            Ok(ref entries) if entries.is_empty() => lines.push(line(text.no_entries)),
+            Ok(ref entries) => {
+                for (place, entry) in entries.iter().take(SHOWN_ENTRIES).enumerate() {
+                    let value = match self.measure {
+                        Measure::Time => format!(
+                            "{}:{:02}.{:03}",
+                            entry.value / 60_000,
+                            entry.value / 1000 % 60,
+                            entry.value % 1000
+                        ),
+                        Measure::Score | Measure::Level => entry.value.to_string()
+                    };
+                    let name = entry.name.chars().take(16).collect::<String>();
+                    lines.push(line(&format!(
+                        "{:>2}. {:<16} {:>10}",
+                        place + 1,
+                        name,
+                        value
+                    )));
+                }
+            }
+            // Errors can be long, so they're wrapped onto as many lines as they need.
+            Err(ref e) => {
+                let message = e.message(text).chars().collect::<Vec<_>>();
+                for chunk in message.chunks(SCREEN_WIDTH) {
+                    lines.push(line(&chunk.iter().collect::<String>()));
+                }
+            }
+        }
+        lines.push(line(""));
+        // The first back key is named, since it can be rebound.
+        let back = config
+            .menu_back
+            .first()
+            .map_or(String::new(), Key::to_string);
+        lines.push(line(&format!("{} {}", back, text.to_leave)));
+        queue!(out, Output(color_support.fg(config.border_color)))?;
+        for (y, text) in lines.into_iter().enumerate() {
+            queue!(out, Goto(x, y as u16 + 1), Output(text))?;
+        }
+        Ok(())
+    }
+}
+
+// GET `<url>/top?course=<course>`, which should be a JSON list of entries, best first.
+#[cfg(feature = "online")]
+fn fetch(url: &str, course: &str) -> Result<Vec<Entry>, LeaderboardError> {
+    let failed = |e: &dyn ToString| LeaderboardError::Failed(e.to_string());
+    let body = ureq::get(&format!("{}/top", url.trim_end_matches('/')))
+        .timeout(TIMEOUT)
+        .query("course", course)
+        .call()
+        .map_err(|e| failed(&e))?
+        .into_string()
+        .map_err(|e| failed(&e))?;
+    serde_json::from_str(&body).map_err(|e| failed(&e))
+}
+
+#[cfg(not(feature = "online"))]
+fn fetch(_url: &str, _course: &str) -> Result<Vec<Entry>, LeaderboardError> {
+    Err(LeaderboardError::NotBuilt)
+}
+
+// A hash of every board in the game as 16 hex digits.
+pub fn replay_hash(pages: &[GameBoard]) -> String {
+    let mut hash = FNV_OFFSET;
+    for page in pages {
+        let board = serde_json::to_string(page).unwrap_or_default();
+        for byte in board.bytes() {
+            hash ^= byte as u64;
+            hash = hash.wrapping_mul(FNV_PRIME);
+        }
+    }
+    format!("{:016x}", hash)
+}
+
+// The same boards always hash the same, and any change to them changes the hash.
+#[test]
+fn test_replay_hash() {
+    use tui_tetris::action::Action;
+    use tui_tetris::rules::Rules;
+
+    let mut game = Game::with_seed(Rules::default(), 0);
+    let mut pages = vec![game.board.clone()];
+    assert_eq!(replay_hash(&pages), replay_hash(&[game.board.clone()]));
+    let empty = replay_hash(&pages);
+    game.handle_action(Action::HardDrop);
+    pages.push(game.board.clone());
+    assert_ne!(replay_hash(&pages), empty);
+    assert_eq!(replay_hash(&[]), format!("{:016x}", FNV_OFFSET));
+}
+
+// Not having the online feature is explained in the player's language, and server errors are
+// passed on inside it.
+#[test]
+fn test_error_messages() {
+    use crate::locale::{ENGLISH, SPANISH};
+
+    assert_eq!(
+        LeaderboardError::NotBuilt.message(&SPANISH),
+        SPANISH.online_not_built
+    );
+    let failed = LeaderboardError::Failed("timed out".to_string());
+    assert_eq!(failed.message(&ENGLISH), "Leaderboard error: timed out");
+}
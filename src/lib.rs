@@ -0,0 +1,28 @@
+// The game itself, with nothing to do with the terminal, so that it can be run headless for tests,
+// benchmarks, and bots, or put behind a different frontend. The binary only does terminal I/O on
+// top of it.
+#[macro_use]
+extern crate log;
+#[cfg(test)]
+extern crate quickcheck;
+extern crate rand;
+extern crate rand_chacha;
+extern crate serde;
+extern crate serde_json;
+#[macro_use]
+extern crate serde_derive;
+
+pub mod action;
+pub mod ai;
+pub mod challenge;
+pub mod fumen;
+pub mod gameboard;
+pub mod item;
+pub mod puzzle;
+pub mod replay;
+pub mod rollback;
+pub mod rules;
+pub mod stats;
+pub mod tetromino;
+pub mod timing;
+pub mod versus;
@@ -0,0 +1,1218 @@
+use std::env;
+use std::fmt::{self, Display};
+
+// The languages the interface can be shown in. Anything printed before the config has been read,
+// including errors in the config itself, is in the language the environment asks for.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum Language {
+    English,
+    Spanish
+}
+
+pub const LANGUAGES: [Language; 2] = [Language::English, Language::Spanish];
+
+impl Language {
+    // Spanish if the locale from the environment is, English otherwise.
+    pub fn from_env() -> Self {
+        let locale = ["LC_ALL", "LC_MESSAGES", "LANG"]
+            .iter()
+            .filter_map(|name| env::var(name).ok())
+            .find(|locale| !locale.is_empty());
+        match locale {
+            Some(ref locale) if locale.starts_with("es") => Language::Spanish,
+            _ => Language::English
+        }
+    }
+
+    pub fn strings(self) -> &'static Strings {
+        match self {
+            Language::English => &ENGLISH,
+            Language::Spanish => &SPANISH
+        }
+    }
+}
+
+impl Display for Language {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Language::English => "english",
+                Language::Spanish => "spanish"
+            }
+        )
+    }
+}
+
+// Every piece of text shown while playing. Labels in the side panel and columns are padded out to
+// a fixed width with `field`, so translations can be longer or shorter than the English as long
+// as the value still fits beside them.
+pub struct Strings {
+    // What the language is called in itself, for the settings menu.
+    pub(crate) name: &'static str,
+    // Side panel
+    pub(crate) score: &'static str,
+    pub(crate) level: &'static str,
+    pub(crate) fixed: &'static str,
+    pub(crate) lines: &'static str,
+    pub(crate) grade: &'static str,
+    pub(crate) next: &'static str,
+    pub(crate) hold: &'static str,
+    // The item waiting to be used in a game with items, and what each is called.
+    pub(crate) item: &'static str,
+    pub(crate) clear_rows: &'static str,
+    pub(crate) shrink_preview: &'static str,
+    pub(crate) scramble_garbage: &'static str,
+    pub(crate) config_error: &'static str,
+    pub(crate) paused: &'static str,
+    pub(crate) opponent_left: &'static str,
+    pub(crate) frame_step: &'static str,
+    pub(crate) built: &'static str,
+    pub(crate) go: &'static str,
+    pub(crate) continue_save: &'static str,
+    pub(crate) settings: &'static str,
+    pub(crate) statistics: &'static str,
+    // Results and high score entry
+    pub(crate) you_win: &'static str,
+    pub(crate) you_lose: &'static str,
+    pub(crate) complete: &'static str,
+    pub(crate) failed: &'static str,
+    pub(crate) game_over: &'static str,
+    pub(crate) singles: &'static str,
+    pub(crate) doubles: &'static str,
+    pub(crate) triples: &'static str,
+    pub(crate) tetrises: &'static str,
+    pub(crate) t_spins: &'static str,
+    pub(crate) drops: &'static str,
+    pub(crate) max_combo: &'static str,
+    pub(crate) time: &'static str,
+    pub(crate) with_pauses: &'static str,
+    pub(crate) pps: &'static str,
+    pub(crate) finesse: &'static str,
+    pub(crate) new_high_score: &'static str,
+    pub(crate) name_label: &'static str,
+    pub(crate) to_save: &'static str,
+    pub(crate) seed: &'static str,
+    pub(crate) to_retry: &'static str,
+    pub(crate) personal_best: &'static str,
+    pub(crate) new_best: &'static str,
+    // Attack log, live stats, the speedrun timer and the ghost race
+    pub(crate) attacks: &'static str,
+    pub(crate) sent: &'static str,
+    pub(crate) incoming: &'static str,
+    pub(crate) target: &'static str,
+    pub(crate) player: &'static str,
+    pub(crate) target_random: &'static str,
+    pub(crate) target_leader: &'static str,
+    pub(crate) target_danger: &'static str,
+    pub(crate) target_even: &'static str,
+    pub(crate) pieces: &'static str,
+    pub(crate) apm: &'static str,
+    pub(crate) holds: &'static str,
+    pub(crate) stack: &'static str,
+    pub(crate) splits: &'static str,
+    pub(crate) ahead: &'static str,
+    pub(crate) behind: &'static str,
+    pub(crate) even: &'static str,
+    // Lifetime statistics
+    pub(crate) games: &'static str,
+    pub(crate) best_score: &'static str,
+    pub(crate) average_pps: &'static str,
+    pub(crate) no_games: &'static str,
+    // Online leaderboard
+    pub(crate) leaderboard: &'static str,
+    pub(crate) no_entries: &'static str,
+    pub(crate) online_not_built: &'static str,
+    pub(crate) leaderboard_failed: &'static str,
+    // Settings menu. Entry labels have to fit in 11 columns.
+    pub(crate) controls: &'static str,
+    pub(crate) preset: &'static str,
+    pub(crate) move_left: &'static str,
+    pub(crate) move_right: &'static str,
+    pub(crate) rotate_clockwise: &'static str,
+    pub(crate) rotate_anticlockwise: &'static str,
+    pub(crate) soft_drop: &'static str,
+    pub(crate) hard_drop: &'static str,
+    pub(crate) sonic_drop: &'static str,
+    pub(crate) hold_entry: &'static str,
+    pub(crate) pause: &'static str,
+    pub(crate) quit: &'static str,
+    pub(crate) stats: &'static str,
+    pub(crate) hint: &'static str,
+    pub(crate) debug: &'static str,
+    pub(crate) ghost: &'static str,
+    pub(crate) cascade: &'static str,
+    pub(crate) big_mode: &'static str,
+    pub(crate) irs: &'static str,
+    pub(crate) countdown: &'static str,
+    pub(crate) ai_level: &'static str,
+    pub(crate) ai_style: &'static str,
+    pub(crate) sound: &'static str,
+    pub(crate) piece_cues: &'static str,
+    pub(crate) palette: &'static str,
+    pub(crate) border: &'static str,
+    pub(crate) background: &'static str,
+    pub(crate) renderer: &'static str,
+    pub(crate) grid: &'static str,
+    pub(crate) block_size: &'static str,
+    pub(crate) effects: &'static str,
+    pub(crate) language: &'static str,
+    pub(crate) save: &'static str,
+    pub(crate) on: &'static str,
+    pub(crate) off: &'static str,
+    pub(crate) none: &'static str,
+    // Keys that don't match any preset.
+    pub(crate) custom: &'static str,
+    pub(crate) key_in_use: &'static str,
+    pub(crate) key_saved: &'static str,
+    pub(crate) preset_saved: &'static str,
+    // Only the default keys can be rebound, since they're the ones saved to the config.
+    pub(crate) default_keys_only: &'static str,
+    pub(crate) saved: &'static str,
+    pub(crate) saving_failed: &'static str,
+    pub(crate) press_key: &'static str,
+    pub(crate) to_cancel: &'static str,
+    pub(crate) to_change: &'static str,
+    pub(crate) to_leave: &'static str,
+    // Messages printed before and after playing. `fill` puts a value in place of each `{}`, in
+    // order.
+    pub(crate) see_help: &'static str,
+    pub(crate) logging_to: &'static str,
+    pub(crate) logging_failed: &'static str,
+    pub(crate) config_written: &'static str,
+    pub(crate) write_failed: &'static str,
+    pub(crate) scores_failed: &'static str,
+    pub(crate) waiting_for_opponent: &'static str,
+    pub(crate) waiting_for_opponents: &'static str,
+    pub(crate) spectator_joined: &'static str,
+    pub(crate) player_joined: &'static str,
+    pub(crate) waiting_for_host: &'static str,
+    pub(crate) no_high_scores: &'static str,
+    pub(crate) table_lines: &'static str,
+    pub(crate) bench_running: &'static str,
+    pub(crate) bench_random: &'static str,
+    pub(crate) bench_ai: &'static str,
+    pub(crate) checking_defaults: &'static str,
+    pub(crate) using_defaults: &'static str,
+    pub(crate) playback_failed: &'static str,
+    pub(crate) cast_written: &'static str,
+    pub(crate) cast_failed: &'static str,
+    pub(crate) preset_unsaved: &'static str,
+    pub(crate) spectate_failed: &'static str,
+    pub(crate) network_failed: &'static str,
+    pub(crate) bot_failed: &'static str,
+    pub(crate) unknown_opener: &'static str,
+    pub(crate) position_failed: &'static str,
+    pub(crate) saved_game_failed: &'static str,
+    pub(crate) terminal_failed: &'static str,
+    pub(crate) opener_built: &'static str,
+    pub(crate) puzzle_solved: &'static str,
+    pub(crate) puzzle_failed: &'static str,
+    pub(crate) challenge_complete: &'static str,
+    pub(crate) challenge_failed: &'static str,
+    pub(crate) game_saved: &'static str,
+    pub(crate) save_failed: &'static str,
+    pub(crate) lifetime_failed: &'static str,
+    pub(crate) new_personal_best: &'static str,
+    pub(crate) best_failed: &'static str,
+    pub(crate) new_best_splits: &'static str,
+    pub(crate) splits_failed: &'static str,
+    pub(crate) result_sent: &'static str,
+    pub(crate) send_failed: &'static str,
+    pub(crate) replay_saved: &'static str,
+    pub(crate) replay_failed: &'static str,
+    pub(crate) fumen_of_game: &'static str,
+    pub(crate) fumen_width: &'static str,
+    pub(crate) stats_added: &'static str,
+    pub(crate) stats_failed: &'static str,
+    // Mistakes on the command line, each shown before `see_help`.
+    pub(crate) no_replay: &'static str,
+    pub(crate) no_cast_replay: &'static str,
+    pub(crate) no_config_command: &'static str,
+    pub(crate) unknown_command: &'static str,
+    pub(crate) unexpected_argument: &'static str,
+    pub(crate) missing_argument_value: &'static str,
+    pub(crate) profile_name_characters: &'static str,
+    pub(crate) bad_player_count: &'static str,
+    pub(crate) challenge_name_characters: &'static str,
+    pub(crate) puzzle_name_characters: &'static str,
+    pub(crate) bad_seed: &'static str,
+    pub(crate) unknown_preset: &'static str,
+    pub(crate) unknown_log_level: &'static str,
+    pub(crate) one_net_role: &'static str,
+    pub(crate) bot_and_ai: &'static str,
+    pub(crate) bot_online: &'static str,
+    pub(crate) players_host_only: &'static str,
+    pub(crate) challenge_versus: &'static str,
+    pub(crate) fumen_versus: &'static str,
+    pub(crate) trainer_alone: &'static str,
+    pub(crate) puzzle_versus: &'static str,
+    pub(crate) seed_versus: &'static str,
+    pub(crate) puzzle_alone: &'static str,
+    // Why a challenge, a puzzle, the config or a bot couldn't be used.
+    pub(crate) unknown_puzzle: &'static str,
+    pub(crate) invalid_puzzle: &'static str,
+    pub(crate) puzzle_no_goal: &'static str,
+    pub(crate) unknown_challenge: &'static str,
+    pub(crate) invalid_challenge: &'static str,
+    pub(crate) challenge_no_goal: &'static str,
+    pub(crate) profile_needs_toml: &'static str,
+    pub(crate) config_read_failed: &'static str,
+    pub(crate) profile_unsaved: &'static str,
+    pub(crate) mode_unsaved: &'static str,
+    pub(crate) bot_board: &'static str,
+    pub(crate) bot_rules: &'static str,
+    pub(crate) help: &'static str,
+    pub(crate) help_presets: &'static str,
+    pub(crate) help_challenges: &'static str,
+    pub(crate) help_openers: &'static str,
+    pub(crate) help_puzzles: &'static str,
+    pub(crate) config_dir_failed: &'static str,
+    pub(crate) config_created: &'static str,
+    pub(crate) config_write_failed: &'static str,
+    pub(crate) config_create_failed: &'static str,
+    pub(crate) config_valid: &'static str,
+    pub(crate) config_problems: &'static str,
+    pub(crate) defaults_below: &'static str,
+    pub(crate) effective_settings: &'static str,
+    pub(crate) playing_against: &'static str,
+    // The terminal's title while playing, and the desktop notifications at the end of a game.
+    pub(crate) window_title: &'static str,
+    pub(crate) best_notification: &'static str,
+    pub(crate) completed_notification: &'static str,
+    // The level and score under the kind of game in Discord.
+    #[cfg_attr(not(feature = "discord"), allow(dead_code))]
+    pub(crate) discord_state: &'static str,
+    // Config errors.
+    pub(crate) in_file: &'static str,
+    pub(crate) error_on_line: &'static str,
+    pub(crate) error_in_arguments: &'static str,
+    pub(crate) on_line: &'static str,
+    pub(crate) of_file: &'static str,
+    pub(crate) in_arguments: &'static str,
+    pub(crate) deprecated_warning: &'static str,
+    pub(crate) unknown_warning: &'static str,
+    pub(crate) invalid_line_format: &'static str,
+    pub(crate) unknown_setting: &'static str,
+    pub(crate) deprecated_setting: &'static str,
+    pub(crate) invalid_value: &'static str,
+    pub(crate) duplicate_setting: &'static str,
+    pub(crate) failed_parse_value: &'static str,
+    pub(crate) missing_value: &'static str,
+    pub(crate) invalid_toml: &'static str,
+    // How to fix each config error, shown after it. `fill` puts the list of setting names, or the
+    // name an old one has now, in place of the `{}`.
+    pub(crate) valid_settings: &'static str,
+    pub(crate) valid_theme_settings: &'static str,
+    pub(crate) renamed_setting: &'static str,
+    pub(crate) fps_limiter_unparsed: &'static str,
+    pub(crate) fps_limiter_range: &'static str,
+    pub(crate) board_width_unparsed: &'static str,
+    pub(crate) board_width_range: &'static str,
+    pub(crate) board_height_unparsed: &'static str,
+    pub(crate) board_height_range: &'static str,
+    pub(crate) hidden_rows_unparsed: &'static str,
+    pub(crate) hidden_rows_range: &'static str,
+    pub(crate) entry_delay_unparsed: &'static str,
+    pub(crate) entry_delay_range: &'static str,
+    pub(crate) line_clear_delay_unparsed: &'static str,
+    pub(crate) line_clear_delay_range: &'static str,
+    pub(crate) lock_delay_unparsed: &'static str,
+    pub(crate) lock_delay_range: &'static str,
+    pub(crate) const_level_unparsed: &'static str,
+    pub(crate) const_level_range: &'static str,
+    pub(crate) lines_per_level_unparsed: &'static str,
+    pub(crate) lines_per_level_range: &'static str,
+    pub(crate) garbage_hole_repeat_unparsed: &'static str,
+    pub(crate) garbage_hole_repeat_range: &'static str,
+    pub(crate) input_delay_unparsed: &'static str,
+    pub(crate) input_delay_range: &'static str,
+    pub(crate) bot_delay_unparsed: &'static str,
+    pub(crate) bot_delay_range: &'static str,
+    pub(crate) handicap_garbage_unparsed: &'static str,
+    pub(crate) handicap_garbage_range: &'static str,
+    pub(crate) handicap_gravity_unparsed: &'static str,
+    pub(crate) handicap_gravity_range: &'static str,
+    pub(crate) handicap_preview_unparsed: &'static str,
+    pub(crate) handicap_preview_range: &'static str,
+    pub(crate) handicap_attack_unparsed: &'static str,
+    pub(crate) handicap_attack_range: &'static str,
+    pub(crate) countdown_unparsed: &'static str,
+    pub(crate) countdown_range: &'static str,
+    pub(crate) game_speed_unparsed: &'static str,
+    pub(crate) game_speed_range: &'static str,
+    pub(crate) block_size_unparsed: &'static str,
+    pub(crate) block_size_range: &'static str,
+    pub(crate) danger_height_unparsed: &'static str,
+    pub(crate) danger_height_range: &'static str,
+    pub(crate) hold_limit_count: &'static str,
+    pub(crate) lock_resets_count: &'static str,
+    pub(crate) mode_values: &'static str,
+    pub(crate) level_goal_values: &'static str,
+    pub(crate) key_names: &'static str,
+    pub(crate) empty_key: &'static str,
+    pub(crate) missing_color_type: &'static str,
+    pub(crate) missing_color: &'static str,
+    pub(crate) ansi_color_unparsed: &'static str,
+    pub(crate) color_formats: &'static str,
+    pub(crate) missing_red: &'static str,
+    pub(crate) red_unparsed: &'static str,
+    pub(crate) missing_green: &'static str,
+    pub(crate) green_unparsed: &'static str,
+    pub(crate) missing_blue: &'static str,
+    pub(crate) blue_unparsed: &'static str,
+    pub(crate) missing_character: &'static str,
+    pub(crate) single_character: &'static str,
+    pub(crate) color_support_values: &'static str,
+    pub(crate) theme_name_characters: &'static str,
+    pub(crate) leaderboard_url_scheme: &'static str,
+    pub(crate) discord_id_number: &'static str,
+    pub(crate) leaderboard_name_length: &'static str,
+    pub(crate) ai_difficulty_values: &'static str,
+    pub(crate) ai_style_values: &'static str,
+    pub(crate) targeting_values: &'static str,
+    pub(crate) input_conflicts_values: &'static str,
+    pub(crate) renderer_values: &'static str,
+    pub(crate) language_values: &'static str,
+    pub(crate) grid_values: &'static str,
+    pub(crate) stats_export_values: &'static str,
+    pub(crate) palette_values: &'static str,
+    pub(crate) bool_values: &'static str,
+    pub(crate) missing_setting_name: &'static str,
+    pub(crate) missing_setting_value: &'static str,
+    pub(crate) toml_value_types: &'static str,
+    pub(crate) missing_profile: &'static str,
+    pub(crate) missing_bindings: &'static str,
+    pub(crate) unknown_option: &'static str,
+    pub(crate) theme_unreadable: &'static str,
+    pub(crate) big_mode_width: &'static str,
+    pub(crate) board_smaller_than_block: &'static str,
+    pub(crate) wrong_section: &'static str,
+    pub(crate) bindings_keys_only: &'static str
+}
+
+pub const ENGLISH: Strings = Strings {
+    name: "english",
+    score: "SCORE",
+    level: "LEVEL",
+    fixed: "FIXED",
+    lines: "LINES",
+    grade: "GRADE",
+    next: "NEXT",
+    hold: "HOLD",
+    item: "ITEM",
+    clear_rows: "CLEAR ROWS",
+    shrink_preview: "SHRINK NEXT",
+    scramble_garbage: "SCRAMBLE",
+    config_error: "CONFIG ERROR",
+    paused: "PAUSED",
+    opponent_left: "OPPONENT LEFT",
+    frame_step: "FRAME STEP",
+    built: "BUILT",
+    go: "GO",
+    continue_save: "CONTINUE SAVE",
+    settings: "SETTINGS",
+    statistics: "STATISTICS",
+    you_win: "YOU WIN",
+    you_lose: "YOU LOSE",
+    complete: "COMPLETE",
+    failed: "FAILED",
+    game_over: "GAME OVER",
+    singles: "SINGLES",
+    doubles: "DOUBLES",
+    triples: "TRIPLES",
+    tetrises: "TETRISES",
+    t_spins: "T-SPINS",
+    drops: "DROPS",
+    max_combo: "MAX COMBO",
+    time: "TIME",
+    with_pauses: "WITH PAUSES",
+    pps: "PPS",
+    finesse: "FINESSE",
+    new_high_score: "NEW HIGH SCORE",
+    name_label: "NAME",
+    to_save: "TO SAVE",
+    seed: "SEED",
+    to_retry: "TO RETRY",
+    personal_best: "VS BEST",
+    new_best: "NEW",
+    attacks: "ATTACKS",
+    sent: "sent",
+    incoming: "incoming",
+    target: "TARGET",
+    player: "PLAYER",
+    target_random: "RANDOM",
+    target_leader: "LEADER",
+    target_danger: "DANGER",
+    target_even: "SPLIT",
+    pieces: "PIECES",
+    apm: "APM",
+    holds: "HOLDS",
+    stack: "STACK",
+    splits: "SPLITS",
+    ahead: "AHEAD",
+    behind: "BEHIND",
+    even: "EVEN",
+    games: "GAMES",
+    best_score: "BEST SCORE",
+    average_pps: "AVERAGE PPS",
+    no_games: "No games played yet",
+    leaderboard: "LEADERBOARD",
+    no_entries: "No entries yet",
+    online_not_built: "Built without the online feature.",
+    leaderboard_failed: "Leaderboard error: {}",
+    controls: "Controls",
+    preset: "Preset",
+    move_left: "Move left",
+    move_right: "Move right",
+    rotate_clockwise: "Rotate CW",
+    rotate_anticlockwise: "Rotate ACW",
+    soft_drop: "Soft drop",
+    hard_drop: "Hard drop",
+    sonic_drop: "Sonic drop",
+    hold_entry: "Hold",
+    pause: "Pause",
+    quit: "Quit",
+    stats: "Stats",
+    hint: "Hint",
+    debug: "Debug",
+    ghost: "Ghost piece",
+    cascade: "Cascade",
+    big_mode: "Big mode",
+    irs: "IRS/IHS",
+    countdown: "Countdown",
+    ai_level: "AI level",
+    ai_style: "AI style",
+    sound: "Sound",
+    piece_cues: "Piece cues",
+    palette: "Palette",
+    border: "Border",
+    background: "Background",
+    renderer: "Renderer",
+    grid: "Grid",
+    block_size: "Block size",
+    effects: "Effects",
+    language: "Language",
+    save: "Save",
+    on: "on",
+    off: "off",
+    none: "none",
+    custom: "custom",
+    key_in_use: "KEY ALREADY IN USE",
+    key_saved: "KEY SAVED",
+    preset_saved: "CONTROLS SAVED",
+    default_keys_only: "ONLY THE DEFAULT KEYS CHANGE",
+    saved: "SAVED",
+    saving_failed: "SAVING FAILED",
+    press_key: "Press a key, or",
+    to_cancel: "to cancel",
+    to_change: "to change",
+    to_leave: "to leave",
+    see_help: "{}\nRun with --help to see the available options.",
+    logging_to: "Logging to {}.",
+    logging_failed: "Warning: failed to start logging.\n{}",
+    config_written: "Wrote a default config to {}.",
+    write_failed: "Failed to write {}.\n{}",
+    scores_failed: "Failed to read the high scores.\n{}",
+    waiting_for_opponent: "Waiting for an opponent on {}...",
+    waiting_for_opponents: "Waiting for {} opponents on {}...",
+    spectator_joined: "A spectator joined.",
+    player_joined: "Player {} joined.",
+    waiting_for_host: "Waiting for the host to start the game...",
+    no_high_scores: "No high scores yet.",
+    table_lines: "{} lines",
+    bench_running: "Timing the game without drawing it, {} seconds for each kind of play...",
+    bench_random: "Random moves: {} ticks in {} seconds, {} ticks a second.",
+    bench_ai: "AI placements: {} pieces in {} seconds, {} pieces a second.",
+    checking_defaults: "No config file found, checking the defaults.",
+    using_defaults: "Warning: using default game config.",
+    playback_failed: "Failed to play back {}.\n{}",
+    cast_written: "Recording written to {}.",
+    cast_failed: "Failed to make a recording of {}.\n{}",
+    preset_unsaved: "{}\nUsing the {} controls for this game only.",
+    spectate_failed: "Failed to watch the network game.\n{}",
+    network_failed: "Failed to set up the network game.\n{}",
+    bot_failed: "Failed to start the bot.\n{}",
+    unknown_opener: "Unknown opener: {}. See --help for the ones there are.",
+    position_failed: "Failed to load the position. {}",
+    saved_game_failed: "Warning: failed to load the saved game.\n{}",
+    terminal_failed: "Critical error! Terminal I/O failed.\n{}",
+    opener_built: "Built {} ({}) in {} of {} attempts.",
+    puzzle_solved: "Puzzle {} ({}) solved.",
+    puzzle_failed: "Puzzle {} ({}) failed.",
+    challenge_complete: "Challenge {} ({}) complete.",
+    challenge_failed: "Challenge {} ({}) failed.",
+    game_saved: "Game saved to {}. Continue it from the pause menu next time.",
+    save_failed: "Warning: failed to save the game.\n{}",
+    lifetime_failed: "Warning: failed to update lifetime stats.\n{}",
+    new_personal_best: "New personal best.",
+    best_failed: "Warning: failed to save the personal best.\n{}",
+    new_best_splits: "New best splits.",
+    splits_failed: "Warning: failed to save the best splits.\n{}",
+    result_sent: "Result sent to the leaderboard.",
+    send_failed: "Warning: failed to send the result to the leaderboard.\n{}",
+    replay_saved: "Replay saved to {}.",
+    replay_failed: "Warning: failed to save the replay.\n{}",
+    fumen_of_game: "Fumen of the game:\n{}",
+    fumen_width: "Only games on 10 wide boards can be exported as fumens.",
+    stats_added: "Game stats added to {}.",
+    stats_failed: "Warning: failed to save game stats.\n{}",
+    no_replay: "Expected the replay to play back.",
+    no_cast_replay: "Expected the replay to make a recording of.",
+    no_config_command: "Expected config check or config init.",
+    unknown_command: "Unknown command: {}",
+    unexpected_argument: "Unexpected argument: {}",
+    missing_argument_value: "Missing value for {}",
+    profile_name_characters: "Profile names can't contain '/', '\\', or '.'.",
+    bad_player_count: "Bad player count: {}. Games can have from 2 to {} players.",
+    challenge_name_characters: "Challenge names can't contain '/', '\\', or '.'.",
+    puzzle_name_characters: "Puzzle names can't contain '/', '\\', or '.'.",
+    bad_seed: "Bad seed: {}. Seeds are the 16 letters and digits shown on the pause menu.",
+    unknown_preset: "Unknown control preset: {}. Use {}.",
+    unknown_log_level: "Unknown log level: {}. Use off, error, warn, info, debug, or trace.",
+    one_net_role: "Only one of --host, --connect, and --spectate can be used.",
+    bot_and_ai: "Only one of --bot and --ai can be used.",
+    bot_online: "Bots can't be played against in network games.",
+    players_host_only: "Only the host picks how many players there are.",
+    challenge_versus: "Challenges can't be played in versus games.",
+    fumen_versus: "Fumen positions can't be played in versus games.",
+    trainer_alone: "The trainer can't be used with --challenge, --fumen, or --puzzle.",
+    puzzle_versus: "Puzzles and the trainer can't be used in versus games.",
+    seed_versus: "Seeds can't be picked in versus games or the trainer.",
+    puzzle_alone: "Puzzles have their own goal and board, so can't be used with --challenge or --fumen.",
+    unknown_puzzle: "Unknown puzzle: {}. See --help for the ones there are.",
+    invalid_puzzle: "Invalid puzzle file {}: {}",
+    puzzle_no_goal: "The puzzle in {} has no goal.",
+    unknown_challenge: "Unknown challenge: {}. See --help for the built-in ones.",
+    invalid_challenge: "Invalid challenge file {}: {}",
+    challenge_no_goal: "The challenge in {} has no goal.",
+    profile_needs_toml: "There's no config file for this profile, and {} can't have profile sections since it isn't a TOML config.",
+    config_read_failed: "Critical error! Failed to read config file {}.\n{}",
+    profile_unsaved: "Settings can't be saved to a profile section.",
+    mode_unsaved: "Settings can't be saved while the {} section applies.",
+    bot_board: "Bots can only play on boards 10 wide and at most 40 tall.",
+    bot_rules: "Bots can only play in modern mode with one hold per piece, without big mode.",
+    help: HELP_ENGLISH,
+    help_presets: "\nControl presets:",
+    help_challenges: "\nBuilt-in challenges:",
+    help_openers: "\nOpeners for the trainer:",
+    help_puzzles: "\nPuzzles:",
+    config_dir_failed: "Warning: failed to create config directory.\n{}",
+    config_created: "Created new config file {} and wrote default config.",
+    config_write_failed: "Warning: failed to write default config to new config file!\n{}",
+    config_create_failed: "Warning: failed to create new config file.\n{}",
+    config_valid: "{} is valid.",
+    config_problems: "Found {} problem(s) in {}:",
+    defaults_below: "\nSettings with problems have been replaced by their defaults below.",
+    effective_settings: "\nEffective settings:\n{}",
+    playing_against: "Playing against {}.",
+    window_title: "tui_tetris - {} - {}",
+    best_notification: "New personal best in {}",
+    completed_notification: "Completed {}",
+    discord_state: "Level {}, {} points",
+    in_file: "In {}:",
+    error_on_line: "Error on line {}",
+    error_in_arguments: "Error in command line argument",
+    on_line: "on line {}",
+    of_file: " of {}",
+    in_arguments: "in command line argument",
+    deprecated_warning: "Warning: deprecated setting name {}: {}\n{}",
+    unknown_warning: "Warning: skipping unknown setting {}: {}",
+    invalid_line_format: "Invalid line format",
+    unknown_setting: "Unknown setting",
+    deprecated_setting: "Deprecated setting name",
+    invalid_value: "Invalid value",
+    duplicate_setting: "Duplicate setting",
+    failed_parse_value: "Failed to parse value",
+    missing_value: "Missing value",
+    invalid_toml: "Invalid TOML: {}",
+    valid_settings: "Valid settings:\n{}",
+    valid_theme_settings: "Valid theme settings:\n{}",
+    renamed_setting: "This setting is now called {}.",
+    fps_limiter_unparsed: "Failed to parse FPS_LIMITER value.",
+    fps_limiter_range: "FPS_LIMITER value is not greater than or equal to 30.",
+    board_width_unparsed: "Failed to parse board width value.",
+    board_width_range: "Board width value is not greater than or equal to 1.",
+    board_height_unparsed: "Failed to parse board height value.",
+    board_height_range: "Board height value is not greater than or equal to 1.",
+    hidden_rows_unparsed: "Failed to parse hidden rows value.",
+    hidden_rows_range: "Hidden rows value was not from 0 to 4.",
+    entry_delay_unparsed: "Failed to parse entry delay value.",
+    entry_delay_range: "Entry delay value was not from 0 to 1000 milliseconds.",
+    line_clear_delay_unparsed: "Failed to parse line clear delay value.",
+    line_clear_delay_range: "Line clear delay value was not from 0 to 120 frames.",
+    lock_delay_unparsed: "Failed to parse lock delay value.",
+    lock_delay_range: "Lock delay value was not from 0 to 5000 milliseconds.",
+    const_level_unparsed: "Failed to parse constant level value.",
+    const_level_range: "Level value was not greater than or equal to 1.",
+    lines_per_level_unparsed: "Failed to parse lines per level value.",
+    lines_per_level_range: "Lines per level value was not greater than or equal to 1.",
+    garbage_hole_repeat_unparsed: "Failed to parse garbage hole repeat chance.",
+    garbage_hole_repeat_range: "Garbage hole repeat chance was not a percentage from 0 to 100.",
+    input_delay_unparsed: "Failed to parse input delay value.",
+    input_delay_range: "Input delay value was not from 0 to 10.",
+    bot_delay_unparsed: "Failed to parse bot delay value.",
+    bot_delay_range: "Bot delay value was not greater than or equal to 0.",
+    handicap_garbage_unparsed: "Failed to parse garbage handicap value.",
+    handicap_garbage_range: "Garbage handicap value was not from 0 to 10.",
+    handicap_gravity_unparsed: "Failed to parse gravity handicap value.",
+    handicap_gravity_range: "Gravity handicap value was not from -30 to 30.",
+    handicap_preview_unparsed: "Failed to parse preview handicap value.",
+    handicap_preview_range: "Preview handicap value was not greater than or equal to 0.",
+    handicap_attack_unparsed: "Failed to parse attack handicap value.",
+    handicap_attack_range: "Attack handicap value was not a percentage from 0 to 400.",
+    countdown_unparsed: "Failed to parse countdown value.",
+    countdown_range: "Countdown value was not from 0 to 9 seconds.",
+    game_speed_unparsed: "Failed to parse game speed.",
+    game_speed_range: "Game speed was not from 10 to 400 percent.",
+    block_size_unparsed: "Failed to parse block size value.",
+    block_size_range: "Block size must be greater than or equal to 1.",
+    danger_height_unparsed: "Failed to parse danger height.",
+    danger_height_range: "Danger height was not a percentage from 0 to 100.",
+    hold_limit_count: "Hold limit must be a number of holds per piece or inf.",
+    lock_resets_count: "Lock resets must be a number of resets per piece or inf.",
+    mode_values: "Accepted game mode indicators: c, classic, m, modern, 20g, master.",
+    level_goal_values: "Level goal must be fixed or variable.",
+    key_names: "Supported non-single-character keys: 'space', 'comma', 'left', 'right', 'up', 'down', \
+                'home', 'end', 'pageup', 'pagedown', 'insert', 'delete', 'backspace', 'enter', 'tab', \
+                'esc', and 'f1' to 'f12'. Modifiers are written before the key, e.g. 'ctrl+left' or \
+                'alt+shift+z'. Controller buttons are 'pad_south', 'pad_east', 'pad_north', \
+                'pad_west', 'pad_lb', 'pad_rb', 'pad_lt', 'pad_rt', 'pad_select', 'pad_start', \
+                'pad_mode', 'pad_ls', 'pad_rs', plus 'pad_up', 'pad_down', 'pad_left', and \
+                'pad_right' for the D-pad.",
+    empty_key: "Key lists can't have empty entries. Use 'comma' for the comma key.",
+    missing_color_type: "Missing color type.",
+    missing_color: "Missing color.",
+    ansi_color_unparsed: "Failed to parse ANSI color value.",
+    color_formats: "Accepted color formats are: rgb, ansi.",
+    missing_red: "Missing R value.",
+    red_unparsed: "Failed to parse R value.",
+    missing_green: "Missing G value.",
+    green_unparsed: "Failed to parse G value.",
+    missing_blue: "Missing B value.",
+    blue_unparsed: "Failed to parse B value.",
+    missing_character: "Missing character value.",
+    single_character: "Expected a single character value.",
+    color_support_values: "Accepted color support values: auto, truecolor, 24bit, 256, 16.",
+    theme_name_characters: "Theme names can't contain '/', '\\', or '.'.",
+    leaderboard_url_scheme: "Leaderboard URLs have to start with http:// or https://.",
+    discord_id_number: "Discord application IDs are numbers.",
+    leaderboard_name_length: "Leaderboard names have to be 1 to 16 printable characters.",
+    ai_difficulty_values: "Accepted values: easy, normal, hard, expert.",
+    ai_style_values: "Accepted values: balanced, downstack, tetris.",
+    targeting_values: "Accepted values: random, leader, danger, even.",
+    input_conflicts_values: "Accepted values: latest, cancel.",
+    renderer_values: "Accepted renderers: blocks, braille.",
+    language_values: "Accepted languages: english, spanish.",
+    grid_values: "Accepted grids: none, dots, checkerboard.",
+    stats_export_values: "Accepted stats export formats: none, json, csv.",
+    palette_values: "Accepted palettes: none, deuteranopia, protanopia, tritanopia, high_contrast.",
+    bool_values: "Accepted boolean values: 1, t, true, on, 0, f, false, off",
+    missing_setting_name: "There must be a setting name on the left side of the equals sign.",
+    missing_setting_value: "There must be a value on the right side of the equals sign.",
+    toml_value_types: "Settings must be strings, integers or booleans.",
+    missing_profile: "There's no config file or [profile] section for this profile.",
+    missing_bindings: "There's no [bindings.<name>] section with this name.",
+    unknown_option: "Run with --help to see the available options.",
+    theme_unreadable: "Failed to read theme file.",
+    big_mode_width: "Big mode needs an even board width of at least 8.",
+    board_smaller_than_block: "Board dimensions must be greater than or equal to block size.",
+    wrong_section: "This setting belongs in a different section.",
+    bindings_keys_only: "Only key settings can go in a [bindings] section."
+};
+
+pub const SPANISH: Strings = Strings {
+    name: "español",
+    score: "PUNTOS",
+    level: "NIVEL",
+    fixed: "FIJO",
+    lines: "LÍNEAS",
+    grade: "GRADO",
+    next: "SIGUIENTE",
+    hold: "RESERVA",
+    item: "OBJETO",
+    clear_rows: "LIMPIAR FILAS",
+    shrink_preview: "ENCOGER COLA",
+    scramble_garbage: "REVOLVER",
+    config_error: "ERROR DE CONFIG",
+    paused: "PAUSA",
+    opponent_left: "RIVAL SE FUE",
+    frame_step: "PASO A PASO",
+    built: "HECHAS",
+    go: "YA",
+    continue_save: "CONTINUAR",
+    settings: "AJUSTES",
+    statistics: "ESTADÍSTICAS",
+    you_win: "GANASTE",
+    you_lose: "PERDISTE",
+    complete: "COMPLETADO",
+    failed: "FALLIDO",
+    game_over: "FIN DEL JUEGO",
+    singles: "SIMPLES",
+    doubles: "DOBLES",
+    triples: "TRIPLES",
+    tetrises: "TETRIS",
+    t_spins: "T-SPINS",
+    drops: "CAÍDAS",
+    max_combo: "COMBO MÁX",
+    time: "TIEMPO",
+    with_pauses: "CON PAUSAS",
+    pps: "PPS",
+    finesse: "PRECISIÓN",
+    new_high_score: "NUEVO RÉCORD",
+    name_label: "NOMBRE",
+    to_save: "GUARDA",
+    seed: "SEMILLA",
+    to_retry: "REINTENTA",
+    personal_best: "VS MARCA",
+    new_best: "NUEVA",
+    attacks: "ATAQUES",
+    sent: "enviadas",
+    incoming: "entrantes",
+    target: "OBJETIVO",
+    player: "JUGADOR",
+    target_random: "AL AZAR",
+    target_leader: "LÍDER",
+    target_danger: "PELIGRO",
+    target_even: "REPARTIDO",
+    pieces: "PIEZAS",
+    apm: "APM",
+    holds: "RESERVAS",
+    stack: "PILA",
+    splits: "PARCIALES",
+    ahead: "DELANTE",
+    behind: "DETRÁS",
+    even: "IGUAL",
+    games: "PARTIDAS",
+    best_score: "RÉCORD",
+    average_pps: "PPS MEDIO",
+    no_games: "Aún no hay partidas",
+    leaderboard: "CLASIFICACIÓN",
+    no_entries: "Aún no hay entradas",
+    online_not_built: "Compilado sin la función online.",
+    leaderboard_failed: "Error de la clasificación: {}",
+    controls: "Controles",
+    preset: "Esquema",
+    move_left: "Mover izq.",
+    move_right: "Mover der.",
+    rotate_clockwise: "Girar der.",
+    rotate_anticlockwise: "Girar izq.",
+    soft_drop: "Caída suave",
+    hard_drop: "Caída dura",
+    sonic_drop: "Bajar todo",
+    hold_entry: "Reservar",
+    pause: "Pausa",
+    quit: "Salir",
+    stats: "Datos",
+    hint: "Pista",
+    debug: "Depurar",
+    ghost: "Fantasma",
+    cascade: "Cascada",
+    big_mode: "Modo grande",
+    irs: "IRS/IHS",
+    countdown: "Conteo",
+    ai_level: "Nivel IA",
+    ai_style: "Estilo IA",
+    sound: "Sonido",
+    piece_cues: "Avisos",
+    palette: "Paleta",
+    border: "Borde",
+    background: "Fondo",
+    renderer: "Dibujo",
+    grid: "Cuadrícula",
+    block_size: "Tamaño",
+    effects: "Efectos",
+    language: "Idioma",
+    save: "Guardar",
+    on: "sí",
+    off: "no",
+    none: "ninguna",
+    custom: "propio",
+    key_in_use: "TECLA YA EN USO",
+    key_saved: "TECLA GUARDADA",
+    preset_saved: "CONTROLES GUARDADOS",
+    default_keys_only: "SOLO CAMBIAN LAS PREDETERMINADAS",
+    saved: "GUARDADO",
+    saving_failed: "NO SE PUDO GUARDAR",
+    press_key: "Pulsa una tecla, o",
+    to_cancel: "cancela",
+    to_change: "cambia",
+    to_leave: "sale",
+    see_help: "{}\nEjecuta con --help para ver las opciones disponibles.",
+    logging_to: "Registrando en {}.",
+    logging_failed: "Aviso: no se pudo iniciar el registro.\n{}",
+    config_written: "Se escribió una configuración predeterminada en {}.",
+    write_failed: "No se pudo escribir {}.\n{}",
+    scores_failed: "No se pudieron leer las puntuaciones máximas.\n{}",
+    waiting_for_opponent: "Esperando a un rival en {}...",
+    waiting_for_opponents: "Esperando a {} rivales en {}...",
+    spectator_joined: "Se unió un espectador.",
+    player_joined: "Se unió el jugador {}.",
+    waiting_for_host: "Esperando a que el anfitrión empiece la partida...",
+    no_high_scores: "Aún no hay puntuaciones máximas.",
+    table_lines: "{} líneas",
+    bench_running: "Midiendo el juego sin dibujarlo, {} segundos por cada tipo de juego...",
+    bench_random: "Movimientos al azar: {} ticks en {} segundos, {} ticks por segundo.",
+    bench_ai: "Colocaciones de la IA: {} piezas en {} segundos, {} piezas por segundo.",
+    checking_defaults:
+        "No se encontró ningún archivo de configuración, se comprueban los valores predeterminados.",
+    using_defaults: "Aviso: se usa la configuración predeterminada.",
+    playback_failed: "No se pudo reproducir {}.\n{}",
+    cast_written: "Grabación escrita en {}.",
+    cast_failed: "No se pudo grabar {}.\n{}",
+    preset_unsaved: "{}\nSe usan los controles {} solo en esta partida.",
+    spectate_failed: "No se pudo ver la partida en red.\n{}",
+    network_failed: "No se pudo preparar la partida en red.\n{}",
+    bot_failed: "No se pudo iniciar el bot.\n{}",
+    unknown_opener: "Apertura desconocida: {}. Consulta --help para ver las que hay.",
+    position_failed: "No se pudo cargar la posición. {}",
+    saved_game_failed: "Aviso: no se pudo cargar la partida guardada.\n{}",
+    terminal_failed: "¡Error crítico! Falló la E/S del terminal.\n{}",
+    opener_built: "{} ({}) construida en {} de {} intentos.",
+    puzzle_solved: "Puzle {} ({}) resuelto.",
+    puzzle_failed: "Puzle {} ({}) fallido.",
+    challenge_complete: "Desafío {} ({}) completado.",
+    challenge_failed: "Desafío {} ({}) fallido.",
+    game_saved: "Partida guardada en {}. Continúala desde el menú de pausa la próxima vez.",
+    save_failed: "Aviso: no se pudo guardar la partida.\n{}",
+    lifetime_failed: "Aviso: no se pudieron actualizar las estadísticas totales.\n{}",
+    new_personal_best: "Nuevo récord personal.",
+    best_failed: "Aviso: no se pudo guardar el récord personal.\n{}",
+    new_best_splits: "Nuevos mejores parciales.",
+    splits_failed: "Aviso: no se pudieron guardar los mejores parciales.\n{}",
+    result_sent: "Resultado enviado a la clasificación.",
+    send_failed: "Aviso: no se pudo enviar el resultado a la clasificación.\n{}",
+    replay_saved: "Repetición guardada en {}.",
+    replay_failed: "Aviso: no se pudo guardar la repetición.\n{}",
+    fumen_of_game: "Fumen de la partida:\n{}",
+    fumen_width: "Solo las partidas en tableros de 10 de ancho se pueden exportar como fumen.",
+    stats_added: "Estadísticas de la partida añadidas a {}.",
+    stats_failed: "Aviso: no se pudieron guardar las estadísticas de la partida.\n{}",
+    no_replay: "Falta la repetición que reproducir.",
+    no_cast_replay: "Falta la repetición de la que hacer una grabación.",
+    no_config_command: "Se esperaba config check o config init.",
+    unknown_command: "Orden desconocida: {}",
+    unexpected_argument: "Argumento inesperado: {}",
+    missing_argument_value: "Falta el valor de {}",
+    profile_name_characters: "Los nombres de perfil no pueden contener '/', '\\' ni '.'.",
+    bad_player_count: "Número de jugadores no válido: {}. Las partidas pueden tener de 2 a {} jugadores.",
+    challenge_name_characters: "Los nombres de desafío no pueden contener '/', '\\' ni '.'.",
+    puzzle_name_characters: "Los nombres de puzle no pueden contener '/', '\\' ni '.'.",
+    bad_seed: "Semilla no válida: {}. Las semillas son las 16 letras y cifras que se muestran en el menú de pausa.",
+    unknown_preset: "Controles predefinidos desconocidos: {}. Usa {}.",
+    unknown_log_level: "Nivel de registro desconocido: {}. Usa off, error, warn, info, debug o trace.",
+    one_net_role: "Solo se puede usar una de --host, --connect y --spectate.",
+    bot_and_ai: "Solo se puede usar una de --bot y --ai.",
+    bot_online: "No se puede jugar contra bots en partidas en red.",
+    players_host_only: "Solo el anfitrión elige cuántos jugadores hay.",
+    challenge_versus: "Los desafíos no se pueden jugar en partidas versus.",
+    fumen_versus: "Las posiciones fumen no se pueden jugar en partidas versus.",
+    trainer_alone: "El entrenador no se puede usar con --challenge, --fumen ni --puzzle.",
+    puzzle_versus: "Los puzles y el entrenador no se pueden usar en partidas versus.",
+    seed_versus: "Las semillas no se pueden elegir en partidas versus ni en el entrenador.",
+    puzzle_alone: "Los puzles tienen su propio objetivo y tablero, así que no se pueden usar con --challenge ni --fumen.",
+    unknown_puzzle: "Puzle desconocido: {}. Consulta --help para ver los que hay.",
+    invalid_puzzle: "Archivo de puzle no válido {}: {}",
+    puzzle_no_goal: "El puzle de {} no tiene objetivo.",
+    unknown_challenge: "Desafío desconocido: {}. Consulta --help para ver los incluidos.",
+    invalid_challenge: "Archivo de desafío no válido {}: {}",
+    challenge_no_goal: "El desafío de {} no tiene objetivo.",
+    profile_needs_toml: "No hay ningún archivo de configuración para este perfil, y {} no puede tener secciones de perfil porque no es una configuración TOML.",
+    config_read_failed: "¡Error crítico! No se pudo leer el archivo de configuración {}.\n{}",
+    profile_unsaved: "Los ajustes no se pueden guardar en una sección de perfil.",
+    mode_unsaved: "Los ajustes no se pueden guardar mientras se aplica la sección {}.",
+    bot_board: "Los bots solo pueden jugar en tableros de 10 de ancho y 40 de alto como mucho.",
+    bot_rules: "Los bots solo pueden jugar en modo moderno con una reserva por pieza, sin el modo grande.",
+    help: HELP_SPANISH,
+    help_presets: "\nControles predefinidos:",
+    help_challenges: "\nDesafíos incluidos:",
+    help_openers: "\nAperturas para el entrenador:",
+    help_puzzles: "\nPuzles:",
+    config_dir_failed: "Aviso: no se pudo crear el directorio de configuración.\n{}",
+    config_created: "Se creó el archivo de configuración {} con la configuración predeterminada.",
+    config_write_failed:
+        "Aviso: ¡no se pudo escribir la configuración predeterminada en el nuevo archivo!\n{}",
+    config_create_failed: "Aviso: no se pudo crear el nuevo archivo de configuración.\n{}",
+    config_valid: "{} es válido.",
+    config_problems: "Se encontraron {} problema(s) en {}:",
+    defaults_below:
+        "\nLos ajustes con problemas se han sustituido abajo por sus valores predeterminados.",
+    effective_settings: "\nAjustes en uso:\n{}",
+    playing_against: "Jugando contra {}.",
+    window_title: "tui_tetris - {} - {}",
+    best_notification: "Nuevo récord personal en {}",
+    completed_notification: "{} completado",
+    discord_state: "Nivel {}, {} puntos",
+    in_file: "En {}:",
+    error_on_line: "Error en la línea {}",
+    error_in_arguments: "Error en un argumento de la línea de órdenes",
+    on_line: "en la línea {}",
+    of_file: " de {}",
+    in_arguments: "en un argumento de la línea de órdenes",
+    deprecated_warning: "Aviso: nombre de ajuste obsoleto {}: {}\n{}",
+    unknown_warning: "Aviso: se omite el ajuste desconocido {}: {}",
+    invalid_line_format: "Formato de línea no válido",
+    unknown_setting: "Ajuste desconocido",
+    deprecated_setting: "Nombre de ajuste obsoleto",
+    invalid_value: "Valor no válido",
+    duplicate_setting: "Ajuste repetido",
+    failed_parse_value: "No se pudo leer el valor",
+    missing_value: "Falta el valor",
+    invalid_toml: "TOML no válido: {}",
+    valid_settings: "Ajustes válidos:\n{}",
+    valid_theme_settings: "Ajustes de tema válidos:\n{}",
+    renamed_setting: "Este ajuste ahora se llama {}.",
+    fps_limiter_unparsed: "No se pudo leer el valor de FPS_LIMITER.",
+    fps_limiter_range: "El valor de FPS_LIMITER no es mayor o igual que 30.",
+    board_width_unparsed: "No se pudo leer el ancho del tablero.",
+    board_width_range: "El ancho del tablero no es mayor o igual que 1.",
+    board_height_unparsed: "No se pudo leer el alto del tablero.",
+    board_height_range: "El alto del tablero no es mayor o igual que 1.",
+    hidden_rows_unparsed: "No se pudo leer el número de filas ocultas.",
+    hidden_rows_range: "Las filas ocultas no estaban entre 0 y 4.",
+    entry_delay_unparsed: "No se pudo leer el retardo de aparición.",
+    entry_delay_range: "El retardo de aparición no estaba entre 0 y 1000 milisegundos.",
+    line_clear_delay_unparsed: "No se pudo leer el retardo al borrar líneas.",
+    line_clear_delay_range: "El retardo al borrar líneas no estaba entre 0 y 120 fotogramas.",
+    lock_delay_unparsed: "No se pudo leer el retardo de bloqueo.",
+    lock_delay_range: "El retardo de bloqueo no estaba entre 0 y 5000 milisegundos.",
+    const_level_unparsed: "No se pudo leer el nivel constante.",
+    const_level_range: "El nivel no era mayor o igual que 1.",
+    lines_per_level_unparsed: "No se pudo leer el número de líneas por nivel.",
+    lines_per_level_range: "Las líneas por nivel no eran mayores o iguales que 1.",
+    garbage_hole_repeat_unparsed: "No se pudo leer la probabilidad de repetir el hueco de la basura.",
+    garbage_hole_repeat_range: "La probabilidad de repetir el hueco de la basura no era un porcentaje entre 0 y 100.",
+    input_delay_unparsed: "No se pudo leer el retardo de entrada.",
+    input_delay_range: "El retardo de entrada no estaba entre 0 y 10.",
+    bot_delay_unparsed: "No se pudo leer el retardo del bot.",
+    bot_delay_range: "El retardo del bot no era mayor o igual que 0.",
+    handicap_garbage_unparsed: "No se pudo leer la desventaja de basura.",
+    handicap_garbage_range: "La desventaja de basura no estaba entre 0 y 10.",
+    handicap_gravity_unparsed: "No se pudo leer la desventaja de gravedad.",
+    handicap_gravity_range: "La desventaja de gravedad no estaba entre -30 y 30.",
+    handicap_preview_unparsed: "No se pudo leer la desventaja de piezas siguientes.",
+    handicap_preview_range: "La desventaja de piezas siguientes no era mayor o igual que 0.",
+    handicap_attack_unparsed: "No se pudo leer la desventaja de ataque.",
+    handicap_attack_range: "La desventaja de ataque no era un porcentaje entre 0 y 400.",
+    countdown_unparsed: "No se pudo leer la cuenta atrás.",
+    countdown_range: "La cuenta atrás no estaba entre 0 y 9 segundos.",
+    game_speed_unparsed: "No se pudo leer la velocidad del juego.",
+    game_speed_range: "La velocidad del juego no estaba entre 10 y 400 por ciento.",
+    block_size_unparsed: "No se pudo leer el tamaño de bloque.",
+    block_size_range: "El tamaño de bloque tiene que ser mayor o igual que 1.",
+    danger_height_unparsed: "No se pudo leer la altura de peligro.",
+    danger_height_range: "La altura de peligro no era un porcentaje entre 0 y 100.",
+    hold_limit_count: "El límite de reserva tiene que ser un número de reservas por pieza o inf.",
+    lock_resets_count: "Los reinicios de bloqueo tienen que ser un número de reinicios por pieza o inf.",
+    mode_values: "Modos de juego aceptados: c, classic, m, modern, 20g, master.",
+    level_goal_values: "El objetivo de nivel tiene que ser fixed o variable.",
+    key_names: "Teclas con nombre de más de un carácter: 'space', 'comma', 'left', 'right', 'up', 'down', \
+                'home', 'end', 'pageup', 'pagedown', 'insert', 'delete', 'backspace', 'enter', 'tab', \
+                'esc', y de 'f1' a 'f12'. Los modificadores se escriben antes de la tecla, p. ej. \
+                'ctrl+left' o 'alt+shift+z'. Los botones del mando son 'pad_south', 'pad_east', \
+                'pad_north', 'pad_west', 'pad_lb', 'pad_rb', 'pad_lt', 'pad_rt', 'pad_select', \
+                'pad_start', 'pad_mode', 'pad_ls', 'pad_rs', además de 'pad_up', 'pad_down', \
+                'pad_left' y 'pad_right' para la cruceta.",
+    empty_key: "Las listas de teclas no pueden tener entradas vacías. Usa 'comma' para la tecla de la coma.",
+    missing_color_type: "Falta el tipo de color.",
+    missing_color: "Falta el color.",
+    ansi_color_unparsed: "No se pudo leer el valor de color ANSI.",
+    color_formats: "Formatos de color aceptados: rgb, ansi.",
+    missing_red: "Falta el valor R.",
+    red_unparsed: "No se pudo leer el valor R.",
+    missing_green: "Falta el valor G.",
+    green_unparsed: "No se pudo leer el valor G.",
+    missing_blue: "Falta el valor B.",
+    blue_unparsed: "No se pudo leer el valor B.",
+    missing_character: "Falta el carácter.",
+    single_character: "Se esperaba un solo carácter.",
+    color_support_values: "Valores de soporte de color aceptados: auto, truecolor, 24bit, 256, 16.",
+    theme_name_characters: "Los nombres de tema no pueden contener '/', '\\' ni '.'.",
+    leaderboard_url_scheme: "Las URL de la clasificación tienen que empezar por http:// o https://.",
+    discord_id_number: "Los ID de aplicación de Discord son números.",
+    leaderboard_name_length: "Los nombres de la clasificación tienen que tener de 1 a 16 caracteres imprimibles.",
+    ai_difficulty_values: "Valores aceptados: easy, normal, hard, expert.",
+    ai_style_values: "Valores aceptados: balanced, downstack, tetris.",
+    targeting_values: "Valores aceptados: random, leader, danger, even.",
+    input_conflicts_values: "Valores aceptados: latest, cancel.",
+    renderer_values: "Renderizadores aceptados: blocks, braille.",
+    language_values: "Idiomas aceptados: english, spanish.",
+    grid_values: "Cuadrículas aceptadas: none, dots, checkerboard.",
+    stats_export_values: "Formatos de exportación de estadísticas aceptados: none, json, csv.",
+    palette_values: "Paletas aceptadas: none, deuteranopia, protanopia, tritanopia, high_contrast.",
+    bool_values: "Valores booleanos aceptados: 1, t, true, on, 0, f, false, off",
+    missing_setting_name: "Tiene que haber un nombre de ajuste a la izquierda del signo igual.",
+    missing_setting_value: "Tiene que haber un valor a la derecha del signo igual.",
+    toml_value_types: "Los ajustes tienen que ser cadenas, enteros o booleanos.",
+    missing_profile: "No hay ningún archivo de configuración ni sección [profile] para este perfil.",
+    missing_bindings: "No hay ninguna sección [bindings.<name>] con este nombre.",
+    unknown_option: "Ejecuta con --help para ver las opciones disponibles.",
+    theme_unreadable: "No se pudo leer el archivo de tema.",
+    big_mode_width: "El modo grande necesita un ancho de tablero par de al menos 8.",
+    board_smaller_than_block: "Las dimensiones del tablero tienen que ser mayores o iguales que el tamaño de bloque.",
+    wrong_section: "Este ajuste va en otra sección.",
+    bindings_keys_only: "En una sección [bindings] solo pueden ir ajustes de teclas."
+};
+
+// What `--help` prints, ahead of the settings, presets, challenges, openers and puzzles.
+const HELP_ENGLISH: &str = "\
+     Usage: tui_tetris [play] [option]...\n       \
+     tui_tetris config check [option]...\n       \
+     tui_tetris config init [path]\n       \
+     tui_tetris scores\n       \
+     tui_tetris replay <replay>\n       \
+     tui_tetris cast <replay> [path]\n       \
+     tui_tetris bench\n\
+     \n\
+     Commands:\n    \
+     play              Play a game, which is also what happens without a command\n    \
+     config check      Report every problem with the config and print the settings in use\n    \
+     config init       Write a default config with every setting explained, to the path or\n                      \
+     the config directory, in the legacy format if the path ends in .conf\n    \
+     scores            Print the high scores for each mode\n    \
+     replay            Watch a replay from the replays folder of the data directory, with\n                      \
+     pause to stop it and the move keys to jump 5 seconds back or forward\n    \
+     cast              Make an asciinema recording of a replay from the replays folder of\n                      \
+     the data directory, to the path or next to the replay\n    \
+     bench             Time how fast the game runs random moves and the AI runs its search,\n                      \
+     without drawing anything. The full benchmarks are run with cargo bench\n\
+     \n\
+     Any config setting can be overridden for a single run by passing it as an option, with\n\
+     dashes in place of underscores. Values are written the same way as in the config file.\n\
+     \n\
+     Settings can also be given for one mode, in a [<challenge>] section at the end of the\n\
+     config, or [marathon] for ordinary games. In tui_tetris.toml these are [mode.<name>.game]\n\
+     and so on. They replace the settings at the top for games of that mode.\n\
+     \n\
+     Options:\n    \
+     -h, --help\n    \
+     --lenient-config  Skip settings this version doesn't know about instead of refusing to\n                      \
+     start\n    \
+     --profile <name>  Use tui_tetris.<name>.toml or .conf, or the [profile.<name>] section of\n                      \
+     tui_tetris.toml\n    \
+     --host <port>     Wait for the other players to connect for a versus game, on\n                      \
+     [address:]port\n    \
+     --players <count> Players in a hosted game, from 2 to 4, the host included (default 2)\n    \
+     --connect <address:port>  Join a versus game hosted by another player\n    \
+     --spectate <address:port> Watch a versus game without playing\n    \
+     --bot <command>   Play against a Tetris Bot Protocol bot started with the command\n    \
+     --ai              Play against the built-in AI, set up with ai_difficulty and ai_style\n    \
+     --challenge <name>  Play for a goal, either a built-in one or challenges/<name>.toml\n                      \
+     in a config directory\n    \
+     --puzzle <name>   Solve a puzzle, either a built-in one or puzzles/<name>.toml in a config\n                      \
+     directory\n    \
+     --trainer <opener>  Practice an opener over and over, counting how often it's built\n    \
+     --fumen <fumen>   Practice from the first page of a fumen, with the queue from its quiz\n                      \
+     comment if it has one\n    \
+     --seed <seed>     Deal the same pieces as the game the seed was shown for\n    \
+     --preset <name>   Switch to one of the control presets below and save it to the config\n    \
+     --log <level>     Write what happens to tui_tetris.log in the data directory, at error,\n                      \
+     warn, info, debug, or trace detail";
+
+const HELP_SPANISH: &str = "\
+     Uso: tui_tetris [play] [opción]...\n     \
+     tui_tetris config check [opción]...\n     \
+     tui_tetris config init [ruta]\n     \
+     tui_tetris scores\n     \
+     tui_tetris replay <repetición>\n     \
+     tui_tetris cast <repetición> [ruta]\n     \
+     tui_tetris bench\n\
+     \n\
+     Órdenes:\n    \
+     play              Jugar una partida, que es también lo que pasa sin una orden\n    \
+     config check      Informar de cada problema de la configuración y mostrar los ajustes en\n                      \
+     uso\n    \
+     config init       Escribir una configuración predeterminada con cada ajuste explicado, en\n                      \
+     la ruta o en el directorio de configuración, en el formato antiguo si la\n                      \
+     ruta termina en .conf\n    \
+     scores            Mostrar las puntuaciones máximas de cada modo\n    \
+     replay            Ver una repetición de la carpeta replays del directorio de datos, con\n                      \
+     pausa para pararla y las teclas de movimiento para saltar 5 segundos\n                      \
+     atrás o adelante\n    \
+     cast              Hacer una grabación de asciinema de una repetición de la carpeta replays\n                      \
+     del directorio de datos, en la ruta o junto a la repetición\n    \
+     bench             Medir lo rápido que el juego hace movimientos al azar y la IA hace su\n                      \
+     búsqueda, sin dibujar nada. Las pruebas completas se ejecutan con\n                      \
+     cargo bench\n\
+     \n\
+     Cualquier ajuste de la configuración se puede cambiar para una sola ejecución pasándolo como\n\
+     opción, con guiones en lugar de guiones bajos. Los valores se escriben igual que en el archivo\n\
+     de configuración.\n\
+     \n\
+     También se pueden dar ajustes para un modo, en una sección [<desafío>] al final de la\n\
+     configuración, o [marathon] para las partidas normales. En tui_tetris.toml son\n\
+     [mode.<nombre>.game] y demás. Sustituyen a los ajustes de arriba en las partidas de ese modo.\n\
+     \n\
+     Opciones:\n    \
+     -h, --help\n    \
+     --lenient-config  Omitir los ajustes que esta versión no conoce en lugar de negarse a\n                      \
+     empezar\n    \
+     --profile <nombre>  Usar tui_tetris.<nombre>.toml o .conf, o la sección [profile.<nombre>]\n                      \
+     de tui_tetris.toml\n    \
+     --host <puerto>   Esperar a que se conecten los demás jugadores para una partida versus, en\n                      \
+     [dirección:]puerto\n    \
+     --players <número>  Jugadores en una partida alojada, de 2 a 4, contando al anfitrión\n                      \
+     (2 por defecto)\n    \
+     --connect <dirección:puerto>  Unirse a una partida versus alojada por otro jugador\n    \
+     --spectate <dirección:puerto> Ver una partida versus sin jugar\n    \
+     --bot <orden>     Jugar contra un bot del Tetris Bot Protocol iniciado con la orden\n    \
+     --ai              Jugar contra la IA incluida, ajustada con ai_difficulty y ai_style\n    \
+     --challenge <nombre>  Jugar por un objetivo, uno incluido o challenges/<nombre>.toml en un\n                      \
+     directorio de configuración\n    \
+     --puzzle <nombre>  Resolver un puzle, uno incluido o puzzles/<nombre>.toml en un\n                      \
+     directorio de configuración\n    \
+     --trainer <apertura>  Practicar una apertura una y otra vez, contando cuántas veces se\n                      \
+     construye\n    \
+     --fumen <fumen>   Practicar desde la primera página de un fumen, con la cola de su\n                      \
+     comentario de quiz si lo tiene\n    \
+     --seed <semilla>  Repartir las mismas piezas que la partida para la que se mostró la semilla\n    \
+     --preset <nombre>  Cambiar a uno de los controles predefinidos de abajo y guardarlo en la\n                      \
+     configuración\n    \
+     --log <nivel>     Escribir lo que pasa en tui_tetris.log en el directorio de datos, con\n                      \
+     detalle error, warn, info, debug o trace";
+
+// A message with each `{}` in it replaced by the next of `values`.
+pub fn fill(message: &str, values: &[&dyn Display]) -> String {
+    let mut values = values.iter();
+    let mut pieces = message.split("{}");
+    let mut filled = pieces.next().unwrap_or_default().to_string();
+    for piece in pieces {
+        if let Some(value) = values.next() {
+            filled.push_str(&value.to_string());
+        }
+        filled.push_str(piece);
+    }
+    filled
+}
+
+// A label with its value right-aligned after it, `width` characters in all unless they don't fit.
+pub fn field<T: Display>(label: &str, value: T, width: usize) -> String {
+    let room = width.saturating_sub(label.chars().count() + 1);
+    format!("{} {:>room$}", label, value.to_string(), room = room)
+}
+
+// Values line up however long the label is, and are never cut off.
+#[test]
+fn test_field() {
+    assert_eq!(field("SCORE", 1200, 16), "SCORE       1200");
+    assert_eq!(field("LÍNEAS", 4, 16), "LÍNEAS         4");
+    assert_eq!(field("LONG LABEL", 123456, 12), "LONG LABEL 123456");
+}
+
+// Values go in order, and a message keeps the same values in every language.
+#[test]
+fn test_fill() {
+    assert_eq!(
+        fill("Built {} in {} of {}.", &[&"PCO", &3, &4]),
+        "Built PCO in 3 of 4."
+    );
+    assert_eq!(fill("Missing {}.", &[]), "Missing .");
+    let placeholders = |message: &str| message.matches("{}").count();
+    assert_eq!(
+        placeholders(ENGLISH.opener_built),
+        placeholders(SPANISH.opener_built)
+    );
+    assert_eq!(placeholders(ENGLISH.help), 0);
+    assert_eq!(placeholders(SPANISH.help), 0);
+}
@@ -0,0 +1,57 @@
+use log::{LevelFilter, Log, Metadata, Record};
+use std::fs::{create_dir_all, File};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::time::Instant;
+
+use crate::paths;
+
+const LOG_FILE: &str = "tui_tetris.log";
+
+// Writes each message on its own line as soon as it's logged, so that nothing is lost if the game
+// crashes. Timestamps are seconds since the game started, which is what matters for timing.
+struct FileLogger {
+    start: Instant,
+    file: Mutex<File>
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "[{:10.3} {:5} {}] {}\n",
+            self.start.elapsed().as_secs_f64(),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        if let Ok(mut file) = self.file.lock() {
+            let _ = file.write_all(line.as_bytes());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+// Start logging everything at `level` and above to the log file, replacing the last one. Returns
+// where it's going.
+pub fn init(level: LevelFilter) -> IoResult<PathBuf> {
+    let dir =
+        paths::data_dir().ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))?;
+    create_dir_all(&dir)?;
+    let path = dir.join(LOG_FILE);
+    let logger = FileLogger {
+        start: Instant::now(),
+        file: Mutex::new(File::create(&path)?)
+    };
+    log::set_logger(Box::leak(Box::new(logger))).map_err(|e| IoError::other(e.to_string()))?;
+    log::set_max_level(level);
+    Ok(path)
+}
@@ -1,54 +1,1925 @@
+#[macro_use]
 extern crate crossterm;
+extern crate dirs;
+#[cfg(feature = "gamepad")]
+extern crate gilrs;
+#[cfg(unix)]
+extern crate libc;
+#[macro_use]
+extern crate log;
 extern crate rand;
+#[cfg(feature = "sound")]
+extern crate rodio;
+extern crate serde;
+#[macro_use]
+extern crate serde_derive;
+extern crate serde_json;
+extern crate toml;
+extern crate tui_tetris;
+#[cfg(feature = "online")]
+extern crate ureq;
 
+mod art;
+mod bot;
+mod cast;
+mod color;
+mod crash;
+mod discord;
 mod game_config;
-mod gameboard;
-mod tetromino;
+mod gamepad;
+mod input;
+mod leaderboard;
+mod locale;
+mod logging;
+mod mirror;
+mod net;
+mod osc;
+mod paths;
+mod records;
+mod render;
+mod save;
+mod screenshot;
+mod session;
+mod settings_menu;
+mod signals;
+mod sound;
+mod speed;
 
+use art::Art;
+use bot::{Bot, BotOpponent, BotPlayer, TBP_BOARD_WIDTH};
+use color::ColorSupport;
+use discord::Presence;
 use game_config::*;
-use gameboard::*;
-use tetromino::*;
+use gamepad::Gamepads;
+use input::*;
+use leaderboard::{Leaderboard, Submission};
+use locale::{fill, Language, Strings};
+use mirror::Mirror;
+use net::{pick_targets, Connection, Message, NetRole, RemoteBoard, Setup, MAX_PLAYERS};
+use records::{
+    save_replay, BestSplits, GameRecord, Ghost, HighScores, LifetimeStats, NameEntry, NameEvent,
+    PersonalBests
+};
+use render::draw_remote_board;
+use screenshot::Screen;
+use session::*;
+use settings_menu::*;
+use sound::{Player, Snapshot};
+use speed::{ai_pieces, random_ticks, Measurement, BENCH_DURATION};
+use tui_tetris::challenge::{self, Challenge};
+use tui_tetris::fumen::{self, Position};
+use tui_tetris::gameboard::{parse_seed, Game};
+use tui_tetris::puzzle::{self, Puzzle};
+use tui_tetris::replay::Replay;
+use tui_tetris::rollback::Rollback;
+use tui_tetris::rules::Mode;
+use tui_tetris::timing::*;
+use tui_tetris::versus::{split_lines, Attack, Match};
 
-use std::fs::{read_to_string, File};
-use std::io::Write;
-use std::path::Path;
+use crossterm::{
+    input, terminal, AlternateScreen, ClearType, Goto, Hide, InputEvent, Output, Show
+};
+use log::LevelFilter;
+use rand::{thread_rng, Rng};
+use std::env;
+use std::error::Error;
+use std::fs::{create_dir_all, read, read_to_string, File, OpenOptions};
+use std::io::{stdout, ErrorKind, Result as IoResult, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::time::{Duration, Instant};
+
+// The TOML config takes priority over the legacy one if both are in the same directory.
+const TOML_CONFIG_FILE: &str = "tui_tetris.toml";
+const LEGACY_CONFIG_FILE: &str = "tui_tetris.conf";
+// Custom challenges are `<name>.toml` files in this subdirectory of any of the config search
+// directories.
+const CHALLENGES_DIR: &str = "challenges";
+// The same goes for puzzles.
+const PUZZLES_DIR: &str = "puzzles";
+// The config section for games without a challenge.
+const MARATHON: &str = "marathon";
+
+// How often the config file is checked for changes while the game is paused.
+const CONFIG_RELOAD_INTERVAL: Duration = Duration::from_millis(500);
+// How far the move keys jump back and forward when watching a replay.
+const REPLAY_SEEK_TICKS: u64 = TICK_RATE * 5;
+
+// Shorter names accepted on the command line, as (alias, setting name).
+const ARGUMENT_ALIASES: [(&str, &str); 2] = [("fps", "fps_limiter"), ("speed", "game_speed")];
 
 fn main() {
-    let game_config = if Path::new("./tui_tetris.conf").exists() {
-        match read_to_string("./tui_tetris.conf") {
-            Ok(contents) => match GameConfig::parse(contents.as_str()) {
-                Ok(game_config) => game_config,
+    crash::install_panic_hook();
+    let args = env::args().skip(1).collect::<Vec<_>>();
+    // Until the config says which language to use, the environment does.
+    let text = Language::from_env().strings();
+    if args.iter().any(|arg| arg == "-h" || arg == "--help") {
+        print_help(text);
+        return;
+    }
+    let args = match parse_args(&args, text) {
+        Ok(args) => args,
+        Err(e) => {
+            println!("{}", fill(text.see_help, &[&e]));
+            return;
+        }
+    };
+    if let Some(level) = args.log {
+        match logging::init(level) {
+            Ok(path) => println!("{}", fill(text.logging_to, &[&path.display()])),
+            Err(e) => println!("{}", fill(text.logging_failed, &[&format!("{:?}", e)]))
+        }
+    }
+    match args.command {
+        Command::Play | Command::CheckConfig | Command::Replay(_) | Command::Cast(..) => {}
+        Command::InitConfig(ref path) => {
+            let path = path.clone().unwrap_or_else(|| {
+                paths::config_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(TOML_CONFIG_FILE)
+            });
+            match init_config(&path) {
+                Ok(()) => println!("{}", fill(text.config_written, &[&path.display()])),
                 Err(e) => {
-                    println!("{}", e);
-                    return;
+                    let e = format!("{:?}", e);
+                    println!("{}", fill(text.write_failed, &[&path.display(), &e]));
+                    process::exit(1);
                 }
-            },
+            }
+            return;
+        }
+        Command::Scores => {
+            match HighScores::load() {
+                Ok(high_scores) => print!("{}", high_scores.table(text)),
+                Err(e) => println!("{}", fill(text.scores_failed, &[&format!("{:?}", e)]))
+            }
+            return;
+        }
+        Command::Bench => {
+            let seconds = BENCH_DURATION.as_secs();
+            println!("{}", fill(text.bench_running, &[&seconds]));
+            let report = |message, measurement: Measurement| {
+                let elapsed = format!("{:.1}", measurement.elapsed.as_secs_f64());
+                let rate = format!("{:.0}", measurement.per_second());
+                println!("{}", fill(message, &[&measurement.count, &elapsed, &rate]));
+            };
+            report(text.bench_random, random_ticks(BENCH_DURATION));
+            report(text.bench_ai, ai_pieces(BENCH_DURATION));
+            return;
+        }
+    }
+    let found = find_config(&args);
+    if let Command::CheckConfig = args.command {
+        let valid = match found {
+            Some(ref source) => check_config(source, text),
+            None => {
+                println!("{}", text.checking_defaults);
+                let path = paths::config_dir()
+                    .unwrap_or_else(|| PathBuf::from("."))
+                    .join(TOML_CONFIG_FILE);
+                check_config(&ConfigSource::new(path, true, &args), text)
+            }
+        };
+        process::exit(if valid { 0 } else { 1 });
+    }
+    let source = found.unwrap_or_else(|| {
+        println!("{}", text.using_defaults);
+        ConfigSource::new(write_default_config(text), true, &args)
+    });
+    let mut game_config = match source.load(text) {
+        Ok((game_config, warnings)) => {
+            for warning in warnings.iter() {
+                println!("{}", warning.warning(text));
+            }
+            game_config
+        }
+        Err(e) => {
+            println!("{}", e);
+            return;
+        }
+    };
+    info!("Using the config at {}", source.path.display());
+    let text = game_config.language.strings();
+    if let Command::Replay(ref replay) = args.command {
+        if let Err(e) = watch_replay(replay, game_config) {
+            println!("{}", fill(text.playback_failed, &[&replay.display(), &e]));
+            process::exit(1);
+        }
+        return;
+    }
+    if let Command::Cast(ref replay, ref output) = args.command {
+        let output = output
+            .clone()
+            .unwrap_or_else(|| replay.with_extension("cast"));
+        match export_cast(replay, &output, game_config) {
+            Ok(()) => println!("{}", fill(text.cast_written, &[&output.display()])),
             Err(e) => {
-                println!("Critical error! Failed to read config file.\n{:?}", e);
-                return;
+                println!("{}", fill(text.cast_failed, &[&replay.display(), &e]));
+                process::exit(1);
             }
         }
-    } else {
-        let game_config = GameConfig::default();
-        println!("Warning: using default game config.");
-        match File::create(Path::new("./tui_tetris.conf")) {
-            Ok(mut file) => match game_config.write_to_file(&mut file) {
-                Ok(()) => println!("Created new config file and wrote default config."),
+        return;
+    }
+    // The preset's keys are used even if they can't be saved.
+    if let Some(preset) = args.preset {
+        preset.apply(&mut game_config);
+        match source.save(&game_config, |_, to| preset.apply(to), text) {
+            Ok(()) => info!("Saved the {} controls to the config", preset.name),
+            Err(e) => println!("{}", fill(text.preset_unsaved, &[&e, &preset.name]))
+        }
+    }
+    // Sizes left as `auto` keep their defaults if the terminal can't say how big it is.
+    if let Ok((columns, rows)) = terminal().size() {
+        game_config.fit_board(columns, rows);
+    }
+    if let Some(NetRole::Spectate(ref address)) = args.net {
+        let result = Connection::spectate(address, text)
+            .map_err(Box::from)
+            .and_then(|connection| spectate(game_config, connection));
+        if let Err(e) = result {
+            println!("{}", fill(text.spectate_failed, &[&format!("{:?}", e)]));
+        }
+        return;
+    }
+    let setup = Setup {
+        handicap: game_config.rules().handicap,
+        targeting: game_config.targeting
+    };
+    let connection = match args.net {
+        Some(NetRole::Host(ref address)) => Connection::host(
+            address,
+            thread_rng().gen(),
+            (game_config.board_width, game_config.board_height),
+            args.players,
+            setup,
+            Some(game_config.rules()).filter(|_| game_config.rollback),
+            text
+        )
+        .map(Some),
+        Some(NetRole::Connect(ref address)) => Connection::connect(address, setup, text).map(Some),
+        Some(NetRole::Spectate(_)) | None => Ok(None)
+    };
+    let mut connection = match connection {
+        Ok(connection) => connection,
+        Err(e) => {
+            println!("{}", fill(text.network_failed, &[&format!("{:?}", e)]));
+            return;
+        }
+    };
+    let mut bot = match args.bot {
+        Some(ref command) => match launch_bot(command, &game_config) {
+            Ok(bot) => Some(BotPlayer::External(bot)),
+            Err(e) => {
+                println!("{}", fill(text.bot_failed, &[&e]));
+                return;
+            }
+        },
+        None if args.ai => Some(BotPlayer::Builtin),
+        None => None
+    };
+    let challenge = match args.challenge {
+        Some(ref name) => match load_challenge(name, text) {
+            Ok(challenge) => Some(challenge),
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        },
+        None => None
+    };
+    let puzzle = match args.puzzle {
+        Some(ref name) => match load_puzzle(name, text) {
+            Ok(puzzle) => Some(puzzle),
+            Err(e) => {
+                println!("{}", e);
+                return;
+            }
+        },
+        None => None
+    };
+    let opener = match args.trainer {
+        Some(ref name) => match puzzle::openers().iter().find(|opener| opener.name == *name) {
+            Some(opener) => Some(opener.clone()),
+            None => {
+                println!("{}", fill(text.unknown_opener, &[name]));
+                return;
+            }
+        },
+        None => None
+    };
+    // A puzzle is played as a challenge, starting from its board, and so is an opener.
+    let puzzle = puzzle.or_else(|| opener.clone());
+    let challenge = challenge.or_else(|| puzzle.as_ref().map(Puzzle::challenge));
+    let position = match args.fumen {
+        Some(ref fumen) => Some(fumen::decode(fumen)),
+        None => puzzle.as_ref().map(Puzzle::position)
+    };
+    let position = match position {
+        Some(position) => {
+            match position
+                .and_then(|position| position.check(&game_config.rules()).map(|()| position))
+            {
+                Ok(position) => Some(position),
                 Err(e) => {
-                    println!(
-                        "Critical error! Failed to write default config to new config file!\n\
-                         {:?}",
-                        e
-                    );
+                    println!("{}", fill(text.position_failed, &[&e]));
                     return;
                 }
-            },
+            }
+        }
+        None => None
+    };
+    // The trainer plays an opener over and over rather than once.
+    let mut start = match (opener, challenge, position) {
+        (Some(_), Some(challenge), Some(position)) => Start {
+            trainer: Some(Trainer::new(position, challenge)),
+            ..Start::default()
+        },
+        (_, challenge, position) => Start {
+            challenge,
+            position,
+            trainer: None,
+            seed: args.seed
+        }
+    };
+    // Versus games can't be picked up again on their own, so they're never offered, and a saved
+    // game would be played for its own challenge, position, and pieces rather than the ones asked
+    // for.
+    let mut saved = if connection.is_none() && bot.is_none() && start.is_plain() {
+        save::load().unwrap_or_else(|e| {
+            println!("{}", fill(text.saved_game_failed, &[&format!("{:?}", e)]));
+            None
+        })
+    } else {
+        None
+    };
+    // Playing again starts over from the same seed with whatever settings the last game ended
+    // with, once that game has been counted.
+    loop {
+        let mut session = match run(
+            game_config.clone(),
+            &source,
+            connection.take(),
+            bot.take(),
+            saved.take(),
+            start.clone()
+        ) {
+            Ok(session) => session,
             Err(e) => {
-                println!("Critical error! Failed to create new config file.\n{:?}", e);
+                println!("{}", fill(text.terminal_failed, &[&format!("{:?}", e)]));
                 return;
             }
+        };
+        finish(&mut session, args.puzzle.is_some());
+        if !session.retry {
+            return;
+        }
+        game_config = session.config.clone();
+        start.challenge = session.game.challenge.clone();
+        start.seed = Some(session.game.seed);
+    }
+}
+
+// Report how the game went, and save or count it.
+fn finish(session: &mut Session, puzzle: bool) {
+    let text = session.config.language.strings();
+    // Attempts at an opener are only practice, so they're neither saved nor counted.
+    if let Some(ref trainer) = session.trainer {
+        let challenge = &trainer.challenge;
+        println!(
+            "{}",
+            fill(
+                text.opener_built,
+                &[
+                    &challenge.name,
+                    &challenge.description,
+                    &trainer.successes,
+                    &trainer.attempts
+                ]
+            )
+        );
+        return;
+    }
+    // Games that never got going aren't worth counting.
+    if session.game.stats.pieces == 0 {
+        return;
+    }
+    if let Some(ref challenge) = session.game.challenge {
+        if session.game.game_over {
+            let message = match (session.game.won, puzzle) {
+                (true, true) => text.puzzle_solved,
+                (false, true) => text.puzzle_failed,
+                (true, false) => text.challenge_complete,
+                (false, false) => text.challenge_failed
+            };
+            println!(
+                "{}",
+                fill(message, &[&challenge.name, &challenge.description])
+            );
+        }
+    }
+    // Unfinished games are kept to carry on with, and only counted once they're over.
+    if !session.game.game_over && !session.game.versus {
+        match save::save(&session.game) {
+            Ok(path) => {
+                println!("{}", fill(text.game_saved, &[&path.display()]));
+                return;
+            }
+            Err(e) => println!("{}", fill(text.save_failed, &[&format!("{:?}", e)]))
+        }
+    }
+    // Practice games start partway through, so they'd throw the totals off.
+    if !session.game.practice {
+        let recorded = LifetimeStats::load().and_then(|mut lifetime| {
+            lifetime.record(&session.game);
+            lifetime.save()
+        });
+        if let Err(e) = recorded {
+            println!("{}", fill(text.lifetime_failed, &[&format!("{:?}", e)]));
+        }
+    }
+    // Versus games are won against the other player rather than for a result of their own.
+    if !session.game.practice && !session.game.versus {
+        let recorded = PersonalBests::load().and_then(|mut bests| {
+            if bests.record(&session.game) {
+                println!("{}", text.new_personal_best);
+                bests.save()?;
+                // The new best is the one to race from now on.
+                if let Some(ref recording) = session.recording {
+                    recording.save(&Splits::course(&session.game))?;
+                }
+            }
+            Ok(())
+        });
+        if let Err(e) = recorded {
+            println!("{}", fill(text.best_failed, &[&format!("{:?}", e)]));
+        }
+    }
+    // Only whole games against the clock are timed, from the start.
+    if session.config.splits
+        && !session.game.practice
+        && !session.game.versus
+        && session.splits.base == 0
+    {
+        let times = &session.splits.times;
+        let recorded = BestSplits::load().and_then(|mut best| {
+            if best.record(Splits::course(&session.game), times) {
+                println!("{}", text.new_best_splits);
+                best.save()?;
+            }
+            Ok(())
+        });
+        if let Err(e) = recorded {
+            println!("{}", fill(text.splits_failed, &[&format!("{:?}", e)]));
+        }
+    }
+    if let Some(ref url) = session.config.leaderboard_url {
+        let submission = Submission::new(&session.config, &session.game, &session.pages);
+        if let Some(submission) = submission.filter(|_| cfg!(feature = "online")) {
+            match submission.send(url) {
+                Ok(()) => println!("{}", text.result_sent),
+                Err(e) => println!("{}", fill(text.send_failed, &[&e.message(text)]))
+            }
+        }
+    }
+    // Going back in a practice game would leave inputs in the replay that never happened.
+    if session.game.game_over && !session.game.practice && !session.game.versus {
+        session.record_inputs();
+        match save_replay(&session.replay, &session.game) {
+            Ok(path) => println!("{}", fill(text.replay_saved, &[&path.display()])),
+            Err(e) => println!("{}", fill(text.replay_failed, &[&format!("{:?}", e)]))
+        }
+    }
+    if session.config.fumen_export {
+        match fumen::encode(&session.pages) {
+            Some(fumen) => println!("{}", fill(text.fumen_of_game, &[&fumen])),
+            None => println!("{}", text.fumen_width)
+        }
+    }
+    if let Some(format) = session.config.stats_export {
+        match GameRecord::new(&session.game).append(format) {
+            Ok(path) => println!("{}", fill(text.stats_added, &[&path.display()])),
+            Err(e) => println!("{}", fill(text.stats_failed, &[&format!("{:?}", e)]))
+        }
+    }
+}
+
+// What to do, picked by the first argument.
+enum Command {
+    Play,
+    CheckConfig,
+    // Write a commented default config to the path, or to the config directory.
+    InitConfig(Option<PathBuf>),
+    Scores,
+    // Play a replay back in the terminal.
+    Replay(PathBuf),
+    // Write a replay out as an asciinema recording, to the path or next to the replay.
+    Cast(PathBuf, Option<PathBuf>),
+    // Time the game and the AI without a terminal.
+    Bench
+}
+
+// The command comes first, unless it's left out to play a game with only options given.
+fn parse_command<'a>(
+    args: &'a [String],
+    text: &Strings
+) -> Result<(Command, &'a [String]), String> {
+    let first = match args.first() {
+        Some(first) if !first.starts_with('-') => first.as_str(),
+        _ => return Ok((Command::Play, args))
+    };
+    match first {
+        "play" => Ok((Command::Play, &args[1..])),
+        "scores" => Ok((Command::Scores, &args[1..])),
+        "bench" => Ok((Command::Bench, &args[1..])),
+        "replay" => match args.get(1) {
+            Some(replay) if !replay.starts_with('-') => {
+                Ok((Command::Replay(PathBuf::from(replay)), &args[2..]))
+            }
+            _ => Err(text.no_replay.to_string())
+        },
+        "cast" => match (args.get(1), args.get(2)) {
+            (Some(replay), Some(output))
+                if !replay.starts_with('-') && !output.starts_with('-') =>
+            {
+                Ok((
+                    Command::Cast(PathBuf::from(replay), Some(PathBuf::from(output))),
+                    &args[3..]
+                ))
+            }
+            (Some(replay), _) if !replay.starts_with('-') => {
+                Ok((Command::Cast(PathBuf::from(replay), None), &args[2..]))
+            }
+            _ => Err(text.no_cast_replay.to_string())
+        },
+        "config" => match args.get(1).map(String::as_str) {
+            Some("check") => Ok((Command::CheckConfig, &args[2..])),
+            Some("init") => match args.get(2) {
+                Some(path) if !path.starts_with('-') => {
+                    Ok((Command::InitConfig(Some(PathBuf::from(path))), &args[3..]))
+                }
+                _ => Ok((Command::InitConfig(None), &args[2..]))
+            },
+            _ => Err(text.no_config_command.to_string())
+        },
+        _ => Err(fill(text.unknown_command, &[&first]))
+    }
+}
+
+struct Arguments {
+    command: Command,
+    // Skip unknown settings in the config instead of refusing to start.
+    lenient_config: bool,
+    profile: Option<String>,
+    net: Option<NetRole>,
+    // How many play in a hosted game, the host included.
+    players: usize,
+    // Command line for a Tetris Bot Protocol bot to play against.
+    bot: Option<String>,
+    // Play against the built-in AI instead.
+    ai: bool,
+    // Name of the challenge to play for.
+    challenge: Option<String>,
+    // Fumen to practice the first page of.
+    fumen: Option<String>,
+    // Name of the puzzle to solve.
+    puzzle: Option<String>,
+    // Name of the opener to practice.
+    trainer: Option<String>,
+    // Seed to deal the pieces from, to play the same game as before.
+    seed: Option<u64>,
+    // Control scheme to switch to and write to the config.
+    preset: Option<&'static Preset>,
+    // How much to write to the log file, if anything.
+    log: Option<LevelFilter>,
+    // (setting name, value) pairs that replace whatever the config file says.
+    overrides: Vec<(String, String)>
+}
+
+impl Arguments {
+    fn against_bot(&self) -> bool {
+        self.bot.is_some() || self.ai
+    }
+
+    // The config section for the mode being played: the challenge's name, or `marathon` for an
+    // ordinary game on your own. Puzzles, openers, and versus games don't have one.
+    fn mode(&self) -> Option<String> {
+        if self.puzzle.is_some() || self.trainer.is_some() || self.fumen.is_some() {
+            None
+        } else if let Some(ref challenge) = self.challenge {
+            Some(challenge.clone())
+        } else if self.net.is_none() && !self.against_bot() {
+            Some(MARATHON.to_string())
+        } else {
+            None
+        }
+    }
+}
+
+// Arguments are `--setting value` or `--setting=value` pairs, with dashes in place of underscores
+// in setting names, plus the few options that aren't settings. Whether the settings exist is
+// checked along with the rest of the config.
+fn parse_args(args: &[String], text: &Strings) -> Result<Arguments, String> {
+    let (command, args) = parse_command(args, text)?;
+    let mut parsed = Arguments {
+        command,
+        lenient_config: false,
+        profile: None,
+        net: None,
+        players: 2,
+        bot: None,
+        ai: false,
+        challenge: None,
+        fumen: None,
+        puzzle: None,
+        trainer: None,
+        seed: None,
+        preset: None,
+        log: None,
+        overrides: Vec::with_capacity(args.len() / 2)
+    };
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        // From before there were commands.
+        if arg == "--check-config" {
+            parsed.command = Command::CheckConfig;
+            continue;
+        }
+        if arg == "--lenient-config" {
+            parsed.lenient_config = true;
+            continue;
+        }
+        if arg == "--ai" {
+            parsed.ai = true;
+            continue;
+        }
+        let option = match arg.strip_prefix("--") {
+            Some(option) => option,
+            None => return Err(fill(text.unexpected_argument, &[arg]))
+        };
+        let (name, value) = match option.find('=') {
+            Some(i) => (&option[..i], option[i + 1..].to_string()),
+            None => match args.next() {
+                Some(value) => (option, value.clone()),
+                None => return Err(fill(text.missing_argument_value, &[arg]))
+            }
+        };
+        let name = name.replace('-', "_");
+        if name == "profile" {
+            // Profile names end up in file names.
+            if value.contains(&['/', '\\', '.'][..]) {
+                return Err(text.profile_name_characters.to_string());
+            }
+            parsed.profile = Some(value);
+            continue;
+        }
+        if name == "bot" {
+            parsed.bot = Some(value);
+            continue;
+        }
+        if name == "players" {
+            match value.parse() {
+                Ok(players) if (2..=MAX_PLAYERS).contains(&players) => parsed.players = players,
+                _ => return Err(fill(text.bad_player_count, &[&value, &MAX_PLAYERS]))
+            }
+            continue;
+        }
+        if name == "challenge" {
+            // Challenge names end up in file names too.
+            if value.contains(&['/', '\\', '.'][..]) {
+                return Err(text.challenge_name_characters.to_string());
+            }
+            parsed.challenge = Some(value);
+            continue;
+        }
+        if name == "puzzle" {
+            // And so do puzzle names.
+            if value.contains(&['/', '\\', '.'][..]) {
+                return Err(text.puzzle_name_characters.to_string());
+            }
+            parsed.puzzle = Some(value);
+            continue;
+        }
+        if name == "trainer" {
+            parsed.trainer = Some(value);
+            continue;
+        }
+        if name == "fumen" {
+            parsed.fumen = Some(value);
+            continue;
+        }
+        if name == "seed" {
+            match parse_seed(&value) {
+                Some(seed) => parsed.seed = Some(seed),
+                None => return Err(fill(text.bad_seed, &[&value]))
+            }
+            continue;
+        }
+        if name == "preset" {
+            match Preset::named(&value) {
+                Some(preset) => parsed.preset = Some(preset),
+                None => {
+                    let names = PRESETS.iter().map(|preset| preset.name).collect::<Vec<_>>();
+                    return Err(fill(text.unknown_preset, &[&value, &names.join(", ")]));
+                }
+            }
+            continue;
+        }
+        if name == "log" {
+            match value.parse() {
+                Ok(level) => parsed.log = Some(level),
+                Err(_) => return Err(fill(text.unknown_log_level, &[&value]))
+            }
+            continue;
+        }
+        let role = match name.as_str() {
+            "host" => Some(NetRole::Host(value.clone())),
+            "connect" => Some(NetRole::Connect(value.clone())),
+            "spectate" => Some(NetRole::Spectate(value.clone())),
+            _ => None
+        };
+        if role.is_some() {
+            if parsed.net.is_some() {
+                return Err(text.one_net_role.to_string());
+            }
+            parsed.net = role;
+            continue;
+        }
+        let name = match ARGUMENT_ALIASES.iter().find(|&&(alias, _)| alias == name) {
+            Some(&(_, setting)) => setting.to_string(),
+            None => name
+        };
+        parsed.overrides.push((name, value));
+    }
+    if parsed.bot.is_some() && parsed.ai {
+        return Err(text.bot_and_ai.to_string());
+    }
+    if parsed.against_bot() && parsed.net.is_some() {
+        return Err(text.bot_online.to_string());
+    }
+    if parsed.players != 2 && !matches!(parsed.net, Some(NetRole::Host(_))) {
+        return Err(text.players_host_only.to_string());
+    }
+    if parsed.challenge.is_some() && (parsed.against_bot() || parsed.net.is_some()) {
+        return Err(text.challenge_versus.to_string());
+    }
+    if parsed.fumen.is_some() && (parsed.against_bot() || parsed.net.is_some()) {
+        return Err(text.fumen_versus.to_string());
+    }
+    if parsed.trainer.is_some()
+        && (parsed.challenge.is_some() || parsed.fumen.is_some() || parsed.puzzle.is_some())
+    {
+        return Err(text.trainer_alone.to_string());
+    }
+    if (parsed.puzzle.is_some() || parsed.trainer.is_some())
+        && (parsed.against_bot() || parsed.net.is_some())
+    {
+        return Err(text.puzzle_versus.to_string());
+    }
+    // The host picks the seed for versus games, and every attempt in the trainer gets new pieces.
+    if parsed.seed.is_some()
+        && (parsed.against_bot() || parsed.net.is_some() || parsed.trainer.is_some())
+    {
+        return Err(text.seed_versus.to_string());
+    }
+    if parsed.puzzle.is_some() && (parsed.challenge.is_some() || parsed.fumen.is_some()) {
+        return Err(text.puzzle_alone.to_string());
+    }
+    Ok(parsed)
+}
+
+// Look for a config in each of the search directories in turn. A profile's own file takes
+// priority over a `[profile.<name>]` section for it in the main config.
+fn find_config(args: &Arguments) -> Option<ConfigSource> {
+    let search = |toml_file: &str, legacy_file: &str, section_profile: bool| {
+        paths::config_search_dirs().into_iter().find_map(|dir| {
+            let (toml_path, legacy_path) = (dir.join(toml_file), dir.join(legacy_file));
+            let source = if toml_path.exists() {
+                ConfigSource::new(toml_path, true, args)
+            } else if legacy_path.exists() {
+                ConfigSource::new(legacy_path, false, args)
+            } else {
+                return None;
+            };
+            Some(if section_profile {
+                source
+            } else {
+                source.without_profile()
+            })
+        })
+    };
+    args.profile
+        .as_ref()
+        .and_then(|profile| {
+            search(
+                &format!("tui_tetris.{}.toml", profile),
+                &format!("tui_tetris.{}.conf", profile),
+                false
+            )
+        })
+        .or_else(|| search(TOML_CONFIG_FILE, LEGACY_CONFIG_FILE, true))
+}
+
+// Play a replay back at the speed it was played, drawn the way the config says. Pause stops and
+// starts it, and the move keys jump back and forward through it by seeking, which only has to
+// play on from the nearest keyframe. The end stays up until quit is pressed.
+fn watch_replay(path: &Path, mut config: GameConfig) -> Result<(), Box<dyn Error>> {
+    let replay = Replay::decode(&read(path)?)?;
+    let game = replay.start().clone();
+    config.set_rules(&game.rules);
+    let (start, end) = (game.stats.ticks, replay.ticks());
+    let mut session = Session::new(config, game);
+    // The first of the replay's inputs that hasn't reached the game yet.
+    let mut next_input = 0;
+    let _screen = AlternateScreen::to_alternate(true)?;
+    let input = input();
+    let mut reader = input.read_async();
+    signals::install();
+    terminal().clear(ClearType::All)?;
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    queue!(out, Hide)?;
+    let mut pacer = FramePacer::new(session.config.fps_limiter);
+    let mut clock = TickClock::new(TICK_RATE);
+    let mut paused = false;
+    let mut frame = Vec::new();
+    let mut frame_time = pacer.wait();
+    'watch: loop {
+        if signals::stop_requested() {
+            break 'watch;
+        }
+        let mut seek = None;
+        for event in &mut reader {
+            let key = match event {
+                InputEvent::Keyboard(ref event) => Key::from_event(event),
+                _ => None
+            };
+            let ticks = seek.unwrap_or(session.game.stats.ticks);
+            match key.and_then(|key| map_key(&session.config, &key)) {
+                Some(Action::Quit) => break 'watch,
+                Some(Action::Pause) => paused = !paused,
+                Some(Action::MoveLeft) => {
+                    seek = Some(ticks.saturating_sub(REPLAY_SEEK_TICKS).max(start))
+                }
+                Some(Action::MoveRight) => seek = Some((ticks + REPLAY_SEEK_TICKS).min(end)),
+                _ => {}
+            }
+        }
+        if let Some(ticks) = seek {
+            session.game = replay.seek(ticks);
+            next_input = replay
+                .inputs()
+                .partition_point(|input| input.ticks <= ticks);
+            terminal().clear(ClearType::All)?;
+        }
+        let ticks = clock.advance(frame_time);
+        for _ in 0..ticks {
+            if paused || session.game.game_over || session.game.stats.ticks >= end {
+                break;
+            }
+            let now = session.game.stats.ticks;
+            let inputs = &replay.inputs()[next_input..];
+            for input in inputs.iter().take_while(|input| input.ticks <= now) {
+                session.handle_action(input.action);
+                next_input += 1;
+            }
+            session.tick();
+        }
+        frame.clear();
+        session.draw(&mut frame)?;
+        out.write_all(&frame)?;
+        out.flush()?;
+        frame_time = pacer.wait();
+    }
+    queue!(out, Show)?;
+    out.flush()?;
+    Ok(())
+}
+
+fn export_cast(replay: &Path, output: &Path, config: GameConfig) -> Result<(), Box<dyn Error>> {
+    let replay = Replay::decode(&read(replay)?)?;
+    let mut file = File::create(output)?;
+    cast::export(&replay, config, &mut file)?;
+    Ok(())
+}
+
+fn print_help(text: &Strings) {
+    println!("{}", text.help);
+    for option in CONFIG_OPTIONS.iter() {
+        let aliases = ARGUMENT_ALIASES
+            .iter()
+            .filter(|&&(_, setting)| setting == *option)
+            .map(|&(alias, _)| format!(", --{}", alias))
+            .collect::<String>();
+        println!("    --{}{} <value>", option.replace('_', "-"), aliases);
+    }
+    println!("{}", text.help_presets);
+    for preset in PRESETS.iter() {
+        println!("    {:<16}{}", preset.name, preset.description);
+    }
+    println!("{}", text.help_challenges);
+    for challenge in challenge::built_in().iter() {
+        println!("    {:<16}{}", challenge.name, challenge.description);
+    }
+    println!("{}", text.help_openers);
+    for opener in puzzle::openers().iter() {
+        println!("    {:<16}{}", opener.name, opener.description);
+    }
+    println!("{}", text.help_puzzles);
+    for (name, puzzle) in puzzles(text) {
+        println!("    {:<16}{}", name, puzzle.description);
+    }
+}
+
+// Every puzzle there is, built-in ones first, with the name to play each with. Files with the
+// same name as one found before them are left out, since they'd never be loaded, and so are ones
+// that can't be read.
+fn puzzles(text: &Strings) -> Vec<(String, Puzzle)> {
+    let mut puzzles = puzzle::built_in()
+        .iter()
+        .map(|puzzle| (puzzle.name.clone(), puzzle.clone()))
+        .collect::<Vec<_>>();
+    for dir in paths::config_search_dirs() {
+        let entries = match dir.join(PUZZLES_DIR).read_dir() {
+            Ok(entries) => entries,
+            Err(_) => continue
+        };
+        let mut names = entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "toml")
+            })
+            .filter_map(|path| {
+                path.file_stem()
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .collect::<Vec<_>>();
+        names.sort();
+        for name in names {
+            if puzzles.iter().any(|(found, _)| *found == name) {
+                continue;
+            }
+            if let Ok(puzzle) = load_puzzle(&name, text) {
+                puzzles.push((name, puzzle));
+            }
+        }
+    }
+    puzzles
+}
+
+// Built-in puzzles take priority over custom ones with the same name, like challenges.
+fn load_puzzle(name: &str, text: &Strings) -> Result<Puzzle, String> {
+    if let Some(puzzle) = puzzle::built_in().iter().find(|p| p.name == name) {
+        return Ok(puzzle.clone());
+    }
+    let file = format!("{}.toml", name);
+    let path = paths::config_search_dirs()
+        .into_iter()
+        .map(|dir| dir.join(PUZZLES_DIR).join(&file))
+        .find(|path| path.exists())
+        .ok_or_else(|| fill(text.unknown_puzzle, &[&name]))?;
+    let contents = read_to_string(&path).map_err(|e| format!("{:?}", e))?;
+    let puzzle = toml::from_str::<Puzzle>(&contents)
+        .map_err(|e| fill(text.invalid_puzzle, &[&path.display(), &e]))?;
+    if !puzzle.challenge().is_valid() {
+        return Err(fill(text.puzzle_no_goal, &[&path.display()]));
+    }
+    puzzle
+        .position()
+        .map_err(|e| fill(text.invalid_puzzle, &[&path.display(), &e]))?;
+    Ok(puzzle)
+}
+
+// Built-in challenges take priority over custom ones with the same name.
+fn load_challenge(name: &str, text: &Strings) -> Result<Challenge, String> {
+    if let Some(challenge) = challenge::built_in().iter().find(|c| c.name == name) {
+        return Ok(challenge.clone());
+    }
+    let file = format!("{}.toml", name);
+    let path = paths::config_search_dirs()
+        .into_iter()
+        .map(|dir| dir.join(CHALLENGES_DIR).join(&file))
+        .find(|path| path.exists())
+        .ok_or_else(|| fill(text.unknown_challenge, &[&name]))?;
+    let contents = read_to_string(&path).map_err(|e| format!("{:?}", e))?;
+    let challenge = toml::from_str::<Challenge>(&contents)
+        .map_err(|e| fill(text.invalid_challenge, &[&path.display(), &e]))?;
+    if !challenge.is_valid() {
+        return Err(fill(text.challenge_no_goal, &[&path.display()]));
+    }
+    Ok(challenge)
+}
+
+// Write the default config to the platform config directory, or the current directory if there
+// isn't one, and return where it went. Failing to is only worth a warning since a missing config
+// file means the defaults are used anyway.
+fn write_default_config(text: &Strings) -> PathBuf {
+    let dir = paths::config_dir().unwrap_or_else(|| PathBuf::from("."));
+    let path = dir.join(TOML_CONFIG_FILE);
+    if let Err(e) = create_dir_all(&dir) {
+        println!("{}", fill(text.config_dir_failed, &[&format!("{:?}", e)]));
+        return path;
+    }
+    match File::create(&path) {
+        Ok(mut file) => {
+            match file.write_all(GameConfig::default().to_commented_toml().as_bytes()) {
+                Ok(()) => println!("{}", fill(text.config_created, &[&path.display()])),
+                Err(e) => println!("{}", fill(text.config_write_failed, &[&format!("{:?}", e)]))
+            }
+        }
+        Err(e) => println!(
+            "{}",
+            fill(text.config_create_failed, &[&format!("{:?}", e)])
+        )
+    }
+    path
+}
+
+// Write a default config with comments for every setting to `path`, in the legacy format if it
+// ends in `.conf`. A file that's already there is never replaced.
+fn init_config(path: &Path) -> IoResult<()> {
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+    let config = GameConfig::default();
+    let contents = if path
+        .extension()
+        .is_some_and(|extension| extension == "conf")
+    {
+        config.to_commented_string()
+    } else {
+        config.to_commented_toml()
+    };
+    OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?
+        .write_all(contents.as_bytes())
+}
+
+// The config file in use and which format it's in, kept so that it can be reloaded.
+struct ConfigSource {
+    path: PathBuf,
+    toml: bool,
+    // The `[profile.<name>]` section to use, if any.
+    profile: Option<String>,
+    // The section for the mode being played, if it has one.
+    mode: Option<String>,
+    overrides: Vec<(String, String)>,
+    // Unknown settings are warned about rather than treated as errors.
+    lenient: bool
+}
+
+impl ConfigSource {
+    fn new(path: PathBuf, toml: bool, args: &Arguments) -> Self {
+        ConfigSource {
+            path,
+            toml,
+            profile: args.profile.clone(),
+            mode: args.mode(),
+            overrides: args.overrides.clone(),
+            lenient: args.lenient_config
+        }
+    }
+
+    // For a profile's own config file, which doesn't need a section for it.
+    fn without_profile(self) -> Self {
+        ConfigSource {
+            profile: None,
+            ..self
+        }
+    }
+
+    // A missing file is treated as empty, i.e. all defaults.
+    fn read(&self, text: &Strings) -> Result<String, String> {
+        if !self.toml && self.profile.is_some() {
+            return Err(fill(text.profile_needs_toml, &[&self.path.display()]));
+        }
+        match read_to_string(&self.path) {
+            Ok(contents) => Ok(contents),
+            Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(String::new()),
+            Err(e) => Err(fill(
+                text.config_read_failed,
+                &[&self.path.display(), &format!("{:?}", e)]
+            ))
+        }
+    }
+
+    // The config along with any unknown settings that were skipped because it's lenient.
+    fn load(&self, text: &Strings) -> Result<(GameConfig, Vec<ParseError>), String> {
+        let (config, errors) = self.check(text)?;
+        let (warnings, errors) = self.split_warnings(errors);
+        if errors.is_empty() {
+            return Ok((config, warnings));
+        }
+        let problems = fill(text.config_problems, &[&errors.len(), &self.path.display()]);
+        Err(format!(
+            "{}\n\n{}",
+            problems,
+            ParseErrors(errors).report(text)
+        ))
+    }
+
+    // Separate out the errors that are only warnings, which are old setting names and, if
+    // lenient, unknown settings.
+    fn split_warnings(&self, errors: Vec<ParseError>) -> (Vec<ParseError>, Vec<ParseError>) {
+        errors
+            .into_iter()
+            .partition(|e| e.is_deprecated() || (self.lenient && e.is_unknown_setting()))
+    }
+
+    // Write the changes made on the settings screen back to the file, on top of the file's own
+    // settings rather than whatever's in use. `copy` picks which of the changes are written.
+    fn save<F: Fn(&GameConfig, &mut GameConfig)>(
+        &self,
+        edited: &GameConfig,
+        copy: F,
+        text: &Strings
+    ) -> Result<(), String> {
+        if self.profile.is_some() {
+            return Err(text.profile_unsaved.to_string());
+        }
+        let contents = self.read(text)?;
+        let parse = |mode| {
+            let parsed = if self.toml {
+                GameConfig::parse_toml(&contents, &[], None, mode)
+            } else {
+                GameConfig::parse(&contents, &[], mode)
+            };
+            parsed.map_err(|e| fill(text.in_file, &[&self.path.display()]) + "\n" + &e.report(text))
+        };
+        let original = parse(None)?;
+        // What's changed would go at the top of the file, where the mode's section would still
+        // override it.
+        if let Some(ref mode) = self.mode {
+            if parse(Some(mode))?.to_string() != original.to_string() {
+                return Err(fill(text.mode_unsaved, &[mode]));
+            }
+        }
+        let mut config = original.clone();
+        copy(edited, &mut config);
+        // Only the changed settings are rewritten, so the rest of the file stays as it was.
+        let rewritten = if self.toml {
+            config.rewrite_toml(&original, &contents)
+        } else {
+            config.rewrite(&original, &contents)
+        };
+        let written =
+            File::create(&self.path).and_then(|mut file| file.write_all(rewritten.as_bytes()));
+        written.map_err(|e| {
+            fill(
+                text.write_failed,
+                &[&self.path.display(), &format!("{:?}", e)]
+            )
+        })
+    }
+
+    fn check(&self, text: &Strings) -> Result<(GameConfig, Vec<ParseError>), String> {
+        let contents = self.read(text)?;
+        Ok(if self.toml {
+            GameConfig::check_toml(
+                &contents,
+                &self.overrides,
+                self.profile.as_deref(),
+                self.mode.as_deref()
+            )
+        } else {
+            GameConfig::check(&contents, &self.overrides, self.mode.as_deref())
+        })
+    }
+}
+
+// Print every problem with the config, followed by all of the settings that would actually be used
+// in the same format as the file. Returns whether the config is valid.
+fn check_config(source: &ConfigSource, text: &Strings) -> bool {
+    let (config, errors) = match source.check(text) {
+        Ok(checked) => checked,
+        Err(e) => {
+            println!("{}", e);
+            return false;
         }
-        game_config
     };
-    println!("Successfully loaded config:\n{}", game_config);
+    let (warnings, errors) = source.split_warnings(errors);
+    for warning in warnings.iter() {
+        println!("{}", warning.warning(text));
+    }
+    if errors.is_empty() {
+        println!("{}", fill(text.config_valid, &[&source.path.display()]));
+    } else {
+        println!(
+            "{}",
+            fill(
+                text.config_problems,
+                &[&errors.len(), &source.path.display()]
+            )
+        );
+        for e in errors.iter() {
+            println!("\n{}", e.report(text));
+        }
+        println!("{}", text.defaults_below);
+    }
+    let effective = if source.toml {
+        config.to_toml()
+    } else {
+        config.to_string()
+    };
+    println!("{}", fill(text.effective_settings, &[&effective]));
+    errors.is_empty()
+}
+
+// What a game that isn't a versus game is played for and started from.
+#[derive(Clone, Default)]
+struct Start {
+    challenge: Option<Challenge>,
+    position: Option<Position>,
+    // Set instead of the others to play an opener over and over.
+    trainer: Option<Trainer>,
+    // Where the pieces come from, if it's been picked.
+    seed: Option<u64>
+}
+
+impl Start {
+    // An ordinary game from an empty board with new pieces.
+    fn is_plain(&self) -> bool {
+        self.challenge.is_none()
+            && self.position.is_none()
+            && self.trainer.is_none()
+            && self.seed.is_none()
+    }
+}
+
+// Art that can't be read is left out rather than stopping the game.
+fn load_background(config: &GameConfig) -> Option<Art> {
+    let path = config.background_art.as_ref()?;
+    match Art::load(path) {
+        Ok(art) => Some(art),
+        Err(e) => {
+            warn!("Failed to load the background art from {}: {}", path, e);
+            None
+        }
+    }
+}
+
+// Input is polled and rendering happens once per frame at the `fps_limiter` rate, while the game
+// logic is stepped at the fixed `TICK_RATE` based on how much time the last frame took.
+fn run(
+    mut game_config: GameConfig,
+    source: &ConfigSource,
+    mut connection: Option<Connection>,
+    bot: Option<BotPlayer>,
+    saved: Option<Game>,
+    start: Start
+) -> Result<Session, Box<dyn Error>> {
+    let _screen = AlternateScreen::to_alternate(true)?;
+    let input = input();
+    let mut reader = input.read_async();
+    signals::install();
+    terminal().clear(ClearType::All)?;
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    queue!(out, Hide)?;
+    let mut pacer = FramePacer::new(game_config.fps_limiter);
+    let mut clock = TickClock::new(TICK_RATE);
+    let mut session = match connection {
+        // Every player needs the same board for garbage to line up, so the host's size wins.
+        Some(ref connection) => {
+            game_config.board_width = connection.board_size.0;
+            game_config.board_height = connection.board_size.1;
+            // Handicaps are set by each player for their own game. With rollback netcode, the
+            // host's rules go for everyone else.
+            let (game, rollback) = match connection.rollback {
+                Some(ref setup) => {
+                    let players = setup.players();
+                    game_config.set_rules(&players[connection.player].0);
+                    let game_match = Match::new(connection.seed, &players);
+                    let game = game_match.games[connection.player].clone();
+                    let delay = game_config.input_delay;
+                    (
+                        game,
+                        Some(Rollback::new(game_match, connection.player, delay))
+                    )
+                }
+                None => {
+                    let mut game = Game::with_seed(game_config.rules(), connection.seed);
+                    game.start_versus();
+                    (game, None)
+                }
+            };
+            let mut session = Session::new(game_config, game);
+            session.opponents =
+                vec![RemoteBoard::new(connection.board_size); connection.players - 1];
+            session.player = connection.player;
+            session.rollback = rollback;
+            session
+        }
+        None => {
+            let game = match start.trainer {
+                Some(ref trainer) => trainer.attempt(game_config.rules())?,
+                None => {
+                    let mut game = match start.seed {
+                        Some(seed) => Game::with_seed(game_config.rules(), seed),
+                        None => Game::new(game_config.rules())
+                    };
+                    game.challenge = start.challenge;
+                    if let Some(position) = start.position {
+                        game.start_from(position)?;
+                    }
+                    game
+                }
+            };
+            let mut session = Session::new(game_config.clone(), game);
+            session.trainer = start.trainer;
+            session
+        }
+    };
+    // A file that can't be read just leaves nothing to compare with.
+    session.personal_best = PersonalBests::load()
+        .ok()
+        .and_then(|bests| bests.get(&session.game));
+    if session.config.splits {
+        let course = Splits::course(&session.game);
+        session.splits.best =
+            BestSplits::load().map_or_else(|_| Vec::new(), |best| best.get(&course));
+    }
+    if session.recording.is_some() {
+        session.race = Ghost::load(&Splits::course(&session.game)).unwrap_or(None);
+    }
+    session.background = load_background(&session.config);
+    let mut bot = match bot {
+        Some(bot) => Some(BotOpponent::new(bot, &mut session)?),
+        None => None
+    };
+    // The other player's game runs at normal speed, so only games against the clock can be slowed
+    // down or sped up, and those count as practice.
+    let speed = if session.game.versus {
+        100
+    } else {
+        session.config.game_speed
+    };
+    if speed != 100 {
+        session.game.practice = true;
+    }
+    if let Some(saved) = saved {
+        session.offer_saved(saved);
+    } else {
+        session.start_countdown();
+    }
+    // The last board sent to the other players.
+    let mut sent = None;
+    let mut frame = Vec::new();
+    let mut last_reload = Instant::now();
+    let mut player = Player::new();
+    let mut held = HeldKeys::default();
+    let mut gamepads = Gamepads::connect();
+    let mut mirror = session
+        .config
+        .mirror_file
+        .as_ref()
+        .map(|path| Mirror::new(path));
+    let mut presence = session.config.discord_app_id.map(Presence::connect);
+    // The title last set, if the game has set one.
+    let mut title = None;
+    let mut frame_time = pacer.wait();
+    'game: loop {
+        // Being killed or having the terminal closed is treated like quitting, so an unfinished
+        // game still gets saved.
+        if signals::stop_requested() {
+            break 'game;
+        }
+        let before = Snapshot::of(&session.game);
+        let pieces = session.game.stats.pieces;
+        let game_over = session.game.game_over;
+        session.inputs.next_frame();
+        let mut screenshot = false;
+        // Controller buttons come after the keys pressed this frame, as if they'd been pressed
+        // just after them.
+        let keys = reader
+            .by_ref()
+            .filter_map(|event| match event {
+                InputEvent::Keyboard(ref event) => Key::from_event(event),
+                _ => None
+            })
+            .chain(gamepads.poll(Instant::now()));
+        let mut piece_actions = Vec::new();
+        for key in keys {
+            debug!("Key {}", key);
+            if key == Key::new(KeyCode::Char('c'), KeyModifiers::CONTROL) {
+                break 'game;
+            }
+            if let Some(ref mut settings) = session.settings {
+                let before = session.config.to_string();
+                match settings.handle_key(&mut session.config, key) {
+                    Some(MenuEvent::Save) => {
+                        let copy = |edited: &GameConfig, to: &mut GameConfig| {
+                            settings.copy_changes(edited, to)
+                        };
+                        let text = session.config.language.strings();
+                        match source.save(&session.config, copy, text) {
+                            Ok(()) => settings.saved(&session.config),
+                            Err(_) => settings.message = text.saving_failed
+                        }
+                    }
+                    Some(MenuEvent::Rebound) => {
+                        let copy = |edited: &GameConfig, to: &mut GameConfig| {
+                            settings.copy_binding(edited, to)
+                        };
+                        let text = session.config.language.strings();
+                        match source.save(&session.config, copy, text) {
+                            Ok(()) => settings.binding_saved(&session.config),
+                            Err(_) => settings.message = text.saving_failed
+                        }
+                    }
+                    Some(MenuEvent::Close) => {
+                        session.settings = None;
+                        terminal().clear(ClearType::All)?;
+                    }
+                    None => {}
+                }
+                session.apply_settings();
+                // The board may have changed size, so get rid of what's left of it.
+                if session.config.to_string() != before {
+                    terminal().clear(ClearType::All)?;
+                }
+                continue;
+            }
+            if let Some(ref mut entry) = session.name_entry {
+                match entry.handle_key(&session.config, key) {
+                    Some(NameEvent::Done(name)) => {
+                        let saved = HighScores::load().and_then(|mut high_scores| {
+                            high_scores.insert(&session.game, name);
+                            high_scores.save()
+                        });
+                        if let Err(e) = saved {
+                            warn!("Failed to save the high score: {}", e);
+                        }
+                        session.name_entry = None;
+                        terminal().clear(ClearType::All)?;
+                    }
+                    Some(NameEvent::Skip) => {
+                        session.name_entry = None;
+                        terminal().clear(ClearType::All)?;
+                    }
+                    None => {}
+                }
+                continue;
+            }
+            let menu_action = map_menu_key(&session.config, &key);
+            if menu_action == Some(MenuAction::Select) && session.can_retry() {
+                session.retry = true;
+                break 'game;
+            }
+            if session.statistics.is_some() || session.leaderboard.is_some() {
+                if menu_action == Some(MenuAction::Select) || menu_action == Some(MenuAction::Back)
+                {
+                    session.statistics = None;
+                    session.leaderboard = None;
+                    terminal().clear(ClearType::All)?;
+                }
+                continue;
+            }
+            if session.game.paused {
+                if menu_action == Some(MenuAction::Up) || menu_action == Some(MenuAction::Down) {
+                    let items = session.pause_items();
+                    let current = items
+                        .iter()
+                        .position(|&item| item == session.pause_item)
+                        .unwrap_or(0);
+                    let step = if menu_action == Some(MenuAction::Up) {
+                        items.len() - 1
+                    } else {
+                        1
+                    };
+                    session.pause_item = items[(current + step) % items.len()];
+                    continue;
+                }
+                if menu_action == Some(MenuAction::Select) {
+                    match session.pause_item {
+                        // The save is gone once it's been picked up, and this game is saved in its
+                        // place if it's quit before it ends.
+                        PauseItem::Continue => {
+                            session.continue_saved();
+                            let _ = save::remove();
+                        }
+                        PauseItem::Settings => {
+                            session.settings = Some(SettingsMenu::new(&session.config))
+                        }
+                        // A file that can't be read shows as no games rather than keeping the
+                        // screen closed. Saving at the end of the game reports the problem.
+                        PauseItem::Statistics => {
+                            session.statistics = Some(LifetimeStats::load().unwrap_or_default())
+                        }
+                        // The game waits while the entries are fetched, which is fine since it's
+                        // paused.
+                        PauseItem::Leaderboard => {
+                            if let Some(ref url) = session.config.leaderboard_url {
+                                session.leaderboard = Some(Leaderboard::load(url, &session.game))
+                            }
+                        }
+                    }
+                    terminal().clear(ClearType::All)?;
+                    continue;
+                }
+            }
+            match map_key(&session.config, &key) {
+                Some(Action::Quit) => break 'game,
+                // Taken once this frame has been drawn, so it's of the screen as it is now.
+                Some(Action::Screenshot) => screenshot = true,
+                Some(action @ Action::ToggleStats)
+                | Some(action @ Action::ToggleDebug)
+                | Some(action @ Action::ToggleFrameStep) => {
+                    session.handle_action(action);
+                    terminal().clear(ClearType::All)?;
+                }
+                // With IRS, holding a rotate or hold key down does it once rather than over and over.
+                Some(action)
+                    if session.config.irs
+                        && INITIAL_ACTIONS.contains(&action)
+                        && !held.press(action, Instant::now()) => {}
+                Some(action) if PIECE_ACTIONS.contains(&action) => piece_actions.push(action),
+                Some(action) => session.handle_action(action),
+                None => {}
+            }
+        }
+        for action in resolve_conflicts(&piece_actions, session.config.input_conflicts) {
+            session.handle_action(action);
+        }
+        let ticks = session.ticks_to_run(clock.advance(frame_time * speed / 100));
+        let tick_start = Instant::now();
+        for _ in 0..ticks {
+            session.tick();
+        }
+        let tick_time = tick_start.elapsed();
+        if let Some(ref mut mirror) = mirror {
+            mirror.update(&session.game, Instant::now());
+        }
+        if let Some(ref mut presence) = presence {
+            presence.update(
+                &session.game,
+                session.config.language.strings(),
+                Instant::now()
+            );
+        }
+        // The boards are also what the leaderboard's replay hash is made from.
+        let keep_pages = session.config.fumen_export
+            || cfg!(feature = "online") && session.config.leaderboard_url.is_some();
+        if keep_pages && session.game.stats.pieces != pieces {
+            session.pages.push(session.game.board.clone());
+        }
+        // A piece that's spawned since the last frame gets whatever rotate or hold keys are still
+        // held, the way it would if they'd been pressed the moment it appeared.
+        if session.config.irs && session.game.stats.pieces != pieces {
+            let now = Instant::now();
+            for &action in INITIAL_ACTIONS.iter() {
+                if held.is_held(action, now) {
+                    session.handle_action(action);
+                }
+            }
+        }
+        // Appearance settings are reloaded while paused so themes can be tweaked mid-game, but not
+        // while the settings screen is open since that would undo whatever's being previewed.
+        if session.game.paused
+            && session.settings.is_none()
+            && last_reload.elapsed() >= CONFIG_RELOAD_INTERVAL
+        {
+            last_reload = Instant::now();
+            match source.load(session.config.language.strings()) {
+                Ok((config, _)) => {
+                    session.config_error = false;
+                    if session.reload_appearance(config) {
+                        session.background = load_background(&session.config);
+                        // The board may have changed size, so get rid of what's left of it.
+                        terminal().clear(ClearType::All)?;
+                    }
+                }
+                Err(_) => session.config_error = true
+            }
+        }
+        if let Some(ref mut net) = connection {
+            let exchanged = if session.rollback.is_some() {
+                exchange_inputs(net, &mut session)
+            } else {
+                exchange(net, &mut session, &mut sent)
+            };
+            // Without the others, the game carries on from where the match had it.
+            if let Err(e) = exchanged {
+                info!("Lost the connection to the other players: {}", e);
+                for opponent in session.opponents.iter_mut() {
+                    opponent.left = true;
+                }
+                session.rollback = None;
+                connection = None;
+            }
+        }
+        if let Some(ref mut opponent) = bot {
+            if let Err(e) = opponent.update(&mut session) {
+                info!("Lost the bot: {}", e);
+                for board in session.opponents.iter_mut() {
+                    board.left = true;
+                }
+                bot = None;
+            }
+        }
+        if session.config.sound {
+            for cue in before.cues(&session.game) {
+                player.play(cue);
+            }
+        }
+        if let Some(effect) = before.effect(&session.game) {
+            session.start_effect(effect);
+        }
+        if session.config.accessibility_cues {
+            for cue in before.accessibility_cues(&session.game, session.config.danger_height) {
+                player.play(cue);
+            }
+        }
+        // The results take the place of the preview and hold, and the other way around when the
+        // trainer starts another attempt, so get rid of what's left of them.
+        // A score good enough for the table gets a name before it's saved. Versus games are won by
+        // outlasting the other player rather than on score, and practice games start partway
+        // through, so neither are counted.
+        if session.game.game_over != game_over {
+            terminal().clear(ClearType::All)?;
+        }
+        // Kept out of the frame, which is only what's on the screen.
+        let mut commands = String::new();
+        if session.config.terminal_title {
+            let text = osc::game_title(&session.game, session.config.language.strings());
+            if title.as_ref() != Some(&text) {
+                if title.is_none() {
+                    commands.push_str(osc::PUSH_TITLE);
+                }
+                commands.push_str(&osc::title(&text));
+                title = Some(text);
+            }
+        } else if title.take().is_some() {
+            commands.push_str(osc::POP_TITLE);
+        }
+        if session.game.game_over && !game_over {
+            if session.config.terminal_title {
+                if let Some(message) = osc::milestone(&session) {
+                    commands.push_str(&osc::notification(&message));
+                }
+            }
+            let qualifies =
+                HighScores::load().is_ok_and(|high_scores| high_scores.qualifies(&session.game));
+            if qualifies && !session.game.versus && !session.game.practice {
+                session.name_entry = Some(NameEntry::default());
+            }
+        }
+        let render_start = Instant::now();
+        frame.clear();
+        session.draw(&mut frame)?;
+        frame.extend_from_slice(player.due_bells().as_bytes());
+        out.write_all(commands.as_bytes())?;
+        out.write_all(&frame)?;
+        out.flush()?;
+        if screenshot {
+            match Screen::of(&frame).save() {
+                Ok(path) => {
+                    info!("Screenshot saved to {}", path.display());
+                    session.start_effect(Effect::Flash);
+                }
+                Err(e) => warn!("Failed to save the screenshot: {}", e)
+            }
+        }
+        session.timings = FrameTimings {
+            frame: frame_time,
+            tick: tick_time,
+            render: render_start.elapsed(),
+            dropped_frames: pacer.dropped_frames(),
+            input_latency: session
+                .inputs
+                .this_frame()
+                .next()
+                .map(|input| input.at.elapsed())
+                .or(session.timings.input_latency)
+        };
+        trace!(
+            "Frame took {:?}: {} ticks in {:?}, {} bytes drawn in {:?}",
+            frame_time,
+            ticks,
+            tick_time,
+            frame.len(),
+            session.timings.render
+        );
+        frame_time = pacer.wait();
+    }
+    if title.is_some() {
+        queue!(out, Output(osc::POP_TITLE.to_string()))?;
+    }
+    queue!(out, Show)?;
+    out.flush()?;
+    Ok(session)
+}
+
+// Bots only know how to play on the protocol's board, and need the preview to know what's coming.
+fn launch_bot(command: &str, config: &GameConfig) -> Result<Bot, String> {
+    let text = config.language.strings();
+    if config.board_width != TBP_BOARD_WIDTH || config.board_height > 40 {
+        return Err(text.bot_board.to_string());
+    }
+    if config.mode != Mode::Modern || config.big_mode || config.hold_limit != Some(1) {
+        return Err(text.bot_rules.to_string());
+    }
+    let bot = Bot::launch(command).map_err(|e| format!("{:?}", e))?;
+    println!("{}", fill(text.playing_against, &[&bot.name]));
+    Ok(bot)
+}
+
+// Pass on everything that's happened since the last frame in both directions. Attacks go to the
+// opponents picked by the targeting setting or by hand, and the last one left standing wins. When
+// there's more than one target, attacks are split between them and items take turns. An error
+// means the other players can't be reached any more.
+fn exchange(
+    connection: &mut Connection,
+    session: &mut Session,
+    sent: &mut Option<RemoteBoard>
+) -> IoResult<()> {
+    let Session {
+        ref config,
+        ref mut game,
+        ref mut opponents,
+        ref mut attack_log,
+        target,
+        ref mut split_turn,
+        ..
+    } = *session;
+    for message in connection.receive()? {
+        match message {
+            Message::Player { index, board } => {
+                if let Some(opponent) = connection.opponent_index(index) {
+                    opponents[opponent] = board;
+                }
+            }
+            Message::Attack { lines, .. } => {
+                game.pending_garbage += lines;
+                attack_log.push(Attack::Incoming(lines));
+            }
+            Message::Item { item, .. } => game.receive_item(item),
+            Message::Join { .. }
+            | Message::Hello { .. }
+            | Message::Inputs { .. }
+            | Message::Left { .. } => {}
+        }
+    }
+    if opponents.iter().all(|opponent| opponent.topped_out) {
+        game.win();
+    }
+    let targets = pick_targets(opponents, config.targeting, target)
+        .into_iter()
+        .map(|opponent| connection.player_of(opponent))
+        .collect::<Vec<_>>();
+    if !targets.is_empty() {
+        if game.outgoing_attack > 0 {
+            let shares = split_lines(game.outgoing_attack, targets.len(), *split_turn);
+            *split_turn += 1;
+            for (&target, lines) in targets.iter().zip(shares) {
+                if lines > 0 {
+                    connection.send_to(target, &Message::Attack { lines, target })?;
+                }
+            }
+            attack_log.push(Attack::Sent(game.outgoing_attack));
+        }
+        for item in game.outgoing_items.drain(..) {
+            let target = targets[*split_turn % targets.len()];
+            *split_turn += 1;
+            connection.send_to(target, &Message::Item { item, target })?;
+        }
+    }
+    game.outgoing_attack = 0;
+    game.outgoing_items.clear();
+    let board = RemoteBoard::of(game);
+    if sent.as_ref() != Some(&board) {
+        connection.send_board(board.clone())?;
+        *sent = Some(board);
+    }
+    Ok(())
+}
+
+// With rollback netcode, send what this player has pressed to everyone else and take in what
+// they've pressed, then catch the match up with it. The host tells everyone when a player has
+// left, and which tick they're out of it from, and shows spectators the boards as they're settled.
+fn exchange_inputs(connection: &mut Connection, session: &mut Session) -> IoResult<()> {
+    {
+        let Session {
+            ref mut rollback,
+            ref mut opponents,
+            ..
+        } = *session;
+        let rollback = match *rollback {
+            Some(ref mut rollback) => rollback,
+            None => return Ok(())
+        };
+        for message in connection.receive()? {
+            match message {
+                Message::Inputs {
+                    player,
+                    tick,
+                    actions
+                } => {
+                    for (tick, actions) in (tick..).zip(actions) {
+                        rollback.receive(player, tick, actions);
+                    }
+                }
+                Message::Left { player, tick } => {
+                    rollback.leave(player, tick);
+                    if let Some(opponent) = connection.opponent_index(player) {
+                        opponents[opponent].left = true;
+                    }
+                }
+                // Only the host hears about a player leaving like this.
+                Message::Player { index, board } if board.left => {
+                    let tick = rollback.received_to(index);
+                    connection.broadcast(&Message::Left {
+                        player: index,
+                        tick
+                    })?;
+                    rollback.leave(index, tick);
+                    if let Some(opponent) = connection.opponent_index(index) {
+                        opponents[opponent].left = true;
+                    }
+                }
+                _ => {}
+            }
+        }
+        rollback.catch_up();
+        if let Some((tick, actions)) = rollback.take_unsent() {
+            connection.broadcast(&Message::Inputs {
+                player: connection.player,
+                tick,
+                actions
+            })?;
+        }
+        connection.show_spectators(&rollback.confirmed().games);
+    }
+    session.follow();
+    Ok(())
+}
+
+// Show every board of a hosted game until the host leaves or the spectator quits.
+fn spectate(config: GameConfig, mut connection: Connection) -> Result<(), Box<dyn Error>> {
+    let _screen = AlternateScreen::to_alternate(true)?;
+    let input = input();
+    let mut reader = input.read_async();
+    signals::install();
+    terminal().clear(ClearType::All)?;
+    let stdout = stdout();
+    let mut out = stdout.lock();
+    queue!(out, Hide)?;
+    let color_support = config.color_support.unwrap_or_else(ColorSupport::detect);
+    let mut pacer = FramePacer::new(config.fps_limiter);
+    let mut players = vec![RemoteBoard::new(connection.board_size); connection.players];
+    let mut host_left = false;
+    let mut frame = Vec::new();
+    'watch: loop {
+        if signals::stop_requested() {
+            break 'watch;
+        }
+        for event in &mut reader {
+            if let InputEvent::Keyboard(ref event) = event {
+                if Key::from_event(event).and_then(|key| map_key(&config, &key))
+                    == Some(Action::Quit)
+                {
+                    break 'watch;
+                }
+            }
+        }
+        if !host_left {
+            match connection.receive() {
+                Ok(messages) => {
+                    for message in messages {
+                        if let Message::Player { index, board } = message {
+                            if index < players.len() {
+                                players[index] = board;
+                            }
+                        }
+                    }
+                }
+                Err(_) => host_left = true
+            }
+        }
+        // Players are numbered from 1 for people, the host being player 1.
+        let standing = players.iter().filter(|board| !board.topped_out).count();
+        let status = match players.iter().position(|board| !board.topped_out) {
+            Some(winner) if standing == 1 => format!("PLAYER {} WINS", winner + 1),
+            _ if host_left => "GAME ENDED".to_string(),
+            _ => "SPECTATING".to_string()
+        };
+        frame.clear();
+        let width = connection.board_size.0 as u16 + 4;
+        for (i, board) in players.iter().enumerate() {
+            draw_remote_board(&mut frame, &config, color_support, i as u16 * width, board)?;
+        }
+        queue!(
+            frame,
+            Goto(0, connection.board_size.1 as u16 + 5),
+            Output(format!("{:14}", status))
+        )?;
+        out.write_all(&frame)?;
+        out.flush()?;
+        pacer.wait();
+    }
+    queue!(out, Show)?;
+    out.flush()?;
+    Ok(())
 }
@@ -0,0 +1,215 @@
+#[cfg(unix)]
+use crate::libc;
+use std::fs::{rename, OpenOptions};
+use std::io::{ErrorKind, Result as IoResult, Write};
+#[cfg(unix)]
+use std::os::unix::fs::{FileTypeExt, OpenOptionsExt};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tui_tetris::gameboard::Game;
+
+use crate::net::RemoteBoard;
+
+// How often the game is written out.
+const MIRROR_INTERVAL: Duration = Duration::from_secs(1);
+
+// The state of the game written out every second for streaming overlays and dashboards, from the
+// `mirror_file` setting. A regular file only ever holds the latest state, so it's swapped in whole
+// to keep anything reading it from seeing half of one. A FIFO gets each state in turn with a blank
+// line after it, and is passed over while nothing's reading from it rather than holding up the
+// game.
+pub struct Mirror {
+    path: PathBuf,
+    last: Option<Instant>,
+    // What's left of the last state when the FIFO filled up partway through it, which has to go
+    // before anything else does.
+    unwritten: Vec<u8>,
+    // Only the first failure is logged, since it'd most likely happen every second after.
+    failed: bool
+}
+
+impl Mirror {
+    pub fn new(path: &str) -> Self {
+        Mirror {
+            path: PathBuf::from(path),
+            last: None,
+            unwritten: Vec::new(),
+            failed: false
+        }
+    }
+
+    pub fn update(&mut self, game: &Game, now: Instant) {
+        if self
+            .last
+            .is_some_and(|last| now.duration_since(last) < MIRROR_INTERVAL)
+        {
+            return;
+        }
+        self.last = Some(now);
+        if let Err(e) = self.write(&mirror_text(game)) {
+            if !self.failed {
+                warn!("Failed to write to {}: {}", self.path.display(), e);
+                self.failed = true;
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn write(&mut self, text: &str) -> IoResult<()> {
+        let is_fifo = self
+            .path
+            .metadata()
+            .is_ok_and(|metadata| metadata.file_type().is_fifo());
+        if !is_fifo {
+            return self.replace(text);
+        }
+        let opened = OpenOptions::new()
+            .write(true)
+            .custom_flags(libc::O_NONBLOCK)
+            .open(&self.path);
+        let mut fifo = match opened {
+            // No one's reading, so the rest of a state would only confuse whoever reads next.
+            Err(ref e) if e.raw_os_error() == Some(libc::ENXIO) => {
+                self.unwritten.clear();
+                return Ok(());
+            }
+            opened => opened?
+        };
+        // A reader that's still behind skips this state.
+        if self.flush(&mut fifo)? {
+            self.unwritten = format!("{}\n", text).into_bytes();
+            self.flush(&mut fifo)?;
+        }
+        Ok(())
+    }
+
+    // Write as much of what's unwritten as the FIFO takes, and say whether that was all of it.
+    #[cfg(unix)]
+    fn flush<W: Write>(&mut self, fifo: &mut W) -> IoResult<bool> {
+        while !self.unwritten.is_empty() {
+            match fifo.write(&self.unwritten) {
+                Ok(0) => {
+                    self.unwritten.clear();
+                    return Err(ErrorKind::WriteZero.into());
+                }
+                Ok(written) => {
+                    self.unwritten.drain(..written);
+                }
+                Err(ref e) if e.kind() == ErrorKind::WouldBlock => return Ok(false),
+                Err(ref e) if e.kind() == ErrorKind::Interrupted => {}
+                Err(e) => {
+                    self.unwritten.clear();
+                    return Err(e);
+                }
+            }
+        }
+        Ok(true)
+    }
+
+    #[cfg(not(unix))]
+    fn write(&mut self, text: &str) -> IoResult<()> {
+        self.replace(text)
+    }
+
+    // The file is written next to the mirror under its full name with `.tmp` on the end, so that
+    // it's never the mirror itself.
+    fn replace(&self, text: &str) -> IoResult<()> {
+        let mut temporary = self.path.clone().into_os_string();
+        temporary.push(".tmp");
+        let temporary = PathBuf::from(temporary);
+        OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&temporary)?
+            .write_all(text.as_bytes())?;
+        rename(temporary, &self.path)
+    }
+}
+
+// One line for each number, then the visible board with the piece in play, a letter for each
+// piece's squares, `G` for garbage and `.` for empty.
+pub fn mirror_text(game: &Game) -> String {
+    let seconds = game.stats.seconds() as u64;
+    let mut text = format!(
+        "SCORE {}\nLINES {}\nLEVEL {}\nTIME {}:{:02}\n",
+        game.score,
+        game.lines_cleared,
+        game.level,
+        seconds / 60,
+        seconds % 60
+    );
+    for row in RemoteBoard::of(game).rows {
+        text.push_str(&row);
+        text.push('\n');
+    }
+    text
+}
+
+// The numbers come first and the board after, one line per row.
+#[test]
+fn test_mirror_text() {
+    use tui_tetris::rules::Rules;
+
+    let game = Game::with_seed(Rules::default(), 0);
+    let text = mirror_text(&game);
+    let lines = text.lines().collect::<Vec<_>>();
+    assert_eq!(&lines[..4], ["SCORE 0", "LINES 0", "LEVEL 0", "TIME 0:00"]);
+    assert_eq!(lines.len(), 4 + game.board.height);
+    assert!(lines[4..].iter().all(|row| row.len() == game.board.width));
+}
+
+// A mirror that already ends in .tmp still gets the whole state, written somewhere else first.
+#[test]
+fn test_replace() {
+    use std::env;
+    use std::fs::{read_to_string, remove_file};
+
+    let path = env::temp_dir().join(format!("tui_tetris_mirror_{}.tmp", std::process::id()));
+    let mirror = Mirror::new(path.to_str().unwrap());
+    mirror.replace("SCORE 100\n").unwrap();
+    assert_eq!(read_to_string(&path).unwrap(), "SCORE 100\n");
+    let mut temporary = path.clone().into_os_string();
+    temporary.push(".tmp");
+    assert!(!PathBuf::from(temporary).exists());
+    remove_file(path).unwrap();
+}
+
+// What the FIFO doesn't take is kept and goes out first next time, so states never get cut short.
+#[cfg(unix)]
+#[test]
+fn test_flush() {
+    // Takes up to `room` bytes at a time, and then nothing until it's given more room.
+    struct Pipe {
+        taken: Vec<u8>,
+        room: usize
+    }
+
+    impl Write for Pipe {
+        fn write(&mut self, bytes: &[u8]) -> IoResult<usize> {
+            if self.room == 0 {
+                return Err(ErrorKind::WouldBlock.into());
+            }
+            let taken = bytes.len().min(self.room);
+            self.taken.extend_from_slice(&bytes[..taken]);
+            self.room -= taken;
+            Ok(taken)
+        }
+
+        fn flush(&mut self) -> IoResult<()> {
+            Ok(())
+        }
+    }
+
+    let mut mirror = Mirror::new("unused");
+    let mut pipe = Pipe {
+        taken: Vec::new(),
+        room: 4
+    };
+    mirror.unwritten = b"SCORE 100\n\n".to_vec();
+    assert!(!mirror.flush(&mut pipe).unwrap());
+    assert_eq!(mirror.unwritten, b"E 100\n\n");
+    pipe.room = 100;
+    assert!(mirror.flush(&mut pipe).unwrap());
+    assert_eq!(pipe.taken, b"SCORE 100\n\n");
+}
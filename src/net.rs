@@ -0,0 +1,792 @@
+use crate::locale::{fill, Strings};
+use crate::rand::thread_rng;
+use crate::serde_json;
+use std::io::{BufRead, BufReader, Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+use tui_tetris::action::Action;
+use tui_tetris::gameboard::Game;
+use tui_tetris::item::Item;
+use tui_tetris::rules::{Handicap, Rules};
+use tui_tetris::tetromino::Tetromino;
+use tui_tetris::versus::{self, Targeting};
+
+// Used when `--host` is only given a port.
+const HOST_ADDRESS: &str = "0.0.0.0";
+// How opponent board cells are sent. Pieces are sent as their letter so that each player sees the
+// other's board in their own colors.
+const EMPTY_CELL: char = '.';
+const GARBAGE_CELL: char = 'G';
+// How long anyone connecting to the host gets to say who they are, and how long a write to a
+// spectator can take before they're dropped.
+const JOIN_TIMEOUT: Duration = Duration::from_secs(1);
+const SPECTATOR_WRITE_TIMEOUT: Duration = Duration::from_millis(100);
+// How many can play in one hosted game, the host included.
+pub const MAX_PLAYERS: usize = 4;
+
+// Which end of a versus game this is, from `--host`, `--connect`, or `--spectate`.
+#[derive(Clone)]
+pub enum NetRole {
+    Host(String),
+    Connect(String),
+    Spectate(String)
+}
+
+// Everything sent between the players and to spectators, one JSON object per line. Players are
+// numbered from 0, the host, in the order they joined. Everything goes through the host, which
+// passes boards on to everyone and attacks on to whoever they're aimed at.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Message {
+    // The first thing sent to the host by anyone connecting.
+    Join {
+        spectator: bool,
+        #[serde(default)]
+        setup: Option<Setup>
+    },
+    // Sent by the host once every player has connected, so that all the games deal the same
+    // pieces onto the same size of board. `player` is the number of whoever it's sent to.
+    Hello {
+        seed: u64,
+        board_width: usize,
+        board_height: usize,
+        player: usize,
+        players: usize,
+        // Set when the game is played with rollback netcode.
+        #[serde(default)]
+        rollback: Option<MatchSetup>
+    },
+    // A player's board, whenever it changes. Topping out is sent as a board that's topped out.
+    Player {
+        index: usize,
+        board: RemoteBoard
+    },
+    Attack {
+        lines: u64,
+        target: usize
+    },
+    // An item used on another player in a game with items.
+    Item {
+        item: Item,
+        target: usize
+    },
+    // With rollback netcode, what a player pressed on each tick from `tick` on. These go to every
+    // other player instead of boards and attacks, since each of them plays out everyone's game.
+    Inputs {
+        player: usize,
+        tick: u64,
+        actions: Vec<Vec<Action>>
+    },
+    // Sent by the host when a player leaves a game with rollback netcode, with the tick they're out
+    // of it from, so that they leave everyone's copy of the game at the same time.
+    Left {
+        player: usize,
+        tick: u64
+    }
+}
+
+// What everyone else needs to know about a player to play out their game for themselves.
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Setup {
+    pub(crate) handicap: Handicap,
+    pub(crate) targeting: Targeting
+}
+
+impl Default for Setup {
+    fn default() -> Self {
+        Setup {
+            handicap: Handicap::default(),
+            targeting: Targeting::Random
+        }
+    }
+}
+
+// How a game with rollback netcode is played: by the host's rules, with each player's own
+// handicap and targeting, by number.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MatchSetup {
+    pub(crate) rules: Rules,
+    pub(crate) players: Vec<Setup>
+}
+
+impl MatchSetup {
+    // Everyone's rules and targeting, for starting the match.
+    pub fn players(&self) -> Vec<(Rules, Targeting)> {
+        self.players
+            .iter()
+            .map(|setup| {
+                let rules = Rules {
+                    handicap: setup.handicap,
+                    ..self.rules.clone()
+                };
+                (rules, setup.targeting)
+            })
+            .collect()
+    }
+}
+
+// A connection to the other players through the host, or for the host, to each of them. For
+// spectators it's to the host. Messages are read, and spectators let in and written to, on their own
+// threads so that the game loop never waits on the network.
+pub struct Connection {
+    // This player's number and how many there are in the game.
+    pub(crate) player: usize,
+    pub(crate) players: usize,
+    pub(crate) seed: u64,
+    pub(crate) board_size: (usize, usize),
+    pub(crate) rollback: Option<MatchSetup>,
+    // The host has a stream to each of the other players by number, and everyone else only has
+    // one to the host. A player's stream is dropped once they've gone.
+    streams: Vec<Option<TcpStream>>,
+    // What's been read from each stream, with the number of the stream it came from. `None` means
+    // the stream has closed.
+    incoming: Receiver<(usize, Option<Message>)>,
+    // Only the host has these, since it keeps letting spectators in for the whole game. Spectators
+    // come through `joined` once they've said they're watching, and are then sent lines through
+    // their writer threads.
+    joined: Option<Receiver<TcpStream>>,
+    spectators: Vec<Sender<String>>,
+    // The latest board from each player, for catching up spectators who join late.
+    boards: Vec<Option<RemoteBoard>>
+}
+
+impl Connection {
+    // Wait for the other players to connect, then tell them all how the game is set up. Spectators
+    // who turn up first are let in as well. The game is played with rollback netcode by `rules`
+    // if they're given.
+    pub fn host(
+        address: &str,
+        seed: u64,
+        board_size: (usize, usize),
+        players: usize,
+        setup: Setup,
+        rules: Option<Rules>,
+        text: &Strings
+    ) -> IoResult<Self> {
+        let address = if address.contains(':') {
+            address.to_string()
+        } else {
+            format!("{}:{}", HOST_ADDRESS, address)
+        };
+        let listener = TcpListener::bind(&address)?;
+        if players == 2 {
+            println!(
+                "{}",
+                fill(text.waiting_for_opponent, &[&listener.local_addr()?])
+            );
+        } else {
+            println!(
+                "{}",
+                fill(
+                    text.waiting_for_opponents,
+                    &[&(players - 1), &listener.local_addr()?]
+                )
+            );
+        }
+        let mut spectators = Vec::new();
+        let mut streams = vec![None];
+        let mut setups = vec![setup];
+        while streams.len() < players {
+            let (stream, _) = listener.accept()?;
+            match read_join(&stream) {
+                // Spectators are only told how the game is set up once everyone's there.
+                Ok((true, _)) => {
+                    if prepare_spectator(&stream).is_ok() {
+                        println!("{}", text.spectator_joined);
+                        spectators.push(write_on(stream));
+                    }
+                }
+                Ok((false, setup)) => {
+                    streams.push(Some(stream));
+                    setups.push(setup.unwrap_or_default());
+                    if streams.len() < players {
+                        println!("{}", fill(text.player_joined, &[&streams.len()]));
+                    }
+                }
+                // Not something that knows how to play.
+                Err(_) => {}
+            }
+        }
+        let rollback = rules.map(|rules| MatchSetup {
+            rules,
+            players: setups
+        });
+        let hello = |player| Message::Hello {
+            seed,
+            board_width: board_size.0,
+            board_height: board_size.1,
+            player,
+            players,
+            rollback: rollback.clone()
+        };
+        for (player, stream) in streams.iter_mut().enumerate() {
+            if let Some(ref mut stream) = *stream {
+                send_on(stream, &hello(player))?;
+            }
+        }
+        let line = to_line(&hello(0))?;
+        spectators.retain(|spectator| spectator.send(line.clone()).is_ok());
+        let mut connection = Connection::start(streams, 0, players, seed, board_size, rollback)?;
+        connection.joined = Some(accept_on(listener));
+        connection.spectators = spectators;
+        Ok(connection)
+    }
+
+    pub fn connect(address: &str, setup: Setup, text: &Strings) -> IoResult<Self> {
+        Connection::join(address, Some(setup), text)
+    }
+
+    // Watch a hosted game without playing in it.
+    pub fn spectate(address: &str, text: &Strings) -> IoResult<Self> {
+        Connection::join(address, None, text)
+    }
+
+    // Only players have a setup to send.
+    fn join(address: &str, setup: Option<Setup>, text: &Strings) -> IoResult<Self> {
+        let spectator = setup.is_none();
+        let mut stream = TcpStream::connect(address)?;
+        send_on(&mut stream, &Message::Join { spectator, setup })?;
+        if !spectator {
+            println!("{}", text.waiting_for_host);
+        }
+        let mut line = String::new();
+        BufReader::new(stream.try_clone()?).read_line(&mut line)?;
+        match serde_json::from_str(&line)? {
+            Message::Hello {
+                seed,
+                board_width,
+                board_height,
+                player,
+                players,
+                rollback
+            } => Connection::start(
+                vec![Some(stream)],
+                player,
+                players,
+                seed,
+                (board_width, board_height),
+                rollback
+            ),
+            _ => Err(IoError::new(
+                ErrorKind::InvalidData,
+                "The host didn't start the game"
+            ))
+        }
+    }
+
+    fn start(
+        streams: Vec<Option<TcpStream>>,
+        player: usize,
+        players: usize,
+        seed: u64,
+        board_size: (usize, usize),
+        rollback: Option<MatchSetup>
+    ) -> IoResult<Self> {
+        let (sender, incoming) = channel();
+        for (index, stream) in streams.iter().enumerate() {
+            if let Some(ref stream) = *stream {
+                stream.set_nodelay(true)?;
+                read_on(stream.try_clone()?, index, sender.clone());
+            }
+        }
+        Ok(Connection {
+            player,
+            players,
+            seed,
+            board_size,
+            rollback,
+            streams,
+            incoming,
+            joined: None,
+            spectators: Vec::new(),
+            boards: vec![None; players]
+        })
+    }
+
+    fn is_host(&self) -> bool {
+        self.joined.is_some()
+    }
+
+    // Where another player's board goes among this player's opponents, which leave this player
+    // out, and the other way around.
+    pub fn opponent_index(&self, player: usize) -> Option<usize> {
+        if player < self.player {
+            Some(player)
+        } else if player > self.player && player < self.players {
+            Some(player - 1)
+        } else {
+            None
+        }
+    }
+
+    pub fn player_of(&self, opponent: usize) -> usize {
+        player_of(self.player, opponent)
+    }
+
+    // This player's board, for everyone else. The host sends it to each of them, and everyone
+    // else sends it to the host to be passed on.
+    pub fn send_board(&mut self, board: RemoteBoard) -> IoResult<()> {
+        let player = self.player;
+        if self.is_host() {
+            self.share(player, board);
+            Ok(())
+        } else {
+            self.send_to_host(&Message::Player {
+                index: player,
+                board
+            })
+        }
+    }
+
+    // Send something to one player, by way of the host unless this is it.
+    pub fn send_to(&mut self, target: usize, message: &Message) -> IoResult<()> {
+        if self.is_host() {
+            self.send_to_player(target, message);
+            Ok(())
+        } else {
+            self.send_to_host(message)
+        }
+    }
+
+    fn send_to_host(&mut self, message: &Message) -> IoResult<()> {
+        match self.streams[0] {
+            Some(ref mut stream) => send_on(stream, message),
+            None => Ok(())
+        }
+    }
+
+    // The host doesn't give up on the game when one player can't be reached. Their stream closing
+    // is noticed when it's next read from.
+    fn send_to_player(&mut self, player: usize, message: &Message) {
+        if let Some(&mut Some(ref mut stream)) = self.streams.get_mut(player) {
+            let _ = send_on(stream, message);
+        }
+    }
+
+    // Pass a player's board on to every other player and everyone watching, dropping any
+    // spectators that can't keep up.
+    fn share(&mut self, index: usize, board: RemoteBoard) {
+        let message = Message::Player { index, board };
+        for player in 1..self.streams.len() {
+            if player != index {
+                self.send_to_player(player, &message);
+            }
+        }
+        self.show(&message);
+        if let Message::Player { board, .. } = message {
+            self.boards[index] = Some(board);
+        }
+    }
+
+    // With rollback netcode every player plays out everyone's game for themselves, so boards only
+    // go to spectators, from the host, and only once they've changed.
+    pub fn show_spectators(&mut self, games: &[Game]) {
+        if !self.is_host() {
+            return;
+        }
+        for (index, game) in games.iter().enumerate() {
+            let board = RemoteBoard::of(game);
+            if self.boards[index].as_ref() == Some(&board) {
+                continue;
+            }
+            let message = Message::Player { index, board };
+            self.show(&message);
+            if let Message::Player { board, .. } = message {
+                self.boards[index] = Some(board);
+            }
+        }
+    }
+
+    // Send something to every other player, by way of the host unless this is it.
+    pub fn broadcast(&mut self, message: &Message) -> IoResult<()> {
+        if !self.is_host() {
+            return self.send_to_host(message);
+        }
+        for player in 1..self.streams.len() {
+            self.send_to_player(player, message);
+        }
+        Ok(())
+    }
+
+    // Send something to everyone watching, dropping any whose writer thread has given up on them.
+    fn show(&mut self, message: &Message) {
+        if let Ok(line) = to_line(message) {
+            self.spectators
+                .retain(|spectator| spectator.send(line.clone()).is_ok());
+        }
+    }
+
+    // Catch up the spectators who've joined since the last call with how the game is set up and
+    // everyone's latest board.
+    fn accept_spectators(&mut self) {
+        let joined = match self.joined {
+            Some(ref joined) => joined.try_iter().collect::<Vec<_>>(),
+            None => return
+        };
+        if joined.is_empty() {
+            return;
+        }
+        let hello = Message::Hello {
+            seed: self.seed,
+            board_width: self.board_size.0,
+            board_height: self.board_size.1,
+            player: 0,
+            players: self.players,
+            rollback: self.rollback.clone()
+        };
+        let mut lines = vec![hello];
+        for (index, board) in self.boards.iter().enumerate() {
+            if let Some(ref board) = *board {
+                lines.push(Message::Player {
+                    index,
+                    board: board.clone()
+                });
+            }
+        }
+        let lines = match lines.iter().map(to_line).collect::<IoResult<Vec<_>>>() {
+            Ok(lines) => lines,
+            Err(_) => return
+        };
+        for stream in joined {
+            let spectator = write_on(stream);
+            if lines
+                .iter()
+                .all(|line| spectator.send(line.clone()).is_ok())
+            {
+                self.spectators.push(spectator);
+            }
+        }
+    }
+
+    // Everything for this player that's arrived since the last call. The host passes on whatever
+    // is for someone else as it goes, and a player leaving comes through as their board topping
+    // out. An error means the host has gone.
+    pub fn receive(&mut self) -> IoResult<Vec<Message>> {
+        self.accept_spectators();
+        let mut messages = Vec::new();
+        while let Ok((from, message)) = self.incoming.try_recv() {
+            if !self.is_host() {
+                match message {
+                    Some(message) => messages.push(message),
+                    None => {
+                        return Err(IoError::new(
+                            ErrorKind::ConnectionAborted,
+                            "The host disconnected"
+                        ))
+                    }
+                }
+                continue;
+            }
+            match message {
+                // Players can only send their own boards.
+                Some(Message::Player { board, .. }) => {
+                    self.share(from, board.clone());
+                    messages.push(Message::Player { index: from, board });
+                }
+                // The same goes for inputs, which go to everyone else.
+                Some(Message::Inputs { tick, actions, .. }) => {
+                    let message = Message::Inputs {
+                        player: from,
+                        tick,
+                        actions
+                    };
+                    for player in 1..self.streams.len() {
+                        if player != from {
+                            self.send_to_player(player, &message);
+                        }
+                    }
+                    messages.push(message);
+                }
+                Some(Message::Attack { target, .. }) | Some(Message::Item { target, .. })
+                    if target != self.player =>
+                {
+                    if let Some(message) = message {
+                        self.send_to_player(target, &message);
+                    }
+                }
+                Some(message) => messages.push(message),
+                None => {
+                    self.streams[from] = None;
+                    let mut board = self.boards[from]
+                        .clone()
+                        .unwrap_or_else(|| RemoteBoard::new(self.board_size));
+                    board.topped_out = true;
+                    self.share(from, board.clone());
+                    board.left = true;
+                    messages.push(Message::Player { index: from, board });
+                }
+            }
+        }
+        Ok(messages)
+    }
+}
+
+// Read messages from a stream on a thread of its own until it closes. Anything unreadable is
+// treated the same as the other end leaving.
+fn read_on(stream: TcpStream, index: usize, sender: Sender<(usize, Option<Message>)>) {
+    let reader = BufReader::new(stream);
+    thread::spawn(move || {
+        for line in reader.lines() {
+            match line.ok().and_then(|line| serde_json::from_str(&line).ok()) {
+                Some(message) => {
+                    if sender.send((index, Some(message))).is_err() {
+                        return;
+                    }
+                }
+                None => break
+            }
+        }
+        let _ = sender.send((index, None));
+    });
+}
+
+// Let spectators in for as long as the game goes on, handing each one to the game loop once they've
+// said they're watching. Each of them is heard from on a thread of its own, so that anyone who
+// connects and says nothing only holds up themselves. Anyone else who wants to play is turned away
+// since the game has already started.
+fn accept_on(listener: TcpListener) -> Receiver<TcpStream> {
+    let (sender, joined) = channel();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let stream = match stream {
+                Ok(stream) => stream,
+                Err(_) => continue
+            };
+            let sender = sender.clone();
+            thread::spawn(move || {
+                let spectator = read_join(&stream).is_ok_and(|(spectator, _)| spectator);
+                if spectator && prepare_spectator(&stream).is_ok() {
+                    let _ = sender.send(stream);
+                }
+            });
+        }
+    });
+    joined
+}
+
+// Write lines to a spectator on a thread of its own, so that one who can't keep up never holds up
+// the game. The thread gives up on them, and so drops the receiving end, once a write fails.
+fn write_on(mut stream: TcpStream) -> Sender<String> {
+    let (sender, lines) = channel::<String>();
+    thread::spawn(move || {
+        for line in lines {
+            if stream.write_all(line.as_bytes()).is_err() {
+                return;
+            }
+        }
+    });
+    sender
+}
+
+// Find out whether whoever just connected wants to play or watch, and how they play.
+fn read_join(stream: &TcpStream) -> IoResult<(bool, Option<Setup>)> {
+    stream.set_read_timeout(Some(JOIN_TIMEOUT))?;
+    let mut line = String::new();
+    BufReader::new(stream.try_clone()?).read_line(&mut line)?;
+    stream.set_read_timeout(None)?;
+    match serde_json::from_str(&line)? {
+        Message::Join { spectator, setup } => Ok((spectator, setup)),
+        _ => Err(IoError::new(
+            ErrorKind::InvalidData,
+            "Expected a join message"
+        ))
+    }
+}
+
+fn prepare_spectator(stream: &TcpStream) -> IoResult<()> {
+    stream.set_nodelay(true)?;
+    stream.set_write_timeout(Some(SPECTATOR_WRITE_TIMEOUT))
+}
+
+fn send_on(stream: &mut TcpStream, message: &Message) -> IoResult<()> {
+    stream.write_all(to_line(message)?.as_bytes())
+}
+
+// A message as it's sent, on a line of its own.
+fn to_line(message: &Message) -> IoResult<String> {
+    let mut line = serde_json::to_string(message)?;
+    line.push('\n');
+    Ok(line)
+}
+
+// What's known about another player's game, for drawing it beside this one or for spectators.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct RemoteBoard {
+    pub(crate) rows: Vec<String>,
+    pub(crate) score: u64,
+    pub(crate) lines: usize,
+    // Rows from the floor to the top of the stack, not counting the piece in play.
+    #[serde(default)]
+    pub(crate) stack_height: usize,
+    pub(crate) topped_out: bool,
+    #[serde(skip)]
+    pub(crate) left: bool
+}
+
+impl RemoteBoard {
+    pub fn new(board_size: (usize, usize)) -> Self {
+        RemoteBoard {
+            rows: vec![EMPTY_CELL.to_string().repeat(board_size.0); board_size.1],
+            score: 0,
+            lines: 0,
+            stack_height: 0,
+            topped_out: false,
+            left: false
+        }
+    }
+
+    // This game as the other player and spectators see it. Winning also ends the game, so only
+    // topping out while the opponent is still going counts.
+    pub fn of(game: &Game) -> Self {
+        RemoteBoard {
+            rows: board_rows(game),
+            score: game.score,
+            lines: game.lines_cleared,
+            stack_height: game.board.stack_height(),
+            topped_out: game.game_over && !game.won,
+            left: false
+        }
+    }
+
+    // What's in each cell of a sent board: `None` for empty, `Some(None)` for garbage.
+    pub fn cell(&self, x: usize, y: usize) -> Option<Option<Tetromino>> {
+        match self.rows.get(y).and_then(|row| row.chars().nth(x)) {
+            None | Some(EMPTY_CELL) => None,
+            Some(GARBAGE_CELL) => Some(None),
+            Some(letter) => Some(Tetromino::from_letter(letter))
+        }
+    }
+}
+
+// Which player an opponent is, for a player who's `player` themselves. The opponents are everyone
+// else in order.
+pub fn player_of(player: usize, opponent: usize) -> usize {
+    if opponent < player {
+        opponent
+    } else {
+        opponent + 1
+    }
+}
+
+// Who an attack goes to among the opponents still in the game, going by their boards. An
+// opponent picked with `switch_target` gets everything while they're standing, and otherwise
+// `targeting` decides.
+pub fn pick_targets(
+    opponents: &[RemoteBoard],
+    targeting: Targeting,
+    picked: Option<usize>
+) -> Vec<usize> {
+    let standing = (0..opponents.len())
+        .filter(|&i| !opponents[i].topped_out)
+        .collect::<Vec<_>>();
+    versus::pick_targets(
+        &standing,
+        targeting,
+        picked,
+        |i| opponents[i].score,
+        |i| opponents[i].stack_height,
+        &mut thread_rng()
+    )
+}
+
+// This game's board with the active piece on it if it's in play, as sent to the other players.
+fn board_rows(game: &Game) -> Vec<String> {
+    let active = if game.piece_in_play() {
+        game.active_piece.cells()
+    } else {
+        Vec::new()
+    };
+    (0..game.board.height)
+        .map(|y| {
+            (0..game.board.width)
+                .map(|x| {
+                    if active.contains(&(x as isize, y as isize)) {
+                        game.active_piece.tetromino.letter()
+                    } else {
+                        match game.board.get(x, y) {
+                            Some(cell) => cell.tetromino.map_or(GARBAGE_CELL, Tetromino::letter),
+                            None => EMPTY_CELL
+                        }
+                    }
+                })
+                .collect()
+        })
+        .collect()
+}
+
+// Boards should come through with the same pieces in the same places.
+#[test]
+fn test_board_rows() {
+    use tui_tetris::rules::Rules;
+    let game = Game::with_seed(Rules::default(), 0);
+    let mut opponent = RemoteBoard::new((game.board.width, game.board.height));
+    opponent.rows = board_rows(&game);
+    // Only the part of the piece below the hidden rows is sent.
+    for &(x, y) in game.active_piece.cells().iter().filter(|&&(_, y)| y >= 0) {
+        assert_eq!(
+            opponent.cell(x as usize, y as usize),
+            Some(Some(game.active_piece.tetromino))
+        );
+    }
+    assert_eq!(opponent.cell(0, game.board.height - 1), None);
+}
+
+// Attacks only ever go to opponents who are still in the game, and a picked target comes before
+// the targeting setting.
+#[test]
+fn test_pick_targets() {
+    let mut opponents = vec![RemoteBoard::new((10, 20)); 3];
+    opponents[0].topped_out = true;
+    opponents[2].topped_out = true;
+    for _ in 0..20 {
+        assert_eq!(pick_targets(&opponents, Targeting::Random, None), [1]);
+    }
+    opponents[1].topped_out = true;
+    assert!(pick_targets(&opponents, Targeting::Random, None).is_empty());
+    let mut opponents = vec![RemoteBoard::new((10, 20)); 3];
+    opponents[0].score = 500;
+    opponents[1].stack_height = 12;
+    opponents[2].score = 500;
+    opponents[2].stack_height = 12;
+    assert_eq!(pick_targets(&opponents, Targeting::Leader, None), [0]);
+    assert_eq!(pick_targets(&opponents, Targeting::Danger, None), [1]);
+    assert_eq!(pick_targets(&opponents, Targeting::Even, None), [0, 1, 2]);
+    assert_eq!(pick_targets(&opponents, Targeting::Even, Some(2)), [2]);
+    opponents[2].topped_out = true;
+    assert_eq!(pick_targets(&opponents, Targeting::Leader, Some(2)), [0]);
+}
+
+// Someone who connects to the host and never says who they are doesn't hold up the game, and a
+// spectator who joins after them is still let in and told how the game is set up.
+#[test]
+fn test_silent_connection() {
+    use std::time::Instant;
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let address = listener.local_addr().unwrap();
+    let mut host = Connection::start(vec![None], 0, 2, 7, (10, 20), None).unwrap();
+    host.joined = Some(accept_on(listener));
+    let _silent = TcpStream::connect(address).unwrap();
+    let mut spectator = TcpStream::connect(address).unwrap();
+    send_on(
+        &mut spectator,
+        &Message::Join {
+            spectator: true,
+            setup: None
+        }
+    )
+    .unwrap();
+    let started = Instant::now();
+    while host.spectators.is_empty() {
+        host.receive().unwrap();
+        assert!(started.elapsed() < JOIN_TIMEOUT / 2);
+    }
+    let mut line = String::new();
+    BufReader::new(spectator).read_line(&mut line).unwrap();
+    match serde_json::from_str(&line).unwrap() {
+        Message::Hello { seed, .. } => assert_eq!(seed, 7),
+        _ => panic!("Expected a hello message")
+    }
+}
@@ -0,0 +1,74 @@
+use tui_tetris::gameboard::Game;
+
+use crate::locale::{fill, Strings};
+use crate::records::Measure;
+use crate::session::{Session, Splits};
+
+// xterm's title stack, for putting back whatever the title was before the game changed it.
+// Terminals without one ignore these, the same as they do the operating system commands below.
+pub const PUSH_TITLE: &str = "\x1B[22;0t";
+pub const POP_TITLE: &str = "\x1B[23;0t";
+
+// Control characters would end the command early.
+fn printable(text: &str) -> String {
+    text.chars().filter(|c| !c.is_control()).collect()
+}
+
+pub fn title(title: &str) -> String {
+    format!("\x1B]2;{}\x07", printable(title))
+}
+
+// A desktop notification, for terminals that show OSC 9 as one.
+pub fn notification(message: &str) -> String {
+    format!("\x1B]9;{}\x07", printable(message))
+}
+
+pub fn game_title(game: &Game, text: &Strings) -> String {
+    fill(text.window_title, &[&Splits::course(game), &game.score])
+}
+
+// What's worth a notification once a game is over: a new personal best, or else a completed
+// challenge. Games that don't count towards personal bests only get the second.
+pub fn milestone(session: &Session) -> Option<String> {
+    let (game, text) = (&session.game, session.config.language.strings());
+    let course = Splits::course(game);
+    let measure = Measure::of(game);
+    let counts = !game.practice && !game.versus;
+    let best = match (measure.value(game), session.personal_best) {
+        (Some(value), Some(best)) => measure.beats(value, best),
+        (Some(_), None) => true,
+        (None, _) => false
+    };
+    if counts && best {
+        Some(fill(text.best_notification, &[&course]))
+    } else if game.won && game.challenge.is_some() {
+        Some(fill(text.completed_notification, &[&course]))
+    } else {
+        None
+    }
+}
+
+// Titles and notifications can't be cut short by what's in them.
+#[test]
+fn test_osc() {
+    assert_eq!(title("a\x07b"), "\x1B]2;ab\x07");
+    assert_eq!(notification("New\nbest"), "\x1B]9;Newbest\x07");
+}
+
+// Notifications are in the language the game is shown in.
+#[test]
+fn test_milestone() {
+    use crate::game_config::GameConfig;
+    use crate::locale::Language;
+
+    let mut config = GameConfig::default();
+    config.language = Language::Spanish;
+    let game = Game::new(config.rules());
+    let mut session = Session::new(config, game);
+    session.game.game_over = true;
+    let course = Splits::course(&session.game);
+    assert_eq!(
+        milestone(&session),
+        Some(format!("Nuevo récord personal en {}", course))
+    );
+}
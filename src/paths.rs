@@ -0,0 +1,24 @@
+use crate::dirs;
+use std::path::PathBuf;
+
+// Name of the subdirectory used inside each of the platform directories.
+const APP_DIR: &str = "tui_tetris";
+
+// Where the config file and themes live, e.g. `$XDG_CONFIG_HOME/tui_tetris` on Linux or
+// `~/Library/Application Support/tui_tetris` on macOS.
+pub fn config_dir() -> Option<PathBuf> {
+    dirs::config_dir().map(|dir| dir.join(APP_DIR))
+}
+
+// Where saved data like stats goes, e.g. `$XDG_DATA_HOME/tui_tetris` on Linux.
+pub fn data_dir() -> Option<PathBuf> {
+    dirs::data_dir().map(|dir| dir.join(APP_DIR))
+}
+
+// Directories to look for config files in, highest priority first. A file in the current
+// directory overrides the user's usual one.
+pub fn config_search_dirs() -> Vec<PathBuf> {
+    let mut search_dirs = vec![PathBuf::from(".")];
+    search_dirs.extend(config_dir());
+    search_dirs
+}
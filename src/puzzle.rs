@@ -0,0 +1,192 @@
+use crate::challenge::{Challenge, Goal, Limit};
+use crate::fumen::{piece_from_letter, Position, FIELD_WIDTH};
+use crate::gameboard::Cell;
+use crate::tetromino::Tetromino;
+
+// A board to solve with a set queue, e.g. "perfect clear with these 5 pieces". Custom ones are
+// written in TOML with the same layout, with `goal` as a table. Like fumen positions, puzzles are
+// always 10 wide.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Puzzle {
+    pub name: String,
+    #[serde(default)]
+    pub description: String,
+    // Rows from the top down, with `.` for empty squares, piece letters for squares in that
+    // piece's color, and anything else for garbage.
+    pub board: Vec<String>,
+    // Every piece there is to solve it with, in order, as letters.
+    pub queue: String,
+    #[serde(default)]
+    pub hold: Option<Tetromino>,
+    pub goal: Goal
+}
+
+impl Puzzle {
+    fn new(name: &str, description: &str, board: &[&str], queue: &str, goal: Goal) -> Self {
+        Puzzle {
+            name: name.to_string(),
+            description: description.to_string(),
+            board: board.iter().map(|row| row.to_string()).collect(),
+            queue: queue.to_string(),
+            hold: None,
+            goal
+        }
+    }
+
+    // The board and queue to start the game from.
+    pub fn position(&self) -> Result<Position, String> {
+        let mut rows = Vec::with_capacity(self.board.len());
+        for line in self.board.iter().rev() {
+            if line.chars().count() != FIELD_WIDTH {
+                return Err(format!("Puzzle rows have to be 10 wide, not \"{}\".", line));
+            }
+            let mut row = [None; FIELD_WIDTH];
+            for (cell, square) in row.iter_mut().zip(line.chars()) {
+                *cell = match square {
+                    '.' => None,
+                    letter => Some(Cell {
+                        tetromino: piece_from_letter(letter),
+                        item: None
+                    })
+                };
+            }
+            rows.push(row);
+        }
+        let queue = self
+            .queue
+            .chars()
+            .map(|letter| {
+                piece_from_letter(letter)
+                    .ok_or_else(|| format!("'{}' in the queue isn't a piece.", letter))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        if queue.is_empty() {
+            return Err("Puzzles need at least one piece in the queue.".to_string());
+        }
+        Ok(Position {
+            rows,
+            hold: self.hold,
+            queue
+        })
+    }
+
+    // The puzzle is solved by reaching the goal, and failed once the queue is used up without
+    // reaching it.
+    pub fn challenge(&self) -> Challenge {
+        Challenge {
+            name: self.name.clone(),
+            description: self.description.clone(),
+            goal: self.goal.clone(),
+            limit: Limit {
+                pieces: Some(self.queue.chars().count() as u64),
+                ..Limit::default()
+            }
+        }
+    }
+}
+
+// The puzzles that come with the game.
+pub fn built_in() -> [Puzzle; 2] {
+    [
+        Puzzle::new(
+            "first-pc",
+            "Perfect clear with two O pieces",
+            &["XXXXXX....", "XXXXXX...."],
+            "OO",
+            Goal {
+                perfect_clear: true,
+                ..Goal::default()
+            }
+        ),
+        Puzzle::new(
+            "tetris-ready",
+            "Clear a Tetris, then another",
+            &[
+                "XXXXXXXXX.",
+                "XXXXXXXXX.",
+                "XXXXXXXXX.",
+                "XXXXXXXXX.",
+                "XXXXXXXXX.",
+                "XXXXXXXXX.",
+                "XXXXXXXXX.",
+                "XXXXXXXXX."
+            ],
+            "II",
+            Goal {
+                tetrises: Some(2),
+                ..Goal::default()
+            }
+        )
+    ]
+}
+
+// Openers for the trainer: an empty board, the same bags every attempt, and what the opener sets up
+// as the goal.
+pub fn openers() -> [Puzzle; 3] {
+    [
+        Puzzle::new(
+            "tki",
+            "TKI-3, into a T-spin double or better",
+            &[],
+            "ILJOSZTJZLOSIT",
+            Goal {
+                lines: Some(2),
+                t_spins: Some(1),
+                ..Goal::default()
+            }
+        ),
+        Puzzle::new(
+            "pco",
+            "Perfect clear opener",
+            &[],
+            "ILJOSZTSZTIOJL",
+            Goal {
+                perfect_clear: true,
+                ..Goal::default()
+            }
+        ),
+        Puzzle::new(
+            "dt-cannon",
+            "DT cannon, for a T-spin double then a triple",
+            &[],
+            "ILJOSZTTSZIOJLJLTOISZ",
+            Goal {
+                lines: Some(5),
+                t_spins: Some(2),
+                ..Goal::default()
+            }
+        )
+    ]
+}
+
+// Solving the first built-in puzzle takes both pieces, and the puzzle is failed if they're placed
+// anywhere else.
+#[test]
+fn test_puzzle_solution() {
+    use crate::action::Action;
+    use crate::challenge::Outcome;
+    use crate::gameboard::Game;
+    use crate::rules::Rules;
+
+    let puzzle = &built_in()[0];
+    let play = |moves: &[isize]| {
+        let mut game = Game::with_seed(Rules::default(), 0);
+        game.start_from(puzzle.position().unwrap()).unwrap();
+        game.challenge = Some(puzzle.challenge());
+        for &dx in moves {
+            let action = if dx > 0 {
+                Action::MoveRight
+            } else {
+                Action::MoveLeft
+            };
+            for _ in 0..dx.abs() {
+                game.handle_action(action);
+            }
+            game.handle_action(Action::HardDrop);
+        }
+        puzzle.challenge().check(&game)
+    };
+    assert_eq!(play(&[2, 4]), Outcome::Succeeded);
+    assert_eq!(play(&[2, -4]), Outcome::Failed);
+}
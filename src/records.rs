@@ -0,0 +1,775 @@
+use crate::serde_json;
+use crate::toml;
+use crossterm::{Goto, Output, Result as CrosstermResult};
+use std::collections::BTreeMap;
+use std::fs::{create_dir_all, read_to_string, File, OpenOptions};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tui_tetris::gameboard::Game;
+use tui_tetris::replay::Replay;
+use tui_tetris::timing::TICK_RATE;
+
+use crate::color::ColorSupport;
+use crate::game_config::{GameConfig, StatsExport};
+use crate::input::{map_menu_key, Key, KeyCode, KeyModifiers, MenuAction};
+use crate::locale::{field, fill, Strings};
+use crate::net::RemoteBoard;
+use crate::paths;
+use crate::session::Splits;
+
+const CSV_HEADER: &str = "timestamp,mode,score,lines,duration,pps,finesse,seed";
+const LIFETIME_FILE: &str = "lifetime_stats.toml";
+const HIGH_SCORES_FILE: &str = "high_scores.toml";
+const SPLITS_FILE: &str = "splits.toml";
+const PERSONAL_BESTS_FILE: &str = "personal_bests.toml";
+const GHOSTS_DIR: &str = "ghosts";
+const REPLAYS_DIR: &str = "replays";
+// How many scores are kept for each mode, and how long the names with them can be.
+const HIGH_SCORE_COUNT: usize = 10;
+const NAME_LENGTH: usize = 8;
+// What the up and down arrows step through when entering a name.
+const NAME_CHARACTERS: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789";
+// Every line of the statistics screen is padded to this so that it covers the game panel.
+const SCREEN_WIDTH: usize = 32;
+
+// One finished game, as written to the stats file. Durations are in seconds, finesse is the
+// percentage of pieces placed with as few inputs as possible, and the seed is written the way
+// `--seed` takes it.
+#[derive(Serialize)]
+pub struct GameRecord {
+    timestamp: u64,
+    mode: String,
+    score: u64,
+    lines: usize,
+    duration: f64,
+    pps: f64,
+    finesse: Option<f64>,
+    seed: String
+}
+
+impl GameRecord {
+    pub fn new(game: &Game) -> Self {
+        let round = |value: f64| (value * 100.0).round() / 100.0;
+        GameRecord {
+            timestamp: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |since| since.as_secs()),
+            mode: game.rules.mode.to_string(),
+            score: game.score,
+            lines: game.lines_cleared,
+            duration: round(game.stats.seconds()),
+            pps: round(game.stats.pieces_per_second()),
+            finesse: game.stats.finesse_percent().map(round),
+            seed: game.seed_text()
+        }
+    }
+
+    fn csv_line(&self) -> String {
+        format!(
+            "{},{},{},{},{},{},{},{}",
+            self.timestamp,
+            self.mode,
+            self.score,
+            self.lines,
+            self.duration,
+            self.pps,
+            self.finesse
+                .map_or(String::new(), |finesse| finesse.to_string()),
+            self.seed
+        )
+    }
+
+    // Add the record to the end of the stats file for the format, starting a new file with a
+    // header line for CSV. Returns where it went.
+    pub fn append(&self, format: StatsExport) -> IoResult<PathBuf> {
+        let dir = paths::data_dir()
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))?;
+        create_dir_all(&dir)?;
+        let path = dir.join(match format {
+            StatsExport::Json => "stats.jsonl",
+            StatsExport::Csv => "stats.csv"
+        });
+        let mut file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let line = match format {
+            StatsExport::Json => serde_json::to_string(self)?,
+            StatsExport::Csv if file.metadata()?.len() == 0 => {
+                format!("{}\n{}", CSV_HEADER, self.csv_line())
+            }
+            StatsExport::Csv => self.csv_line()
+        };
+        writeln!(file, "{}", line)?;
+        Ok(path)
+    }
+}
+
+// Totals across every game played in one mode. Pieces and ticks are kept rather than an average
+// so that each game counts for as long as it lasted.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct ModeTotals {
+    pub(crate) games: u64,
+    pub(crate) lines: u64,
+    pub(crate) best_score: u64,
+    pub(crate) pieces: u64,
+    pub(crate) ticks: u64
+}
+
+impl ModeTotals {
+    pub fn average_pps(&self) -> f64 {
+        if self.ticks == 0 {
+            0.0
+        } else {
+            self.pieces as f64 * TICK_RATE as f64 / self.ticks as f64
+        }
+    }
+}
+
+// Lifetime totals for each mode, kept in the data directory and shown on the statistics screen.
+#[derive(Clone, Default)]
+pub struct LifetimeStats {
+    modes: BTreeMap<String, ModeTotals>
+}
+
+impl LifetimeStats {
+    fn path() -> IoResult<PathBuf> {
+        paths::data_dir()
+            .map(|dir| dir.join(LIFETIME_FILE))
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))
+    }
+
+    // Nothing has been played yet if there's no file.
+    pub fn load() -> IoResult<Self> {
+        let contents = match read_to_string(LifetimeStats::path()?) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(LifetimeStats::default()),
+            Err(e) => return Err(e)
+        };
+        let modes = toml::from_str(&contents)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        Ok(LifetimeStats { modes })
+    }
+
+    pub fn save(&self) -> IoResult<()> {
+        let path = LifetimeStats::path()?;
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        let contents = toml::to_string(&self.modes)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        File::create(path)?.write_all(contents.as_bytes())
+    }
+
+    pub fn record(&mut self, game: &Game) {
+        let totals = self.modes.entry(game.rules.mode.to_string()).or_default();
+        totals.games += 1;
+        totals.lines += game.lines_cleared as u64;
+        totals.best_score = totals.best_score.max(game.score);
+        totals.pieces += game.stats.pieces;
+        totals.ticks += game.stats.ticks;
+    }
+
+    pub fn draw<W: Write>(
+        &self,
+        config: &GameConfig,
+        color_support: ColorSupport,
+        out: &mut W,
+        x: u16
+    ) -> CrosstermResult<()> {
+        let text = config.language.strings();
+        let line = |s: &str| format!("{:width$}", s, width = SCREEN_WIDTH);
+        let mut lines = vec![line(text.statistics), line("")];
+        for (mode, totals) in self.modes.iter() {
+            lines.push(line(&mode.to_uppercase()));
+            lines.push(line(&field(text.games, totals.games, 20)));
+            lines.push(line(&field(text.lines, totals.lines, 20)));
+            lines.push(line(&field(text.best_score, totals.best_score, 20)));
+            lines.push(line(&field(
+                text.average_pps,
+                format!("{:.2}", totals.average_pps()),
+                20
+            )));
+            lines.push(line(""));
+        }
+        if self.modes.is_empty() {
+            lines.push(line(text.no_games));
+            lines.push(line(""));
+        }
+        // The first back key is named, since it can be rebound.
+        let back = config
+            .menu_back
+            .first()
+            .map_or(String::new(), Key::to_string);
+        lines.push(line(&format!("{} {}", back, text.to_leave)));
+        queue!(out, Output(color_support.fg(config.border_color)))?;
+        for (y, text) in lines.into_iter().enumerate() {
+            queue!(out, Goto(x, y as u16 + 1), Output(text))?;
+        }
+        Ok(())
+    }
+}
+
+// One place in the high score table.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct HighScore {
+    pub(crate) name: String,
+    pub(crate) score: u64,
+    pub(crate) lines: usize,
+    pub(crate) timestamp: u64
+}
+
+// The best scores in each mode, highest first, kept in the data directory alongside the lifetime
+// totals.
+#[derive(Clone, Default)]
+pub struct HighScores {
+    modes: BTreeMap<String, Vec<HighScore>>
+}
+
+impl HighScores {
+    fn path() -> IoResult<PathBuf> {
+        paths::data_dir()
+            .map(|dir| dir.join(HIGH_SCORES_FILE))
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))
+    }
+
+    // There are no scores yet if there's no file.
+    pub fn load() -> IoResult<Self> {
+        let contents = match read_to_string(HighScores::path()?) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(HighScores::default()),
+            Err(e) => return Err(e)
+        };
+        let modes = toml::from_str(&contents)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        Ok(HighScores { modes })
+    }
+
+    pub fn save(&self) -> IoResult<()> {
+        let path = HighScores::path()?;
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        let contents = toml::to_string(&self.modes)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        File::create(path)?.write_all(contents.as_bytes())
+    }
+
+    // A score makes the table if there's room left in it or it beats the lowest one. Ties go to
+    // whoever got there first.
+    pub fn qualifies(&self, game: &Game) -> bool {
+        if game.score == 0 {
+            return false;
+        }
+        match self.modes.get(&game.rules.mode.to_string()) {
+            Some(scores) if scores.len() >= HIGH_SCORE_COUNT => scores
+                .last()
+                .is_some_and(|lowest| game.score > lowest.score),
+            _ => true
+        }
+    }
+
+    pub fn insert(&mut self, game: &Game, name: String) {
+        let scores = self.modes.entry(game.rules.mode.to_string()).or_default();
+        let place = scores
+            .iter()
+            .position(|high_score| game.score > high_score.score)
+            .unwrap_or(scores.len());
+        scores.insert(
+            place,
+            HighScore {
+                name,
+                score: game.score,
+                lines: game.lines_cleared,
+                timestamp: SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_or(0, |since| since.as_secs())
+            }
+        );
+        scores.truncate(HIGH_SCORE_COUNT);
+    }
+}
+
+// The split times of the best run of each kind, by `Splits::course`. The best run is the one that
+// got furthest, and the fastest of those.
+#[derive(Clone, Default)]
+pub struct BestSplits {
+    courses: BTreeMap<String, Vec<u64>>
+}
+
+impl BestSplits {
+    fn path() -> IoResult<PathBuf> {
+        paths::data_dir()
+            .map(|dir| dir.join(SPLITS_FILE))
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))
+    }
+
+    // Nothing has been timed yet if there's no file.
+    pub fn load() -> IoResult<Self> {
+        let contents = match read_to_string(BestSplits::path()?) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(BestSplits::default()),
+            Err(e) => return Err(e)
+        };
+        let courses = toml::from_str(&contents)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        Ok(BestSplits { courses })
+    }
+
+    pub fn save(&self) -> IoResult<()> {
+        let path = BestSplits::path()?;
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        let contents = toml::to_string(&self.courses)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        File::create(path)?.write_all(contents.as_bytes())
+    }
+
+    pub fn get(&self, course: &str) -> Vec<u64> {
+        self.courses.get(course).cloned().unwrap_or_default()
+    }
+
+    // Keep the times if they beat the best run. Returns whether they did.
+    pub fn record(&mut self, course: String, times: &[u64]) -> bool {
+        let best = self.courses.entry(course).or_default();
+        let beaten = times.len() > best.len()
+            || (!times.is_empty() && times.len() == best.len() && times.last() < best.last());
+        if beaten {
+            *best = times.to_vec();
+        }
+        beaten
+    }
+}
+
+// What a personal best is for, going by the kind of game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Measure {
+    // Ticks to complete a challenge, like sprint. Lower is better.
+    Time,
+    // Score in a challenge against the clock, like ultra.
+    Score,
+    // The level reached in a game played until topping out.
+    Level
+}
+
+impl Measure {
+    pub fn of(game: &Game) -> Self {
+        match game.challenge {
+            Some(ref challenge) if challenge.limit.seconds.is_some() => Measure::Score,
+            Some(_) => Measure::Time,
+            None => Measure::Level
+        }
+    }
+
+    // How the game did, or `None` if it doesn't count, which is a challenge that wasn't completed.
+    pub fn value(self, game: &Game) -> Option<u64> {
+        match self {
+            Measure::Time if game.won => Some(game.stats.ticks),
+            Measure::Time => None,
+            Measure::Score => Some(game.score),
+            Measure::Level => Some(game.level as u64)
+        }
+    }
+
+    pub fn beats(self, value: u64, best: u64) -> bool {
+        match self {
+            Measure::Time => value < best,
+            Measure::Score | Measure::Level => value > best
+        }
+    }
+}
+
+// The best result of each kind of game by `Splits::course`, kept apart from the high score table
+// since what counts as best depends on the kind of game.
+#[derive(Clone, Default)]
+pub struct PersonalBests {
+    courses: BTreeMap<String, u64>
+}
+
+impl PersonalBests {
+    fn path() -> IoResult<PathBuf> {
+        paths::data_dir()
+            .map(|dir| dir.join(PERSONAL_BESTS_FILE))
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))
+    }
+
+    // Nothing has been played yet if there's no file.
+    pub fn load() -> IoResult<Self> {
+        let contents = match read_to_string(PersonalBests::path()?) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(PersonalBests::default()),
+            Err(e) => return Err(e)
+        };
+        let courses = toml::from_str(&contents)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        Ok(PersonalBests { courses })
+    }
+
+    pub fn save(&self) -> IoResult<()> {
+        let path = PersonalBests::path()?;
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        let contents = toml::to_string(&self.courses)
+            .map_err(|e| IoError::new(ErrorKind::InvalidData, e.to_string()))?;
+        File::create(path)?.write_all(contents.as_bytes())
+    }
+
+    pub fn get(&self, game: &Game) -> Option<u64> {
+        self.courses.get(&Splits::course(game)).cloned()
+    }
+
+    // Keep how the game did if it beats the best. Returns whether it did.
+    pub fn record(&mut self, game: &Game) -> bool {
+        let measure = Measure::of(game);
+        let value = match measure.value(game) {
+            Some(value) => value,
+            None => return false
+        };
+        let course = Splits::course(game);
+        if self
+            .courses
+            .get(&course)
+            .is_some_and(|&best| !measure.beats(value, best))
+        {
+            return false;
+        }
+        self.courses.insert(course, value);
+        true
+    }
+}
+
+// The board at one moment of a recorded run.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct GhostFrame {
+    ticks: u64,
+    board: RemoteBoard
+}
+
+// A run recorded to be raced against, as the board every time it changed. The best timed run of
+// each kind of game is kept in the ghosts folder of the data directory, by `Splits::course`.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Ghost {
+    frames: Vec<GhostFrame>
+}
+
+// Custom challenges can be called anything, so only the safe characters of the name are used in
+// file names.
+fn file_name(course: &str) -> String {
+    course
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
+
+impl Ghost {
+    fn path(course: &str) -> IoResult<PathBuf> {
+        let name = file_name(course);
+        paths::data_dir()
+            .map(|dir| dir.join(GHOSTS_DIR).join(format!("{}.json", name)))
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))
+    }
+
+    // There's nothing to race if there's no file.
+    pub fn load(course: &str) -> IoResult<Option<Self>> {
+        let contents = match read_to_string(Ghost::path(course)?) {
+            Ok(contents) => contents,
+            Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e)
+        };
+        Ok(Some(serde_json::from_str(&contents)?))
+    }
+
+    pub fn save(&self, course: &str) -> IoResult<()> {
+        let path = Ghost::path(course)?;
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        File::create(path)?.write_all(serde_json::to_string(self)?.as_bytes())
+    }
+
+    // Add the game's board if it's changed since the last frame.
+    pub fn record(&mut self, game: &Game) {
+        let board = RemoteBoard::of(game);
+        if self.frames.last().is_none_or(|frame| frame.board != board) {
+            self.frames.push(GhostFrame {
+                ticks: game.stats.ticks,
+                board
+            });
+        }
+    }
+
+    // Drop the frames after `ticks`, for going back in a practice game.
+    pub fn rewind(&mut self, ticks: u64) {
+        self.frames.retain(|frame| frame.ticks <= ticks);
+    }
+
+    // The board as it was `ticks` into the run.
+    pub fn at(&self, ticks: u64) -> Option<&RemoteBoard> {
+        let shown = self.frames.partition_point(|frame| frame.ticks <= ticks);
+        shown.checked_sub(1).map(|frame| &self.frames[frame].board)
+    }
+}
+
+// Keep the replay of a finished game in the replays folder of the data directory, named after the
+// kind of game and when it ended. Returns where it went.
+pub fn save_replay(replay: &Replay, game: &Game) -> IoResult<PathBuf> {
+    // The game may have only just ended, before the replay saw it.
+    let mut replay = replay.clone();
+    replay.tick(game);
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |since| since.as_secs());
+    let name = format!("{}-{}.ttr", file_name(&Splits::course(game)), timestamp);
+    let path = paths::data_dir()
+        .map(|dir| dir.join(REPLAYS_DIR).join(name))
+        .ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))?;
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+    File::create(&path)?.write_all(&replay.encode())?;
+    Ok(path)
+}
+
+impl HighScores {
+    // Each mode's table, for printing from the command line.
+    pub fn table(&self, text: &Strings) -> String {
+        if self.modes.is_empty() {
+            return format!("{}\n", text.no_high_scores);
+        }
+        let mut table = String::new();
+        for (i, (mode, scores)) in self.modes.iter().enumerate() {
+            if i > 0 {
+                table.push('\n');
+            }
+            table += &format!("{}\n", mode.to_uppercase());
+            for (place, high_score) in scores.iter().enumerate() {
+                let lines = format!("{:>5}", high_score.lines);
+                table += &format!(
+                    "{:>2}. {:<w$} {:>10} {}\n",
+                    place + 1,
+                    high_score.name,
+                    high_score.score,
+                    fill(text.table_lines, &[&lines]),
+                    w = NAME_LENGTH
+                );
+            }
+        }
+        table
+    }
+}
+
+pub enum NameEvent {
+    Done(String),
+    Skip
+}
+
+// The name to go with a new high score, typed in or picked a character at a time with the menu
+// keys the way arcade machines do it.
+#[derive(Default)]
+pub struct NameEntry {
+    pub(crate) name: Vec<char>,
+    // The character being picked, which is one past the end while there's room for another.
+    pub(crate) position: usize
+}
+
+impl NameEntry {
+    // Typing a letter wins over menu keys bound to it, so names can still have j and k in them.
+    // Only selecting and going back are checked before, so that they can't be locked out.
+    pub fn handle_key(&mut self, config: &GameConfig, key: Key) -> Option<NameEvent> {
+        match map_menu_key(config, &key) {
+            Some(MenuAction::Select) if !self.name.is_empty() => {
+                return Some(NameEvent::Done(self.name.iter().collect()));
+            }
+            Some(MenuAction::Back) => return Some(NameEvent::Skip),
+            _ => {}
+        }
+        if key.modifiers.contains(KeyModifiers::CONTROL)
+            || key.modifiers.contains(KeyModifiers::ALT)
+        {
+            return None;
+        }
+        if key == Key::plain(KeyCode::Backspace) || key == Key::plain(KeyCode::Delete) {
+            self.name.pop();
+            self.position = self.position.min(self.name.len());
+            return None;
+        }
+        match (key.code, map_menu_key(config, &key)) {
+            (KeyCode::Char(c), _) if c.is_ascii_alphanumeric() => {
+                let c = c.to_ascii_uppercase();
+                if self.position < self.name.len() {
+                    self.name[self.position] = c;
+                } else {
+                    self.name.push(c);
+                }
+                self.position = (self.position + 1).min(NAME_LENGTH - 1);
+            }
+            (_, Some(direction @ MenuAction::Up)) | (_, Some(direction @ MenuAction::Down)) => {
+                let characters = NAME_CHARACTERS.chars().collect::<Vec<_>>();
+                if self.position == self.name.len() {
+                    self.name.push(characters[0]);
+                } else {
+                    let current = characters
+                        .iter()
+                        .position(|&c| c == self.name[self.position])
+                        .unwrap_or(0);
+                    let step = if direction == MenuAction::Up {
+                        characters.len() - 1
+                    } else {
+                        1
+                    };
+                    self.name[self.position] = characters[(current + step) % characters.len()];
+                }
+            }
+            (_, Some(MenuAction::Left)) => self.position = self.position.saturating_sub(1),
+            (_, Some(MenuAction::Right)) => {
+                self.position = (self.position + 1)
+                    .min(self.name.len())
+                    .min(NAME_LENGTH - 1)
+            }
+            _ => {}
+        }
+        None
+    }
+}
+
+// Lifetime totals should add up across games and survive being saved.
+#[test]
+fn test_lifetime_totals() {
+    use tui_tetris::rules::Rules;
+    let mut lifetime = LifetimeStats::default();
+    let mut game = Game::with_seed(Rules::default(), 0);
+    game.score = 500;
+    game.lines_cleared = 4;
+    game.stats.pieces = 10;
+    game.stats.ticks = TICK_RATE * 5;
+    lifetime.record(&game);
+    game.score = 200;
+    game.stats.ticks = TICK_RATE * 15;
+    lifetime.record(&game);
+    let saved = toml::to_string(&lifetime.modes).unwrap();
+    let modes = toml::from_str::<BTreeMap<String, ModeTotals>>(&saved).unwrap();
+    let totals = &modes["modern"];
+    assert_eq!((totals.games, totals.lines, totals.best_score), (2, 8, 500));
+    assert_eq!(totals.average_pps(), 1.0);
+}
+
+// Scores are kept highest first, only the best few are kept, and one that doesn't beat any of them
+// doesn't qualify.
+#[test]
+fn test_high_scores() {
+    use tui_tetris::rules::Rules;
+    let mut high_scores = HighScores::default();
+    let mut game = Game::with_seed(Rules::default(), 0);
+    for score in 1..=HIGH_SCORE_COUNT as u64 {
+        game.score = score * 100;
+        assert!(high_scores.qualifies(&game));
+        high_scores.insert(&game, score.to_string());
+    }
+    game.score = 100;
+    assert!(!high_scores.qualifies(&game));
+    game.score = 550;
+    high_scores.insert(&game, "NEW".to_string());
+    let scores = &high_scores.modes["modern"];
+    assert_eq!(scores.len(), HIGH_SCORE_COUNT);
+    assert_eq!((scores[0].score, scores[5].name.as_str()), (1000, "NEW"));
+    assert_eq!(scores.last().map(|lowest| lowest.score), Some(200));
+    use crate::locale::{ENGLISH, SPANISH};
+
+    let printed = high_scores.table(&ENGLISH);
+    assert!(printed.starts_with("MODERN\n 1. 10             1000     0 lines\n"));
+    assert!(high_scores.table(&SPANISH).contains(" 1000     0 líneas\n"));
+    assert_eq!(
+        HighScores::default().table(&SPANISH),
+        "Aún no hay puntuaciones máximas.\n"
+    );
+}
+
+// Letters can be typed or stepped through with the menu keys, and typing over a picked one replaces
+// it, even with a letter that's also a menu key.
+#[test]
+fn test_name_entry() {
+    let config = GameConfig::default();
+    let mut entry = NameEntry::default();
+    assert!(entry
+        .handle_key(&config, Key::plain(KeyCode::Enter))
+        .is_none());
+    entry.handle_key(&config, Key::plain(KeyCode::Char('a')));
+    entry.handle_key(&config, Key::plain(KeyCode::Up));
+    entry.handle_key(&config, Key::plain(KeyCode::Up));
+    entry.handle_key(&config, Key::plain(KeyCode::Left));
+    entry.handle_key(&config, Key::plain(KeyCode::Char('j')));
+    match entry.handle_key(&config, Key::plain(KeyCode::Enter)) {
+        Some(NameEvent::Done(name)) => assert_eq!(name, "J9"),
+        _ => panic!("Expected a name")
+    }
+}
+
+// A run that gets further is always the best, and one that gets as far only if it's faster.
+#[test]
+fn test_best_splits() {
+    let mut best = BestSplits::default();
+    assert!(best.record("modern sprint".to_string(), &[100, 200]));
+    assert!(!best.record("modern sprint".to_string(), &[90, 210]));
+    assert!(best.record("modern sprint".to_string(), &[110, 190]));
+    assert!(best.record("modern sprint".to_string(), &[300, 400, 500]));
+    assert!(!best.record("modern".to_string(), &[]));
+    assert_eq!(best.get("modern sprint"), vec![300, 400, 500]);
+    assert_eq!(best.get("master"), Vec::<u64>::new());
+}
+
+// Sprint is timed and only counts once it's complete, ultra goes by score, and games without a
+// challenge by level.
+#[test]
+fn test_personal_bests() {
+    use tui_tetris::challenge;
+    use tui_tetris::rules::Rules;
+
+    let mut bests = PersonalBests::default();
+    let mut game = Game::with_seed(Rules::default(), 0);
+    game.level = 5;
+    assert!(bests.record(&game));
+    game.level = 3;
+    assert!(!bests.record(&game));
+    game.challenge = Some(challenge::built_in()[0].clone());
+    game.stats.ticks = TICK_RATE * 60;
+    assert_eq!(Measure::of(&game), Measure::Time);
+    assert!(!bests.record(&game));
+    game.won = true;
+    assert!(bests.record(&game));
+    game.stats.ticks = TICK_RATE * 50;
+    assert!(bests.record(&game));
+    game.challenge = Some(challenge::built_in()[1].clone());
+    assert_eq!(Measure::of(&game), Measure::Score);
+    assert!(bests.record(&game));
+    assert_eq!(bests.courses["modern"], 5);
+    assert_eq!(bests.courses["modern sprint"], TICK_RATE * 50);
+}
+
+// A ghost shows the board from the last change before each moment, and nothing before it starts.
+#[test]
+fn test_ghost() {
+    use tui_tetris::action::Action;
+    use tui_tetris::rules::Rules;
+
+    let mut ghost = Ghost::default();
+    let mut game = Game::with_seed(Rules::default(), 0);
+    game.stats.ticks = 10;
+    ghost.record(&game);
+    game.stats.ticks = 20;
+    ghost.record(&game);
+    game.handle_action(Action::HardDrop);
+    game.stats.ticks = 30;
+    ghost.record(&game);
+    assert_eq!(ghost.frames.len(), 2);
+    assert!(ghost.at(5).is_none());
+    assert!(ghost.at(29) == Some(&ghost.frames[0].board));
+    assert!(ghost.at(100) == Some(&RemoteBoard::of(&game)));
+    ghost.rewind(25);
+    assert_eq!(ghost.frames.len(), 1);
+}
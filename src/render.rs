@@ -0,0 +1,1125 @@
+use crossterm::{Color, Goto, Output, Result as CrosstermResult};
+use std::cmp::Ordering;
+use std::io::Write;
+use std::time::Duration;
+use tui_tetris::item::Item;
+use tui_tetris::tetromino::Tetromino;
+use tui_tetris::timing::TICK_RATE;
+use tui_tetris::versus::{Attack, Targeting, ATTACK_LOG_LENGTH};
+
+use crate::art::ArtSquare;
+use crate::color::{self, ColorSupport};
+use crate::game_config::{GameConfig, GhostColor, Renderer};
+use crate::locale::field;
+use crate::net::{player_of, RemoteBoard};
+use crate::records::Measure;
+use crate::session::{Session, PAUSE_ITEMS};
+
+// Bit for each dot of a braille character, indexed by [y][x] within its 2x4 dot grid. The dot
+// numbering goes down the left column first, with the bottom row added on later, hence the order.
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+// The empty braille pattern. Every other pattern is this plus its dot bits.
+const BRAILLE_BLANK: u32 = 0x2800;
+// Used for the hint when the ghost piece is turned off.
+const HINT_CHARACTER: char = '□';
+// Squares carrying an item are drawn with this in their piece's color.
+const ITEM_CHARACTER: char = '◆';
+// The border's color while it flashes.
+const FLASH_COLOR: Color = Color::Rgb {
+    r: 255,
+    g: 255,
+    b: 255
+};
+// The border's color while the stack is over the danger line.
+const DANGER_COLOR: Color = Color::Rgb { r: 220, g: 0, b: 0 };
+// Bars for the stats graphs from lowest to highest, and how many seconds the graphs show.
+const SPARKLINE_BARS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+const SPARKLINE_WIDTH: usize = 15;
+// How many of the latest splits the speedrun timer shows.
+const SPLIT_ROWS: usize = 8;
+// How many ticks each half of the danger pulse lasts.
+const DANGER_PULSE_TICKS: u64 = 30;
+// How bright an automatically colored ghost piece is, as a percentage of the active piece's color.
+const GHOST_BRIGHTNESS: u8 = 40;
+// Garbage rows are drawn with the block character in this color whatever the palette is, so that
+// they stand out from placed pieces.
+const GARBAGE_COLOR: Color = Color::Rgb {
+    r: 128,
+    g: 128,
+    b: 128
+};
+// How bright the background art is, as a percentage of its own colors, so that it doesn't get
+// mistaken for the game.
+const BACKGROUND_BRIGHTNESS: u8 = 35;
+
+// What's drawn for one square of the board.
+type Square = (char, Color);
+// One character cell of the board's interior, or `None` for empty space.
+type Row = Vec<Option<Square>>;
+
+impl Session {
+    // Draw the bordered board at the top left of the screen with the score, level, preview and
+    // hold pieces in a panel to its right.
+    pub fn draw<W: Write>(&self, out: &mut W) -> CrosstermResult<()> {
+        let config = &self.config;
+        let rows = match config.renderer {
+            Renderer::Blocks => self.block_rows(),
+            Renderer::Braille => self.braille_rows()
+        };
+        let inner_width = rows.first().map_or(0, Vec::len);
+        let horizontal = |c: char| c.to_string().repeat(inner_width);
+        let pulsed_off =
+            config.danger_pulse && (self.game.stats.ticks / DANGER_PULSE_TICKS) % 2 == 1;
+        let border_color = if self.border_flashing() {
+            FLASH_COLOR
+        } else if self.in_danger() && !pulsed_off {
+            DANGER_COLOR
+        } else {
+            config.border_color
+        };
+        // Shaking moves the board into the blank column between it and the panel, and the column
+        // it leaves behind is blanked so nothing's left of where it was.
+        let shift = self.shake_offset();
+        let (before, after) = if shift > 0 { (" ", "") } else { ("", " ") };
+        self.draw_background(out)?;
+        queue!(
+            out,
+            Output(self.color_support.bg(config.background_color)),
+            Output(self.color_support.fg(border_color)),
+            Goto(0, 0),
+            Output(format!(
+                "{}{}{}{}{}",
+                before,
+                config.tl_corner_character,
+                horizontal(config.top_border_character),
+                config.tr_corner_character,
+                after
+            )),
+            Goto(0, rows.len() as u16 + 1),
+            Output(format!(
+                "{}{}{}{}{}",
+                before,
+                config.bl_corner_character,
+                horizontal(config.bottom_border_character),
+                config.br_corner_character,
+                after
+            ))
+        )?;
+        for (y, row) in rows.iter().enumerate() {
+            queue!(
+                out,
+                Goto(0, y as u16 + 1),
+                Output(before.to_string()),
+                Output(self.color_support.fg(border_color)),
+                Output(config.left_border_character.to_string())
+            )?;
+            // Only switch colors when they actually change, since braille rows in particular can
+            // be long runs of the same piece.
+            let mut current = Some(config.border_color);
+            for (x, cell) in row.iter().enumerate() {
+                let art = self
+                    .background
+                    .as_ref()
+                    .and_then(|art| art.get(before.len() + 1 + x, y + 1));
+                match (*cell, art) {
+                    (Some((character, colour)), _) => {
+                        if current != Some(colour) {
+                            queue!(out, Output(self.color_support.fg(colour)))?;
+                            current = Some(colour);
+                        }
+                        queue!(out, Output(character.to_string()))?;
+                    }
+                    // Empty squares show the art behind the board, in its colors and then back to
+                    // the board's.
+                    (None, Some(square)) => {
+                        let (fg, bg) = self.background_colors(square);
+                        queue!(
+                            out,
+                            Output(self.color_support.bg(bg)),
+                            Output(self.color_support.fg(fg)),
+                            Output(square.character.to_string()),
+                            Output(self.color_support.bg(config.background_color))
+                        )?;
+                        current = None;
+                    }
+                    (None, None) => queue!(out, Output(" ".to_string()))?
+                }
+            }
+            queue!(
+                out,
+                Output(self.color_support.fg(border_color)),
+                Output(config.right_border_character.to_string()),
+                Output(after.to_string())
+            )?;
+        }
+        if let Some(text) = self.countdown_text() {
+            queue!(
+                out,
+                Output(self.color_support.fg(config.border_color)),
+                Goto(
+                    (inner_width.saturating_sub(text.len()) / 2) as u16 + 1 + shift,
+                    rows.len() as u16 / 2 + 1
+                ),
+                Output(text)
+            )?;
+        }
+        self.draw_panel(out, inner_width as u16 + 3)
+    }
+
+    // Draw the background art from the top left of the screen, for everything else to be drawn
+    // over. Colors are only sent when they change, since art is mostly runs of the same ones.
+    fn draw_background<W: Write>(&self, out: &mut W) -> CrosstermResult<()> {
+        let art = match self.background {
+            Some(ref art) => art,
+            None => return Ok(())
+        };
+        let mut current = None;
+        for (y, row) in art.rows().iter().enumerate() {
+            // Where the cursor is after the last square, so that runs of them only move it once.
+            let mut cursor = None;
+            for (x, square) in row.iter().enumerate() {
+                let square = match *square {
+                    Some(square) => square,
+                    None => continue
+                };
+                if cursor != Some(x) {
+                    queue!(out, Goto(x as u16, y as u16))?;
+                }
+                let colors = self.background_colors(square);
+                if current != Some(colors) {
+                    queue!(
+                        out,
+                        Output(self.color_support.bg(colors.1)),
+                        Output(self.color_support.fg(colors.0))
+                    )?;
+                    current = Some(colors);
+                }
+                queue!(out, Output(square.character.to_string()))?;
+                cursor = Some(x + 1);
+            }
+        }
+        Ok(())
+    }
+
+    // The dimmed foreground and background colors a square of the art is drawn in. Squares without
+    // a background go on the board's.
+    fn background_colors(&self, square: ArtSquare) -> (Color, Color) {
+        let fg = color::dim(square.fg.unwrap_or(Color::Grey), BACKGROUND_BRIGHTNESS);
+        let bg = square.bg.map_or(self.config.background_color, |bg| {
+            color::dim(bg, BACKGROUND_BRIGHTNESS)
+        });
+        (fg, bg)
+    }
+
+    // What should be shown in each square of the board, row by row. The active piece covers the
+    // board, which covers the ghost piece. The flag is set for ghost cells. None of the three are
+    // shown while the next piece is waiting to come into play.
+    fn visible_cells(&self) -> Vec<Option<(Square, bool)>> {
+        let config = &self.config;
+        let in_play = self.game.piece_in_play();
+        let active = if in_play {
+            self.game.active_piece.cells()
+        } else {
+            Vec::new()
+        };
+        let active_square = square(config, Some(self.game.active_piece.tetromino));
+        let ghost = match (
+            config.ghost_tetromino_character,
+            config.ghost_tetromino_color
+        ) {
+            (Some(character), Some(ghost_color))
+                if in_play && self.game.rules.rule_set().has_ghost() =>
+            {
+                let colour = match ghost_color {
+                    GhostColor::Fixed(colour) => colour,
+                    GhostColor::Auto => color::dim(active_square.1, GHOST_BRIGHTNESS)
+                };
+                Some((self.game.ghost_piece().cells(), (character, colour)))
+            }
+            _ => None
+        };
+        // The hint is drawn like a second ghost, but in the piece's own color so the two can be
+        // told apart.
+        let hint = self.game.hint.filter(|_| in_play).map(|hint| {
+            let character = config.ghost_tetromino_character.unwrap_or(HINT_CHARACTER);
+            (hint.cells(), (character, active_square.1))
+        });
+        let mut cells = Vec::with_capacity(self.game.board.width * self.game.board.height);
+        for y in 0..self.game.board.height {
+            for x in 0..self.game.board.width {
+                let pos = (x as isize, y as isize);
+                cells.push(if active.contains(&pos) {
+                    Some((active_square, false))
+                } else if let Some(cell) = self.game.board.get(x, y) {
+                    let (character, colour) = square(config, cell.tetromino);
+                    let character = if cell.item.is_some() {
+                        ITEM_CHARACTER
+                    } else {
+                        character
+                    };
+                    Some(((character, colour), false))
+                } else {
+                    match (&hint, &ghost) {
+                        (&Some((ref hint_cells, square)), _) if hint_cells.contains(&pos) => {
+                            Some((square, true))
+                        }
+                        (_, &Some((ref ghost_cells, square))) if ghost_cells.contains(&pos) => {
+                            Some((square, true))
+                        }
+                        _ => None
+                    }
+                });
+            }
+        }
+        cells
+    }
+
+    // Each square is drawn as a block_size by block_size patch of its character. Empty squares
+    // get the grid character instead if the grid marks them.
+    fn block_rows(&self) -> Vec<Row> {
+        let config = &self.config;
+        let block_size = config.block_size;
+        let cells = self.visible_cells();
+        let grid_square = (config.grid_character, config.grid_color);
+        let mut rows = Vec::with_capacity(self.game.board.height * block_size);
+        for (y, board_row) in cells.chunks(self.game.board.width).enumerate() {
+            let row = board_row
+                .iter()
+                .enumerate()
+                .flat_map(|(x, cell)| {
+                    let square = match *cell {
+                        Some((square, _)) => Some(square),
+                        None => config
+                            .grid
+                            .filter(|grid| grid.marks(x, y))
+                            .map(|_| grid_square)
+                    };
+                    (0..block_size).map(move |_| square)
+                })
+                .collect::<Row>();
+            for _ in 0..block_size {
+                rows.push(row.clone());
+            }
+        }
+        rows
+    }
+
+    // Each square becomes a block_size by block_size patch of braille dots, and each character
+    // holds a 2x4 group of dots. A character can only have one color, so pieces win over the
+    // ghost, and otherwise whichever piece is met first does.
+    fn braille_rows(&self) -> Vec<Row> {
+        let block_size = self.config.block_size;
+        let dots_width = self.game.board.width * block_size;
+        let dots_height = self.game.board.height * block_size;
+        let (chars_width, chars_height) = (dots_width.div_ceil(2), dots_height.div_ceil(4));
+        let cells = self.visible_cells();
+        let mut rows = Vec::with_capacity(chars_height);
+        for char_y in 0..chars_height {
+            let mut row = Vec::with_capacity(chars_width);
+            for char_x in 0..chars_width {
+                let mut dots = [[false; 2]; 4];
+                let mut colour = None;
+                let mut ghost_colour = None;
+                for (dy, dot_row) in dots.iter_mut().enumerate() {
+                    for (dx, dot) in dot_row.iter_mut().enumerate() {
+                        let (x, y) = (char_x * 2 + dx, char_y * 4 + dy);
+                        if x >= dots_width || y >= dots_height {
+                            continue;
+                        }
+                        match cells[y / block_size * self.game.board.width + x / block_size] {
+                            Some(((_, square_colour), false)) => {
+                                *dot = true;
+                                colour = colour.or(Some(square_colour));
+                            }
+                            Some(((_, square_colour), true)) => {
+                                *dot = true;
+                                ghost_colour = ghost_colour.or(Some(square_colour));
+                            }
+                            None => {}
+                        }
+                    }
+                }
+                row.push(
+                    colour
+                        .or(ghost_colour)
+                        .map(|colour| (braille_character(&dots), colour))
+                );
+            }
+            rows.push(row);
+        }
+        rows
+    }
+
+    fn draw_panel<W: Write>(&self, out: &mut W, x: u16) -> CrosstermResult<()> {
+        let config = &self.config;
+        let text = config.language.strings();
+        if let Some(ref settings) = self.settings {
+            return settings.draw(config, self.color_support, out, x);
+        }
+        if let Some(ref statistics) = self.statistics {
+            return statistics.draw(config, self.color_support, out, x);
+        }
+        if let Some(ref leaderboard) = self.leaderboard {
+            return leaderboard.draw(config, self.color_support, out, x);
+        }
+        if self.game.game_over {
+            self.draw_results(out, x)?;
+            return self.draw_columns(out, x);
+        }
+        queue!(
+            out,
+            Output(self.color_support.fg(config.border_color)),
+            Goto(x, 1),
+            Output(field(text.score, self.game.score, 16)),
+            Goto(x, 2),
+            Output(match self.game.level_stop() {
+                Some(stop) => field(text.level, format!("{}/{}", self.game.level, stop), 16),
+                None if self.game.level_fixed() => field(
+                    text.level,
+                    format!("{} ({})", self.game.level, text.fixed),
+                    16
+                ),
+                None => field(text.level, self.game.level, 16)
+            }),
+            Goto(x, 3),
+            Output(field(text.lines, self.game.lines_cleared, 16))
+        )?;
+        if let Some(grade) = self.game.grade() {
+            queue!(out, Goto(x, 4), Output(field(text.grade, grade, 16)))?;
+        }
+        let mut y = 5;
+        if !self.game.preview.is_empty() {
+            queue!(
+                out,
+                Output(self.color_support.fg(config.border_color)),
+                Goto(x, y),
+                Output(text.next.to_string())
+            )?;
+            y += 1;
+            // A preview cut down by the opponent's item keeps its space, with the rest blanked.
+            let shown = self.game.shown_preview();
+            for i in 0..self.game.preview.len() {
+                let tetromino = shown.get(i).copied();
+                self.draw_mini(
+                    out,
+                    x,
+                    y,
+                    tetromino,
+                    tetromino.map(|t| config.piece_color(t))
+                )?;
+                y += 3;
+            }
+        }
+        // Holds left are only worth showing when there can be more than one, and the held piece is
+        // greyed out once it can't be swapped back in.
+        if !config.hold.is_empty()
+            && self.game.rules.rule_set().has_hold()
+            && self.game.rules.hold_limit != Some(0)
+        {
+            let title = match self.game.rules.hold_limit {
+                Some(limit) if limit > 1 => {
+                    format!("{} {:<2}", text.hold, self.game.holds_left().unwrap_or(0))
+                }
+                _ => format!("{}   ", text.hold)
+            };
+            queue!(
+                out,
+                Output(self.color_support.fg(config.border_color)),
+                Goto(x, y),
+                Output(title)
+            )?;
+            let color = self.game.hold.map(|held| {
+                if self.game.can_hold() {
+                    config.piece_color(held)
+                } else {
+                    GARBAGE_COLOR
+                }
+            });
+            self.draw_mini(out, x, y + 1, self.game.hold, color)?;
+            y += 4;
+        }
+        if self.game.rules.items {
+            let item = match self.game.item {
+                Some(Item::ClearRows) => text.clear_rows,
+                Some(Item::ShrinkPreview) => text.shrink_preview,
+                Some(Item::ScrambleGarbage) => text.scramble_garbage,
+                None => ""
+            };
+            queue!(
+                out,
+                Output(self.color_support.fg(config.border_color)),
+                Goto(x, y),
+                Output(text.item.to_string()),
+                Goto(x, y + 1),
+                Output(format!("{:15}", item))
+            )?;
+            y += 3;
+        }
+        // Padded so that unpausing clears the text.
+        let status = if self.config_error {
+            text.config_error
+        } else if self.game.paused {
+            text.paused
+        } else if !self.opponents.is_empty() && self.opponents.iter().all(|opponent| opponent.left)
+        {
+            text.opponent_left
+        } else if self.frame_stepping {
+            text.frame_step
+        } else {
+            ""
+        };
+        let status = match self.trainer {
+            Some(ref trainer) if status.is_empty() => {
+                format!("{} {}/{}", text.built, trainer.successes, trainer.attempts)
+            }
+            _ => status.to_string()
+        };
+        queue!(
+            out,
+            Output(self.color_support.fg(config.border_color)),
+            Goto(x, y),
+            Output(format!("{:15}", status))
+        )?;
+        // Always as many rows as there can be items, so that the menu shrinking once a save has been
+        // picked up clears the last one.
+        let items = self.pause_items();
+        for row in 0..PAUSE_ITEMS {
+            let text = match (self.game.paused, items.get(row)) {
+                (true, Some(&item)) if item == self.pause_item => format!("> {}", item.label(text)),
+                (true, Some(&item)) => format!("  {}", item.label(text)),
+                _ => String::new()
+            };
+            queue!(
+                out,
+                Goto(x, y + 1 + row as u16),
+                Output(format!("{:15}", text))
+            )?;
+        }
+        // The seed goes under the menu, so the same pieces can be played again with `--seed`.
+        let (label, seed) = if self.game.paused {
+            (text.seed.to_string(), self.game.seed_text())
+        } else {
+            (String::new(), String::new())
+        };
+        queue!(
+            out,
+            Goto(x, y + PAUSE_ITEMS as u16 + 2),
+            Output(format!("{:16}", label)),
+            Goto(x, y + PAUSE_ITEMS as u16 + 3),
+            Output(format!("{:16}", seed))
+        )?;
+        self.draw_columns(out, x)
+    }
+
+    // The opponents' boards, attack log, live stats and debug info, each in a column of its own to
+    // the right of the panel. A single opponent's board is drawn full size, and any more as
+    // thumbnails.
+    fn draw_columns<W: Write>(&self, out: &mut W, x: u16) -> CrosstermResult<()> {
+        let mut column = x + 18;
+        if let [ref opponent] = self.opponents[..] {
+            draw_remote_board(out, &self.config, self.color_support, column, opponent)?;
+            column += self.game.board.width as u16 + 4;
+        } else {
+            let targets = self.shown_targets();
+            for (i, opponent) in self.opponents.iter().enumerate() {
+                let targeted = targets.contains(&i);
+                draw_thumbnail(
+                    out,
+                    &self.config,
+                    self.color_support,
+                    column,
+                    opponent,
+                    targeted
+                )?;
+                column += self.game.board.width as u16 + 3;
+            }
+        }
+        if !self.opponents.is_empty() {
+            self.draw_attack_log(out, column)?;
+            column += 13;
+        }
+        if let Some(board) = self
+            .race
+            .as_ref()
+            .and_then(|race| race.at(self.game.stats.ticks))
+        {
+            column += self.draw_race(out, column, board)? + 4;
+        }
+        if self.config.splits {
+            self.draw_splits(out, column)?;
+            column += 18;
+        }
+        if self.show_stats {
+            self.draw_stats(out, column)?;
+            column += 17;
+        }
+        if self.show_debug {
+            self.draw_debug(out, column)?;
+        }
+        Ok(())
+    }
+
+    // Where the points came from and how the game was played, in place of the usual panel once the
+    // game is over.
+    fn draw_results<W: Write>(&self, out: &mut W, x: u16) -> CrosstermResult<()> {
+        let (game, stats) = (&self.game, &self.game.stats);
+        let text = self.config.language.strings();
+        let result = if !self.opponents.is_empty() {
+            if game.won {
+                text.you_win
+            } else {
+                text.you_lose
+            }
+        } else if game.won {
+            text.complete
+        } else if game.challenge.is_some() {
+            text.failed
+        } else {
+            text.game_over
+        };
+        let clock = |seconds: f64| {
+            let seconds = seconds as u64;
+            format!("{}:{:02}", seconds / 60, seconds % 60)
+        };
+        let finesse = stats
+            .finesse_percent()
+            .map_or("-".to_string(), |percent| format!("{:.1}%", percent));
+        let lines = [
+            result.to_string(),
+            String::new(),
+            field(text.score, game.score, 16),
+            field(text.singles, stats.clear_score[0], 16),
+            field(text.doubles, stats.clear_score[1], 16),
+            field(text.triples, stats.clear_score[2], 16),
+            field(text.tetrises, stats.clear_score[3], 16),
+            field(text.t_spins, stats.t_spin_score, 16),
+            field(text.drops, stats.drop_score, 16),
+            String::new(),
+            field(text.lines, game.lines_cleared, 16),
+            field(text.max_combo, stats.max_combo, 16),
+            field(text.time, clock(stats.seconds()), 16),
+            field(text.with_pauses, clock(stats.wall_seconds()), 16),
+            field(text.pps, format!("{:.2}", stats.pieces_per_second()), 16),
+            field(text.finesse, finesse, 16),
+            text.seed.to_string(),
+            game.seed_text(),
+            match self.trainer {
+                Some(ref trainer) => field(
+                    text.built,
+                    format!("{}/{}", trainer.successes, trainer.attempts),
+                    16
+                ),
+                None => self.personal_best_line()
+            }
+        ];
+        queue!(out, Output(self.color_support.fg(self.config.border_color)))?;
+        for (row, line) in lines.iter().enumerate() {
+            queue!(out, Goto(x, row as u16 + 1), Output(format!("{:16}", line)))?;
+        }
+        let select = self
+            .config
+            .menu_select
+            .first()
+            .map_or(String::new(), |key| key.to_string().to_uppercase());
+        if self.can_retry() {
+            let y = lines.len() as u16 + 2;
+            queue!(
+                out,
+                Goto(x, y),
+                Output(format!("{:16}", format!("{} {}", select, text.to_retry)))
+            )?;
+        }
+        // A caret under the name marks the character the arrows change.
+        if let Some(ref entry) = self.name_entry {
+            let name = entry.name.iter().collect::<String>();
+            let indent = text.name_label.chars().count() + 2;
+            let entry_lines = [
+                text.new_high_score.to_string(),
+                format!("{}  {}", text.name_label, name),
+                format!("{:>1$}", "^", indent + entry.position + 1),
+                format!("{} {}", select, text.to_save)
+            ];
+            for (row, line) in entry_lines.iter().enumerate() {
+                let y = (lines.len() + row) as u16 + 2;
+                queue!(out, Goto(x, y), Output(format!("{:16}", line)))?;
+            }
+        }
+        Ok(())
+    }
+
+    // How far the game was from the best before it, or whether it's the first to count. Versus
+    // and practice games aren't counted.
+    fn personal_best_line(&self) -> String {
+        let (game, text) = (&self.game, self.config.language.strings());
+        if game.practice || game.versus {
+            return String::new();
+        }
+        let measure = Measure::of(game);
+        let difference = match (measure.value(game), self.personal_best) {
+            (None, _) => "-".to_string(),
+            (Some(_), None) => text.new_best.to_string(),
+            (Some(value), Some(best)) if measure == Measure::Time => split_delta(value, best),
+            (Some(value), Some(best)) => format!("{:+}", value as i64 - best as i64)
+        };
+        field(text.personal_best, difference, 16)
+    }
+
+    // The best run's board as it was at this point, with whether this run has cleared more lines.
+    // Returns how wide the board is, which can differ from this one's if the settings have changed.
+    fn draw_race<W: Write>(
+        &self,
+        out: &mut W,
+        x: u16,
+        board: &RemoteBoard
+    ) -> CrosstermResult<u16> {
+        let text = self.config.language.strings();
+        let width = board.rows.first().map_or(0, |row| row.chars().count());
+        draw_remote_board(out, &self.config, self.color_support, x, board)?;
+        let standing = match self.game.lines_cleared.cmp(&board.lines) {
+            Ordering::Greater => text.ahead,
+            Ordering::Less => text.behind,
+            Ordering::Equal => text.even
+        };
+        queue!(
+            out,
+            Goto(x, board.rows.len() as u16 + 4),
+            Output(format!("{:<w$}", standing, w = width + 2))
+        )?;
+        Ok(width as u16)
+    }
+
+    // The latest splits with how far ahead or behind the best run each was, then the time so far
+    // against the best run's next split. Padded so that splits scrolling up overwrite what was
+    // there.
+    fn draw_splits<W: Write>(&self, out: &mut W, x: u16) -> CrosstermResult<()> {
+        let splits = &self.splits;
+        // A saved game carried on with started partway, so there's nothing to compare it with.
+        let best = if splits.base == 0 {
+            &splits.best[..]
+        } else {
+            &[]
+        };
+        let row = |label: String, ticks: u64, best: Option<&u64>| {
+            let delta = best.map_or(String::new(), |&best| split_delta(ticks, best));
+            format!("{:<4}{:>7} {:>5}", label, split_time(ticks), delta)
+        };
+        let mut lines = vec![self.config.language.strings().splits.to_string()];
+        let shown = splits.times.len().saturating_sub(SPLIT_ROWS);
+        for (i, &ticks) in splits.times.iter().enumerate().skip(shown) {
+            lines.push(row(splits.label(i).to_string(), ticks, best.get(i)));
+        }
+        let next = best.get(splits.times.len());
+        lines.push(row(String::new(), self.game.stats.ticks, next));
+        lines.resize(SPLIT_ROWS + 2, String::new());
+        queue!(out, Output(self.color_support.fg(self.config.border_color)))?;
+        for (y, line) in lines.iter().enumerate() {
+            queue!(out, Goto(x, y as u16 + 1), Output(format!("{:17}", line)))?;
+        }
+        Ok(())
+    }
+
+    // The latest attacks with the newest at the bottom, padded so that older ones scrolling up
+    // overwrite what was there.
+    fn draw_attack_log<W: Write>(&self, out: &mut W, x: u16) -> CrosstermResult<()> {
+        let text = self.config.language.strings();
+        queue!(
+            out,
+            Output(self.color_support.fg(self.config.border_color)),
+            Goto(x, 1),
+            Output(text.attacks.to_string())
+        )?;
+        let attacks = &self.attack_log.attacks;
+        for row in 0..ATTACK_LOG_LENGTH {
+            let entry = match attacks.get(row) {
+                Some(Attack::Sent(lines)) => format!("+{} {}", lines, text.sent),
+                Some(Attack::Incoming(lines)) => format!("{} {}", lines, text.incoming),
+                None => String::new()
+            };
+            queue!(
+                out,
+                Goto(x, row as u16 + 2),
+                Output(format!("{:12}", entry))
+            )?;
+        }
+        // Who attacks are going to only matters with more than one opponent.
+        if self.opponents.len() > 1 {
+            let target = match self.picked_target() {
+                Some(target) => format!("{} {}", text.player, player_of(self.player, target) + 1),
+                None => match self.config.targeting {
+                    Targeting::Random => text.target_random.to_string(),
+                    Targeting::Leader => text.target_leader.to_string(),
+                    Targeting::Danger => text.target_danger.to_string(),
+                    Targeting::Even => text.target_even.to_string()
+                }
+            };
+            queue!(
+                out,
+                Goto(x, ATTACK_LOG_LENGTH as u16 + 3),
+                Output(text.target.to_string()),
+                Goto(x, ATTACK_LOG_LENGTH as u16 + 4),
+                Output(format!("{:12}", target))
+            )?;
+        }
+        Ok(())
+    }
+
+    fn draw_debug<W: Write>(&self, out: &mut W, x: u16) -> CrosstermResult<()> {
+        let timings = &self.timings;
+        let millis = |duration: Duration| duration.as_secs_f64() * 1000.0;
+        let latency = timings
+            .input_latency
+            .map_or("-".to_string(), |latency| format!("{:.2}", millis(latency)));
+        // Frames since the last input, for seeing how far apart inputs land.
+        let idle = self.inputs.last().map_or("-".to_string(), |input| {
+            (self.inputs.frame() - input.frame).to_string()
+        });
+        let piece = self.game.active_piece;
+        queue!(
+            out,
+            Output(self.color_support.fg(self.config.border_color)),
+            Goto(x, 1),
+            Output(format!("FRAME MS {:>6.2}", millis(timings.frame))),
+            Goto(x, 2),
+            Output(format!("TICK MS {:>7.2}", millis(timings.tick))),
+            Goto(x, 3),
+            Output(format!("RENDER MS {:>5.2}", millis(timings.render))),
+            Goto(x, 4),
+            Output(format!("DROPPED {:>7}", timings.dropped_frames)),
+            Goto(x, 5),
+            Output(format!("INPUT MS {:>6}", latency)),
+            Goto(x, 6),
+            Output(format!(
+                "PIECE {:>9}",
+                format!("{},{} R{}", piece.x, piece.y, piece.rotation)
+            )),
+            Goto(x, 7),
+            Output(format!("TICK {:>10}", self.game.stats.ticks)),
+            Goto(x, 8),
+            Output(format!("IDLE FR {:>7}", idle))
+        )?;
+        // How many times a rollback match has gone back, and how many ticks it's being played
+        // past what's settled.
+        if let Some(ref rollback) = self.rollback {
+            let ahead = rollback.current().ticks - rollback.confirmed().ticks;
+            queue!(
+                out,
+                Goto(x, 9),
+                Output(format!("ROLLBACK {:>6}", rollback.rollbacks)),
+                Goto(x, 10),
+                Output(format!("AHEAD {:>9}", ahead))
+            )?;
+        }
+        Ok(())
+    }
+
+    fn draw_stats<W: Write>(&self, out: &mut W, x: u16) -> CrosstermResult<()> {
+        let stats = &self.game.stats;
+        let text = self.config.language.strings();
+        queue!(
+            out,
+            Output(self.color_support.fg(self.config.border_color)),
+            Goto(x, 1),
+            Output(field(text.pieces, stats.pieces, 15)),
+            Goto(x, 2),
+            Output(field(
+                text.pps,
+                format!("{:.2}", stats.pieces_per_second()),
+                15
+            )),
+            Goto(x, 3),
+            Output(field(
+                text.apm,
+                format!("{:.1}", stats.attack_per_minute()),
+                15
+            )),
+            Goto(x, 4),
+            Output(field(text.t_spins, stats.t_spins, 15)),
+            Goto(x, 5),
+            Output(field(text.tetrises, stats.tetrises, 15)),
+            Goto(x, 6),
+            Output(field(text.holds, stats.holds, 15))
+        )?;
+        // A second per character, newest on the right. Pieces per second is scaled to the fastest
+        // it's been lately and the stack to the height of the board.
+        let recent = stats
+            .samples
+            .iter()
+            .skip(stats.samples.len().saturating_sub(SPARKLINE_WIDTH));
+        let pps = recent
+            .clone()
+            .map(|sample| sample.pieces_per_second)
+            .collect::<Vec<_>>();
+        let fastest = pps.iter().cloned().fold(1.0, f64::max);
+        let heights = recent
+            .map(|sample| sample.stack_height as f64)
+            .collect::<Vec<_>>();
+        queue!(
+            out,
+            Goto(x, 8),
+            Output(text.pps.to_string()),
+            Goto(x, 9),
+            Output(sparkline(&pps, fastest)),
+            Goto(x, 10),
+            Output(text.stack.to_string()),
+            Goto(x, 11),
+            Output(sparkline(&heights, self.game.board.height as f64))
+        )?;
+        Ok(())
+    }
+
+    // Draw a piece in its spawn orientation in a 4x2 box, or clear the box if there's no piece.
+    fn draw_mini<W: Write>(
+        &self,
+        out: &mut W,
+        x: u16,
+        y: u16,
+        tetromino: Option<Tetromino>,
+        color: Option<Color>
+    ) -> CrosstermResult<()> {
+        let mut rows = [[' '; 4]; 2];
+        if let (Some(tetromino), Some(color)) = (tetromino, color) {
+            for &(mx, my) in tetromino.minos(0).iter() {
+                // The I piece's spawn orientation sits on the second row of its bounding box.
+                let my = if tetromino == Tetromino::I {
+                    my - 1
+                } else {
+                    my
+                };
+                rows[my as usize][mx as usize] = self.config.piece_character(tetromino);
+            }
+            queue!(out, Output(self.color_support.fg(color)))?;
+        }
+        for (dy, row) in rows.iter().enumerate() {
+            queue!(
+                out,
+                Goto(x, y + dy as u16),
+                Output(row.iter().collect::<String>())
+            )?;
+        }
+        Ok(())
+    }
+}
+
+// Another player's board, one character per square whatever the block size is so that it doesn't
+// take up as much room as this one. Their pieces are drawn in this player's colors.
+pub fn draw_remote_board<W: Write>(
+    out: &mut W,
+    config: &GameConfig,
+    color_support: ColorSupport,
+    x: u16,
+    board: &RemoteBoard
+) -> CrosstermResult<()> {
+    let height = board.rows.len();
+    let width = board.rows.first().map_or(0, |row| row.chars().count());
+    let horizontal = |c: char| c.to_string().repeat(width);
+    queue!(
+        out,
+        Output(color_support.fg(config.border_color)),
+        Goto(x, 0),
+        Output(format!(
+            "{}{}{}",
+            config.tl_corner_character,
+            horizontal(config.top_border_character),
+            config.tr_corner_character
+        )),
+        Goto(x, height as u16 + 1),
+        Output(format!(
+            "{}{}{}",
+            config.bl_corner_character,
+            horizontal(config.bottom_border_character),
+            config.br_corner_character
+        )),
+        Goto(x, height as u16 + 2),
+        Output(format!("{:<w$}", board.score, w = width + 2)),
+        Goto(x, height as u16 + 3),
+        Output(format!(
+            "{:<w$}",
+            format!("{} LINES", board.lines),
+            w = width + 2
+        ))
+    )?;
+    for y in 0..height {
+        queue!(
+            out,
+            Goto(x, y as u16 + 1),
+            Output(color_support.fg(config.border_color)),
+            Output(config.left_border_character.to_string())
+        )?;
+        for board_x in 0..width {
+            match board.cell(board_x, y) {
+                Some(tetromino) => {
+                    let (character, colour) = square(config, tetromino);
+                    queue!(
+                        out,
+                        Output(color_support.fg(colour)),
+                        Output(character.to_string())
+                    )?
+                }
+                None => queue!(out, Output(" ".to_string()))?
+            }
+        }
+        queue!(
+            out,
+            Output(color_support.fg(config.border_color)),
+            Output(config.right_border_character.to_string())
+        )?;
+    }
+    Ok(())
+}
+
+// Another player's board at half height, with each character covering two rows, for when there
+// are too many to draw full size. A character takes the color of its top square if that's filled.
+// Boards that attacks are going to are marked underneath.
+fn draw_thumbnail<W: Write>(
+    out: &mut W,
+    config: &GameConfig,
+    color_support: ColorSupport,
+    x: u16,
+    board: &RemoteBoard,
+    targeted: bool
+) -> CrosstermResult<()> {
+    let height = board.rows.len();
+    let width = board.rows.first().map_or(0, |row| row.chars().count());
+    let rows = height.div_ceil(2);
+    let horizontal = |c: char| c.to_string().repeat(width);
+    queue!(
+        out,
+        Output(color_support.fg(config.border_color)),
+        Goto(x, 0),
+        Output(format!(
+            "{}{}{}",
+            config.tl_corner_character,
+            horizontal(config.top_border_character),
+            config.tr_corner_character
+        )),
+        Goto(x, rows as u16 + 1),
+        Output(format!(
+            "{}{}{}",
+            config.bl_corner_character,
+            horizontal(config.bottom_border_character),
+            config.br_corner_character
+        )),
+        Goto(x, rows as u16 + 2),
+        Output(format!("{:<w$}", board.score, w = width + 2)),
+        Goto(x, rows as u16 + 3),
+        Output(format!(
+            "{:<w$}",
+            if board.topped_out {
+                "OUT"
+            } else if targeted {
+                config.language.strings().target
+            } else {
+                ""
+            },
+            w = width + 2
+        ))
+    )?;
+    for row in 0..rows {
+        queue!(
+            out,
+            Goto(x, row as u16 + 1),
+            Output(color_support.fg(config.border_color)),
+            Output(config.left_border_character.to_string())
+        )?;
+        for board_x in 0..width {
+            let top = board.cell(board_x, row * 2);
+            let bottom = board.cell(board_x, row * 2 + 1);
+            let (character, filled) = match (top, bottom) {
+                (Some(top), Some(_)) => ('█', top),
+                (Some(top), None) => ('▀', top),
+                (None, Some(bottom)) => ('▄', bottom),
+                (None, None) => {
+                    queue!(out, Output(" ".to_string()))?;
+                    continue;
+                }
+            };
+            queue!(
+                out,
+                Output(color_support.fg(square(config, filled).1)),
+                Output(character.to_string())
+            )?;
+        }
+        queue!(
+            out,
+            Output(color_support.fg(config.border_color)),
+            Output(config.right_border_character.to_string())
+        )?;
+    }
+    Ok(())
+}
+
+// How a filled square looks, going by the piece it was part of. `None` is for garbage.
+fn square(config: &GameConfig, tetromino: Option<Tetromino>) -> Square {
+    match tetromino {
+        Some(tetromino) => (
+            config.piece_character(tetromino),
+            config.piece_color(tetromino)
+        ),
+        None => (config.block_character, GARBAGE_COLOR)
+    }
+}
+
+// Each value as one of eight bar heights, `max` being the tallest. Padded on the left to
+// `SPARKLINE_WIDTH`.
+fn sparkline(values: &[f64], max: f64) -> String {
+    let bars = values
+        .iter()
+        .map(|&value| {
+            let level = (value / max * 7.0).round().clamp(0.0, 7.0) as usize;
+            SPARKLINE_BARS[level]
+        })
+        .collect::<String>();
+    format!("{:>w$}", bars, w = SPARKLINE_WIDTH)
+}
+
+// Ticks as minutes, seconds and tenths.
+fn split_time(ticks: u64) -> String {
+    let tenths = ticks * 10 / TICK_RATE;
+    format!("{}:{:02}.{}", tenths / 600, tenths / 10 % 60, tenths % 10)
+}
+
+// How far ahead of (-) or behind (+) the best time a split was, in seconds and tenths.
+fn split_delta(ticks: u64, best: u64) -> String {
+    let (sign, difference) = if ticks <= best {
+        ('-', best - ticks)
+    } else {
+        ('+', ticks - best)
+    };
+    let tenths = difference * 10 / TICK_RATE;
+    format!("{}{}.{}", sign, tenths / 10, tenths % 10)
+}
+
+fn braille_character(dots: &[[bool; 2]; 4]) -> char {
+    let mut bits = 0;
+    for (dot_row, bit_row) in dots.iter().zip(BRAILLE_DOTS.iter()) {
+        for (&dot, &bit) in dot_row.iter().zip(bit_row.iter()) {
+            if dot {
+                bits |= bit;
+            }
+        }
+    }
+    std::char::from_u32(BRAILLE_BLANK + bits).unwrap()
+}
+
+// Split times are shown to the tenth, with the difference from the best run signed.
+#[test]
+fn test_split_time() {
+    assert_eq!(split_time(TICK_RATE * 83 + TICK_RATE / 2), "1:23.5");
+    assert_eq!(split_delta(TICK_RATE * 10, TICK_RATE * 12), "-2.0");
+    assert_eq!(split_delta(TICK_RATE * 61, TICK_RATE * 60), "+1.0");
+}
+
+// Dots have to map to the right bits of the braille pattern block.
+#[test]
+fn test_braille_character() {
+    assert_eq!(braille_character(&[[false; 2]; 4]), '⠀');
+    assert_eq!(braille_character(&[[true; 2]; 4]), '⣿');
+    assert_eq!(
+        braille_character(&[[true, false], [false, false], [false, false], [false, true]]),
+        '⢁'
+    );
+    assert_eq!(
+        braille_character(&[[false, false], [false, true], [true, false], [false, false]]),
+        '⠔'
+    );
+}
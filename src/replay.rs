@@ -0,0 +1,320 @@
+use crate::action::Action;
+use crate::gameboard::Game;
+use crate::timing::TICK_RATE;
+use serde_json;
+
+// A replay is the game as it was at the start, and then every input that reached it with the tick
+// it came on. Games are deterministic given their state, so that's enough to play one back, and
+// it's small: most inputs take one or two bytes. A copy of the whole game is kept every so often
+// as well, so that seeking only has to play forward from the one before, and so that the end of a
+// replay can be checked against what the game actually did.
+//
+// The file is `MAGIC` and the version, followed by records. Each record starts with a LEB128
+// number made of the ticks since the last record shifted up by `KIND_BITS`, with the kind of
+// record in the bits below. Inputs are the index of the action in `ACTIONS`, followed by the
+// frames since the last input. Keyframes are `KEYFRAME` and then the game as JSON, preceded by its
+// length. The first record is always a keyframe.
+//
+// Replays from any other version are refused rather than misread. Version 2 had no frames, and
+// version 1 had games that took a plain number for `entry_delay`.
+const MAGIC: &[u8; 4] = b"TTRP";
+pub const VERSION: u8 = 3;
+const KIND_BITS: u32 = 4;
+const KEYFRAME: u64 = (1 << KIND_BITS) - 1;
+// Keyframes are a few kilobytes each, so half a minute apart keeps an hour-long game under half a
+// megabyte while seeking never has more than half a minute to play through.
+pub const KEYFRAME_TICKS: u64 = TICK_RATE * 30;
+// The inputs that change the game. Everything else is the frontend's business. New ones can only
+// go on the end, so that older replays keep their meaning.
+const ACTIONS: [Action; 9] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::RotateClockwise,
+    Action::RotateAnticlockwise,
+    Action::SoftDrop,
+    Action::HardDrop,
+    Action::SonicDrop,
+    Action::Hold,
+    Action::UseItem
+];
+
+// An input and the tick it came on, counted in the game's own ticks so that pauses leave no gaps.
+// It happened after that many ticks had been played and before the next. `frame` is the frontend's
+// number for the frame it was read on, which playing it back doesn't need but which says how it
+// was actually pressed.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub struct Input {
+    pub ticks: u64,
+    pub frame: u64,
+    pub action: Action
+}
+
+#[derive(Clone)]
+struct Keyframe {
+    game: Game,
+    // How many inputs came before it.
+    inputs: usize
+}
+
+#[derive(Clone)]
+pub struct Replay {
+    keyframes: Vec<Keyframe>,
+    inputs: Vec<Input>
+}
+
+impl Replay {
+    // Start recording from the game as it is. Pausing isn't part of a replay, and a saved game
+    // that's being carried on with starts paused.
+    pub fn new(game: &Game) -> Self {
+        let mut game = game.clone();
+        game.paused = false;
+        Replay {
+            keyframes: vec![Keyframe { game, inputs: 0 }],
+            inputs: Vec::new()
+        }
+    }
+
+    pub fn inputs(&self) -> &[Input] {
+        &self.inputs
+    }
+
+    // The game as it was when the replay started.
+    pub fn start(&self) -> &Game {
+        &self.keyframes[0].game
+    }
+
+    // How long the replay is, in the game's ticks.
+    pub fn ticks(&self) -> u64 {
+        let keyframe = self.last_keyframe().game.stats.ticks;
+        self.inputs
+            .last()
+            .map_or(keyframe, |input| input.ticks.max(keyframe))
+    }
+
+    fn last_keyframe(&self) -> &Keyframe {
+        &self.keyframes[self.keyframes.len() - 1]
+    }
+
+    // Keep an input the game took while it was going, if it's one that changes the game. Inputs
+    // have to be recorded in order, and before the tick after theirs.
+    pub fn record(&mut self, input: Input) {
+        if ACTIONS.contains(&input.action) {
+            self.inputs.push(input);
+        }
+    }
+
+    // Called after every tick to take a keyframe when one's due, and at the end of the game. Inputs
+    // pressed before a piece comes in aren't part of the game's saved state, so keyframes wait
+    // until there aren't any.
+    pub fn tick(&mut self, game: &Game) {
+        let last = &self.last_keyframe().game;
+        let due = game.stats.ticks >= last.stats.ticks + KEYFRAME_TICKS && game.piece_in_play();
+        if due || game.game_over && !last.game_over {
+            self.keyframes.push(Keyframe {
+                game: game.clone(),
+                inputs: self.inputs.len()
+            });
+        }
+    }
+
+    // The game as it was `ticks` in, after that tick's inputs. Seeking past the end gives the end.
+    pub fn seek(&self, ticks: u64) -> Game {
+        let keyframe = self
+            .keyframes
+            .partition_point(|keyframe| keyframe.game.stats.ticks <= ticks)
+            .max(1)
+            - 1;
+        let keyframe = &self.keyframes[keyframe];
+        let mut game = keyframe.game.clone();
+        for input in self.inputs[keyframe.inputs..]
+            .iter()
+            .take_while(|input| input.ticks <= ticks)
+        {
+            advance(&mut game, input.ticks);
+            game.handle_action(input.action);
+        }
+        advance(&mut game, ticks);
+        game
+    }
+
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.push(VERSION);
+        let (mut last_ticks, mut last_frame) = (0, 0u64);
+        let mut record = |bytes: &mut Vec<u8>, ticks: u64, kind: u64, frame: Option<u64>| {
+            write_number(bytes, (ticks - last_ticks) << KIND_BITS | kind);
+            last_ticks = ticks;
+            if let Some(frame) = frame {
+                write_number(bytes, frame.wrapping_sub(last_frame));
+                last_frame = frame;
+            }
+        };
+        let kind = |input: &Input| {
+            let kind = ACTIONS.iter().position(|&action| action == input.action);
+            kind.unwrap_or(0) as u64
+        };
+        let mut inputs = self.inputs.iter().enumerate().peekable();
+        for keyframe in &self.keyframes {
+            while let Some((_, input)) = inputs.next_if(|&(i, _)| i < keyframe.inputs) {
+                record(&mut bytes, input.ticks, kind(input), Some(input.frame));
+            }
+            let game = serde_json::to_vec(&keyframe.game).unwrap_or_default();
+            record(&mut bytes, keyframe.game.stats.ticks, KEYFRAME, None);
+            write_number(&mut bytes, game.len() as u64);
+            bytes.extend(game);
+        }
+        for (_, input) in inputs {
+            record(&mut bytes, input.ticks, kind(input), Some(input.frame));
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if bytes.len() < MAGIC.len() + 1 || &bytes[..MAGIC.len()] != MAGIC {
+            return Err("That isn't a replay.".to_string());
+        }
+        let version = bytes[MAGIC.len()];
+        if version != VERSION {
+            return Err(format!(
+                "The replay is version {}, but only version {} can be played.",
+                version, VERSION
+            ));
+        }
+        let mut rest = &bytes[MAGIC.len() + 1..];
+        let (mut ticks, mut frame) = (0u64, 0u64);
+        let mut keyframes = Vec::new();
+        let mut inputs = Vec::new();
+        let broken = || "The replay is broken.".to_string();
+        while !rest.is_empty() {
+            let number = read_number(&mut rest).ok_or_else(broken)?;
+            ticks = ticks.checked_add(number >> KIND_BITS).ok_or_else(broken)?;
+            let kind = number & KEYFRAME;
+            if kind == KEYFRAME {
+                let length = read_number(&mut rest).ok_or_else(broken)? as usize;
+                if length > rest.len() {
+                    return Err(broken());
+                }
+                let game: Game = serde_json::from_slice(&rest[..length]).map_err(|_| broken())?;
+                rest = &rest[length..];
+                if game.stats.ticks != ticks {
+                    return Err(broken());
+                }
+                keyframes.push(Keyframe {
+                    game,
+                    inputs: inputs.len()
+                });
+            } else if keyframes.is_empty() {
+                return Err(broken());
+            } else {
+                let action = *ACTIONS.get(kind as usize).ok_or_else(broken)?;
+                frame = frame.wrapping_add(read_number(&mut rest).ok_or_else(broken)?);
+                inputs.push(Input {
+                    ticks,
+                    frame,
+                    action
+                });
+            }
+        }
+        if keyframes.is_empty() {
+            return Err(broken());
+        }
+        Ok(Replay { keyframes, inputs })
+    }
+}
+
+// Play the game on to `ticks`, or for as long as it lasts.
+fn advance(game: &mut Game, ticks: u64) {
+    while game.stats.ticks < ticks && !game.game_over && !game.paused {
+        game.tick();
+    }
+}
+
+// LEB128: seven bits at a time, lowest first, with the top bit set on every byte but the last.
+fn write_number(bytes: &mut Vec<u8>, mut number: u64) {
+    while number >= 0x80 {
+        bytes.push(number as u8 | 0x80);
+        number >>= 7;
+    }
+    bytes.push(number as u8);
+}
+
+fn read_number(bytes: &mut &[u8]) -> Option<u64> {
+    let mut number = 0u64;
+    for shift in (0..64).step_by(7) {
+        let (&byte, rest) = bytes.split_first()?;
+        *bytes = rest;
+        number |= ((byte & 0x7f) as u64) << shift;
+        if byte & 0x80 == 0 {
+            return Some(number);
+        }
+    }
+    None
+}
+
+// A replay comes back from its file the same, and seeking to any point of it gives the game as it
+// was then, whether or not there's a keyframe there.
+#[test]
+fn test_replay_round_trip() {
+    use crate::rules::Rules;
+
+    let mut game = Game::with_seed(Rules::default(), 3);
+    let mut replay = Replay::new(&game);
+    let mut snapshots = Vec::new();
+    let pattern = [
+        Action::MoveLeft,
+        Action::RotateClockwise,
+        Action::HardDrop,
+        Action::Hold,
+        Action::MoveRight,
+        Action::SonicDrop,
+        Action::HardDrop
+    ];
+    for i in 0..KEYFRAME_TICKS * 3 {
+        if i % 40 == 0 {
+            let action = pattern[(i / 40) as usize % pattern.len()];
+            replay.record(Input {
+                ticks: game.stats.ticks,
+                frame: i,
+                action
+            });
+            game.handle_action(action);
+        }
+        if i % 500 == 0 {
+            snapshots.push(game.clone());
+        }
+        game.tick();
+        replay.tick(&game);
+        if game.game_over {
+            break;
+        }
+    }
+    let bytes = replay.encode();
+    let decoded = Replay::decode(&bytes).unwrap();
+    assert!(decoded.keyframes.len() > 1);
+    assert_eq!(decoded.inputs(), replay.inputs());
+    assert_eq!(decoded.ticks(), replay.ticks());
+    let json = |game: &Game| serde_json::to_string(game).unwrap();
+    for snapshot in snapshots {
+        assert_eq!(json(&decoded.seek(snapshot.stats.ticks)), json(&snapshot));
+    }
+    assert_eq!(json(&decoded.seek(decoded.ticks())), json(&game));
+    assert!(Replay::decode(&bytes[..bytes.len() - 1]).is_err());
+    assert!(Replay::decode(b"TTRP\x03").is_err());
+    let mut older = bytes.clone();
+    older[MAGIC.len()] = 2;
+    assert_eq!(
+        Replay::decode(&older).err().unwrap(),
+        "The replay is version 2, but only version 3 can be played."
+    );
+}
+
+// Numbers come back as they went in, and small ones take a single byte.
+#[test]
+fn test_replay_numbers() {
+    for &number in &[0, 1, 127, 128, 300, u64::from(u32::MAX), u64::MAX] {
+        let mut bytes = Vec::new();
+        write_number(&mut bytes, number);
+        assert_eq!(bytes.len() == 1, number < 128);
+        assert_eq!(read_number(&mut &bytes[..]), Some(number));
+    }
+}
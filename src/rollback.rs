@@ -0,0 +1,230 @@
+use std::collections::VecDeque;
+
+use crate::action::Action;
+use crate::gameboard::Game;
+use crate::timing::TICK_RATE;
+use crate::versus::Match;
+
+// The furthest a match is played on past the last tick everyone's inputs are in for. A player
+// who gets that far ahead waits for the others to catch up rather than guessing any further, which
+// also keeps players who started at slightly different times in step.
+pub const MAX_ROLLBACK: u64 = TICK_RATE / 2;
+
+// Rollback netcode for a network match. This player's inputs are put in `delay` ticks after
+// they're pressed, which gives them that long to reach everyone else before they're needed. The
+// other players' inputs are guessed at until they arrive, as nothing pressed, and the match is
+// played on with the guesses so that it never waits on the network. When a guess turns out to be
+// wrong, the match goes back to the last tick everyone's inputs were in for and plays on again
+// from there with the real ones. Nothing pressed is right for nearly every tick, so that's rare.
+pub struct Rollback {
+    // The match as of the last tick everyone's inputs are in for, and as it's shown.
+    confirmed: Match,
+    predicted: Match,
+    // Each player's inputs from the confirmed tick on, with `None` for those that haven't arrived.
+    inputs: Vec<VecDeque<Option<Vec<Action>>>>,
+    pub player: usize,
+    pub delay: u64,
+    // This player's inputs that haven't been sent to the others yet, and the tick the first of them
+    // is for.
+    unsent: Vec<Vec<Action>>,
+    unsent_from: u64,
+    // Set when inputs have arrived for a tick that's been played with a wrong guess.
+    mispredicted: bool,
+    // How many times the match has gone back, for the debug overlay.
+    pub rollbacks: u64
+}
+
+impl Rollback {
+    pub fn new(game_match: Match, player: usize, delay: u64) -> Self {
+        let mut inputs = vec![VecDeque::new(); game_match.games.len()];
+        // Nothing's pressed on the ticks before this player's first inputs come in, which the
+        // others have to be told as well.
+        inputs[player] = (0..delay).map(|_| Some(Vec::new())).collect();
+        Rollback {
+            confirmed: game_match.clone(),
+            predicted: game_match,
+            inputs,
+            player,
+            delay,
+            unsent: vec![Vec::new(); delay as usize],
+            unsent_from: 0,
+            mispredicted: false,
+            rollbacks: 0
+        }
+    }
+
+    pub fn current(&self) -> &Match {
+        &self.predicted
+    }
+
+    pub fn confirmed(&self) -> &Match {
+        &self.confirmed
+    }
+
+    // This player's game as it's shown.
+    pub fn game(&self) -> &Game {
+        &self.predicted.games[self.player]
+    }
+
+    // Whether the match can be played on a tick without getting too far ahead of the others.
+    pub fn can_advance(&self) -> bool {
+        self.predicted.ticks - self.confirmed.ticks < MAX_ROLLBACK
+    }
+
+    // Play the shown match on a tick, with `actions` pressed by this player to be put in `delay`
+    // ticks from now.
+    pub fn advance(&mut self, actions: Vec<Action>) {
+        let tick = self.predicted.ticks + self.delay;
+        self.unsent.push(actions.clone());
+        self.receive(self.player, tick, actions);
+        self.catch_up();
+        let inputs = self.inputs_at(self.predicted.ticks);
+        self.predicted.advance(&inputs);
+    }
+
+    // This player's inputs that are still to be sent to the others, one list of actions per tick,
+    // with the tick the first is for.
+    pub fn take_unsent(&mut self) -> Option<(u64, Vec<Vec<Action>>)> {
+        if self.unsent.is_empty() {
+            return None;
+        }
+        let tick = self.unsent_from;
+        self.unsent_from += self.unsent.len() as u64;
+        Some((tick, self.unsent.split_off(0)))
+    }
+
+    // Take in what a player pressed on a tick. If it's for a tick that's already been played and
+    // anything was pressed, the guess was wrong.
+    pub fn receive(&mut self, player: usize, tick: u64, actions: Vec<Action>) {
+        if tick < self.confirmed.ticks {
+            return;
+        }
+        let index = (tick - self.confirmed.ticks) as usize;
+        let inputs = &mut self.inputs[player];
+        if inputs.len() <= index {
+            inputs.resize(index + 1, None);
+        }
+        if tick < self.predicted.ticks && !actions.is_empty() {
+            self.mispredicted = true;
+        }
+        inputs[index] = Some(actions);
+    }
+
+    // Take a player out of the match from `tick` on, once they've left. Nothing they pressed
+    // after that counts.
+    pub fn leave(&mut self, player: usize, tick: u64) {
+        self.confirmed.leave(player, tick);
+        self.predicted.leave(player, tick);
+        if tick < self.predicted.ticks {
+            self.mispredicted = true;
+        }
+    }
+
+    // The tick up to which a player's inputs have all arrived.
+    pub fn received_to(&self, player: usize) -> u64 {
+        let arrived = self.inputs[player]
+            .iter()
+            .take_while(|actions| actions.is_some())
+            .count();
+        self.confirmed.ticks + arrived as u64
+    }
+
+    // Play the confirmed match on as far as everyone's inputs go, and then the shown match again
+    // from there if it was played with a wrong guess. The confirmed match never gets ahead of the
+    // shown one, so this player's inputs are always there for it.
+    pub fn catch_up(&mut self) {
+        while self.confirmed.ticks < self.predicted.ticks {
+            let inputs = match self.arrived() {
+                Some(inputs) => inputs,
+                None => break
+            };
+            for inputs in self.inputs.iter_mut() {
+                inputs.pop_front();
+            }
+            self.confirmed.advance(&inputs);
+        }
+        if !self.mispredicted {
+            return;
+        }
+        self.mispredicted = false;
+        self.rollbacks += 1;
+        let ticks = self.predicted.ticks;
+        self.predicted = self.confirmed.clone();
+        while self.predicted.ticks < ticks {
+            let inputs = self.inputs_at(self.predicted.ticks);
+            self.predicted.advance(&inputs);
+        }
+    }
+
+    // Everyone's inputs for the tick after the confirmed one, if they're all in. Players who've
+    // left don't press anything.
+    fn arrived(&self) -> Option<Vec<Vec<Action>>> {
+        (0..self.inputs.len())
+            .map(|player| match self.inputs[player].front() {
+                Some(Some(actions)) => Some(actions.clone()),
+                _ if self.confirmed.has_left(player) => Some(Vec::new()),
+                _ => None
+            })
+            .collect()
+    }
+
+    // Everyone's inputs for a tick, guessing nothing pressed for any that haven't arrived.
+    fn inputs_at(&self, tick: u64) -> Vec<Vec<Action>> {
+        let index = (tick - self.confirmed.ticks) as usize;
+        self.inputs
+            .iter()
+            .map(|inputs| inputs.get(index).cloned().flatten().unwrap_or_default())
+            .collect()
+    }
+}
+
+// However late the other player's inputs turn up, the match comes out the same as if it had had
+// them all along, and it waits instead of getting too far ahead.
+#[test]
+fn test_late_inputs() {
+    use crate::rules::Rules;
+    use crate::versus::Targeting;
+
+    let players = [
+        (Rules::default(), Targeting::Random),
+        (Rules::default(), Targeting::Random)
+    ];
+    let game_match = Match::new(7, &players);
+    let pressed = |tick: u64, player: u64| match (tick + player * 5) % 20 {
+        0 => vec![Action::MoveLeft],
+        5 => vec![Action::RotateClockwise],
+        10 => vec![Action::HardDrop],
+        _ => Vec::new()
+    };
+    let mut lockstep = game_match.clone();
+    let mut rollback = Rollback::new(game_match, 0, 2);
+    let mut sent = Vec::new();
+    for tick in 0..MAX_ROLLBACK {
+        assert!(rollback.can_advance());
+        rollback.advance(pressed(tick + 2, 0));
+        sent.push(pressed(tick, 1));
+    }
+    let (from, unsent) = rollback.take_unsent().unwrap();
+    assert_eq!((from, unsent.len() as u64), (0, MAX_ROLLBACK + 2));
+    assert!(unsent[..2].iter().all(Vec::is_empty));
+    assert_eq!(unsent[5], pressed(5, 0));
+    assert!(!rollback.can_advance());
+    for (tick, actions) in sent.into_iter().enumerate() {
+        rollback.receive(1, tick as u64, actions);
+    }
+    rollback.catch_up();
+    assert!(rollback.rollbacks > 0 && rollback.can_advance());
+    for tick in 0..MAX_ROLLBACK {
+        let local = if tick < 2 {
+            Vec::new()
+        } else {
+            pressed(tick, 0)
+        };
+        lockstep.advance(&[local, pressed(tick, 1)]);
+    }
+    for (shown, expected) in rollback.current().games.iter().zip(lockstep.games.iter()) {
+        assert_eq!(shown.board.stack_height(), expected.board.stack_height());
+        assert_eq!(shown.stats.pieces, expected.stats.pieces);
+        assert_eq!(shown.active_piece.cells(), expected.active_piece.cells());
+    }
+}
@@ -0,0 +1,529 @@
+use std::fmt::{self, Display};
+
+use crate::tetromino::Tetromino;
+
+// Number of logic ticks it takes for a piece to fall one row at each level. Levels past the end of
+// the table use the last entry.
+const GRAVITY_TABLE: [u32; 30] = [
+    48, 43, 38, 33, 28, 23, 18, 13, 8, 6, 5, 5, 5, 4, 4, 4, 3, 3, 3, 2, 2, 2, 2, 2, 2, 2, 2, 2, 2,
+    1
+];
+// Base score for clearing one to four lines at once, multiplied by (level + 1).
+const LINE_CLEAR_SCORES: [u64; 4] = [40, 100, 300, 1200];
+// Master mode is split into sections of this many levels, and clears are scored by section.
+pub const MASTER_SECTION: usize = 100;
+// In master mode pieces wait at the top for this many ticks before they fall, unless the rules
+// give an entry delay of their own, so that a rotation pressed in the meantime decides how they
+// land (IRS).
+pub(crate) const MASTER_ENTRY_DELAY: u32 = 25;
+// Ticks a piece in master mode can rest on the stack before it locks, unless the rules give a lock
+// delay of their own.
+pub(crate) const MASTER_LOCK_DELAY: u32 = 30;
+// Master mode levels stop before each new section until a line is cleared, and the game is
+// complete at the final level.
+const MASTER_FINAL_LEVEL: usize = 999;
+// Score needed for each master mode grade, from 9 up to S9. Finishing with S9 gives GM.
+const MASTER_GRADES: [(u64, &str); 18] = [
+    (0, "9"),
+    (400, "8"),
+    (800, "7"),
+    (1400, "6"),
+    (2000, "5"),
+    (3500, "4"),
+    (5500, "3"),
+    (8000, "2"),
+    (12000, "1"),
+    (16000, "S1"),
+    (22000, "S2"),
+    (30000, "S3"),
+    (40000, "S4"),
+    (52000, "S5"),
+    (66000, "S6"),
+    (82000, "S7"),
+    (100_000, "S8"),
+    (120_000, "S9")
+];
+
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum Mode {
+    Classic,
+    Modern,
+    // TGM-style 20G: pieces fall straight onto the stack, with lock delay to move them around.
+    Master
+}
+
+impl Mode {
+    pub fn rule_set(self) -> &'static dyn RuleSet {
+        match self {
+            Mode::Classic => &ClassicRules,
+            Mode::Modern => &ModernRules,
+            Mode::Master => &MasterRules
+        }
+    }
+}
+
+impl Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Mode::Classic => "classic",
+                Mode::Modern => "modern",
+                Mode::Master => "master"
+            }
+        )
+    }
+}
+
+// How the order pieces are dealt in is decided.
+#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+pub enum Randomizer {
+    // Each group of seven pieces is one of every piece, shuffled.
+    Bag
+}
+
+// Everything about how the game plays that depends on the mode. The game asks its mode's rule set
+// rather than checking the mode, so a new mode only needs a new rule set.
+pub trait RuleSet {
+    // Offsets to try, in order, when rotating `tetromino` out of `rotation`. The first is always no
+    // offset at all.
+    fn kicks(
+        &self,
+        tetromino: Tetromino,
+        rotation: usize,
+        clockwise: bool
+    ) -> &'static [(isize, isize)];
+    // Points for clearing `lines` lines at once at `level`.
+    fn line_clear_score(&self, lines: usize, level: usize) -> u64;
+    // Ticks it takes a piece to fall a row at `level`, or `None` if pieces drop straight onto the
+    // stack (20G).
+    fn gravity(&self, level: usize) -> Option<u32>;
+    fn has_hold(&self) -> bool;
+    // Hard and sonic drops.
+    fn has_hard_drop(&self) -> bool;
+    fn has_ghost(&self) -> bool;
+    // How many upcoming pieces are shown, up to 7.
+    fn preview_count(&self) -> usize;
+    fn randomizer(&self) -> Randomizer;
+    // Ticks from a piece being dealt until it comes into play, unless the rules say otherwise.
+    fn entry_delay(&self) -> u32;
+    // Ticks a piece can rest on the stack before it locks unless the rules say otherwise, or
+    // `None` to lock as soon as gravity can't move it.
+    fn lock_delay(&self) -> Option<u32>;
+    // Whether soft dropping a piece that's resting on the stack locks it straight away rather than
+    // leaving it to the lock delay, unless the rules say otherwise.
+    fn soft_drop_locks(&self) -> bool;
+    // The level after clearing `lines` lines at `level`, or `None` to go by the rules' level goal.
+    fn level_after_clear(&self, level: usize, lines: usize) -> Option<usize>;
+    // The level after a piece locks at `level`, which only changes where every piece counts.
+    fn advance_level(&self, level: usize) -> usize;
+    // The level a game at `level` is held at until a line is cleared, if there is one.
+    fn level_stop(&self, level: usize) -> Option<usize>;
+    // The level that completes the game, if there is one.
+    fn final_level(&self) -> Option<usize>;
+    // What a game with `score` at `level` is graded, if the mode grades games.
+    fn grade(&self, score: u64, level: usize) -> Option<&'static str>;
+    // Whether the rules' `const_level` can hold the level in place.
+    fn allows_const_level(&self) -> bool;
+    // How many levels make up each section of a game, as split timers count them.
+    fn section_size(&self) -> usize;
+}
+
+// NES-style: no wall kicks, hold, hard drops, ghost or preview.
+pub struct ClassicRules;
+
+impl RuleSet for ClassicRules {
+    fn kicks(
+        &self,
+        tetromino: Tetromino,
+        rotation: usize,
+        clockwise: bool
+    ) -> &'static [(isize, isize)] {
+        &tetromino.kicks(rotation, clockwise)[..1]
+    }
+
+    fn line_clear_score(&self, lines: usize, level: usize) -> u64 {
+        LINE_CLEAR_SCORES[lines.min(4) - 1] * (level as u64 + 1)
+    }
+
+    fn gravity(&self, level: usize) -> Option<u32> {
+        Some(GRAVITY_TABLE[level.min(GRAVITY_TABLE.len() - 1)])
+    }
+
+    fn has_hold(&self) -> bool {
+        false
+    }
+
+    fn has_hard_drop(&self) -> bool {
+        false
+    }
+
+    fn has_ghost(&self) -> bool {
+        false
+    }
+
+    fn preview_count(&self) -> usize {
+        0
+    }
+
+    fn randomizer(&self) -> Randomizer {
+        Randomizer::Bag
+    }
+
+    fn entry_delay(&self) -> u32 {
+        0
+    }
+
+    fn lock_delay(&self) -> Option<u32> {
+        None
+    }
+
+    fn soft_drop_locks(&self) -> bool {
+        false
+    }
+
+    fn level_after_clear(&self, _level: usize, _lines: usize) -> Option<usize> {
+        None
+    }
+
+    fn advance_level(&self, level: usize) -> usize {
+        level
+    }
+
+    fn level_stop(&self, _level: usize) -> Option<usize> {
+        None
+    }
+
+    fn final_level(&self) -> Option<usize> {
+        None
+    }
+
+    fn grade(&self, _score: u64, _level: usize) -> Option<&'static str> {
+        None
+    }
+
+    fn allows_const_level(&self) -> bool {
+        true
+    }
+
+    fn section_size(&self) -> usize {
+        1
+    }
+}
+
+// Guideline-style: SRS wall kicks, hold, hard drops, a ghost piece and four pieces of preview.
+pub struct ModernRules;
+
+impl RuleSet for ModernRules {
+    fn kicks(
+        &self,
+        tetromino: Tetromino,
+        rotation: usize,
+        clockwise: bool
+    ) -> &'static [(isize, isize)] {
+        &tetromino.kicks(rotation, clockwise)[..]
+    }
+
+    fn line_clear_score(&self, lines: usize, level: usize) -> u64 {
+        ClassicRules.line_clear_score(lines, level)
+    }
+
+    fn gravity(&self, level: usize) -> Option<u32> {
+        ClassicRules.gravity(level)
+    }
+
+    fn has_hold(&self) -> bool {
+        true
+    }
+
+    fn has_hard_drop(&self) -> bool {
+        true
+    }
+
+    fn has_ghost(&self) -> bool {
+        true
+    }
+
+    fn preview_count(&self) -> usize {
+        4
+    }
+
+    fn randomizer(&self) -> Randomizer {
+        Randomizer::Bag
+    }
+
+    fn entry_delay(&self) -> u32 {
+        0
+    }
+
+    fn lock_delay(&self) -> Option<u32> {
+        None
+    }
+
+    fn soft_drop_locks(&self) -> bool {
+        false
+    }
+
+    fn level_after_clear(&self, level: usize, lines: usize) -> Option<usize> {
+        ClassicRules.level_after_clear(level, lines)
+    }
+
+    fn advance_level(&self, level: usize) -> usize {
+        ClassicRules.advance_level(level)
+    }
+
+    fn level_stop(&self, level: usize) -> Option<usize> {
+        ClassicRules.level_stop(level)
+    }
+
+    fn final_level(&self) -> Option<usize> {
+        ClassicRules.final_level()
+    }
+
+    fn grade(&self, score: u64, level: usize) -> Option<&'static str> {
+        ClassicRules.grade(score, level)
+    }
+
+    fn allows_const_level(&self) -> bool {
+        ClassicRules.allows_const_level()
+    }
+
+    fn section_size(&self) -> usize {
+        ClassicRules.section_size()
+    }
+}
+
+// TGM-style 20G on top of the modern rules, with entry and lock delays. Clears are scored by
+// section rather than level, since levels go up to 999.
+pub struct MasterRules;
+
+impl RuleSet for MasterRules {
+    fn kicks(
+        &self,
+        tetromino: Tetromino,
+        rotation: usize,
+        clockwise: bool
+    ) -> &'static [(isize, isize)] {
+        ModernRules.kicks(tetromino, rotation, clockwise)
+    }
+
+    fn line_clear_score(&self, lines: usize, level: usize) -> u64 {
+        LINE_CLEAR_SCORES[lines.min(4) - 1] * ((level / MASTER_SECTION) as u64 + 1)
+    }
+
+    fn gravity(&self, _level: usize) -> Option<u32> {
+        None
+    }
+
+    fn has_hold(&self) -> bool {
+        true
+    }
+
+    fn has_hard_drop(&self) -> bool {
+        true
+    }
+
+    fn has_ghost(&self) -> bool {
+        true
+    }
+
+    fn preview_count(&self) -> usize {
+        4
+    }
+
+    fn randomizer(&self) -> Randomizer {
+        Randomizer::Bag
+    }
+
+    fn entry_delay(&self) -> u32 {
+        MASTER_ENTRY_DELAY
+    }
+
+    fn lock_delay(&self) -> Option<u32> {
+        Some(MASTER_LOCK_DELAY)
+    }
+
+    fn soft_drop_locks(&self) -> bool {
+        true
+    }
+
+    // Levels go up a line at a time, to the final level.
+    fn level_after_clear(&self, level: usize, lines: usize) -> Option<usize> {
+        Some((level + lines).min(MASTER_FINAL_LEVEL))
+    }
+
+    // Every piece counts for a level, except that the level can't reach the end of a section or
+    // the final level without a line clear.
+    fn advance_level(&self, level: usize) -> usize {
+        let next = level + 1;
+        if next.is_multiple_of(MASTER_SECTION) || next >= MASTER_FINAL_LEVEL {
+            level
+        } else {
+            next
+        }
+    }
+
+    fn level_stop(&self, level: usize) -> Option<usize> {
+        Some(((level / MASTER_SECTION + 1) * MASTER_SECTION - 1).min(MASTER_FINAL_LEVEL))
+    }
+
+    fn final_level(&self) -> Option<usize> {
+        Some(MASTER_FINAL_LEVEL)
+    }
+
+    fn grade(&self, score: u64, level: usize) -> Option<&'static str> {
+        let (threshold, grade) = *MASTER_GRADES
+            .iter()
+            .rev()
+            .find(|&&(threshold, _)| score >= threshold)
+            .unwrap();
+        if level >= MASTER_FINAL_LEVEL && threshold == MASTER_GRADES[17].0 {
+            Some("GM")
+        } else {
+            Some(grade)
+        }
+    }
+
+    // Levels are how the game is completed, so they can't be held.
+    fn allows_const_level(&self) -> bool {
+        false
+    }
+
+    fn section_size(&self) -> usize {
+        MASTER_SECTION
+    }
+}
+
+// How many lines it takes to go up a level.
+#[derive(Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+pub enum LevelGoal {
+    // The same number of lines every level.
+    Fixed,
+    // Guideline style: each level needs more than the last, and bigger clears count for more.
+    Variable
+}
+
+impl Display for LevelGoal {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                LevelGoal::Fixed => "fixed",
+                LevelGoal::Variable => "variable"
+            }
+        )
+    }
+}
+
+// What a player gives up to even out a versus game. None of it applies outside versus.
+#[derive(Copy, Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub struct Handicap {
+    // Rows of garbage under the stack when the game starts.
+    pub garbage_rows: usize,
+    // Levels added to (or taken off) the level gravity is worked out from.
+    pub gravity_offset: isize,
+    // How many pieces of the preview are shown, or `None` for all of them.
+    pub preview: Option<usize>,
+    // Percent of the lines each attack would send that actually get sent.
+    pub attack_percent: u64
+}
+
+impl Default for Handicap {
+    fn default() -> Self {
+        Handicap {
+            garbage_rows: 0,
+            gravity_offset: 0,
+            preview: None,
+            attack_percent: 100
+        }
+    }
+}
+
+// The settings that change how the game plays, as opposed to how it looks or is controlled.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Rules {
+    pub board_width: usize,
+    pub board_height: usize,
+    // Rows above the visible board, for pieces to spawn and rotate in.
+    pub hidden_rows: usize,
+    pub mode: Mode,
+    // Blocks left floating by a line clear fall in connected groups, and can clear more lines.
+    pub cascade: bool,
+    // Every mino is a 2x2 block of squares, as in TGM's big mode. Pieces move sideways two squares
+    // at a time but still fall one at a time, so lines can clear through the middle of a piece.
+    pub big: bool,
+    // How many times hold can be used on each piece, or `None` for as often as wanted.
+    pub hold_limit: Option<usize>,
+    // Lines per level with a fixed goal. With a variable goal the first level takes half this
+    // many, and each one after takes that many more again.
+    pub lines_per_level: usize,
+    // A level the game starts at and never leaves, outside master mode.
+    pub const_level: Option<usize>,
+    pub level_goal: LevelGoal,
+    // Milliseconds between a piece locking and the next one coming into play, or `None` for the
+    // mode's own, which is only more than 0 in master mode.
+    pub entry_delay: Option<u64>,
+    // Logic ticks added to the entry delay after a lock that clears lines.
+    pub line_clear_delay: u32,
+    // Milliseconds a piece can rest on the stack before it locks. `None` locks it as soon as
+    // gravity can't move it, except in master mode, which has a lock delay of its own.
+    pub lock_delay: Option<u64>,
+    // How many times moving or rotating a piece on the stack can restart its lock delay before it
+    // has to fall again, or `None` for no limit.
+    pub lock_resets: Option<usize>,
+    // Soft dropping a piece that's resting on the stack locks it at once, or `None` to go by the
+    // mode's rule set. Games saved before this existed go by the rule set.
+    #[serde(default)]
+    pub soft_drop_lock: Option<bool>,
+    // Percent chance that each garbage row's hole is in the same column as the last one's.
+    pub garbage_hole_repeat: usize,
+    // The casual item mode, where some locked pieces carry items that clearing their line picks
+    // up.
+    #[serde(default)]
+    pub items: bool,
+    // Only applies to versus games.
+    #[serde(default)]
+    pub handicap: Handicap
+}
+
+impl Default for Rules {
+    fn default() -> Self {
+        Rules {
+            board_width: 10,
+            board_height: 20,
+            hidden_rows: 2,
+            mode: Mode::Modern,
+            cascade: false,
+            big: false,
+            hold_limit: Some(1),
+            lines_per_level: 10,
+            const_level: None,
+            level_goal: LevelGoal::Fixed,
+            entry_delay: None,
+            line_clear_delay: 0,
+            lock_delay: None,
+            lock_resets: Some(0),
+            soft_drop_lock: None,
+            garbage_hole_repeat: 70,
+            items: false,
+            handicap: Handicap::default()
+        }
+    }
+}
+
+impl Rules {
+    pub fn rule_set(&self) -> &'static dyn RuleSet {
+        self.mode.rule_set()
+    }
+
+    // How many squares across each mino is.
+    pub fn scale(&self) -> isize {
+        if self.big {
+            2
+        } else {
+            1
+        }
+    }
+}
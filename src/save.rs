@@ -0,0 +1,72 @@
+use crate::serde_json;
+use std::fs::{create_dir_all, read_to_string, remove_file, File};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::path::PathBuf;
+use tui_tetris::gameboard::Game;
+
+use crate::paths;
+
+const SAVE_FILE: &str = "saved_game.json";
+
+fn path() -> IoResult<PathBuf> {
+    paths::data_dir()
+        .map(|dir| dir.join(SAVE_FILE))
+        .ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))
+}
+
+// Keep an unfinished game to carry on with next time, replacing any game saved before. Returns
+// where it went.
+pub fn save(game: &Game) -> IoResult<PathBuf> {
+    let path = path()?;
+    if let Some(dir) = path.parent() {
+        create_dir_all(dir)?;
+    }
+    let contents = serde_json::to_string(game)?;
+    File::create(&path)?.write_all(contents.as_bytes())?;
+    Ok(path)
+}
+
+// The saved game, ready to carry on with, or `None` if there isn't one.
+pub fn load() -> IoResult<Option<Game>> {
+    let contents = match read_to_string(path()?) {
+        Ok(contents) => contents,
+        Err(ref e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e)
+    };
+    let mut game: Game = serde_json::from_str(&contents)?;
+    if !game.resume() {
+        return Err(IoError::new(
+            ErrorKind::InvalidData,
+            "The saved game isn't a game in progress"
+        ));
+    }
+    Ok(Some(game))
+}
+
+// Once a saved game has been picked up again it's gone, so that it can't be played twice.
+pub fn remove() -> IoResult<()> {
+    match remove_file(path()?) {
+        Err(ref e) if e.kind() == ErrorKind::NotFound => Ok(()),
+        removed => removed
+    }
+}
+
+// A game should carry on from a save with the same pieces coming up.
+#[test]
+fn test_save_round_trip() {
+    use tui_tetris::action::Action;
+    use tui_tetris::rules::Rules;
+    let mut game = Game::with_seed(Rules::default(), 7);
+    for _ in 0..4 {
+        game.handle_action(Action::HardDrop);
+    }
+    let mut loaded: Game = serde_json::from_str(&serde_json::to_string(&game).unwrap()).unwrap();
+    assert!(loaded.resume());
+    loaded.paused = false;
+    assert_eq!(loaded.score, game.score);
+    for _ in 0..20 {
+        assert_eq!(loaded.queue(), game.queue());
+        game.handle_action(Action::HardDrop);
+        loaded.handle_action(Action::HardDrop);
+    }
+}
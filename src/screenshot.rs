@@ -0,0 +1,210 @@
+use std::fs::{create_dir_all, File};
+use std::io::{Error as IoError, ErrorKind, Result as IoResult, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::paths;
+
+const SCREENSHOTS_DIR: &str = "screenshots";
+const RESET: &str = "\x1B[0m";
+
+// One square of the screen: what's in it and the color escapes it was drawn with.
+#[derive(Clone, Default, PartialEq)]
+struct Square {
+    character: char,
+    fg: Option<String>,
+    bg: Option<String>
+}
+
+// The screen as a frame draws it, worked out from the frame's escape sequences. Frames only move
+// the cursor and set colors besides writing text, so everything else is skipped over.
+#[derive(Default)]
+pub struct Screen {
+    rows: Vec<Vec<Square>>
+}
+
+impl Screen {
+    pub fn of(frame: &[u8]) -> Self {
+        let mut screen = Screen::default();
+        let (mut x, mut y) = (0, 0);
+        let (mut fg, mut bg) = (None, None);
+        let text = String::from_utf8_lossy(frame);
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '\x1B' {
+                if c == '\n' {
+                    x = 0;
+                    y += 1;
+                } else if !c.is_control() {
+                    screen.put(x, y, c, &fg, &bg);
+                    x += 1;
+                }
+                continue;
+            }
+            if chars.next_if_eq(&'[').is_none() {
+                continue;
+            }
+            let mut params = String::new();
+            let end = loop {
+                match chars.next() {
+                    Some(c) if c.is_ascii_digit() || c == ';' || c == '?' => params.push(c),
+                    end => break end
+                }
+            };
+            match end {
+                // `Goto`, which counts from one.
+                Some('H') => {
+                    let mut numbers = params.split(';').map(|n| n.parse::<usize>().unwrap_or(1));
+                    y = numbers.next().unwrap_or(1).saturating_sub(1);
+                    x = numbers.next().unwrap_or(1).saturating_sub(1);
+                }
+                Some('m') => {
+                    let escape = format!("\x1B[{}m", params);
+                    let first = params.split(';').next().unwrap_or("");
+                    match first.parse::<u8>().unwrap_or(0) {
+                        0 => {
+                            fg = None;
+                            bg = None;
+                        }
+                        39 => fg = None,
+                        49 => bg = None,
+                        30..=38 | 90..=97 => fg = Some(escape),
+                        40..=48 | 100..=107 => bg = Some(escape),
+                        _ => {}
+                    }
+                }
+                _ => {}
+            }
+        }
+        screen
+    }
+
+    fn put(
+        &mut self,
+        x: usize,
+        y: usize,
+        character: char,
+        fg: &Option<String>,
+        bg: &Option<String>
+    ) {
+        if self.rows.len() <= y {
+            self.rows.resize(y + 1, Vec::new());
+        }
+        let row = &mut self.rows[y];
+        if row.len() <= x {
+            row.resize(
+                x + 1,
+                Square {
+                    character: ' ',
+                    ..Square::default()
+                }
+            );
+        }
+        row[x] = Square {
+            character,
+            fg: fg.clone(),
+            bg: bg.clone()
+        };
+    }
+
+    // How many columns and rows the screen takes up.
+    pub fn size(&self) -> (usize, usize) {
+        let columns = self.rows.iter().map(Vec::len).max().unwrap_or(0);
+        (columns, self.rows.len())
+    }
+
+    // What has to be drawn over `previous` to turn it into this screen.
+    pub fn changes_from(&self, previous: &Screen) -> String {
+        let mut changes = String::new();
+        for (y, row) in self.rows.iter().enumerate() {
+            // Where the cursor is after the last change, so that a run of changes only moves it once.
+            let mut cursor = None;
+            for (x, square) in row.iter().enumerate() {
+                let before = previous.rows.get(y).and_then(|row| row.get(x));
+                if before == Some(square) {
+                    continue;
+                }
+                if cursor != Some(x) {
+                    changes.push_str(&format!("\x1B[{};{}H", y + 1, x + 1));
+                }
+                changes.push_str(RESET);
+                changes.extend(square.fg.iter().chain(square.bg.iter()).map(String::as_str));
+                changes.push(square.character);
+                cursor = Some(x + 1);
+            }
+        }
+        changes
+    }
+
+    // The screen with its colors, as ANSI art.
+    pub fn ansi(&self) -> String {
+        let mut ansi = String::new();
+        for row in &self.rows {
+            let mut last = (&None, &None);
+            for square in row {
+                if (&square.fg, &square.bg) != last {
+                    ansi.push_str(RESET);
+                    ansi.extend(square.fg.iter().chain(square.bg.iter()).map(String::as_str));
+                    last = (&square.fg, &square.bg);
+                }
+                ansi.push(square.character);
+            }
+            ansi.push_str(RESET);
+            ansi.push('\n');
+        }
+        ansi
+    }
+
+    // Just the text, with nothing after the end of each line.
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for row in &self.rows {
+            let line = row
+                .iter()
+                .map(|square| square.character)
+                .collect::<String>();
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+
+    // Write the screen as `.ans` and `.txt` files named after when they were taken, in the
+    // screenshots folder of the data directory. Returns where the ANSI one went.
+    pub fn save(&self) -> IoResult<PathBuf> {
+        let millis = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |since| since.as_millis());
+        let path = paths::data_dir()
+            .map(|dir| {
+                dir.join(SCREENSHOTS_DIR)
+                    .join(format!("screen-{}.ans", millis))
+            })
+            .ok_or_else(|| IoError::new(ErrorKind::NotFound, "No data directory"))?;
+        if let Some(dir) = path.parent() {
+            create_dir_all(dir)?;
+        }
+        File::create(&path)?.write_all(self.ansi().as_bytes())?;
+        File::create(path.with_extension("txt"))?.write_all(self.text().as_bytes())?;
+        Ok(path)
+    }
+}
+
+// Text lands where the cursor was sent, in the colors last set, and the plain copy leaves them out.
+#[test]
+fn test_screen() {
+    let frame = "\x1B[?25l\x1B[31m\x1B[2;3Hab\x1B[0m\x1B[1;1Hx\x1B[44m\x1B[2;6Hc";
+    let screen = Screen::of(frame.as_bytes());
+    assert_eq!(screen.text(), "x\n  ab c\n");
+    assert_eq!(
+        screen.ansi(),
+        "x\x1B[0m\n  \x1B[0m\x1B[31mab\x1B[0m \x1B[0m\x1B[44mc\x1B[0m\n"
+    );
+    assert_eq!(screen.size(), (6, 2));
+    assert!(screen.changes_from(&screen).is_empty());
+    let moved = Screen::of("\x1B[1;1Hx\x1B[2;3Hab\x1B[2;6Hd".as_bytes());
+    assert_eq!(
+        moved.changes_from(&screen),
+        "\x1B[2;3H\x1B[0ma\x1B[0mb\x1B[2;6H\x1B[0md"
+    );
+}
@@ -0,0 +1,972 @@
+use std::collections::VecDeque;
+use std::mem;
+use std::time::{Duration, Instant};
+use tui_tetris::challenge::Challenge;
+use tui_tetris::fumen::Position;
+use tui_tetris::gameboard::{Game, GameBoard};
+use tui_tetris::replay::{Input, Replay};
+use tui_tetris::rollback::Rollback;
+use tui_tetris::rules::Rules;
+use tui_tetris::timing::TICK_RATE;
+use tui_tetris::versus::{AttackLog, Targeting};
+
+use crate::art::Art;
+use crate::color::ColorSupport;
+use crate::game_config::GameConfig;
+use crate::input::{Action, InputLog};
+use crate::leaderboard::Leaderboard;
+use crate::locale::Strings;
+use crate::net::{pick_targets, player_of, RemoteBoard};
+use crate::records::{Ghost, LifetimeStats, Measure, NameEntry};
+use crate::settings_menu::SettingsMenu;
+
+// How long "GO" stays up once the countdown is over.
+const GO_TICKS: u64 = TICK_RATE / 2;
+// How long board effects last, and how many ticks each step of a shake or flash takes.
+const EFFECT_TICKS: u64 = TICK_RATE / 4;
+const EFFECT_STEP_TICKS: u64 = 3;
+// How long the end of an attempt stays up before the trainer starts the next one.
+const TRAINER_RESET_TICKS: u64 = TICK_RATE * 2;
+// How far back each rewind goes, how often practice games are snapshotted for it, and how many
+// snapshots are kept.
+const REWIND_TICKS: u64 = TICK_RATE * 5;
+const REWIND_INTERVAL: u64 = TICK_RATE / 4;
+const REWIND_HISTORY: usize = 240;
+// How many placements can be undone in a row.
+const UNDO_PLACEMENTS: usize = 30;
+// The most items the pause menu can have.
+pub const PAUSE_ITEMS: usize = 4;
+// How many lines each split is when racing for lines.
+const SPLIT_LINES: usize = 10;
+// How often the board is looked at for a new frame of the ghost being recorded.
+const GHOST_INTERVAL: u64 = TICK_RATE / 10;
+
+// What can be picked from the pause menu.
+#[derive(Copy, Clone, Eq, PartialEq)]
+pub enum PauseItem {
+    Continue,
+    Settings,
+    Statistics,
+    Leaderboard
+}
+
+impl PauseItem {
+    pub fn label(self, text: &Strings) -> &'static str {
+        match self {
+            PauseItem::Continue => text.continue_save,
+            PauseItem::Settings => text.settings,
+            PauseItem::Statistics => text.statistics,
+            PauseItem::Leaderboard => text.leaderboard
+        }
+    }
+}
+
+// How many rows from the floor to the top of the stack.
+pub fn stack_height(game: &Game) -> usize {
+    game.board.stack_height()
+}
+
+// Whether a stack this high is past the danger line, `danger_height` percent of the way up the
+// board. A danger height of 0 means there's no line.
+pub fn over_danger_line(stack_height: usize, board_height: usize, danger_height: usize) -> bool {
+    danger_height > 0 && stack_height * 100 > board_height * danger_height
+}
+
+// Something the board does briefly to mark a big clear.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Effect {
+    // Knocked one column to the right and back, for Tetrises.
+    Shake,
+    // The border blinks, for T-spin clears.
+    Flash
+}
+
+// Where the time went in the last frame, for the debug overlay.
+#[derive(Copy, Clone, Default)]
+pub struct FrameTimings {
+    pub(crate) frame: Duration,
+    pub(crate) tick: Duration,
+    pub(crate) render: Duration,
+    pub(crate) dropped_frames: u64,
+    // From the first action of a frame being given to the game to the frame showing what it did
+    // being written out, for the last frame that had any.
+    pub(crate) input_latency: Option<Duration>
+}
+
+// When each section of a game was finished for the speedrun timer, with the best run of the same
+// kind to compare against. Games played for lines are split every `SPLIT_LINES` lines, master mode
+// at every section, and everything else at every level.
+pub struct Splits {
+    by_lines: bool,
+    // Lines or levels in each section.
+    size: usize,
+    // Sections already finished when the timer started, which only a saved game carried on with
+    // has.
+    pub(crate) base: usize,
+    // Ticks of play at the end of each section, and the same for the best run.
+    pub(crate) times: Vec<u64>,
+    pub(crate) best: Vec<u64>
+}
+
+impl Splits {
+    pub fn new(game: &Game) -> Self {
+        let by_lines = game
+            .challenge
+            .as_ref()
+            .is_some_and(|challenge| challenge.goal.lines.is_some());
+        let size = if by_lines {
+            SPLIT_LINES
+        } else {
+            game.rules.rule_set().section_size()
+        };
+        let mut splits = Splits {
+            by_lines,
+            size,
+            base: 0,
+            times: Vec::new(),
+            best: Vec::new()
+        };
+        splits.base = splits.sections(game);
+        splits
+    }
+
+    // Which best run this game is compared with, by its mode and challenge.
+    pub fn course(game: &Game) -> String {
+        match game.challenge {
+            Some(ref challenge) => format!("{} {}", game.rules.mode, challenge.name),
+            None => game.rules.mode.to_string()
+        }
+    }
+
+    fn sections(&self, game: &Game) -> usize {
+        if self.by_lines {
+            game.lines_cleared / self.size
+        } else {
+            game.level / self.size
+        }
+    }
+
+    // Time the sections finished since the last update. Going back in a practice game takes back
+    // the ones finished since.
+    pub fn update(&mut self, game: &Game) {
+        let finished = self.sections(game).saturating_sub(self.base);
+        self.times.truncate(finished);
+        while self.times.len() < finished {
+            self.times.push(game.stats.ticks);
+        }
+    }
+
+    // The lines or level the split at `index` was taken at.
+    pub fn label(&self, index: usize) -> usize {
+        (self.base + index + 1) * self.size
+    }
+
+    // Start again for a new game, keeping the best run.
+    pub fn restart(&mut self, game: &Game) {
+        let best = mem::take(&mut self.best);
+        *self = Splits::new(game);
+        self.best = best;
+    }
+}
+
+// A game being played in the terminal, along with everything that's shown around it.
+// Plays an opener over and over from the same board and pieces, keeping count of how many attempts
+// built it.
+#[derive(Clone)]
+pub struct Trainer {
+    position: Position,
+    pub(crate) challenge: Challenge,
+    pub(crate) attempts: u64,
+    pub(crate) successes: u64,
+    // Ticks left before the next attempt, once the last one is over.
+    reset_ticks: Option<u64>
+}
+
+impl Trainer {
+    pub fn new(position: Position, challenge: Challenge) -> Self {
+        Trainer {
+            position,
+            challenge,
+            attempts: 0,
+            successes: 0,
+            reset_ticks: None
+        }
+    }
+
+    pub fn attempt(&self, rules: Rules) -> Result<Game, String> {
+        let mut game = Game::new(rules);
+        game.start_from(self.position.clone())?;
+        game.challenge = Some(self.challenge.clone());
+        Ok(game)
+    }
+}
+
+pub struct Session {
+    pub(crate) game: Game,
+    pub(crate) config: GameConfig,
+    pub(crate) color_support: ColorSupport,
+    // Set when reloading the config while paused fails, until a reload succeeds.
+    pub(crate) config_error: bool,
+    pub(crate) show_stats: bool,
+    pub(crate) show_debug: bool,
+    // Set while the game only moves on when stepped, with the number of steps asked for that
+    // haven't been run yet.
+    pub(crate) frame_stepping: bool,
+    pending_steps: u32,
+    // Snapshots of a practice game taken every `REWIND_INTERVAL` ticks, oldest first.
+    history: VecDeque<Game>,
+    // Snapshots of a practice game from as each of the last few pieces came in, oldest first.
+    placements: VecDeque<Game>,
+    pub(crate) timings: FrameTimings,
+    pub(crate) inputs: InputLog,
+    // The settings screen, if it's open. Only reachable while paused.
+    pub(crate) settings: Option<SettingsMenu>,
+    // Which screen Enter opens while paused.
+    pub(crate) pause_item: PauseItem,
+    // The lifetime statistics screen, if it's open. Also only reachable while paused.
+    pub(crate) statistics: Option<LifetimeStats>,
+    // The online leaderboard screen, if it's open. Also only reachable while paused.
+    pub(crate) leaderboard: Option<Leaderboard>,
+    // The name being entered for a new high score, once the game is over.
+    pub(crate) name_entry: Option<NameEntry>,
+    // Set when the player asks to play the game that's just ended again, from the same seed.
+    pub(crate) retry: bool,
+    // The board at the start of the game and after every piece since, for exporting as a fumen.
+    pub(crate) pages: Vec<GameBoard>,
+    // The opener being practiced, if any.
+    pub(crate) trainer: Option<Trainer>,
+    // The other players in a network or bot game, and the attacks that have gone between them.
+    pub(crate) opponents: Vec<RemoteBoard>,
+    pub(crate) attack_log: AttackLog,
+    // This player's number in a network game, counting from 0, for naming the others.
+    pub(crate) player: usize,
+    // The opponent picked with `switch_target`, if any, and how many times attacks have been
+    // split or items handed out between opponents.
+    pub(crate) target: Option<usize>,
+    pub(crate) split_turn: usize,
+    // In a network game with rollback netcode, the match that plays this game and everyone
+    // else's, and what's been pressed since its last tick.
+    pub(crate) rollback: Option<Rollback>,
+    queued: Vec<Action>,
+    // A game saved last time, which can be carried on with instead of this one.
+    pub(crate) saved: Option<Game>,
+    // Ticks left before play starts, and then how much longer "GO" is shown for.
+    pub(crate) countdown: u64,
+    go_ticks: u64,
+    // The board effect playing, if any, and how many ticks it has left.
+    effect: Option<(Effect, u64)>,
+    pub(crate) splits: Splits,
+    // The best result before this game for its kind of game, to compare it with at the end.
+    pub(crate) personal_best: Option<u64>,
+    // The best run to race against, and this one being recorded to replace it if it's faster.
+    // Only timed games are raced.
+    pub(crate) race: Option<Ghost>,
+    pub(crate) recording: Option<Ghost>,
+    // Everything that's gone into the game, to be saved as a replay once it's over. It's kept from
+    // the input log, which has given it everything up to `recorded`.
+    pub(crate) replay: Replay,
+    recorded: u64,
+    // The last piece whose finesse was checked, by the game's `spawned`.
+    finesse_checked: Option<u64>,
+    // The art from the `background_art` setting, once it's been loaded.
+    pub(crate) background: Option<Art>
+}
+
+impl Session {
+    pub fn new(config: GameConfig, game: Game) -> Self {
+        let color_support = config.color_support.unwrap_or_else(ColorSupport::detect);
+        let pages = vec![game.board.clone()];
+        let splits = Splits::new(&game);
+        let replay = Replay::new(&game);
+        let recording = if config.ghost_race && Measure::of(&game) == Measure::Time {
+            Some(Ghost::default())
+        } else {
+            None
+        };
+        Session {
+            game,
+            config,
+            color_support,
+            config_error: false,
+            show_stats: false,
+            show_debug: false,
+            frame_stepping: false,
+            pending_steps: 0,
+            history: VecDeque::with_capacity(REWIND_HISTORY),
+            placements: VecDeque::with_capacity(UNDO_PLACEMENTS),
+            timings: FrameTimings::default(),
+            inputs: InputLog::default(),
+            settings: None,
+            pause_item: PauseItem::Settings,
+            statistics: None,
+            leaderboard: None,
+            name_entry: None,
+            retry: false,
+            pages,
+            trainer: None,
+            opponents: Vec::new(),
+            attack_log: AttackLog::default(),
+            player: 0,
+            target: None,
+            split_turn: 0,
+            rollback: None,
+            queued: Vec::new(),
+            saved: None,
+            countdown: 0,
+            go_ticks: 0,
+            effect: None,
+            splits,
+            personal_best: None,
+            race: None,
+            recording,
+            replay,
+            recorded: 0,
+            finesse_checked: None,
+            background: None
+        }
+    }
+
+    // Offer to carry on with a saved game, which starts off selected on the pause menu.
+    pub fn offer_saved(&mut self, saved: Game) {
+        self.saved = Some(saved);
+        self.game.paused = true;
+        self.pause_item = PauseItem::Continue;
+    }
+
+    // Swap this game for the saved one. The saved game's rules win over the config's, since it has
+    // to carry on with the same board. Carrying on at a different speed makes it practice too.
+    pub fn continue_saved(&mut self) {
+        if let Some(mut game) = self.saved.take() {
+            self.config.set_rules(&game.rules);
+            self.pages = vec![game.board.clone()];
+            game.practice |= self.game.practice;
+            self.history.clear();
+            self.placements.clear();
+            self.splits.restart(&game);
+            // Only whole runs are worth racing.
+            self.recording = None;
+            self.replay = Replay::new(&game);
+            self.recorded = self.inputs.pushed();
+            self.finesse_checked = None;
+            self.game = game;
+            self.pause_item = PauseItem::Settings;
+        }
+    }
+
+    // The leaderboard is only there for games that can be sent to it, when there's one to go to.
+    pub fn pause_items(&self) -> Vec<PauseItem> {
+        let mut items = Vec::with_capacity(PAUSE_ITEMS);
+        if self.saved.is_some() {
+            items.push(PauseItem::Continue);
+        }
+        items.push(PauseItem::Settings);
+        items.push(PauseItem::Statistics);
+        if cfg!(feature = "online")
+            && self.config.leaderboard_url.is_some()
+            && Measure::of(&self.game) != Measure::Level
+        {
+            items.push(PauseItem::Leaderboard);
+        }
+        items
+    }
+
+    // A finished game can be played again with the same pieces once any high score has a name.
+    // Versus games are over for both players, and the trainer starts its own next attempt.
+    pub fn can_retry(&self) -> bool {
+        self.game.game_over
+            && self.name_entry.is_none()
+            && self.opponents.is_empty()
+            && self.trainer.is_none()
+    }
+
+    // Hold off play for the configured countdown.
+    pub fn start_countdown(&mut self) {
+        self.countdown = self.config.countdown * TICK_RATE;
+        self.go_ticks = 0;
+    }
+
+    // Advance the countdown if there is one, or the game if not. The countdown waits while paused.
+    // Time spent paused or counting down to carry on is kept apart from play time, leaving out the
+    // countdown before the first tick.
+    pub fn tick(&mut self) {
+        let stats = &mut self.game.stats;
+        if !self.game.game_over && stats.ticks > 0 && (self.game.paused || self.countdown > 0) {
+            stats.paused_ticks += 1;
+        }
+        if let Some((effect, ticks)) = self.effect {
+            self.effect = if ticks > 1 {
+                Some((effect, ticks - 1))
+            } else {
+                None
+            };
+        }
+        if self.countdown > 0 {
+            if !self.game.paused {
+                self.countdown -= 1;
+                if self.countdown == 0 {
+                    self.go_ticks = GO_TICKS;
+                }
+            }
+            return;
+        }
+        self.go_ticks = self.go_ticks.saturating_sub(1);
+        if self.rollback.is_some() {
+            self.tick_match();
+            return;
+        }
+        self.record_inputs();
+        self.game.tick();
+        self.check_finesse();
+        self.replay.tick(&self.game);
+        self.splits.update(&self.game);
+        self.record_ghost();
+        self.record_history();
+        self.record_placement();
+        self.update_trainer();
+    }
+
+    // Play the rollback match on a tick with what's been pressed since the last one, unless it has
+    // to wait for the other players to catch up.
+    fn tick_match(&mut self) {
+        if let Some(ref mut rollback) = self.rollback {
+            if rollback.can_advance() {
+                rollback.advance(mem::take(&mut self.queued));
+            }
+        }
+        self.follow();
+    }
+
+    // Show this game, the other players' boards, and the attacks between them as the rollback
+    // match has them.
+    pub fn follow(&mut self) {
+        let rollback = match self.rollback {
+            Some(ref rollback) => rollback,
+            None => return
+        };
+        let game_match = rollback.current();
+        let player = self.player;
+        // Finesse is checked here, against this player's input log, rather than by the match.
+        let stats = &self.game.stats;
+        let finesse = (stats.finesse_pieces, stats.finesse_faults);
+        self.game = rollback.game().clone();
+        let stats = &mut self.game.stats;
+        (stats.finesse_pieces, stats.finesse_faults) = finesse;
+        for (opponent, board) in self.opponents.iter_mut().enumerate() {
+            let left = board.left;
+            *board = RemoteBoard::of(&game_match.games[player_of(player, opponent)]);
+            board.left = left;
+        }
+        self.attack_log = game_match.attack_logs[player].clone();
+        self.target = game_match.picked[player].map(|picked| picked - (picked > player) as usize);
+        self.check_finesse();
+    }
+
+    fn record_ghost(&mut self) {
+        if let Some(ref mut recording) = self.recording {
+            if self.game.stats.ticks.is_multiple_of(GHOST_INTERVAL) || self.game.game_over {
+                recording.record(&self.game);
+            }
+        }
+    }
+
+    fn record_history(&mut self) {
+        if !self.game.practice
+            || self.game.game_over
+            || !self.game.stats.ticks.is_multiple_of(REWIND_INTERVAL)
+        {
+            return;
+        }
+        if self.history.len() == REWIND_HISTORY {
+            self.history.pop_front();
+        }
+        self.history.push_back(self.game.clone());
+    }
+
+    // Go back to the last snapshot from at least `REWIND_TICKS` ago, or the oldest one there is.
+    // Everything after it is dropped, so rewinding again goes back further. Works after topping
+    // out too, which is when it's most wanted.
+    fn rewind(&mut self) {
+        if !self.game.practice || self.game.paused {
+            return;
+        }
+        let target = self.game.stats.ticks.saturating_sub(REWIND_TICKS);
+        while self.history.len() > 1
+            && self
+                .history
+                .back()
+                .is_some_and(|game| game.stats.ticks > target)
+        {
+            self.history.pop_back();
+        }
+        if let Some(game) = self.history.pop_back() {
+            self.restore(game);
+        }
+    }
+
+    // Take a snapshot whenever a piece has been placed since the last one.
+    fn record_placement(&mut self) {
+        let placed = self
+            .placements
+            .back()
+            .is_none_or(|game| game.stats.pieces != self.game.stats.pieces);
+        if !self.game.practice || self.game.game_over || !placed {
+            return;
+        }
+        if self.placements.len() == UNDO_PLACEMENTS {
+            self.placements.pop_front();
+        }
+        self.placements.push_back(self.game.clone());
+    }
+
+    // The opponent picked with `switch_target`, while they're still in the game.
+    pub fn picked_target(&self) -> Option<usize> {
+        self.target.filter(|&target| {
+            self.opponents
+                .get(target)
+                .is_some_and(|board| !board.topped_out)
+        })
+    }
+
+    // The opponents attacks are going to, for marking on their boards. Random targeting only
+    // picks someone as each attack goes out, so there's no one to mark unless they've been picked.
+    pub fn shown_targets(&self) -> Vec<usize> {
+        let picked = self.picked_target();
+        if picked.is_none() && self.config.targeting == Targeting::Random {
+            return Vec::new();
+        }
+        pick_targets(&self.opponents, self.config.targeting, picked)
+    }
+
+    // Pick the next opponent who's still in the game after the one picked now, going back to the
+    // targeting setting after the last of them.
+    fn switch_target(&mut self) {
+        let next = self.target.map_or(0, |target| target + 1);
+        self.target = (next..self.opponents.len()).find(|&i| !self.opponents[i].topped_out);
+    }
+
+    // Go back to when the last piece placed came in, with the lines it cleared and the queue as
+    // they were. The snapshot from when the piece in play came in is dropped first, unless the
+    // game's over, in which case it's the piece that topped out that's taken back.
+    fn undo(&mut self) {
+        if !self.game.practice || self.game.paused {
+            return;
+        }
+        let pieces = self.game.stats.pieces;
+        while self
+            .placements
+            .back()
+            .is_some_and(|game| game.stats.pieces >= pieces)
+        {
+            self.placements.pop_back();
+        }
+        if let Some(game) = self.placements.back() {
+            let game = game.clone();
+            self.restore(game);
+        }
+    }
+
+    fn restore(&mut self, game: Game) {
+        // An attempt at an opener that's been counted as over isn't any more.
+        if let Some(ref mut trainer) = self.trainer {
+            if trainer.reset_ticks.take().is_some() {
+                trainer.attempts -= 1;
+                trainer.successes -= self.game.won as u64;
+            }
+        }
+        self.pages.truncate(game.stats.pieces as usize + 1);
+        self.splits.update(&game);
+        if let Some(ref mut recording) = self.recording {
+            recording.rewind(game.stats.ticks);
+        }
+        // The game went back to after its last check, so it's been counted already.
+        self.finesse_checked = game.finesse_check.map(|(piece, _)| piece);
+        self.game = game;
+    }
+
+    // How many of the ticks that are due should actually be run. While frame stepping, only the
+    // steps asked for are, and the time that passes in between is thrown away.
+    pub fn ticks_to_run(&mut self, due: u32) -> u32 {
+        if self.frame_stepping {
+            let steps = self.pending_steps;
+            self.pending_steps = 0;
+            steps
+        } else {
+            due
+        }
+    }
+
+    // Count each attempt once it's over, and start the next one after a moment to see how it went.
+    // A board size change in the settings can leave the opener with nowhere to go, which ends the
+    // training.
+    fn update_trainer(&mut self) {
+        if !self.game.game_over {
+            return;
+        }
+        let won = self.game.won;
+        let next = match self.trainer {
+            Some(ref mut trainer) => match trainer.reset_ticks {
+                None => {
+                    trainer.attempts += 1;
+                    trainer.successes += won as u64;
+                    trainer.reset_ticks = Some(TRAINER_RESET_TICKS);
+                    return;
+                }
+                Some(ticks) if ticks > 1 => {
+                    trainer.reset_ticks = Some(ticks - 1);
+                    return;
+                }
+                Some(_) => {
+                    trainer.reset_ticks = None;
+                    trainer.attempt(self.config.rules())
+                }
+            },
+            None => return
+        };
+        match next {
+            Ok(game) => {
+                self.pages = vec![game.board.clone()];
+                self.history.clear();
+                self.placements.clear();
+                self.splits.restart(&game);
+                self.finesse_checked = None;
+                self.game = game;
+            }
+            Err(_) => self.trainer = None
+        }
+    }
+
+    pub fn start_effect(&mut self, effect: Effect) {
+        if self.config.effects {
+            self.effect = Some((effect, EFFECT_TICKS));
+        }
+    }
+
+    // Whether the current step of the effect is the shaken or flashed one, rather than the
+    // board's usual look.
+    fn effect_step(&self, of: Effect) -> bool {
+        match self.effect {
+            Some((effect, ticks)) => effect == of && (ticks / EFFECT_STEP_TICKS) % 2 == 1,
+            None => false
+        }
+    }
+
+    // How many columns the board is shaken over by.
+    pub fn shake_offset(&self) -> u16 {
+        self.effect_step(Effect::Shake) as u16
+    }
+
+    pub fn border_flashing(&self) -> bool {
+        self.effect_step(Effect::Flash)
+    }
+
+    pub fn in_danger(&self) -> bool {
+        let height = stack_height(&self.game);
+        over_danger_line(height, self.game.board.height, self.config.danger_height)
+    }
+
+    // What's shown over the board while counting down, if anything.
+    pub fn countdown_text(&self) -> Option<String> {
+        if self.countdown > 0 {
+            Some(self.countdown.div_ceil(TICK_RATE).to_string())
+        } else if self.go_ticks > 0 {
+            Some(self.config.language.strings().go.to_string())
+        } else {
+            None
+        }
+    }
+
+    // Pieces can't be moved during the countdown, but the game can still be paused. Unpausing
+    // starts the countdown over.
+    pub fn handle_action(&mut self, action: Action) {
+        self.inputs.push(action, Instant::now());
+        // In a rollback match, what's pressed is played by the match on its next tick. The game
+        // can't be paused, since no one else's would be.
+        if self.rollback.is_some() {
+            match action {
+                Action::MoveLeft
+                | Action::MoveRight
+                | Action::RotateClockwise
+                | Action::RotateAnticlockwise
+                | Action::SoftDrop
+                | Action::HardDrop
+                | Action::SonicDrop
+                | Action::Hold
+                | Action::UseItem
+                | Action::SwitchTarget => {
+                    if self.countdown == 0 {
+                        self.inputs.played(&self.game);
+                        self.queued.push(action);
+                    }
+                    return;
+                }
+                Action::Pause | Action::ToggleHint => return,
+                _ => {}
+            }
+        }
+        if action == Action::ToggleStats {
+            self.show_stats = !self.show_stats;
+        } else if action == Action::ToggleDebug {
+            self.show_debug = !self.show_debug;
+        } else if action == Action::ToggleFrameStep {
+            // The other player's game wouldn't stop, and a game that's been stepped through is
+            // only practice. The debug overlay is opened to show what each step did.
+            if !self.game.versus {
+                self.frame_stepping = !self.frame_stepping;
+                self.pending_steps = 0;
+                if self.frame_stepping {
+                    self.game.practice = true;
+                    self.show_debug = true;
+                }
+            }
+        } else if action == Action::FrameStep {
+            if self.frame_stepping {
+                self.pending_steps += 1;
+            }
+        } else if action == Action::Rewind {
+            self.rewind();
+        } else if action == Action::Undo {
+            self.undo();
+        } else if action == Action::SwitchTarget {
+            self.switch_target();
+        } else if self.countdown == 0 || action == Action::Pause || action == Action::ToggleHint {
+            let was_paused = self.game.paused;
+            if !self.game.paused && !self.game.game_over {
+                self.inputs.played(&self.game);
+            }
+            self.game.handle_action(action);
+            self.check_finesse();
+            self.splits.update(&self.game);
+            self.record_placement();
+            if was_paused && !self.game.paused {
+                self.start_countdown();
+            }
+        }
+    }
+
+    // Give the replay the inputs the game has played since it was last given any. They have to be
+    // in before the game's next tick, and before the replay is saved.
+    pub(crate) fn record_inputs(&mut self) {
+        for input in self.inputs.since(self.recorded) {
+            if let Some(ticks) = input.ticks {
+                self.replay.record(Input {
+                    ticks,
+                    frame: input.frame,
+                    action: input.action
+                });
+            }
+        }
+        self.recorded = self.inputs.pushed();
+    }
+
+    // Once a piece has locked, count it as optimal if the input log has it taking no more moves and
+    // rotations than it needed.
+    fn check_finesse(&mut self) {
+        if let Some((piece, optimal)) = self.game.finesse_check {
+            if self.finesse_checked != Some(piece) {
+                self.finesse_checked = Some(piece);
+                let moves = self.inputs.piece_moves(piece);
+                self.game.stats.record_finesse(moves <= optimal);
+            }
+        }
+    }
+
+    // Settings changed on the settings screen apply to the game straight away, including the ones
+    // it plays by.
+    pub fn apply_settings(&mut self) {
+        self.game.rules = self.config.rules();
+    }
+
+    // Switch to the appearance settings from `config`, keeping everything else as it is. Returns
+    // whether anything actually changed.
+    pub fn reload_appearance(&mut self, config: GameConfig) -> bool {
+        let mut updated = self.config.clone();
+        updated.apply_appearance(config);
+        if updated.to_string() == self.config.to_string() {
+            return false;
+        }
+        self.color_support = updated.color_support.unwrap_or_else(ColorSupport::detect);
+        self.config = updated;
+        true
+    }
+}
+
+// An attempt counts once it's over, and the next one starts from the opener's board after a pause.
+#[test]
+fn test_trainer_attempts() {
+    use tui_tetris::puzzle;
+
+    let config = GameConfig::default();
+    let opener = &puzzle::openers()[1];
+    let trainer = Trainer::new(opener.position().unwrap(), opener.challenge());
+    let mut session = Session::new(config.clone(), trainer.attempt(config.rules()).unwrap());
+    session.trainer = Some(trainer);
+    session.game.game_over = true;
+    session.game.won = true;
+    for _ in 0..TRAINER_RESET_TICKS {
+        session.tick();
+        assert!(session.game.game_over);
+    }
+    session.tick();
+    assert!(!session.game.game_over && session.game.practice);
+    let trainer = session.trainer.as_ref().unwrap();
+    assert_eq!((trainer.successes, trainer.attempts), (1, 1));
+}
+
+// Only the steps asked for are run while frame stepping, however much time has gone by.
+#[test]
+fn test_frame_step() {
+    let config = GameConfig::default();
+    let mut session = Session::new(config.clone(), Game::new(config.rules()));
+    assert_eq!(session.ticks_to_run(3), 3);
+    session.handle_action(Action::FrameStep);
+    session.handle_action(Action::ToggleFrameStep);
+    assert!(session.frame_stepping && session.game.practice);
+    assert_eq!(session.ticks_to_run(3), 0);
+    session.handle_action(Action::FrameStep);
+    session.handle_action(Action::FrameStep);
+    assert_eq!(session.ticks_to_run(0), 2);
+    assert_eq!(session.ticks_to_run(3), 0);
+    session.handle_action(Action::ToggleFrameStep);
+    assert_eq!(session.ticks_to_run(3), 3);
+}
+
+// Rewinding goes back at least five seconds each time, and only in practice games.
+#[test]
+fn test_rewind() {
+    let config = GameConfig::default();
+    let mut session = Session::new(config.clone(), Game::new(config.rules()));
+    for _ in 0..REWIND_TICKS * 2 {
+        session.tick();
+    }
+    session.handle_action(Action::Rewind);
+    assert_eq!(session.game.stats.ticks, REWIND_TICKS * 2);
+    session.game.practice = true;
+    for _ in 0..REWIND_TICKS * 2 {
+        session.tick();
+    }
+    session.handle_action(Action::Rewind);
+    assert_eq!(session.game.stats.ticks, REWIND_TICKS * 3);
+    session.handle_action(Action::Rewind);
+    assert_eq!(session.game.stats.ticks, REWIND_TICKS * 2 + REWIND_INTERVAL);
+}
+
+// Each undo takes back one more placement, along with whatever it scored.
+#[test]
+fn test_undo() {
+    let config = GameConfig::default();
+    let mut game = Game::new(config.rules());
+    game.practice = true;
+    let mut session = Session::new(config, game);
+    session.tick();
+    session.handle_action(Action::HardDrop);
+    let after_first = session.game.score;
+    session.tick();
+    session.handle_action(Action::HardDrop);
+    assert_eq!(session.game.stats.pieces, 2);
+    session.handle_action(Action::Undo);
+    assert_eq!(
+        (session.game.stats.pieces, session.game.score),
+        (1, after_first)
+    );
+    session.handle_action(Action::Undo);
+    assert_eq!((session.game.stats.pieces, session.game.score), (0, 0));
+    assert!(session.game.board.is_empty());
+    session.handle_action(Action::Undo);
+    assert_eq!(session.game.stats.pieces, 0);
+}
+
+// Pausing and the countdown after it stop the play time but not the time on the clock.
+#[test]
+fn test_paused_time() {
+    let config = GameConfig::default();
+    let mut session = Session::new(config.clone(), Game::new(config.rules()));
+    session.tick();
+    session.handle_action(Action::Pause);
+    for _ in 0..TICK_RATE {
+        session.tick();
+    }
+    session.handle_action(Action::Pause);
+    while session.countdown > 0 {
+        session.tick();
+    }
+    session.tick();
+    let stats = &session.game.stats;
+    assert_eq!(stats.ticks, 2);
+    assert_eq!(stats.paused_ticks, TICK_RATE + config.countdown * TICK_RATE);
+}
+
+// Games for lines are split every ten lines at the time each was reached, and going back to before
+// a split takes it back.
+#[test]
+fn test_splits() {
+    use tui_tetris::challenge;
+
+    let config = GameConfig::default();
+    let mut game = Game::new(config.rules());
+    game.challenge = Some(challenge::built_in()[0].clone());
+    let mut session = Session::new(config, game);
+    assert_eq!(Splits::course(&session.game), "modern sprint");
+    session.tick();
+    session.game.lines_cleared = 12;
+    session.tick();
+    assert_eq!(session.splits.times, vec![2]);
+    assert_eq!(session.splits.label(0), 10);
+    session.game.lines_cleared = 0;
+    session.tick();
+    assert!(session.splits.times.is_empty());
+}
+
+// Switching targets goes through the opponents still in the game and then back to none.
+#[test]
+fn test_switch_target() {
+    let config = GameConfig::default();
+    let mut session = Session::new(config.clone(), Game::new(config.rules()));
+    session.opponents = vec![RemoteBoard::new((10, 20)); 3];
+    session.opponents[1].topped_out = true;
+    session.handle_action(Action::SwitchTarget);
+    assert_eq!(session.target, Some(0));
+    session.handle_action(Action::SwitchTarget);
+    assert_eq!(session.target, Some(2));
+    session.handle_action(Action::SwitchTarget);
+    assert_eq!(session.target, None);
+}
+
+// Finesse is checked against the moves the input log has for each piece, and the replay is given
+// what the game played from the log, with the frames it was pressed on.
+#[test]
+fn test_logged_inputs() {
+    let config = GameConfig::default();
+    let mut session = Session::new(config.clone(), Game::with_seed(config.rules(), 1));
+    session.inputs.next_frame();
+    session.handle_action(Action::MoveLeft);
+    session.handle_action(Action::MoveRight);
+    session.inputs.next_frame();
+    session.handle_action(Action::HardDrop);
+    let stats = &session.game.stats;
+    assert_eq!((stats.finesse_pieces, stats.finesse_faults), (1, 1));
+    for _ in 0..TICK_RATE / 2 {
+        session.tick();
+    }
+    session.inputs.next_frame();
+    session.handle_action(Action::HardDrop);
+    let stats = &session.game.stats;
+    assert_eq!((stats.finesse_pieces, stats.finesse_faults), (2, 1));
+    session.handle_action(Action::Pause);
+    session.handle_action(Action::MoveLeft);
+    session.tick();
+    let frames = session
+        .replay
+        .inputs()
+        .iter()
+        .map(|input| (input.frame, input.action))
+        .collect::<Vec<_>>();
+    assert_eq!(
+        frames,
+        [
+            (1, Action::MoveLeft),
+            (1, Action::MoveRight),
+            (2, Action::HardDrop),
+            (3, Action::HardDrop)
+        ]
+    );
+}
@@ -0,0 +1,767 @@
+use crossterm::{Color, Goto, Output, Result as CrosstermResult};
+use std::io::Write;
+
+use crate::color::ColorSupport;
+use crate::game_config::{
+    color_string, keys_string, GameConfig, Grid, Palette, Renderer, AI_DIFFICULTIES, AI_STYLES,
+    BIG_MODE_MIN_WIDTH, MAX_COUNTDOWN, PRESETS
+};
+use crate::input::{map_menu_key, Key, KeyCode, MenuAction};
+use crate::locale::{Strings, LANGUAGES};
+
+// Every line of the menu is padded to this so that shorter values overwrite longer ones.
+const MENU_WIDTH: usize = 32;
+// What's left of a line for an entry's value, after the marker and the label.
+const VALUE_WIDTH: usize = MENU_WIDTH - 14;
+
+// What the border and background colors are cycled through.
+const COLOR_CHOICES: [Color; 8] = [
+    Color::Rgb { r: 0, g: 0, b: 0 },
+    Color::Rgb {
+        r: 255,
+        g: 255,
+        b: 255
+    },
+    Color::Rgb {
+        r: 128,
+        g: 128,
+        b: 128
+    },
+    Color::Rgb {
+        r: 40,
+        g: 40,
+        b: 40
+    },
+    Color::Rgb { r: 240, g: 0, b: 0 },
+    Color::Rgb { r: 0, g: 240, b: 0 },
+    Color::Rgb { r: 0, g: 0, b: 240 },
+    Color::Rgb {
+        r: 240,
+        g: 240,
+        b: 0
+    }
+];
+
+const GRID_CHOICES: [Option<Grid>; 3] = [None, Some(Grid::Dots), Some(Grid::Checkerboard)];
+
+const PALETTE_CHOICES: [Option<Palette>; 5] = [
+    None,
+    Some(Palette::Deuteranopia),
+    Some(Palette::Protanopia),
+    Some(Palette::Tritanopia),
+    Some(Palette::HighContrast)
+];
+
+// The settings the menu can change, in the order they're listed.
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Entry {
+    Controls,
+    Preset,
+    MoveLeft,
+    MoveRight,
+    RotateClockwise,
+    RotateAnticlockwise,
+    SoftDrop,
+    HardDrop,
+    SonicDrop,
+    Hold,
+    Pause,
+    Quit,
+    ToggleStats,
+    ToggleHint,
+    ToggleDebug,
+    Ghost,
+    Cascade,
+    BigMode,
+    Irs,
+    Countdown,
+    AiDifficulty,
+    AiStyle,
+    Sound,
+    AccessibilityCues,
+    Palette,
+    BorderColor,
+    BackgroundColor,
+    Renderer,
+    Grid,
+    BlockSize,
+    Effects,
+    Language,
+    Save
+}
+
+const ENTRIES: [Entry; 33] = [
+    Entry::Controls,
+    Entry::Preset,
+    Entry::MoveLeft,
+    Entry::MoveRight,
+    Entry::RotateClockwise,
+    Entry::RotateAnticlockwise,
+    Entry::SoftDrop,
+    Entry::HardDrop,
+    Entry::SonicDrop,
+    Entry::Hold,
+    Entry::Pause,
+    Entry::Quit,
+    Entry::ToggleStats,
+    Entry::ToggleHint,
+    Entry::ToggleDebug,
+    Entry::Ghost,
+    Entry::Cascade,
+    Entry::BigMode,
+    Entry::Irs,
+    Entry::Countdown,
+    Entry::AiDifficulty,
+    Entry::AiStyle,
+    Entry::Sound,
+    Entry::AccessibilityCues,
+    Entry::Palette,
+    Entry::BorderColor,
+    Entry::BackgroundColor,
+    Entry::Renderer,
+    Entry::Grid,
+    Entry::BlockSize,
+    Entry::Effects,
+    Entry::Language,
+    Entry::Save
+];
+
+impl Entry {
+    fn label(self, text: &Strings) -> &'static str {
+        match self {
+            Entry::Controls => text.controls,
+            Entry::Preset => text.preset,
+            Entry::MoveLeft => text.move_left,
+            Entry::MoveRight => text.move_right,
+            Entry::RotateClockwise => text.rotate_clockwise,
+            Entry::RotateAnticlockwise => text.rotate_anticlockwise,
+            Entry::SoftDrop => text.soft_drop,
+            Entry::HardDrop => text.hard_drop,
+            Entry::SonicDrop => text.sonic_drop,
+            Entry::Hold => text.hold_entry,
+            Entry::Pause => text.pause,
+            Entry::Quit => text.quit,
+            Entry::ToggleStats => text.stats,
+            Entry::ToggleHint => text.hint,
+            Entry::ToggleDebug => text.debug,
+            Entry::Ghost => text.ghost,
+            Entry::Cascade => text.cascade,
+            Entry::BigMode => text.big_mode,
+            Entry::Irs => text.irs,
+            Entry::Countdown => text.countdown,
+            Entry::AiDifficulty => text.ai_level,
+            Entry::AiStyle => text.ai_style,
+            Entry::Sound => text.sound,
+            Entry::AccessibilityCues => text.piece_cues,
+            Entry::Palette => text.palette,
+            Entry::BorderColor => text.border,
+            Entry::BackgroundColor => text.background,
+            Entry::Renderer => text.renderer,
+            Entry::Grid => text.grid,
+            Entry::BlockSize => text.block_size,
+            Entry::Effects => text.effects,
+            Entry::Language => text.language,
+            Entry::Save => text.save
+        }
+    }
+
+    fn is_binding(self) -> bool {
+        matches!(
+            self,
+            Entry::MoveLeft
+                | Entry::MoveRight
+                | Entry::RotateClockwise
+                | Entry::RotateAnticlockwise
+                | Entry::SoftDrop
+                | Entry::HardDrop
+                | Entry::SonicDrop
+                | Entry::Hold
+                | Entry::Pause
+                | Entry::Quit
+                | Entry::ToggleStats
+                | Entry::ToggleHint
+                | Entry::ToggleDebug
+        )
+    }
+
+    // Only bindings and the ghost piece that the mode's rule set has are shown, palettes don't do
+    // anything in monochrome, and the grid isn't drawn in braille. There's only a choice of
+    // controls if the config has sets of keys to choose from, and presets only change the default
+    // keys.
+    fn applies_to(self, config: &GameConfig) -> bool {
+        let rule_set = config.mode.rule_set();
+        match self {
+            Entry::Controls => config.bindings.len() > 1,
+            Entry::Preset => config.is_default_bindings(),
+            Entry::HardDrop | Entry::SonicDrop => rule_set.has_hard_drop(),
+            Entry::Hold => rule_set.has_hold(),
+            Entry::Ghost => rule_set.has_ghost(),
+            Entry::Palette => config.monochrome.is_none(),
+            Entry::Grid => config.renderer == Renderer::Blocks,
+            _ => true
+        }
+    }
+
+    fn bound_keys(self, config: &GameConfig) -> &[Key] {
+        match self {
+            Entry::MoveLeft => &config.left,
+            Entry::MoveRight => &config.right,
+            Entry::RotateClockwise => &config.rot_cw,
+            Entry::RotateAnticlockwise => &config.rot_acw,
+            Entry::SoftDrop => &config.soft_drop,
+            Entry::HardDrop => &config.hard_drop,
+            Entry::SonicDrop => &config.sonic_drop,
+            Entry::Hold => &config.hold,
+            Entry::Pause => &config.pause,
+            Entry::Quit => &config.quit,
+            Entry::ToggleStats => &config.toggle_stats,
+            Entry::ToggleHint => &config.toggle_hint,
+            Entry::ToggleDebug => &config.toggle_debug,
+            _ => &[]
+        }
+    }
+
+    // Only the optional actions can be unbound, since the game can't be played without the rest.
+    fn bind(self, config: &mut GameConfig, keys: Vec<Key>) {
+        let optional = matches!(
+            self,
+            Entry::HardDrop
+                | Entry::SonicDrop
+                | Entry::Hold
+                | Entry::ToggleStats
+                | Entry::ToggleHint
+                | Entry::ToggleDebug
+        );
+        if keys.is_empty() && !optional {
+            return;
+        }
+        match self {
+            Entry::MoveLeft => config.left = keys,
+            Entry::MoveRight => config.right = keys,
+            Entry::RotateClockwise => config.rot_cw = keys,
+            Entry::RotateAnticlockwise => config.rot_acw = keys,
+            Entry::SoftDrop => config.soft_drop = keys,
+            Entry::HardDrop => config.hard_drop = keys,
+            Entry::SonicDrop => config.sonic_drop = keys,
+            Entry::Hold => config.hold = keys,
+            Entry::Pause => config.pause = keys,
+            Entry::Quit => config.quit = keys,
+            Entry::ToggleStats => config.toggle_stats = keys,
+            Entry::ToggleHint => config.toggle_hint = keys,
+            Entry::ToggleDebug => config.toggle_debug = keys,
+            _ => {}
+        }
+    }
+
+    // Step a setting to its next (or previous) value.
+    fn adjust(self, config: &mut GameConfig, forward: bool) {
+        let cycle = |len: usize, current: Option<usize>| match (current, forward) {
+            (Some(i), true) => (i + 1) % len,
+            (Some(i), false) => (i + len - 1) % len,
+            (None, _) => 0
+        };
+        match self {
+            Entry::Controls => {
+                let current = config
+                    .bindings
+                    .iter()
+                    .position(|bindings| bindings.name == config.key_bindings);
+                let name = config.bindings[cycle(config.bindings.len(), current)]
+                    .name
+                    .clone();
+                config.use_bindings(&name);
+            }
+            Entry::Preset => {
+                let current = PRESETS.iter().position(|preset| preset.matches(config));
+                PRESETS[cycle(PRESETS.len(), current)].apply(config);
+            }
+            Entry::Ghost => {
+                if config.ghost_tetromino_character.is_some() {
+                    config.ghost_tetromino_character = None;
+                    config.ghost_tetromino_color = None;
+                } else {
+                    let default = GameConfig::default();
+                    config.ghost_tetromino_character = default.ghost_tetromino_character;
+                    config.ghost_tetromino_color = default.ghost_tetromino_color;
+                }
+            }
+            Entry::Cascade => config.cascade = !config.cascade,
+            Entry::Irs => config.irs = !config.irs,
+            Entry::Sound => config.sound = !config.sound,
+            Entry::Effects => config.effects = !config.effects,
+            Entry::AccessibilityCues => config.accessibility_cues = !config.accessibility_cues,
+            // Big pieces need an even board at least twice as wide as an I piece.
+            Entry::BigMode => {
+                config.big_mode = !config.big_mode
+                    && config.board_width >= BIG_MODE_MIN_WIDTH
+                    && config.board_width.is_multiple_of(2)
+            }
+            Entry::Countdown => {
+                let current = Some(config.countdown as usize);
+                config.countdown = cycle(MAX_COUNTDOWN as usize + 1, current) as u64;
+            }
+            Entry::AiDifficulty => {
+                let current = AI_DIFFICULTIES
+                    .iter()
+                    .position(|&d| d == config.ai_difficulty);
+                config.ai_difficulty = AI_DIFFICULTIES[cycle(AI_DIFFICULTIES.len(), current)];
+            }
+            Entry::AiStyle => {
+                let current = AI_STYLES.iter().position(|&s| s == config.ai_style);
+                config.ai_style = AI_STYLES[cycle(AI_STYLES.len(), current)];
+            }
+            Entry::Palette => {
+                let current = PALETTE_CHOICES.iter().position(|&p| p == config.palette);
+                config.palette = PALETTE_CHOICES[cycle(PALETTE_CHOICES.len(), current)];
+                let colors = match config.palette {
+                    Some(palette) => palette.colors(),
+                    None => {
+                        let default = GameConfig::default();
+                        [
+                            default.i_color,
+                            default.j_color,
+                            default.l_color,
+                            default.s_color,
+                            default.z_color,
+                            default.t_color,
+                            default.o_color
+                        ]
+                    }
+                };
+                config.i_color = colors[0];
+                config.j_color = colors[1];
+                config.l_color = colors[2];
+                config.s_color = colors[3];
+                config.z_color = colors[4];
+                config.t_color = colors[5];
+                config.o_color = colors[6];
+            }
+            Entry::BorderColor => {
+                let current = COLOR_CHOICES.iter().position(|&c| c == config.border_color);
+                config.border_color = COLOR_CHOICES[cycle(COLOR_CHOICES.len(), current)];
+            }
+            Entry::BackgroundColor => {
+                let current = COLOR_CHOICES
+                    .iter()
+                    .position(|&c| c == config.background_color);
+                config.background_color = COLOR_CHOICES[cycle(COLOR_CHOICES.len(), current)];
+            }
+            Entry::Renderer => {
+                config.renderer = match config.renderer {
+                    Renderer::Blocks => Renderer::Braille,
+                    Renderer::Braille => Renderer::Blocks
+                }
+            }
+            Entry::Grid => {
+                let current = GRID_CHOICES.iter().position(|&g| g == config.grid);
+                config.grid = GRID_CHOICES[cycle(GRID_CHOICES.len(), current)];
+            }
+            Entry::Language => {
+                let current = LANGUAGES.iter().position(|&l| l == config.language);
+                config.language = LANGUAGES[cycle(LANGUAGES.len(), current)];
+            }
+            Entry::BlockSize => {
+                // The board has to stay bigger than an I piece, same as when the config is loaded.
+                let largest = (config.board_width.min(config.board_height) - 1) / 4;
+                let current = Some(config.block_size - 1);
+                config.block_size = cycle(largest, current) + 1;
+            }
+            _ => {}
+        }
+    }
+
+    // Copy this entry's setting from one config to another.
+    fn copy(self, from: &GameConfig, to: &mut GameConfig) {
+        match self {
+            Entry::Controls => {
+                to.use_bindings(&from.key_bindings);
+            }
+            Entry::Preset => {
+                if let Some(preset) = PRESETS.iter().find(|preset| preset.matches(from)) {
+                    preset.apply(to);
+                }
+            }
+            Entry::Ghost => {
+                to.ghost_tetromino_character = from.ghost_tetromino_character;
+                to.ghost_tetromino_color = from.ghost_tetromino_color;
+            }
+            Entry::Cascade => to.cascade = from.cascade,
+            Entry::BigMode => to.big_mode = from.big_mode,
+            Entry::Irs => to.irs = from.irs,
+            Entry::Countdown => to.countdown = from.countdown,
+            Entry::AiDifficulty => to.ai_difficulty = from.ai_difficulty,
+            Entry::AiStyle => to.ai_style = from.ai_style,
+            Entry::Sound => to.sound = from.sound,
+            Entry::AccessibilityCues => to.accessibility_cues = from.accessibility_cues,
+            Entry::Palette => {
+                to.palette = from.palette;
+                to.i_color = from.i_color;
+                to.j_color = from.j_color;
+                to.l_color = from.l_color;
+                to.s_color = from.s_color;
+                to.z_color = from.z_color;
+                to.t_color = from.t_color;
+                to.o_color = from.o_color;
+            }
+            Entry::BorderColor => to.border_color = from.border_color,
+            Entry::BackgroundColor => to.background_color = from.background_color,
+            Entry::Renderer => to.renderer = from.renderer,
+            Entry::Grid => to.grid = from.grid,
+            Entry::BlockSize => to.block_size = from.block_size,
+            Entry::Effects => to.effects = from.effects,
+            Entry::Language => to.language = from.language,
+            Entry::Save => {}
+            binding => binding.bind(to, binding.bound_keys(from).to_vec())
+        }
+    }
+
+    fn value(self, config: &GameConfig, text: &Strings) -> String {
+        let on_off = |on: bool| if on { text.on } else { text.off }.to_string();
+        match self {
+            Entry::Controls => config.key_bindings.clone(),
+            Entry::Preset => PRESETS
+                .iter()
+                .find(|preset| preset.matches(config))
+                .map_or(text.custom, |preset| preset.name)
+                .to_string(),
+            Entry::Ghost => on_off(config.ghost_tetromino_character.is_some()),
+            Entry::Cascade => on_off(config.cascade),
+            Entry::BigMode => on_off(config.big_mode),
+            Entry::Irs => on_off(config.irs),
+            Entry::Countdown => format!("{}s", config.countdown),
+            Entry::AiDifficulty => config.ai_difficulty.to_string(),
+            Entry::AiStyle => config.ai_style.to_string(),
+            Entry::Sound => on_off(config.sound),
+            Entry::AccessibilityCues => on_off(config.accessibility_cues),
+            Entry::Palette => config
+                .palette
+                .map_or(text.none.to_string(), |palette| palette.to_string()),
+            // Leaves room for a swatch of the color.
+            Entry::BorderColor => format!("   {}", color_string(&config.border_color)),
+            Entry::BackgroundColor => format!("   {}", color_string(&config.background_color)),
+            Entry::Save => String::new(),
+            Entry::Renderer => config.renderer.to_string(),
+            Entry::Grid => config
+                .grid
+                .map_or(text.off.to_string(), |grid| grid.to_string()),
+            Entry::BlockSize => config.block_size.to_string(),
+            Entry::Effects => on_off(config.effects),
+            Entry::Language => config.language.strings().name.to_string(),
+            binding => keys_string(binding.bound_keys(config))
+        }
+    }
+}
+
+pub enum MenuEvent {
+    Close,
+    Save,
+    // The selected entry's keys have changed, or a preset's been picked, and should be written out
+    // straight away.
+    Rebound
+}
+
+// The settings screen shown in place of the side panel while paused. Changes are made to the
+// game's config straight away so they can be seen on the board, and are undone on leaving unless
+// they've been saved.
+pub struct SettingsMenu {
+    selected: usize,
+    // Set while waiting for the key to bind the selected entry to.
+    capturing: bool,
+    // What the config was when the menu was opened or last saved.
+    saved: GameConfig,
+    pub(crate) message: &'static str
+}
+
+impl SettingsMenu {
+    pub fn new(config: &GameConfig) -> Self {
+        SettingsMenu {
+            selected: 0,
+            capturing: false,
+            saved: config.clone(),
+            message: ""
+        }
+    }
+
+    fn entries(config: &GameConfig) -> Vec<Entry> {
+        ENTRIES
+            .iter()
+            .cloned()
+            .filter(|entry| entry.applies_to(config))
+            .collect()
+    }
+
+    pub fn handle_key(&mut self, config: &mut GameConfig, key: Key) -> Option<MenuEvent> {
+        let entries = SettingsMenu::entries(config);
+        let entry = entries[self.selected];
+        self.message = "";
+        if self.capturing {
+            self.capturing = false;
+            if config.menu_back.contains(&key) {
+                return None;
+            }
+            let taken = entries
+                .iter()
+                .any(|&other| other != entry && other.bound_keys(config).contains(&key));
+            if taken {
+                self.message = config.language.strings().key_in_use;
+                return None;
+            }
+            // The new key replaces all of the old ones.
+            return SettingsMenu::rebind(entry, config, vec![key]);
+        }
+        let unbind = key == Key::plain(KeyCode::Backspace) || key == Key::plain(KeyCode::Delete);
+        let select = map_menu_key(config, &key) == Some(MenuAction::Select);
+        if entry.is_binding() && (unbind || select) && !config.is_default_bindings() {
+            self.message = config.language.strings().default_keys_only;
+            return None;
+        }
+        if unbind {
+            return SettingsMenu::rebind(entry, config, Vec::new());
+        }
+        match map_menu_key(config, &key) {
+            Some(MenuAction::Up) => {
+                self.selected = (self.selected + entries.len() - 1) % entries.len()
+            }
+            Some(MenuAction::Down) => self.selected = (self.selected + 1) % entries.len(),
+            Some(MenuAction::Left) if entry == Entry::Preset => {
+                entry.adjust(config, false);
+                return Some(MenuEvent::Rebound);
+            }
+            Some(MenuAction::Right) | Some(MenuAction::Select) if entry == Entry::Preset => {
+                entry.adjust(config, true);
+                return Some(MenuEvent::Rebound);
+            }
+            Some(MenuAction::Left) => entry.adjust(config, false),
+            Some(MenuAction::Right) => entry.adjust(config, true),
+            Some(MenuAction::Select) if entry == Entry::Save => return Some(MenuEvent::Save),
+            Some(MenuAction::Select) if entry.is_binding() => self.capturing = true,
+            Some(MenuAction::Select) => entry.adjust(config, true),
+            Some(MenuAction::Back) => {
+                for entry in ENTRIES.iter() {
+                    entry.copy(&self.saved, config);
+                }
+                return Some(MenuEvent::Close);
+            }
+            None => {}
+        }
+        None
+    }
+
+    fn rebind(entry: Entry, config: &mut GameConfig, keys: Vec<Key>) -> Option<MenuEvent> {
+        let before = entry.bound_keys(config).to_vec();
+        entry.bind(config, keys);
+        if entry.bound_keys(config) != before.as_slice() {
+            Some(MenuEvent::Rebound)
+        } else {
+            None
+        }
+    }
+
+    // Copy only the selected entry's keys onto another config, for writing out a rebinding without
+    // anything else that's been changed in the menu.
+    pub fn copy_binding(&self, edited: &GameConfig, to: &mut GameConfig) {
+        let entry = SettingsMenu::entries(edited)[self.selected];
+        entry.copy(edited, to);
+    }
+
+    // Called once a rebinding has been written out, so that leaving without saving keeps it.
+    pub fn binding_saved(&mut self, config: &GameConfig) {
+        let entry = SettingsMenu::entries(config)[self.selected];
+        entry.copy(config, &mut self.saved);
+        let text = config.language.strings();
+        self.message = if entry == Entry::Preset {
+            text.preset_saved
+        } else {
+            text.key_saved
+        };
+    }
+
+    // Copy whatever's been changed in the menu since it was opened or last saved onto another
+    // config. Settings that haven't been touched are left alone so that command line overrides
+    // don't end up in the config file. Both values are shown in the same language so that
+    // changing it doesn't make every other entry look changed too.
+    pub fn copy_changes(&self, edited: &GameConfig, to: &mut GameConfig) {
+        let text = edited.language.strings();
+        for entry in ENTRIES.iter() {
+            if entry.value(edited, text) != entry.value(&self.saved, text) {
+                entry.copy(edited, to);
+            }
+        }
+    }
+
+    // Called once the config has been written out, so that leaving keeps the changes.
+    pub fn saved(&mut self, config: &GameConfig) {
+        self.saved = config.clone();
+        self.message = config.language.strings().saved;
+    }
+
+    pub fn draw<W: Write>(
+        &self,
+        config: &GameConfig,
+        color_support: ColorSupport,
+        out: &mut W,
+        x: u16
+    ) -> CrosstermResult<()> {
+        let strings = config.language.strings();
+        let text = color_support.fg(config.border_color);
+        let line = |s: &str| format!("{:width$}", s, width = MENU_WIDTH);
+        queue!(
+            out,
+            Output(text.clone()),
+            Goto(x, 1),
+            Output(line(strings.settings))
+        )?;
+        let entries = SettingsMenu::entries(config);
+        let mut y = 3;
+        for (i, &entry) in entries.iter().enumerate() {
+            let marker = if i == self.selected { '>' } else { ' ' };
+            let value = if i == self.selected && self.capturing {
+                "...".to_string()
+            } else {
+                fit_value(entry.value(config, strings))
+            };
+            queue!(
+                out,
+                Goto(x, y),
+                Output(text.clone()),
+                Output(line(&format!(
+                    "{} {:12}{}",
+                    marker,
+                    entry.label(strings),
+                    value
+                )))
+            )?;
+            let swatch = match entry {
+                Entry::BorderColor => Some(config.border_color),
+                Entry::BackgroundColor => Some(config.background_color),
+                _ => None
+            };
+            if let Some(colour) = swatch {
+                queue!(
+                    out,
+                    Goto(x + 14, y),
+                    Output(color_support.fg(colour)),
+                    Output("██".to_string())
+                )?;
+            }
+            y += 1;
+        }
+        // Entries come and go with the mode, so clear the space the full list would take up.
+        for _ in entries.len()..ENTRIES.len() {
+            queue!(out, Goto(x, y), Output(line("")))?;
+            y += 1;
+        }
+        // The first of each menu key is named, since they can be rebound.
+        let first = |keys: &[Key]| keys.first().map_or(String::new(), Key::to_string);
+        let hint = if self.capturing {
+            format!(
+                "{} {} {}",
+                strings.press_key,
+                first(&config.menu_back),
+                strings.to_cancel
+            )
+        } else if self.message.is_empty() {
+            format!(
+                "{} {}, {} {}",
+                first(&config.menu_select),
+                strings.to_change,
+                first(&config.menu_back),
+                strings.to_leave
+            )
+        } else {
+            self.message.to_string()
+        };
+        queue!(out, Output(text), Goto(x, y + 1), Output(line(&hint)))?;
+        Ok(())
+    }
+}
+
+// Values too long for the menu, like a long list of keys, are cut short.
+fn fit_value(value: String) -> String {
+    if value.chars().count() <= VALUE_WIDTH {
+        value
+    } else {
+        value
+            .chars()
+            .take(VALUE_WIDTH - 1)
+            .chain(Some('…'))
+            .collect()
+    }
+}
+
+// Only settings changed in the menu should be copied when saving.
+#[test]
+fn test_copy_changes() {
+    use crate::input::KeyModifiers;
+
+    let mut config = GameConfig::default();
+    config.cascade = true;
+    let mut menu = SettingsMenu::new(&config);
+    menu.handle_key(&mut config, Key::plain(KeyCode::Down));
+    menu.handle_key(&mut config, Key::plain(KeyCode::Enter));
+    menu.handle_key(&mut config, Key::new(KeyCode::Char('a'), KeyModifiers::ALT));
+    for _ in 0..23 {
+        menu.handle_key(&mut config, Key::plain(KeyCode::Down));
+    }
+    menu.handle_key(&mut config, Key::plain(KeyCode::Right));
+    let mut on_disk = GameConfig::default();
+    menu.copy_changes(&config, &mut on_disk);
+    assert_eq!(
+        on_disk.left,
+        vec![Key::new(KeyCode::Char('a'), KeyModifiers::ALT)]
+    );
+    assert!(on_disk.border_color == config.border_color);
+    assert!(on_disk.border_color != GameConfig::default().border_color);
+    assert!(!on_disk.cascade);
+}
+
+// A new key is written out on its own straight away, unless another action already has it.
+#[test]
+fn test_rebind() {
+    let mut config = GameConfig::default();
+    let mut menu = SettingsMenu::new(&config);
+    menu.handle_key(&mut config, Key::plain(KeyCode::Down));
+    menu.handle_key(&mut config, Key::plain(KeyCode::Enter));
+    assert!(menu
+        .handle_key(&mut config, Key::plain(KeyCode::Right))
+        .is_none());
+    assert_eq!(menu.message, "KEY ALREADY IN USE");
+    menu.handle_key(&mut config, Key::plain(KeyCode::Enter));
+    match menu.handle_key(&mut config, Key::plain(KeyCode::Char('a'))) {
+        Some(MenuEvent::Rebound) => {}
+        _ => panic!("Expected the new key to be saved")
+    }
+    config.cascade = true;
+    let mut on_disk = GameConfig::default();
+    menu.copy_binding(&config, &mut on_disk);
+    assert_eq!(on_disk.left, vec![Key::plain(KeyCode::Char('a'))]);
+    assert!(!on_disk.cascade);
+}
+
+// Picking a preset changes its keys and writes all of them out straight away, keeping controller
+// buttons.
+#[test]
+fn test_preset() {
+    use crate::input::Button;
+
+    let mut config = GameConfig::default();
+    let mut menu = SettingsMenu::new(&config);
+    match menu.handle_key(&mut config, Key::plain(KeyCode::Right)) {
+        Some(MenuEvent::Rebound) => {}
+        _ => panic!("Expected the preset to be saved")
+    }
+    assert!(PRESETS[0].matches(&config));
+    menu.handle_key(&mut config, Key::plain(KeyCode::Left));
+    assert!(PRESETS[3].matches(&config));
+    let mut on_disk = GameConfig::default();
+    menu.copy_binding(&config, &mut on_disk);
+    assert_eq!(
+        on_disk.left,
+        vec![
+            Key::plain(KeyCode::Char('a')),
+            Key::plain(KeyCode::Button(Button::Left))
+        ]
+    );
+    menu.binding_saved(&config);
+    assert_eq!(menu.message, "CONTROLS SAVED");
+}
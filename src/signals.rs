@@ -0,0 +1,45 @@
+#[cfg(unix)]
+use crate::libc;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set once the process has been asked to stop, so that the game loop can wind down the same way
+// it does when quitting: leaving raw mode and the alternate screen, then saving.
+static STOP_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+#[cfg(unix)]
+extern "C" fn request_stop(_: libc::c_int) {
+    STOP_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+// Catch Ctrl-C from outside raw mode, `kill`, and the terminal being closed instead of dying on
+// the spot. Only setting a flag is safe inside a signal handler, so everything else waits for the
+// game loop to notice.
+#[cfg(unix)]
+pub fn install() {
+    for &signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP].iter() {
+        unsafe {
+            libc::signal(
+                signal,
+                request_stop as extern "C" fn(libc::c_int) as libc::sighandler_t
+            );
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn install() {}
+
+pub fn stop_requested() -> bool {
+    STOP_REQUESTED.load(Ordering::SeqCst)
+}
+
+// A signal should be caught and remembered rather than ending the process.
+#[cfg(unix)]
+#[test]
+fn test_stop_requested() {
+    install();
+    unsafe {
+        libc::raise(libc::SIGTERM);
+    }
+    assert!(stop_requested());
+}
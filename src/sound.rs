@@ -0,0 +1,248 @@
+#[cfg(feature = "sound")]
+use rodio::source::{SineWave, Source};
+#[cfg(feature = "sound")]
+use rodio::{OutputStream, OutputStreamHandle};
+use std::time::{Duration, Instant};
+use tui_tetris::gameboard::Game;
+use tui_tetris::tetromino::Tetromino;
+
+use crate::session::{over_danger_line, stack_height, Effect};
+
+// Something happening in the game that's worth a sound.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Cue {
+    Move,
+    Rotate,
+    Lock,
+    LineClear,
+    Tetris,
+    LevelUp,
+    GameOver,
+    // Accessibility cues, so the game can be followed without seeing the board.
+    Spawn(Tetromino),
+    Danger
+}
+
+impl Cue {
+    // The (frequency in Hz, milliseconds) of each note, played one after another.
+    #[cfg(feature = "sound")]
+    fn notes(self) -> &'static [(f32, u64)] {
+        match self {
+            Cue::Move => &[(220.0, 15)],
+            Cue::Rotate => &[(330.0, 20)],
+            Cue::Lock => &[(110.0, 40)],
+            Cue::LineClear => &[(440.0, 60), (660.0, 80)],
+            Cue::Tetris => &[(440.0, 60), (554.0, 60), (659.0, 60), (880.0, 120)],
+            Cue::LevelUp => &[(523.0, 80), (784.0, 120)],
+            Cue::GameOver => &[(392.0, 150), (330.0, 150), (262.0, 300)],
+            // A rising scale, so each piece has its own pitch.
+            Cue::Spawn(Tetromino::I) => &[(262.0, 120)],
+            Cue::Spawn(Tetromino::J) => &[(294.0, 120)],
+            Cue::Spawn(Tetromino::L) => &[(330.0, 120)],
+            Cue::Spawn(Tetromino::S) => &[(349.0, 120)],
+            Cue::Spawn(Tetromino::Z) => &[(392.0, 120)],
+            Cue::Spawn(Tetromino::T) => &[(440.0, 120)],
+            Cue::Spawn(Tetromino::O) => &[(494.0, 120)],
+            Cue::Danger => &[(880.0, 100), (660.0, 100), (880.0, 100), (660.0, 100)]
+        }
+    }
+
+    // How many terminal bells stand in for the cue when there's no sound, and the gap between
+    // them. Pieces ring once per place in I, J, L, S, Z, T, O order, and danger rings three times
+    // in quick succession. The other cues would be too much as bells, so they're left out.
+    fn bells(self) -> (usize, Duration) {
+        match self {
+            Cue::Spawn(tetromino) => (tetromino as usize + 1, Duration::from_millis(200)),
+            Cue::Danger => (3, Duration::from_millis(60)),
+            _ => (0, Duration::from_millis(0))
+        }
+    }
+}
+
+// The parts of a game that cues are worked out from, taken at the start of each frame.
+#[derive(Copy, Clone)]
+pub struct Snapshot {
+    x: isize,
+    rotation: usize,
+    tetromino: Tetromino,
+    dealt: u64,
+    stack_height: usize,
+    pieces: u64,
+    lines_cleared: usize,
+    tetrises: u64,
+    t_spins: u64,
+    level: usize,
+    game_over: bool
+}
+
+impl Snapshot {
+    pub fn of(game: &Game) -> Self {
+        Snapshot {
+            x: game.active_piece.x,
+            rotation: game.active_piece.rotation,
+            tetromino: game.active_piece.tetromino,
+            dealt: game.dealt,
+            stack_height: stack_height(game),
+            pieces: game.stats.pieces,
+            lines_cleared: game.lines_cleared,
+            tetrises: game.stats.tetrises,
+            t_spins: game.stats.t_spins,
+            level: game.level,
+            game_over: game.game_over
+        }
+    }
+
+    // What's happened to the game since this was taken. Only the most notable of a lock, clear,
+    // or movement is given, since they'd drown each other out.
+    pub fn cues(&self, game: &Game) -> Vec<Cue> {
+        let now = Snapshot::of(game);
+        if now.game_over && !self.game_over {
+            return vec![Cue::GameOver];
+        }
+        let mut cues = Vec::with_capacity(2);
+        if now.tetrises > self.tetrises {
+            cues.push(Cue::Tetris);
+        } else if now.lines_cleared > self.lines_cleared {
+            cues.push(Cue::LineClear);
+        } else if now.pieces > self.pieces {
+            cues.push(Cue::Lock);
+        } else if now.rotation != self.rotation {
+            cues.push(Cue::Rotate);
+        } else if now.x != self.x {
+            cues.push(Cue::Move);
+        }
+        if now.level > self.level {
+            cues.push(Cue::LevelUp);
+        }
+        cues
+    }
+
+    // The board effect called for by a clear since this was taken, if any.
+    pub fn effect(&self, game: &Game) -> Option<Effect> {
+        if game.stats.tetrises > self.tetrises {
+            Some(Effect::Shake)
+        } else if game.stats.t_spins > self.t_spins && game.lines_cleared > self.lines_cleared {
+            Some(Effect::Flash)
+        } else {
+            None
+        }
+    }
+
+    // A new piece coming into play, whether dealt or swapped in from hold, and the stack crossing
+    // the danger line.
+    pub fn accessibility_cues(&self, game: &Game, danger_height: usize) -> Vec<Cue> {
+        let now = Snapshot::of(game);
+        if now.game_over {
+            return Vec::new();
+        }
+        let mut cues = Vec::with_capacity(2);
+        let danger = |height| over_danger_line(height, game.board.height, danger_height);
+        if danger(now.stack_height) && !danger(self.stack_height) {
+            cues.push(Cue::Danger);
+        }
+        if now.dealt != self.dealt || now.tetromino != self.tetromino {
+            cues.push(Cue::Spawn(now.tetromino));
+        }
+        cues
+    }
+}
+
+// Plays cues on the default output device. Without the `sound` feature, or without a device to
+// play on, accessibility cues are rung on the terminal bell instead and the rest are dropped.
+pub struct Player {
+    #[cfg(feature = "sound")]
+    output: Option<(OutputStream, OutputStreamHandle)>,
+    // When each bell still to come should ring.
+    bells: Vec<Instant>
+}
+
+#[cfg(feature = "sound")]
+const VOLUME: f32 = 0.2;
+
+impl Player {
+    #[cfg(feature = "sound")]
+    pub fn new() -> Self {
+        let output = match OutputStream::try_default() {
+            Ok(output) => Some(output),
+            Err(e) => {
+                warn!("No sound output: {}", e);
+                None
+            }
+        };
+        Player {
+            output,
+            bells: Vec::new()
+        }
+    }
+
+    #[cfg(not(feature = "sound"))]
+    pub fn new() -> Self {
+        Player { bells: Vec::new() }
+    }
+
+    #[cfg(feature = "sound")]
+    pub fn play(&mut self, cue: Cue) {
+        let handle = match self.output {
+            Some((_, ref handle)) => handle,
+            None => return self.ring(cue)
+        };
+        let mut start = Duration::from_millis(0);
+        for &(frequency, millis) in cue.notes() {
+            let length = Duration::from_millis(millis);
+            let note = SineWave::new(frequency)
+                .take_duration(length)
+                .amplify(VOLUME)
+                .delay(start);
+            if let Err(e) = handle.play_raw(note) {
+                warn!("Failed to play {:?}: {}", cue, e);
+                return;
+            }
+            start += length;
+        }
+    }
+
+    #[cfg(not(feature = "sound"))]
+    pub fn play(&mut self, cue: Cue) {
+        self.ring(cue);
+    }
+
+    fn ring(&mut self, cue: Cue) {
+        let (count, gap) = cue.bells();
+        let now = Instant::now();
+        self.bells.extend((0..count as u32).map(|i| now + gap * i));
+    }
+
+    // Bell characters for every bell that's due, to be written out with the next frame.
+    pub fn due_bells(&mut self) -> String {
+        let now = Instant::now();
+        let due = self.bells.iter().filter(|&&at| at <= now).count();
+        self.bells.retain(|&at| at > now);
+        "\x07".repeat(due)
+    }
+}
+
+// A lock that clears four lines is a Tetris, not a lock or a plain line clear.
+#[test]
+fn test_tetris_cue() {
+    use tui_tetris::rules::Rules;
+
+    let mut game = Game::with_seed(Rules::default(), 0);
+    let before = Snapshot::of(&game);
+    game.stats.pieces += 1;
+    game.lines_cleared += 4;
+    game.stats.tetrises += 1;
+    assert_eq!(before.cues(&game), vec![Cue::Tetris]);
+}
+
+// Danger is only announced as the stack crosses the line, not on every frame it stays above it.
+#[test]
+fn test_danger_cue() {
+    use tui_tetris::rules::Rules;
+
+    let mut game = Game::with_seed(Rules::default(), 0);
+    let before = Snapshot::of(&game);
+    game.add_garbage(game.board.height * 3 / 4 + 1);
+    assert_eq!(before.accessibility_cues(&game, 75), vec![Cue::Danger]);
+    let after = Snapshot::of(&game);
+    assert!(after.accessibility_cues(&game, 75).is_empty());
+}
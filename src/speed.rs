@@ -0,0 +1,85 @@
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::time::{Duration, Instant};
+use tui_tetris::action::Action;
+use tui_tetris::ai::best_placement;
+use tui_tetris::gameboard::Game;
+use tui_tetris::rules::Rules;
+
+// How long the `bench` command spends on each kind of play.
+pub const BENCH_DURATION: Duration = Duration::from_secs(3);
+// The moves random play mashes, the same as the benchmarks'.
+const MOVES: [Action; 5] = [
+    Action::MoveLeft,
+    Action::MoveRight,
+    Action::RotateClockwise,
+    Action::SoftDrop,
+    Action::HardDrop
+];
+
+// How many of something were done in how long.
+pub struct Measurement {
+    pub count: u64,
+    pub elapsed: Duration
+}
+
+impl Measurement {
+    pub fn per_second(&self) -> f64 {
+        self.count as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+}
+
+// Play random moves with a tick after each one, the way a frame of input would go, for about
+// `duration`, starting a new game whenever one tops out. Counts the ticks played. Nothing is drawn,
+// so this is only the game itself, on the default rules so that runs can be compared.
+pub fn random_ticks(duration: Duration) -> Measurement {
+    let start = Instant::now();
+    let mut count = 0;
+    let mut seed = 0;
+    while start.elapsed() < duration {
+        seed += 1;
+        let mut game = Game::with_seed(Rules::default(), seed);
+        let mut rng = StdRng::seed_from_u64(seed);
+        while !game.game_over && start.elapsed() < duration {
+            game.advance(&[MOVES[rng.gen_range(0, MOVES.len())]]);
+            count += 1;
+        }
+    }
+    Measurement {
+        count,
+        elapsed: start.elapsed()
+    }
+}
+
+// Place pieces where the AI would for about `duration`, starting a new game whenever one ends, and
+// count the pieces. This is mostly the AI's search.
+pub fn ai_pieces(duration: Duration) -> Measurement {
+    let start = Instant::now();
+    let mut count = 0;
+    let mut seed = 0;
+    while start.elapsed() < duration {
+        seed += 1;
+        let mut game = Game::with_seed(Rules::default(), seed);
+        while start.elapsed() < duration {
+            match best_placement(&game) {
+                Some(placement) if game.play_placement(placement) => count += 1,
+                _ => break
+            }
+        }
+    }
+    Measurement {
+        count,
+        elapsed: start.elapsed()
+    }
+}
+
+// Both kinds of play get something done in a short run, and take about as long as they're given.
+#[test]
+fn test_measurements() {
+    let duration = Duration::from_millis(50);
+    for measurement in [random_ticks(duration), ai_pieces(duration)].iter() {
+        assert!(measurement.count > 0);
+        assert!(measurement.elapsed >= duration);
+        assert!(measurement.per_second() > 0.0);
+    }
+}
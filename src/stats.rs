@@ -0,0 +1,191 @@
+use crate::timing::TICK_RATE;
+use std::collections::VecDeque;
+
+// Lines of garbage each clear would send in a versus game, indexed by the number of lines cleared.
+// Used for attack per minute even though there's no one to send them to.
+const ATTACK: [u64; 5] = [0, 0, 1, 2, 4];
+const T_SPIN_ATTACK: [u64; 4] = [0, 2, 4, 6];
+// Seconds of samples kept for graphing, and how many seconds each pieces per second sample is
+// averaged over.
+pub const SAMPLE_HISTORY: usize = 30;
+const PPS_WINDOW: usize = 5;
+
+// Running totals for the current game. Time is counted in logic ticks so that pauses don't count.
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct Stats {
+    pub pieces: u64,
+    pub ticks: u64,
+    // Ticks since the game started that were spent paused or counting down to carry on, which
+    // `ticks` leaves out. Counted by the frontend, since the game doesn't tick while paused.
+    #[serde(default)]
+    pub paused_ticks: u64,
+    pub attack: u64,
+    pub t_spins: u64,
+    pub tetrises: u64,
+    pub holds: u64,
+    // Pieces whose finesse could be checked, and how many of those took more inputs than needed.
+    pub finesse_pieces: u64,
+    pub finesse_faults: u64,
+    // Points from clears of one to four lines, from T-spin clears, and from soft and hard drops.
+    pub clear_score: [u64; 4],
+    pub t_spin_score: u64,
+    pub drop_score: u64,
+    // Pieces in a row that have cleared lines, and the most there have been.
+    pub combo: u64,
+    pub max_combo: u64,
+    // Set after a Tetris or T-spin clear, which makes the next one worth an extra line of attack.
+    pub back_to_back: bool,
+    // The end of each of the last few seconds of play, oldest first.
+    #[serde(default)]
+    pub samples: VecDeque<Sample>
+}
+
+#[derive(Copy, Clone, Serialize, Deserialize)]
+pub struct Sample {
+    pub pieces: u64,
+    pub stack_height: usize,
+    // Averaged over the `PPS_WINDOW` seconds up to the sample, or as many as there had been.
+    pub pieces_per_second: f64
+}
+
+impl Stats {
+    pub fn new() -> Self {
+        Stats::default()
+    }
+
+    pub fn record_lock(&mut self, lines: usize, t_spin: bool) {
+        self.pieces += 1;
+        if t_spin {
+            self.t_spins += 1;
+        }
+        if lines == 0 {
+            self.combo = 0;
+            return;
+        }
+        self.combo += 1;
+        self.max_combo = self.max_combo.max(self.combo);
+        if lines >= 4 {
+            self.tetrises += 1;
+        }
+        let difficult = t_spin || lines >= 4;
+        self.attack += if t_spin {
+            T_SPIN_ATTACK[lines.min(3)]
+        } else {
+            ATTACK[lines.min(4)]
+        };
+        if difficult && self.back_to_back {
+            self.attack += 1;
+        }
+        self.back_to_back = difficult;
+    }
+
+    pub fn record_clear_score(&mut self, lines: usize, t_spin: bool, points: u64) {
+        if t_spin {
+            self.t_spin_score += points;
+        } else {
+            self.clear_score[lines.min(4) - 1] += points;
+        }
+    }
+
+    pub fn record_sample(&mut self, stack_height: usize) {
+        let window = self.samples.len().min(PPS_WINDOW);
+        let (before, seconds) = match window {
+            0 => (0, 1),
+            _ => (self.samples[self.samples.len() - window].pieces, window)
+        };
+        if self.samples.len() == SAMPLE_HISTORY {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample {
+            pieces: self.pieces,
+            stack_height,
+            pieces_per_second: (self.pieces - before) as f64 / seconds as f64
+        });
+    }
+
+    pub fn record_finesse(&mut self, optimal: bool) {
+        self.finesse_pieces += 1;
+        if !optimal {
+            self.finesse_faults += 1;
+        }
+    }
+
+    // Percentage of checked pieces that were placed with as few inputs as possible.
+    pub fn finesse_percent(&self) -> Option<f64> {
+        if self.finesse_pieces == 0 {
+            None
+        } else {
+            let optimal = self.finesse_pieces - self.finesse_faults;
+            Some(optimal as f64 * 100.0 / self.finesse_pieces as f64)
+        }
+    }
+
+    pub fn seconds(&self) -> f64 {
+        self.ticks as f64 / TICK_RATE as f64
+    }
+
+    // How long the game took on the clock, pauses included.
+    pub fn wall_seconds(&self) -> f64 {
+        (self.ticks + self.paused_ticks) as f64 / TICK_RATE as f64
+    }
+
+    fn minutes(&self) -> f64 {
+        self.seconds() / 60.0
+    }
+
+    pub fn pieces_per_second(&self) -> f64 {
+        if self.ticks == 0 {
+            0.0
+        } else {
+            self.pieces as f64 / self.seconds()
+        }
+    }
+
+    pub fn attack_per_minute(&self) -> f64 {
+        if self.ticks == 0 {
+            0.0
+        } else {
+            self.attack as f64 / self.minutes()
+        }
+    }
+}
+
+// Back-to-back bonuses only apply between consecutive difficult clears.
+#[test]
+fn test_attack() {
+    let mut stats = Stats::new();
+    stats.record_lock(4, false);
+    stats.record_lock(2, true);
+    stats.record_lock(2, false);
+    stats.record_lock(4, false);
+    assert_eq!(stats.attack, 4 + 5 + 1 + 4);
+    assert_eq!((stats.pieces, stats.tetrises, stats.t_spins), (4, 2, 1));
+    stats.ticks = TICK_RATE * 2;
+    assert_eq!(stats.pieces_per_second(), 2.0);
+}
+
+// A combo is broken by any piece that doesn't clear lines, but the best one is kept.
+#[test]
+fn test_combo() {
+    let mut stats = Stats::new();
+    for &lines in &[1, 2, 1, 0, 4] {
+        stats.record_lock(lines, false);
+    }
+    assert_eq!((stats.combo, stats.max_combo), (1, 3));
+}
+
+// Only the latest samples are kept, and pieces per second is averaged over a few of them.
+#[test]
+fn test_samples() {
+    let mut stats = Stats::new();
+    for second in 0..SAMPLE_HISTORY as u64 + 5 {
+        stats.pieces = second * 2;
+        stats.record_sample(second as usize);
+    }
+    assert_eq!(stats.samples.len(), SAMPLE_HISTORY);
+    assert_eq!(stats.samples[0].stack_height, 5);
+    assert!(stats
+        .samples
+        .iter()
+        .all(|sample| sample.pieces_per_second == 2.0));
+}
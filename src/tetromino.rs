@@ -1,8 +1,6 @@
-use crossterm::Color;
-use rand::{Rng, rngs::ThreadRng};
 use std::hint::unreachable_unchecked;
 
-#[derive(Copy, Clone, Eq, PartialEq, Debug)]
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
 pub enum Tetromino {
     I,
     J,
@@ -10,7 +8,7 @@ pub enum Tetromino {
     S,
     Z,
     T,
-    O,
+    O
 }
 
 impl From<u16> for Tetromino {
@@ -26,4 +24,134 @@ impl From<u16> for Tetromino {
             _ => unsafe { unreachable_unchecked() }
         }
     }
-}
\ No newline at end of file
+}
+
+impl Tetromino {
+    pub fn letter(self) -> char {
+        match self {
+            Tetromino::I => 'I',
+            Tetromino::J => 'J',
+            Tetromino::L => 'L',
+            Tetromino::S => 'S',
+            Tetromino::Z => 'Z',
+            Tetromino::T => 'T',
+            Tetromino::O => 'O'
+        }
+    }
+
+    pub fn from_letter(letter: char) -> Option<Self> {
+        Some(match letter {
+            'I' => Tetromino::I,
+            'J' => Tetromino::J,
+            'L' => Tetromino::L,
+            'S' => Tetromino::S,
+            'Z' => Tetromino::Z,
+            'T' => Tetromino::T,
+            'O' => Tetromino::O,
+            _ => return None
+        })
+    }
+
+    // Offsets of each mino from the top left corner of the piece's bounding box for the given
+    // rotation state (0 = spawn, 1 = R, 2 = 2, 3 = L).
+    pub fn minos(self, rotation: usize) -> [(isize, isize); 4] {
+        SHAPES[self as usize][rotation & 3]
+    }
+
+    // Offsets to try, in order, when rotating out of `rotation`. The first one that doesn't
+    // collide with anything is used.
+    pub fn kicks(self, rotation: usize, clockwise: bool) -> &'static [(isize, isize); 5] {
+        match (self, clockwise) {
+            (Tetromino::O, _) => &NO_KICKS,
+            (Tetromino::I, true) => &I_KICKS_CW[rotation & 3],
+            (Tetromino::I, false) => &I_KICKS_ACW[rotation & 3],
+            (_, true) => &JLSTZ_KICKS_CW[rotation & 3],
+            (_, false) => &JLSTZ_KICKS_ACW[rotation & 3]
+        }
+    }
+}
+
+// SRS piece shapes. Y increases downwards to match board rows.
+const SHAPES: [[[(isize, isize); 4]; 4]; 7] = [
+    // I
+    [
+        [(0, 1), (1, 1), (2, 1), (3, 1)],
+        [(2, 0), (2, 1), (2, 2), (2, 3)],
+        [(0, 2), (1, 2), (2, 2), (3, 2)],
+        [(1, 0), (1, 1), (1, 2), (1, 3)]
+    ],
+    // J
+    [
+        [(0, 0), (0, 1), (1, 1), (2, 1)],
+        [(1, 0), (2, 0), (1, 1), (1, 2)],
+        [(0, 1), (1, 1), (2, 1), (2, 2)],
+        [(1, 0), (1, 1), (0, 2), (1, 2)]
+    ],
+    // L
+    [
+        [(2, 0), (0, 1), (1, 1), (2, 1)],
+        [(1, 0), (1, 1), (1, 2), (2, 2)],
+        [(0, 1), (1, 1), (2, 1), (0, 2)],
+        [(0, 0), (1, 0), (1, 1), (1, 2)]
+    ],
+    // S
+    [
+        [(1, 0), (2, 0), (0, 1), (1, 1)],
+        [(1, 0), (1, 1), (2, 1), (2, 2)],
+        [(1, 1), (2, 1), (0, 2), (1, 2)],
+        [(0, 0), (0, 1), (1, 1), (1, 2)]
+    ],
+    // Z
+    [
+        [(0, 0), (1, 0), (1, 1), (2, 1)],
+        [(2, 0), (1, 1), (2, 1), (1, 2)],
+        [(0, 1), (1, 1), (1, 2), (2, 2)],
+        [(1, 0), (0, 1), (1, 1), (0, 2)]
+    ],
+    // T
+    [
+        [(1, 0), (0, 1), (1, 1), (2, 1)],
+        [(1, 0), (1, 1), (2, 1), (1, 2)],
+        [(0, 1), (1, 1), (2, 1), (1, 2)],
+        [(1, 0), (0, 1), (1, 1), (1, 2)]
+    ],
+    // O
+    [
+        [(1, 0), (2, 0), (1, 1), (2, 1)],
+        [(1, 0), (2, 0), (1, 1), (2, 1)],
+        [(1, 0), (2, 0), (1, 1), (2, 1)],
+        [(1, 0), (2, 0), (1, 1), (2, 1)]
+    ]
+];
+
+// SRS wall kick tables, indexed by the starting rotation state. These are written with Y
+// increasing downwards, so the Y values are negated compared to most published tables.
+const NO_KICKS: [(isize, isize); 5] = [(0, 0); 5];
+
+const JLSTZ_KICKS_CW: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]
+];
+
+const JLSTZ_KICKS_ACW: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (1, 0), (1, -1), (0, 2), (1, 2)],
+    [(0, 0), (1, 0), (1, 1), (0, -2), (1, -2)],
+    [(0, 0), (-1, 0), (-1, -1), (0, 2), (-1, 2)],
+    [(0, 0), (-1, 0), (-1, 1), (0, -2), (-1, -2)]
+];
+
+const I_KICKS_CW: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)],
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)]
+];
+
+const I_KICKS_ACW: [[(isize, isize); 5]; 4] = [
+    [(0, 0), (-1, 0), (2, 0), (-1, -2), (2, 1)],
+    [(0, 0), (2, 0), (-1, 0), (2, -1), (-1, 2)],
+    [(0, 0), (1, 0), (-2, 0), (1, 2), (-2, -1)],
+    [(0, 0), (-2, 0), (1, 0), (-2, 1), (1, -2)]
+];
@@ -0,0 +1,119 @@
+use std::thread;
+use std::time::{Duration, Instant};
+
+// Game logic always runs at this many ticks per second, no matter what the render rate is, so
+// that gravity and other timers behave the same with any `fps_limiter` setting.
+pub const TICK_RATE: u64 = 60;
+
+// After a long stall (e.g. the process being suspended) only this many ticks are caught up on.
+// The rest are dropped instead of fast-forwarding the game.
+const MAX_CATCH_UP_TICKS: u32 = 10;
+
+// `thread::sleep` routinely oversleeps by anywhere from a few microseconds to a couple of
+// milliseconds. To stay on schedule the pacer keeps a running average of how much each sleep
+// overshoots, sleeps for that much less than the remaining time, and yields for the remainder.
+// Deadlines are advanced by a whole frame each time rather than measured from "now", so small
+// errors don't accumulate into drift.
+pub struct FramePacer {
+    frame_duration: Option<Duration>,
+    deadline: Instant,
+    last_frame: Instant,
+    oversleep: Duration,
+    // How many times the schedule has been given up on for falling too far behind.
+    dropped_frames: u64
+}
+
+impl FramePacer {
+    pub fn new(fps_limiter: Option<u64>) -> Self {
+        let now = Instant::now();
+        let frame_duration = fps_limiter.map(|fps| Duration::from_nanos(1_000_000_000 / fps));
+        FramePacer {
+            frame_duration,
+            deadline: now + frame_duration.unwrap_or_default(),
+            last_frame: now,
+            oversleep: Duration::from_secs(0),
+            dropped_frames: 0
+        }
+    }
+
+    pub fn dropped_frames(&self) -> u64 {
+        self.dropped_frames
+    }
+
+    // Block until the next frame is due, then return how long the previous frame took.
+    pub fn wait(&mut self) -> Duration {
+        if let Some(frame_duration) = self.frame_duration {
+            let now = Instant::now();
+            if self.deadline > now {
+                let remaining = self.deadline - now;
+                if remaining > self.oversleep {
+                    let requested = remaining - self.oversleep;
+                    let sleep_start = Instant::now();
+                    thread::sleep(requested);
+                    let overshoot = sleep_start.elapsed().saturating_sub(requested);
+                    self.oversleep = (self.oversleep * 7 + overshoot) / 8;
+                }
+                while Instant::now() < self.deadline {
+                    thread::yield_now();
+                }
+                self.deadline += frame_duration;
+            } else if now - self.deadline > frame_duration {
+                // More than a whole frame behind, so give up on catching up and start over.
+                debug!("Fell {:?} behind the frame schedule", now - self.deadline);
+                self.dropped_frames += 1;
+                self.deadline = now + frame_duration;
+            } else {
+                self.deadline += frame_duration;
+            }
+        }
+        let now = Instant::now();
+        let frame_time = now - self.last_frame;
+        self.last_frame = now;
+        frame_time
+    }
+}
+
+// Converts elapsed wall-clock time into a whole number of fixed-length logic ticks, carrying any
+// leftover time over to the next frame.
+pub struct TickClock {
+    tick_duration: Duration,
+    accumulator: Duration
+}
+
+impl TickClock {
+    pub fn new(tick_rate: u64) -> Self {
+        TickClock {
+            tick_duration: Duration::from_nanos(1_000_000_000 / tick_rate),
+            accumulator: Duration::from_secs(0)
+        }
+    }
+
+    pub fn advance(&mut self, elapsed: Duration) -> u32 {
+        self.accumulator += elapsed;
+        let ticks = self.accumulator.as_nanos() / self.tick_duration.as_nanos();
+        if ticks > MAX_CATCH_UP_TICKS as u128 {
+            debug!(
+                "Dropped {} ticks after a {:?} stall",
+                ticks - MAX_CATCH_UP_TICKS as u128,
+                self.accumulator
+            );
+            self.accumulator = Duration::from_secs(0);
+            MAX_CATCH_UP_TICKS
+        } else {
+            self.accumulator -= self.tick_duration * ticks as u32;
+            ticks as u32
+        }
+    }
+}
+
+// Leftover time from partial ticks must carry over instead of being lost.
+#[test]
+fn test_tick_clock_carry() {
+    let mut clock = TickClock::new(TICK_RATE);
+    let tick = Duration::from_nanos(1_000_000_000 / TICK_RATE);
+    assert_eq!(clock.advance(tick * 3), 3);
+    assert_eq!(clock.advance(tick / 2), 0);
+    assert_eq!(clock.advance(tick / 2), 1);
+    assert_eq!(clock.advance(Duration::from_secs(5)), MAX_CATCH_UP_TICKS);
+    assert_eq!(clock.advance(tick), 1);
+}
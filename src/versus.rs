@@ -0,0 +1,262 @@
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use rand_chacha::ChaCha20Rng;
+use std::collections::VecDeque;
+use std::fmt::{self, Display};
+use std::mem;
+
+use crate::action::Action;
+use crate::gameboard::Game;
+use crate::rules::Rules;
+
+// How many of the latest attacks an attack log keeps.
+pub const ATTACK_LOG_LENGTH: usize = 8;
+
+// Who attacks go to in a network game with more than one opponent. `Even` splits each attack
+// between everyone still in the game.
+#[derive(Copy, Clone, Eq, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Targeting {
+    Random,
+    Leader,
+    Danger,
+    Even
+}
+
+impl Display for Targeting {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}",
+            match self {
+                Targeting::Random => "random",
+                Targeting::Leader => "leader",
+                Targeting::Danger => "danger",
+                Targeting::Even => "even"
+            }
+        )
+    }
+}
+
+// Garbage going either way in a versus game.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Attack {
+    Sent(u64),
+    Incoming(u64)
+}
+
+// The latest attacks in a versus game, oldest first.
+#[derive(Clone, Default)]
+pub struct AttackLog {
+    pub attacks: VecDeque<Attack>
+}
+
+impl AttackLog {
+    pub fn push(&mut self, attack: Attack) {
+        if self.attacks.len() == ATTACK_LOG_LENGTH {
+            self.attacks.pop_front();
+        }
+        self.attacks.push_back(attack);
+    }
+}
+
+// Who an attack goes to among `standing`, the opponents still in the game, with `score` and
+// `height` giving each one's score and stack height. An opponent picked by hand gets everything
+// while they're standing, and otherwise `targeting` decides. Ties for the leader or the highest
+// stack go to whoever comes first.
+pub fn pick_targets<R: Rng, S: Fn(usize) -> u64, H: Fn(usize) -> usize>(
+    standing: &[usize],
+    targeting: Targeting,
+    picked: Option<usize>,
+    score: S,
+    height: H,
+    rng: &mut R
+) -> Vec<usize> {
+    if let Some(picked) = picked.filter(|picked| standing.contains(picked)) {
+        return vec![picked];
+    }
+    // `max_by_key` keeps the last of any ties, so they're looked at back to front.
+    let first_max =
+        |key: &dyn Fn(usize) -> u64| standing.iter().rev().copied().max_by_key(|&i| key(i));
+    let target = match targeting {
+        Targeting::Random => standing.choose(rng).copied(),
+        Targeting::Leader => first_max(&score),
+        Targeting::Danger => first_max(&|i| height(i) as u64),
+        Targeting::Even => return standing.to_vec()
+    };
+    target.into_iter().collect()
+}
+
+// How many of an attack's lines each of `targets` opponents gets when it's split between them.
+// The lines left over after an even split go one each to the next opponents from `turn` on, so
+// that small attacks still get spread around.
+pub fn split_lines(lines: u64, targets: usize, turn: usize) -> Vec<u64> {
+    let (each, left_over) = (lines / targets as u64, (lines % targets as u64) as usize);
+    (0..targets)
+        .map(|i| each + ((i + targets - turn % targets) % targets < left_over) as u64)
+        .collect()
+}
+
+// A versus game with every player's game in it, played out from nothing but their inputs so that
+// every player's copy of it comes out the same. Attacks go straight into the games they're aimed
+// at, so garbage comes in on the same tick everywhere. Players are numbered the same as on the
+// network.
+#[derive(Clone)]
+pub struct Match {
+    pub games: Vec<Game>,
+    pub ticks: u64,
+    // How each player aims their attacks, who they've picked by hand with `SwitchTarget`, and
+    // whose turn it is for the lines left over when they split an attack.
+    targeting: Vec<Targeting>,
+    pub picked: Vec<Option<usize>>,
+    turns: Vec<usize>,
+    pub attack_logs: Vec<AttackLog>,
+    // The tick each player who's left the game is out of it from.
+    left: Vec<Option<u64>>,
+    // Random targeting draws from this rather than from the thread, so every copy picks the same.
+    rng: ChaCha20Rng
+}
+
+impl Match {
+    // Everyone is dealt the same pieces, each playing by their own rules for their handicap.
+    pub fn new(seed: u64, players: &[(Rules, Targeting)]) -> Self {
+        let games = players
+            .iter()
+            .map(|(rules, _)| {
+                let mut game = Game::with_seed(rules.clone(), seed);
+                game.start_versus();
+                game
+            })
+            .collect();
+        Match {
+            games,
+            ticks: 0,
+            targeting: players.iter().map(|&(_, targeting)| targeting).collect(),
+            picked: vec![None; players.len()],
+            turns: vec![0; players.len()],
+            attack_logs: vec![AttackLog::default(); players.len()],
+            left: vec![None; players.len()],
+            rng: ChaCha20Rng::seed_from_u64(!seed)
+        }
+    }
+
+    // Play one tick with what each player pressed on it, by number. Attacks and items go out once
+    // every game has had the tick, and the last one standing wins.
+    pub fn advance(&mut self, inputs: &[Vec<Action>]) {
+        for (player, actions) in inputs.iter().enumerate() {
+            if self.has_left(player) {
+                self.games[player].game_over = true;
+                continue;
+            }
+            for _ in actions
+                .iter()
+                .filter(|&&action| action == Action::SwitchTarget)
+            {
+                self.switch_target(player);
+            }
+            self.games[player].advance(actions);
+        }
+        self.ticks += 1;
+        for player in 0..self.games.len() {
+            self.send_attacks(player);
+        }
+        for player in 0..self.games.len() {
+            if (0..self.games.len()).all(|other| other == player || self.topped_out(other)) {
+                self.games[player].win();
+            }
+        }
+    }
+
+    // Take a player out of the game from `tick` on, when they've left.
+    pub fn leave(&mut self, player: usize, tick: u64) {
+        self.left[player] = Some(tick);
+    }
+
+    pub fn has_left(&self, player: usize) -> bool {
+        self.left[player].is_some_and(|tick| self.ticks >= tick)
+    }
+
+    // Winning also ends the game, so only losing counts.
+    fn topped_out(&self, player: usize) -> bool {
+        self.games[player].game_over && !self.games[player].won
+    }
+
+    // Pick the next player who's still in the game after the one picked now, going back to the
+    // player's targeting after the last of them.
+    fn switch_target(&mut self, player: usize) {
+        let next = self.picked[player].map_or(0, |target| target + 1);
+        self.picked[player] =
+            (next..self.games.len()).find(|&i| i != player && !self.topped_out(i));
+    }
+
+    // Send a player's attack and items to whoever they're aimed at. When there's more than one
+    // target, attacks are split between them and items take turns.
+    fn send_attacks(&mut self, player: usize) {
+        let attack = mem::take(&mut self.games[player].outgoing_attack);
+        let items = mem::take(&mut self.games[player].outgoing_items);
+        if attack == 0 && items.is_empty() {
+            return;
+        }
+        let standing = (0..self.games.len())
+            .filter(|&i| i != player && !self.topped_out(i))
+            .collect::<Vec<_>>();
+        let targets = {
+            let games = &self.games;
+            pick_targets(
+                &standing,
+                self.targeting[player],
+                self.picked[player],
+                |i| games[i].score,
+                |i| games[i].board.stack_height(),
+                &mut self.rng
+            )
+        };
+        if targets.is_empty() {
+            return;
+        }
+        if attack > 0 {
+            let shares = split_lines(attack, targets.len(), self.turns[player]);
+            self.turns[player] += 1;
+            for (&target, lines) in targets.iter().zip(shares) {
+                if lines > 0 {
+                    self.games[target].pending_garbage += lines;
+                    self.attack_logs[target].push(Attack::Incoming(lines));
+                }
+            }
+            self.attack_logs[player].push(Attack::Sent(attack));
+        }
+        for item in items {
+            let target = targets[self.turns[player] % targets.len()];
+            self.turns[player] += 1;
+            self.games[target].receive_item(item);
+        }
+    }
+}
+
+// Split attacks add up to the whole attack and take turns with what's left over.
+#[test]
+fn test_split_lines() {
+    assert_eq!(split_lines(5, 3, 0), [2, 2, 1]);
+    assert_eq!(split_lines(5, 3, 1), [1, 2, 2]);
+    assert_eq!(split_lines(1, 3, 5), [0, 0, 1]);
+    assert_eq!(split_lines(4, 1, 3), [4]);
+}
+
+// Attack sent in a match lands in the other game on the same tick, and topping out hands the
+// win to whoever's left.
+#[test]
+fn test_match_attacks() {
+    let rules = Rules::default();
+    let players = [(rules.clone(), Targeting::Even), (rules, Targeting::Even)];
+    let mut game_match = Match::new(0, &players);
+    game_match.games[0].outgoing_attack = 4;
+    game_match.advance(&[Vec::new(), Vec::new()]);
+    assert_eq!(game_match.games[1].pending_garbage, 4);
+    assert_eq!(game_match.attack_logs[0].attacks, [Attack::Sent(4)]);
+    assert_eq!(game_match.attack_logs[1].attacks, [Attack::Incoming(4)]);
+    game_match.leave(1, 2);
+    game_match.advance(&[Vec::new(), Vec::new()]);
+    assert!(!game_match.games[1].game_over);
+    game_match.advance(&[Vec::new(), Vec::new()]);
+    assert!(game_match.games[1].game_over && game_match.games[0].won);
+}